@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+
+use symphonia::core::meta::{StandardVisualKey, Visual};
+
+use crate::track::Track;
+
+/// Sidecar images, in order of preference
+const COVER_NAMES: &[&str] = &["cover", "folder", "front", "album"];
+const COVER_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artwork {
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Artwork {
+    /// Takes the picture embedded in the file first, then a cover image next to it.
+    pub fn load(track: &Track) -> Option<Self> {
+        Self::read_embedded(&track.path).or_else(|| {
+            let path = sidecar(track)?;
+            let media_type = match path.extension()?.to_str()?.to_lowercase().as_str() {
+                "png" => "image/png",
+                _ => "image/jpeg",
+            };
+            Some(Self {
+                media_type: media_type.to_string(),
+                data: std::fs::read(path).ok()?,
+            })
+        })
+    }
+
+    /// The front cover, or the first picture when none is marked as such.
+    fn read_embedded(path: &Path) -> Option<Self> {
+        let mut probed = Track::probe(path).ok()?;
+        let pick = |visuals: &[Visual]| {
+            visuals
+                .iter()
+                .find(|v| v.usage == Some(StandardVisualKey::FrontCover))
+                .or(visuals.first())
+                .map(|v| Self {
+                    media_type: v.media_type.clone(),
+                    data: v.data.to_vec(),
+                })
+        };
+        let mut artwork = probed
+            .format
+            .metadata()
+            .skip_to_latest()
+            .and_then(|rev| pick(rev.visuals()));
+        if artwork.is_none() {
+            if let Some(mut m) = probed.metadata.get() {
+                artwork = m.skip_to_latest().and_then(|rev| pick(rev.visuals()));
+            }
+        }
+        artwork
+    }
+}
+
+/// A cover image next to the track, e.g. `cover.jpg` or `Folder.png`.
+pub fn sidecar(track: &Track) -> Option<PathBuf> {
+    let dir = track.path.parent()?;
+    let images: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| COVER_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        })
+        .collect();
+    COVER_NAMES.iter().find_map(|name| {
+        images
+            .iter()
+            .find(|path| {
+                path.file_stem()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|s| s.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+    })
+}
+
+#[cfg(test)]
+#[path = "./artwork.tests.rs"]
+mod tests;
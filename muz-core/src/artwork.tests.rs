@@ -0,0 +1,36 @@
+use super::*;
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("muz-artwork-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_sidecar_prefers_cover_over_folder() {
+    let dir = temp_dir();
+    std::fs::write(dir.join("Folder.JPG"), b"folder").unwrap();
+    std::fs::write(dir.join("Cover.png"), b"cover").unwrap();
+    std::fs::write(dir.join("back.jpg"), b"back").unwrap();
+    let track = Track::new(dir.join("01 Song.flac"));
+
+    assert_eq!(sidecar(&track), Some(dir.join("Cover.png")));
+    assert_eq!(
+        Artwork::load(&track),
+        Some(Artwork {
+            media_type: "image/png".to_string(),
+            data: b"cover".to_vec(),
+        })
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_no_artwork() {
+    let dir = temp_dir();
+    std::fs::write(dir.join("back.jpg"), b"back").unwrap();
+    let track = Track::new(dir.join("01 Song.flac"));
+    assert_eq!(sidecar(&track), None);
+    assert_eq!(Artwork::load(&track), None);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast;
+
+use crate::events::{PlayerEvent, ProgressEvent, StateChangedEvent};
+use crate::track::Track;
+
+/// Events kept for slow receivers before they lag. Progress and spectrum events come a
+/// few dozen times a second.
+const CAPACITY: usize = 1024;
+
+/// An event and its place on the bus. Sequence numbers start at 1 and have no gaps, a
+/// receiver that lagged sees the jump.
+#[derive(Clone, Debug)]
+pub struct BusEvent {
+    pub seq: u64,
+    pub event: PlayerEvent,
+}
+
+/// The state the events so far add up to, for subscribers that weren't there to see them.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    /// The last event included, 0 before any
+    pub seq: u64,
+    pub track: Option<Track>,
+    pub queue: Vec<Track>,
+    pub has_history: bool,
+    /// Playback state, volume and the other player settings, once the player announced them
+    pub player: Option<StateChangedEvent>,
+    /// The last progress of the current track
+    pub progress: Option<ProgressEvent>,
+}
+
+impl StateSnapshot {
+    fn apply(&mut self, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::TrackChanged(changed) => {
+                self.track = changed.track.clone();
+                self.progress = None;
+            }
+            PlayerEvent::QueueChanged(changed) => self.queue = changed.queue.clone(),
+            PlayerEvent::HistoryUpdate(update) => self.has_history = update.has_history,
+            PlayerEvent::StateChanged(state) => self.player = Some(state.clone()),
+            PlayerEvent::Progress(progress) => self.progress = Some(progress.clone()),
+            _ => {}
+        }
+    }
+}
+
+/// Carries player, queue and library events to any number of subscribers. Publishing
+/// never blocks, receivers that fall behind by more than the channel holds lose the
+/// oldest events.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<BusEvent>,
+    state: Arc<Mutex<StateSnapshot>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self {
+            sender,
+            state: Arc::new(Mutex::new(StateSnapshot::default())),
+        }
+    }
+
+    /// Numbers the event and sends it, returning its sequence number.
+    pub fn publish(&self, event: PlayerEvent) -> u64 {
+        // Sent under the lock, so that a subscriber's snapshot and first event line up
+        let mut state = self.state();
+        state.seq += 1;
+        state.apply(&event);
+        let seq = state.seq;
+        let _ = self.sender.send(BusEvent { seq, event });
+        seq
+    }
+
+    /// The state so far and the events after it.
+    pub fn subscribe(&self) -> (StateSnapshot, broadcast::Receiver<BusEvent>) {
+        let state = self.state();
+        (state.clone(), self.sender.subscribe())
+    }
+
+    pub fn snapshot(&self) -> StateSnapshot {
+        self.state().clone()
+    }
+
+    /// Only `publish` writes the snapshot, so a poisoned lock still holds a whole one.
+    fn state(&self) -> MutexGuard<'_, StateSnapshot> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[path = "./bus.tests.rs"]
+mod tests;
@@ -0,0 +1,90 @@
+use super::*;
+use crate::events::{
+    HistoryUpdateEvent, QueueChangedEvent, SpectrumEvent, StateChangedEvent, TrackChangedEvent,
+};
+use crate::playback::{PlaybackState, RepeatMode};
+use tokio::sync::broadcast::error::TryRecvError;
+
+fn track_changed(path: &str) -> PlayerEvent {
+    PlayerEvent::TrackChanged(Box::new(TrackChangedEvent {
+        track: Some(Track::new(path)),
+    }))
+}
+
+fn progress(position: f64) -> PlayerEvent {
+    PlayerEvent::Progress(ProgressEvent {
+        position,
+        frames_played: 0,
+        lyric_line: None,
+    })
+}
+
+#[test]
+fn test_every_subscriber_gets_every_event_in_order() {
+    let bus = EventBus::new();
+    let (_, mut first) = bus.subscribe();
+    let (_, mut second) = bus.subscribe();
+    assert_eq!(bus.publish(progress(0.1)), 1);
+    assert_eq!(bus.publish(progress(0.2)), 2);
+
+    for receiver in [&mut first, &mut second] {
+        let seqs: Vec<u64> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|event| event.seq)
+            .collect();
+        assert_eq!(seqs, [1, 2]);
+    }
+}
+
+#[test]
+fn test_late_subscribers_start_from_a_snapshot() {
+    let bus = EventBus::new();
+    let queue = vec![Track::new("/music/b.flac"), Track::new("/music/c.flac")];
+    bus.publish(PlayerEvent::QueueChanged(QueueChangedEvent {
+        queue: queue.clone(),
+    }));
+    bus.publish(progress(0.9));
+    bus.publish(track_changed("/music/a.flac"));
+    bus.publish(PlayerEvent::HistoryUpdate(HistoryUpdateEvent {
+        has_history: true,
+    }));
+    bus.publish(progress(0.25));
+    let player = StateChangedEvent {
+        state: PlaybackState::Playing,
+        volume: 0.5,
+        muted: false,
+        speed: 1.25,
+        pitch_semitones: 0.0,
+        repeat: RepeatMode::All,
+        shuffle: true,
+    };
+    bus.publish(PlayerEvent::StateChanged(player.clone()));
+
+    let (snapshot, mut events) = bus.subscribe();
+    assert_eq!(snapshot.seq, 6);
+    assert_eq!(snapshot.player, Some(player));
+    assert_eq!(snapshot.queue, queue);
+    assert_eq!(
+        snapshot.track.map(|track| track.path),
+        Some("/music/a.flac".into())
+    );
+    assert!(snapshot.has_history);
+    assert_eq!(snapshot.progress.map(|p| p.position), Some(0.25));
+
+    // A new track forgets the progress of the last one
+    bus.publish(track_changed("/music/b.flac"));
+    assert!(bus.snapshot().progress.is_none());
+    assert_eq!(events.try_recv().unwrap().seq, 7);
+}
+
+#[test]
+fn test_lagging_receivers_see_the_gap() {
+    let bus = EventBus::new();
+    let (_, mut events) = bus.subscribe();
+    for _ in 0..CAPACITY + 10 {
+        bus.publish(PlayerEvent::Spectrum(SpectrumEvent {
+            spectrum_data: Vec::new(),
+        }));
+    }
+    assert_eq!(events.try_recv().unwrap_err(), TryRecvError::Lagged(10));
+    assert_eq!(events.try_recv().unwrap().seq, 11);
+}
@@ -0,0 +1,92 @@
+use crate::playback::RepeatMode;
+use crate::time_stretch::{MAX_PITCH_SEMITONES, MAX_SPEED, MIN_SPEED};
+use serde::{Deserialize, Serialize};
+
+/// A float argument whose range is checked when the command is deserialized.
+macro_rules! bounded {
+    ($name:ident, $min:expr, $max:expr, $what:literal) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+        #[serde(try_from = "f32", into = "f32")]
+        pub struct $name(f32);
+
+        impl $name {
+            pub fn get(self) -> f32 {
+                self.0
+            }
+        }
+
+        impl From<$name> for f32 {
+            fn from(value: $name) -> f32 {
+                value.0
+            }
+        }
+
+        impl TryFrom<f32> for $name {
+            type Error = String;
+
+            fn try_from(value: f32) -> Result<Self, Self::Error> {
+                if ($min..=$max).contains(&value) {
+                    Ok($name(value))
+                } else {
+                    Err(format!(
+                        "{} must be between {} and {}, got {value}",
+                        $what, $min, $max
+                    ))
+                }
+            }
+        }
+    };
+}
+
+bounded!(Volume, 0.0, 1.0, "Volume");
+bounded!(Speed, MIN_SPEED, MAX_SPEED, "Playback speed");
+bounded!(
+    Semitones,
+    -MAX_PITCH_SEMITONES,
+    MAX_PITCH_SEMITONES,
+    "Pitch shift"
+);
+
+/// Commands accepted by `Playback::execute`, tagged by `type`, e.g.
+/// `{ "type": "SeekRelative", "deltaMs": -5000 }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+pub enum PlaybackCommand {
+    Play,
+    Pause,
+    TogglePlayPause,
+    Stop,
+    Next,
+    Previous,
+    /// Absolute position from the start of the track
+    Seek {
+        ms: u64,
+    },
+    /// Clamped to the bounds of the track
+    SeekRelative {
+        delta_ms: i64,
+    },
+    SetVolume {
+        volume: Volume,
+    },
+    /// Muting keeps the volume, restored when unmuted
+    SetMuted {
+        muted: bool,
+    },
+    SetSpeed {
+        speed: Speed,
+    },
+    SetPitch {
+        semitones: Semitones,
+    },
+    SetRepeat {
+        mode: RepeatMode,
+    },
+    SetShuffle {
+        shuffle: bool,
+    },
+}
+
+#[cfg(test)]
+#[path = "./command.tests.rs"]
+mod tests;
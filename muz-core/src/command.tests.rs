@@ -0,0 +1,64 @@
+use super::*;
+use serde_json::json;
+
+fn parse(value: serde_json::Value) -> Result<PlaybackCommand, serde_json::Error> {
+    serde_json::from_value(value)
+}
+
+#[test]
+fn test_commands_are_tagged_by_type() {
+    assert_eq!(
+        parse(json!({ "type": "Play" })).unwrap(),
+        PlaybackCommand::Play
+    );
+    assert_eq!(
+        parse(json!({ "type": "SeekRelative", "deltaMs": -5000 })).unwrap(),
+        PlaybackCommand::SeekRelative { delta_ms: -5000 }
+    );
+    assert_eq!(
+        parse(json!({ "type": "SetRepeat", "mode": "All" })).unwrap(),
+        PlaybackCommand::SetRepeat {
+            mode: RepeatMode::All
+        }
+    );
+}
+
+#[test]
+fn test_unknown_or_incomplete_commands_are_rejected() {
+    assert!(parse(json!({ "type": "Rewind" })).is_err());
+    assert!(parse(json!({ "type": "Seek" })).is_err());
+    assert!(parse(json!({ "type": "Seek", "ms": -1 })).is_err());
+    assert!(parse(json!({ "type": "SetMuted", "muted": "yes" })).is_err());
+}
+
+#[test]
+fn test_arguments_are_range_checked() {
+    assert_eq!(
+        parse(json!({ "type": "SetVolume", "volume": 0.5 })).unwrap(),
+        PlaybackCommand::SetVolume {
+            volume: Volume(0.5)
+        }
+    );
+    let error = parse(json!({ "type": "SetVolume", "volume": 1.5 })).unwrap_err();
+    assert!(error.to_string().contains("Volume must be between 0 and 1"));
+    assert!(parse(json!({ "type": "SetSpeed", "speed": 4.0 })).is_err());
+    assert!(parse(json!({ "type": "SetPitch", "semitones": -13.0 })).is_err());
+}
+
+#[test]
+fn test_commands_serialize_to_what_they_parse_from() {
+    for command in [
+        PlaybackCommand::Next,
+        PlaybackCommand::SeekRelative { delta_ms: -5000 },
+        PlaybackCommand::SetVolume {
+            volume: Volume(0.25),
+        },
+    ] {
+        let value = serde_json::to_value(&command).unwrap();
+        assert_eq!(parse(value).unwrap(), command);
+    }
+    assert_eq!(
+        serde_json::to_value(PlaybackCommand::SeekRelative { delta_ms: 10 }).unwrap(),
+        json!({ "type": "SeekRelative", "deltaMs": 10 })
+    );
+}
@@ -0,0 +1,330 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::driver::factory::{DefaultDriverFactory, DriverKind, PlaybackDriverFactory};
+use crate::driver::PlaybackDriver;
+use crate::dsp::DspSettings;
+use crate::equalizer::EqualizerSettings;
+use crate::error::{ErrorCode, LibraryError};
+use crate::normalization::NormalizationSettings;
+use crate::playback::Playback;
+
+/// The `identifier` of `tauri.conf.json`, naming the app data directory
+const APP_IDENTIFIER: &str = "com.muz.app";
+const CONFIG_FILE: &str = "config.json";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to get app data dir: {0}")]
+    AppDataDir(String),
+    #[error("Failed to read the config file")]
+    Read,
+    #[error("Failed to write the config file")]
+    Write,
+    #[error("The config file is invalid")]
+    Invalid,
+}
+
+impl ErrorCode for ConfigError {
+    fn code(&self) -> &'static str {
+        match self {
+            ConfigError::AppDataDir(_) => "CONFIG_DIR_UNAVAILABLE",
+            ConfigError::Read => "CONFIG_READ_FAILED",
+            ConfigError::Write => "CONFIG_WRITE_FAILED",
+            ConfigError::Invalid => "CONFIG_INVALID",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppConfig {
+    pub library_path: PathBuf,
+    #[serde(default)]
+    pub normalization: NormalizationSettings,
+    #[serde(default)]
+    pub equalizer: EqualizerSettings,
+    #[serde(default)]
+    pub dsp: DspSettings,
+    /// Output device name, `None` for the system default
+    #[serde(default)]
+    pub output_device: Option<String>,
+    /// Overridden by the `MUZ_PLAYBACK_DRIVER` environment variable
+    #[serde(default)]
+    pub playback_driver: DriverKind,
+    /// Address of the MPD protocol server, e.g. `127.0.0.1:6600`, `None` to disable it
+    #[serde(default)]
+    pub mpd_address: Option<String>,
+    /// The HTTP and WebSocket remote control API
+    #[serde(default)]
+    pub remote: RemoteSettings,
+    /// The Subsonic API for phone and desktop Subsonic clients
+    #[serde(default)]
+    pub subsonic: SubsonicSettings,
+    /// The UPnP media server for TVs and receivers
+    #[serde(default)]
+    pub dlna: DlnaSettings,
+    /// Where played tracks are submitted
+    #[serde(default)]
+    pub scrobbling: ScrobbleSettings,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            library_path: PathBuf::from("/System/Library/Sounds"),
+            normalization: NormalizationSettings::default(),
+            equalizer: EqualizerSettings::default(),
+            dsp: DspSettings::default(),
+            output_device: None,
+            playback_driver: DriverKind::default(),
+            mpd_address: None,
+            remote: RemoteSettings::default(),
+            subsonic: SubsonicSettings::default(),
+            dlna: DlnaSettings::default(),
+            scrobbling: ScrobbleSettings::default(),
+        }
+    }
+}
+
+/// The HTTP and WebSocket remote control API, served by the desktop app and the daemon.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteSettings {
+    pub enabled: bool,
+    pub address: String,
+    /// Required as a bearer token or `token` query parameter when set, and to listen on
+    /// anything but a loopback address
+    pub token: Option<String>,
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:7700".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// The Subsonic-compatible API, serving the library to Subsonic and OpenSubsonic clients.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubsonicSettings {
+    pub enabled: bool,
+    /// Loopback by default, clients on other machines need it changed
+    pub address: String,
+    /// The single account clients sign in with
+    pub username: String,
+    /// Required to serve the API. Token authentication needs it in the clear
+    pub password: Option<String>,
+}
+
+impl Default for SubsonicSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:4533".to_string(),
+            username: "muz".to_string(),
+            password: None,
+        }
+    }
+}
+
+/// The UPnP AV media server, announced over SSDP and browsed through its ContentDirectory.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DlnaSettings {
+    pub enabled: bool,
+    /// Where descriptions, browsing and media are served over HTTP
+    pub address: String,
+    /// The name renderers list the server under
+    pub name: String,
+}
+
+impl Default for DlnaSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "0.0.0.0:8200".to_string(),
+            name: "muz".to_string(),
+        }
+    }
+}
+
+/// The services played tracks are scrobbled to, each used when enabled.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrobbleSettings {
+    pub listenbrainz: ListenBrainzSettings,
+    pub lastfm: LastFmSettings,
+}
+
+/// A ListenBrainz account, or another server with the same API.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListenBrainzSettings {
+    pub enabled: bool,
+    /// The API root, without `/1/`
+    pub url: String,
+    /// The user token from the account settings, required
+    pub token: Option<String>,
+}
+
+impl Default for ListenBrainzSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "https://api.listenbrainz.org".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// A Last.fm account, or another server with the same API such as Libre.fm.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastFmSettings {
+    pub enabled: bool,
+    pub url: String,
+    /// The API account key and shared secret, required
+    pub api_key: Option<String>,
+    pub secret: Option<String>,
+    /// A session from the web authentication flow. Without it one is requested with the
+    /// username and password
+    pub session_key: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Default for LastFmSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "https://ws.audioscrobbler.com/2.0/".to_string(),
+            api_key: None,
+            secret: None,
+            session_key: None,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Reads the config in `data_dir`, creating it with the defaults when missing.
+    pub async fn load(data_dir: &Path) -> Result<Self> {
+        let config_path = data_dir.join(CONFIG_FILE);
+        if config_path.exists() {
+            let content = fs::read_to_string(&config_path)
+                .await
+                .context(ConfigError::Read)?;
+            let config: AppConfig = serde_json::from_str(&content).context(ConfigError::Invalid)?;
+            Ok(config)
+        } else {
+            let default_config = AppConfig::default();
+            default_config.save(data_dir).await?;
+            Ok(default_config)
+        }
+    }
+
+    pub async fn save(&self, data_dir: &Path) -> Result<()> {
+        fs::create_dir_all(data_dir)
+            .await
+            .context(ConfigError::Write)?;
+
+        let content = serde_json::to_string_pretty(self)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Only readable by the user, as it holds the passwords and tokens of the
+        // servers and scrobblers
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options
+            .open(data_dir.join(CONFIG_FILE))
+            .await
+            .context(ConfigError::Write)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            // Files saved before keep their mode when opened
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))
+                .await
+                .context(ConfigError::Write)?;
+        }
+        file.write_all(content.as_bytes())
+            .await
+            .context(ConfigError::Write)?;
+        Ok(())
+    }
+
+    /// The data directory of the desktop app, found without a running app.
+    pub fn default_data_dir() -> Result<PathBuf> {
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| ConfigError::AppDataDir("no data directory".to_string()))?;
+        Ok(data_dir.join(APP_IDENTIFIER))
+    }
+
+    /// The configured driver, discarding output instead when it can't be created.
+    pub fn create_driver(&self, volume: f32) -> Box<dyn PlaybackDriver> {
+        self.create_driver_with::<DefaultDriverFactory>(volume)
+    }
+
+    fn create_driver_with<F: PlaybackDriverFactory>(&self, volume: f32) -> Box<dyn PlaybackDriver> {
+        let driver_kind = DriverKind::resolve(&self.playback_driver);
+        tracing::info!("Using the {} playback driver", driver_kind.name());
+        F::create_driver(&driver_kind, volume)
+            .or_else(|e| {
+                tracing::error!(
+                    "Failed to create playback driver, discarding output instead: {e:#}"
+                );
+                F::create_driver(&DriverKind::Null { realtime: true }, volume)
+            })
+            .expect("Failed to create playback driver")
+    }
+
+    /// Applies the saved audio settings, logging the ones that fail.
+    pub fn apply_to(&self, playback: &mut Playback) {
+        if let Err(e) = playback.set_normalization(self.normalization.clone()) {
+            tracing::error!("Failed to apply normalization settings: {e}");
+        }
+        if let Err(e) = playback.set_equalizer(self.equalizer.clone()) {
+            tracing::error!("Failed to apply equalizer settings: {e}");
+        }
+        if let Err(e) = playback.set_dsp(self.dsp.clone()) {
+            tracing::error!("Failed to apply DSP settings: {e}");
+        }
+        if self.output_device.is_some() {
+            if let Err(e) = playback.set_output_device(self.output_device.clone()) {
+                tracing::error!("Failed to select output device: {e}");
+            }
+        }
+    }
+
+    pub fn update_library_path(&mut self, path: PathBuf) -> Result<()> {
+        if !path.exists() {
+            return Err(LibraryError::PathInvalid {
+                path,
+                reason: "does not exist",
+            }
+            .into());
+        }
+        if !path.is_dir() {
+            return Err(LibraryError::PathInvalid {
+                path,
+                reason: "is not a directory",
+            }
+            .into());
+        }
+        self.library_path = path;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "./config.tests.rs"]
+mod tests;
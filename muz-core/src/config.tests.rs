@@ -0,0 +1,53 @@
+use super::*;
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_save_keeps_the_config_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("muz-config-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(CONFIG_FILE), "{}").unwrap();
+    std::fs::set_permissions(
+        dir.join(CONFIG_FILE),
+        std::fs::Permissions::from_mode(0o644),
+    )
+    .unwrap();
+
+    let mut config = AppConfig::default();
+    config.subsonic.password = Some("secret".to_string());
+    config.save(&dir).await.unwrap();
+
+    let mode = std::fs::metadata(dir.join(CONFIG_FILE))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+    let saved = AppConfig::load(&dir).await.unwrap();
+    assert_eq!(saved.subsonic.password.as_deref(), Some("secret"));
+    assert_eq!(saved.subsonic.address, "127.0.0.1:4533");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// A machine without a sound card.
+struct HeadlessDriverFactory;
+
+impl PlaybackDriverFactory for HeadlessDriverFactory {
+    fn create_driver(kind: &DriverKind, volume: f32) -> Result<Box<dyn PlaybackDriver>> {
+        match kind {
+            DriverKind::Rodio => Err(anyhow::anyhow!("No default output device")),
+            kind => DefaultDriverFactory::create_driver(kind, volume),
+        }
+    }
+}
+
+#[test]
+fn test_driver_falls_back_to_discarding_the_output() {
+    let config = AppConfig {
+        playback_driver: DriverKind::Rodio,
+        ..AppConfig::default()
+    };
+    let driver = config.create_driver_with::<HeadlessDriverFactory>(1.0);
+    let devices = driver.output_devices().unwrap();
+    assert_eq!(devices[0].name, "Null output");
+}
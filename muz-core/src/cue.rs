@@ -0,0 +1,278 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, Tag};
+use symphonia::core::probe::Hint;
+use uuid::Uuid;
+
+use crate::track::{Track, TrackMetadata, TrackSegment, SUPPORTED_EXTENSIONS};
+
+/// Extensions of files that may carry an embedded `CUESHEET` tag.
+pub static EMBEDDED_CUE_EXTENSIONS: &[&str] = &["flac", "wav", "wave", "ape", "wv"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueSheet {
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub genre: Option<String>,
+    pub date: Option<String>,
+    pub files: Vec<CueFile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueFile {
+    pub path: String,
+    pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueTrack {
+    pub number: u32,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub start_ms: u64,
+}
+
+impl CueSheet {
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut sheet = CueSheet::default();
+
+        for (line_number, line) in content.lines().enumerate() {
+            let mut args = split_args(line.trim_start_matches('\u{feff}'));
+            if args.is_empty() {
+                continue;
+            }
+            let keyword = args.remove(0).to_uppercase();
+            let first = args.first().cloned();
+
+            match keyword.as_str() {
+                "FILE" => {
+                    let path = first.ok_or_else(|| anyhow!("Missing FILE path"))?;
+                    sheet.files.push(CueFile {
+                        path,
+                        tracks: Vec::new(),
+                    });
+                }
+                "TRACK" => {
+                    let file = sheet
+                        .files
+                        .last_mut()
+                        .ok_or_else(|| anyhow!("TRACK before FILE on line {}", line_number + 1))?;
+                    let number = first.and_then(|n| n.parse::<u32>().ok()).ok_or_else(|| {
+                        anyhow!("Invalid TRACK number on line {}", line_number + 1)
+                    })?;
+                    file.tracks.push(CueTrack {
+                        number,
+                        ..Default::default()
+                    });
+                }
+                "INDEX" => {
+                    // Only INDEX 01 marks the start of a track, INDEX 00 is the pregap
+                    if args.first().and_then(|n| n.parse::<u32>().ok()) != Some(1) {
+                        continue;
+                    }
+                    let start_ms = args
+                        .get(1)
+                        .and_then(|t| parse_timestamp(t))
+                        .ok_or_else(|| anyhow!("Invalid INDEX on line {}", line_number + 1))?;
+                    if let Some(track) = current_track(&mut sheet) {
+                        track.start_ms = start_ms;
+                    }
+                }
+                "TITLE" => match current_track(&mut sheet) {
+                    Some(track) => track.title = first,
+                    None => sheet.title = first,
+                },
+                "PERFORMER" => match current_track(&mut sheet) {
+                    Some(track) => track.performer = first,
+                    None => sheet.performer = first,
+                },
+                "REM" => match first.map(|k| k.to_uppercase()).as_deref() {
+                    Some("GENRE") => sheet.genre = args.get(1).cloned(),
+                    Some("DATE") => sheet.date = args.get(1).cloned(),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        if sheet.files.iter().all(|f| f.tracks.is_empty()) {
+            return Err(anyhow!("Cue sheet has no tracks"));
+        }
+        Ok(sheet)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::parse(&String::from_utf8_lossy(&bytes))
+    }
+
+    /// Reads the `CUESHEET` tag embedded in a single-file rip, if any.
+    pub fn read_embedded(path: &Path) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext_str) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext_str);
+        }
+
+        let mut probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .ok()?;
+
+        let find_cue_sheet = |tags: &[Tag]| {
+            tags.iter()
+                .find(|tag| tag.key.eq_ignore_ascii_case("CUESHEET"))
+                .map(|tag| tag.value.to_string())
+        };
+
+        let mut content = probed
+            .format
+            .metadata()
+            .skip_to_latest()
+            .and_then(|rev| find_cue_sheet(rev.tags()));
+        if content.is_none() {
+            if let Some(mut m) = probed.metadata.get() {
+                content = m
+                    .skip_to_latest()
+                    .and_then(|rev| find_cue_sheet(rev.tags()));
+            }
+        }
+
+        Self::parse(&content?).ok()
+    }
+
+    /// Resolves the audio file a `FILE` entry refers to. Rips are often re-encoded
+    /// without updating the sheet, so fall back to any supported file with the same stem.
+    pub fn resolve_file(cue_dir: &Path, file: &CueFile) -> Option<PathBuf> {
+        let path = cue_dir.join(&file.path);
+        if path.is_file() {
+            return Some(path);
+        }
+        SUPPORTED_EXTENSIONS
+            .iter()
+            .map(|ext| path.with_extension(ext))
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Splits a decoded source file into one virtual track per indexed cue track.
+    pub fn split_track(&self, file: &CueFile, source: &Track) -> Vec<Track> {
+        let frames_for = |ms: u64| {
+            (ms * source.total_frames)
+                .checked_div(source.duration_ms)
+                .unwrap_or(0)
+        };
+
+        file.tracks
+            .iter()
+            .enumerate()
+            .map(|(index, cue_track)| {
+                let end_ms = file.tracks.get(index + 1).map(|next| next.start_ms);
+                let duration_ms = end_ms
+                    .unwrap_or(source.duration_ms)
+                    .saturating_sub(cue_track.start_ms);
+
+                Track {
+                    id: Uuid::new_v4().to_string(),
+                    path: source.path.clone(),
+                    total_frames: frames_for(duration_ms),
+                    duration_ms,
+                    metadata: Some(self.track_metadata(cue_track, source)),
+                    segment: Some(TrackSegment {
+                        start_ms: cue_track.start_ms,
+                        end_ms,
+                    }),
+                    replay_gain: source.replay_gain,
+                    unplayable: false,
+                }
+            })
+            .collect()
+    }
+
+    fn track_metadata(&self, cue_track: &CueTrack, source: &Track) -> TrackMetadata {
+        let mut metadata = source.metadata.clone().unwrap_or(TrackMetadata {
+            title: None,
+            album: None,
+            artist: None,
+            album_artist: None,
+            track_number: None,
+            disc_number: None,
+            genre: None,
+            year: None,
+        });
+
+        metadata.title = cue_track
+            .title
+            .clone()
+            .or_else(|| Some(format!("Track {:02}", cue_track.number)));
+        metadata.track_number = Some(cue_track.number as i32);
+        if let Some(performer) = cue_track.performer.clone().or(self.performer.clone()) {
+            metadata.artist = Some(performer);
+        }
+        if self.performer.is_some() {
+            metadata.album_artist = self.performer.clone();
+        }
+        if self.title.is_some() {
+            metadata.album = self.title.clone();
+        }
+        if self.genre.is_some() {
+            metadata.genre = self.genre.clone();
+        }
+        if self.date.is_some() {
+            metadata.year = self.date.clone();
+        }
+        metadata
+    }
+}
+
+fn current_track(sheet: &mut CueSheet) -> Option<&mut CueTrack> {
+    sheet.files.last_mut().and_then(|f| f.tracks.last_mut())
+}
+
+/// Parses a `mm:ss:ff` timestamp (75 frames per second) into milliseconds.
+fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let mut parts = timestamp.split(':').map(|p| p.parse::<u64>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || seconds >= 60 || frames >= 75 {
+        return None;
+    }
+    Some((minutes * 60 + seconds) * 1000 + frames * 1000 / 75)
+}
+
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            args.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else {
+            let mut arg = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                arg.push(c);
+                chars.next();
+            }
+            args.push(arg);
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+#[path = "./cue.tests.rs"]
+mod tests;
@@ -0,0 +1,124 @@
+use super::*;
+
+const SHEET: &str = r#"REM GENRE "Progressive Rock"
+REM DATE 1973
+PERFORMER "Some Band"
+TITLE "Some Album"
+FILE "album.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "Opening"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Second Song"
+    PERFORMER "Guest Singer"
+    INDEX 00 03:58:50
+    INDEX 01 04:00:00
+  TRACK 03 AUDIO
+    INDEX 01 07:30:37
+"#;
+
+fn source_track() -> Track {
+    let mut track = Track::new("/music/album.flac");
+    track.duration_ms = 600_000;
+    track.total_frames = 600 * 44_100;
+    track
+}
+
+#[test]
+fn test_parse_sheet_metadata() {
+    let sheet = CueSheet::parse(SHEET).unwrap();
+    assert_eq!(sheet.title.as_deref(), Some("Some Album"));
+    assert_eq!(sheet.performer.as_deref(), Some("Some Band"));
+    assert_eq!(sheet.genre.as_deref(), Some("Progressive Rock"));
+    assert_eq!(sheet.date.as_deref(), Some("1973"));
+    assert_eq!(sheet.files.len(), 1);
+    assert_eq!(sheet.files[0].path, "album.flac");
+}
+
+#[test]
+fn test_parse_tracks() {
+    let sheet = CueSheet::parse(SHEET).unwrap();
+    let tracks = &sheet.files[0].tracks;
+    assert_eq!(tracks.len(), 3);
+    assert_eq!(tracks[0].title.as_deref(), Some("Opening"));
+    assert_eq!(tracks[0].start_ms, 0);
+    assert_eq!(tracks[1].performer.as_deref(), Some("Guest Singer"));
+    // INDEX 00 (pregap) is ignored in favour of INDEX 01
+    assert_eq!(tracks[1].start_ms, 240_000);
+    // 37 frames at 75 frames per second
+    assert_eq!(tracks[2].start_ms, 450_493);
+}
+
+#[test]
+fn test_parse_without_tracks_fails() {
+    let result = CueSheet::parse("TITLE \"Empty\"\nFILE \"album.flac\" WAVE\n");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_parse_track_before_file_fails() {
+    let result = CueSheet::parse("TRACK 01 AUDIO\n  INDEX 01 00:00:00\n");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_parse_timestamp() {
+    assert_eq!(parse_timestamp("00:00:00"), Some(0));
+    assert_eq!(parse_timestamp("01:02:15"), Some(62_200));
+    assert_eq!(parse_timestamp("80:00:00"), Some(4_800_000));
+    assert_eq!(parse_timestamp("00:60:00"), None);
+    assert_eq!(parse_timestamp("00:00:75"), None);
+    assert_eq!(parse_timestamp("00:00"), None);
+}
+
+#[test]
+fn test_split_track_segments() {
+    let sheet = CueSheet::parse(SHEET).unwrap();
+    let source = source_track();
+    let tracks = sheet.split_track(&sheet.files[0], &source);
+
+    assert_eq!(tracks.len(), 3);
+    assert!(tracks.iter().all(|t| t.path == source.path));
+
+    assert_eq!(
+        tracks[0].segment,
+        Some(TrackSegment {
+            start_ms: 0,
+            end_ms: Some(240_000)
+        })
+    );
+    assert_eq!(tracks[0].duration_ms, 240_000);
+    assert_eq!(tracks[0].total_frames, 240 * 44_100);
+
+    // The last track runs until the end of the file
+    assert_eq!(tracks[2].segment.as_ref().unwrap().end_ms, None);
+    assert_eq!(tracks[2].duration_ms, 600_000 - 450_493);
+}
+
+#[test]
+fn test_split_track_metadata() {
+    let sheet = CueSheet::parse(SHEET).unwrap();
+    let tracks = sheet.split_track(&sheet.files[0], &source_track());
+
+    let first = tracks[0].metadata.as_ref().unwrap();
+    assert_eq!(first.title.as_deref(), Some("Opening"));
+    assert_eq!(first.artist.as_deref(), Some("Some Band"));
+    assert_eq!(first.album.as_deref(), Some("Some Album"));
+    assert_eq!(first.album_artist.as_deref(), Some("Some Band"));
+    assert_eq!(first.track_number, Some(1));
+    assert_eq!(first.year.as_deref(), Some("1973"));
+
+    let second = tracks[1].metadata.as_ref().unwrap();
+    assert_eq!(second.artist.as_deref(), Some("Guest Singer"));
+
+    let third = tracks[2].metadata.as_ref().unwrap();
+    assert_eq!(third.title.as_deref(), Some("Track 03"));
+}
+
+#[test]
+fn test_split_track_unique_ids() {
+    let sheet = CueSheet::parse(SHEET).unwrap();
+    let tracks = sheet.split_track(&sheet.files[0], &source_track());
+    assert_ne!(tracks[0].id, tracks[1].id);
+    assert_ne!(tracks[1].id, tracks[2].id);
+}
@@ -0,0 +1,204 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::{Time, TimeBase};
+
+use crate::track::Track;
+
+/// Decodes a track (or its cue segment) into interleaved f32 blocks.
+pub struct TrackDecoder {
+    path: PathBuf,
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
+    sample_rate: u32,
+    channels: usize,
+    sample_buffer: Option<SampleBuffer<f32>>,
+    /// First frame of the file to emit: the segment start, or the last seek target
+    emit_from: u64,
+    start_frame: u64,
+    end_frame: Option<u64>,
+    finished: bool,
+}
+
+impl TrackDecoder {
+    pub fn open(track: &Track) -> Result<Self> {
+        let format = Self::open_format(&track.path)?;
+        let audio_track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| anyhow!("No audio track found"))?;
+        let track_id = audio_track.id;
+        let time_base = audio_track.codec_params.time_base;
+        let sample_rate = audio_track
+            .codec_params
+            .sample_rate
+            .ok_or_else(|| anyhow!("Unknown sample rate"))?;
+        let channels = audio_track
+            .codec_params
+            .channels
+            .map(|c| c.count())
+            .ok_or_else(|| anyhow!("Unknown channel layout"))?;
+        let decoder = symphonia::default::get_codecs()
+            .make(&audio_track.codec_params, &DecoderOptions::default())?;
+
+        let to_frames = |ms: u64| ms * sample_rate as u64 / 1000;
+        let start_frame = track.segment.as_ref().map_or(0, |s| to_frames(s.start_ms));
+        let end_frame = track.segment.as_ref().and_then(|s| s.end_ms).map(to_frames);
+
+        let mut decoder = Self {
+            path: track.path.clone(),
+            format,
+            decoder,
+            track_id,
+            time_base,
+            sample_rate,
+            channels,
+            sample_buffer: None,
+            emit_from: start_frame,
+            start_frame,
+            end_frame,
+            finished: false,
+        };
+        if start_frame > 0 {
+            decoder.seek(Duration::ZERO)?;
+        }
+        Ok(decoder)
+    }
+
+    fn open_format(path: &Path) -> Result<Box<dyn FormatReader>> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext_str) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext_str);
+        }
+
+        let probed = symphonia::default::get_probe().format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?;
+        Ok(probed.format)
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Seeks to `position`, relative to the start of the track. Files the demuxer can't seek
+    /// in, e.g. without a frame count or seek index, are decoded from the start instead.
+    pub fn seek(&mut self, position: Duration) -> Result<()> {
+        let target =
+            self.start_frame + position.as_millis() as u64 * self.sample_rate as u64 / 1000;
+        let time = Time::from(target as f64 / self.sample_rate as f64);
+        if let Err(e) = self.format.seek(
+            SeekMode::Accurate,
+            SeekTo::Time {
+                time,
+                track_id: Some(self.track_id),
+            },
+        ) {
+            tracing::debug!("Seeking {:?} by decoding from the start: {e}", self.path);
+            // Frames before `emit_from` are decoded and dropped by `next_block`
+            self.format = Self::open_format(&self.path)?;
+        }
+        self.decoder.reset();
+        self.emit_from = target;
+        self.finished = false;
+        Ok(())
+    }
+
+    /// Decodes the next block of samples, `None` once the track has ended.
+    pub fn next_block(&mut self) -> Result<Option<&[f32]>> {
+        let channels = self.channels;
+        let (from, to) = loop {
+            if self.finished {
+                return Ok(None);
+            }
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            let position = self.ts_to_frames(packet.ts());
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(e)) => {
+                    tracing::warn!("Skipping undecodable packet in {:?}: {e}", self.path);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            if self
+                .sample_buffer
+                .as_ref()
+                .is_none_or(|b| b.capacity() < decoded.capacity() * channels)
+            {
+                self.sample_buffer = Some(SampleBuffer::new(
+                    decoded.capacity() as u64,
+                    *decoded.spec(),
+                ));
+            }
+            let Some(buffer) = self.sample_buffer.as_mut() else {
+                continue;
+            };
+            buffer.copy_interleaved_ref(decoded);
+
+            let frames = (buffer.samples().len() / channels) as u64;
+            let from = self.emit_from.saturating_sub(position).min(frames) as usize;
+            let to = self
+                .end_frame
+                .map_or(frames, |end| end.saturating_sub(position).min(frames))
+                as usize;
+            if self.end_frame.is_some_and(|end| position + frames >= end) {
+                self.finished = true;
+            }
+            if from < to {
+                break (from, to);
+            }
+        };
+
+        Ok(self
+            .sample_buffer
+            .as_ref()
+            .map(|buffer| &buffer.samples()[from * channels..to * channels]))
+    }
+
+    fn ts_to_frames(&self, ts: u64) -> u64 {
+        match self.time_base {
+            Some(time_base) => {
+                let time = time_base.calc_time(ts);
+                ((time.seconds as f64 + time.frac) * self.sample_rate as f64).round() as u64
+            }
+            None => ts,
+        }
+    }
+}
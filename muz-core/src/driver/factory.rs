@@ -0,0 +1,107 @@
+use crate::driver::{
+    null::NullPlaybackDriver, render::Clock, wav::WavPlaybackDriver, PlaybackDriver,
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Environment variable overriding the configured driver, see `DriverKind::from_str`.
+pub const DRIVER_ENV_VAR: &str = "MUZ_PLAYBACK_DRIVER";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DriverKind {
+    /// The system sound card
+    #[default]
+    Rodio,
+    /// Discards the output, rendering in real time or as fast as possible
+    Null {
+        #[serde(default)]
+        realtime: bool,
+    },
+    /// Renders the output stream to a WAV file
+    Wav {
+        path: PathBuf,
+        #[serde(default)]
+        realtime: bool,
+    },
+}
+
+impl DriverKind {
+    /// The driver named in `MUZ_PLAYBACK_DRIVER` when set, the configured one otherwise.
+    pub fn resolve(configured: &DriverKind) -> DriverKind {
+        match std::env::var(DRIVER_ENV_VAR) {
+            Ok(value) => value.parse().unwrap_or_else(|e| {
+                tracing::error!("Ignoring {DRIVER_ENV_VAR}: {e}");
+                configured.clone()
+            }),
+            Err(_) => configured.clone(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DriverKind::Rodio => "rodio",
+            DriverKind::Null { .. } => "null",
+            DriverKind::Wav { .. } => "wav",
+        }
+    }
+}
+
+/// Parses `rodio`, `null`, `null:realtime`, `wav:<path>` or `wav:realtime:<path>`.
+impl FromStr for DriverKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (name, options) = value.split_once(':').unwrap_or((value, ""));
+        let (realtime, rest) = match options.strip_prefix("realtime:") {
+            Some(rest) => (true, rest),
+            None if options == "realtime" => (true, ""),
+            None => (false, options),
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "rodio" => Ok(DriverKind::Rodio),
+            "null" => Ok(DriverKind::Null { realtime }),
+            "wav" if !rest.is_empty() => Ok(DriverKind::Wav {
+                path: PathBuf::from(rest),
+                realtime,
+            }),
+            "wav" => Err(anyhow!(
+                "The wav driver needs an output path, as wav:<path>"
+            )),
+            other => Err(anyhow!("Unknown playback driver: {other}")),
+        }
+    }
+}
+
+fn clock(realtime: bool) -> Clock {
+    if realtime {
+        Clock::RealTime
+    } else {
+        Clock::Virtual
+    }
+}
+
+pub trait PlaybackDriverFactory {
+    fn create_driver(kind: &DriverKind, volume: f32) -> Result<Box<dyn PlaybackDriver>>;
+}
+
+pub struct DefaultDriverFactory;
+
+impl PlaybackDriverFactory for DefaultDriverFactory {
+    fn create_driver(kind: &DriverKind, volume: f32) -> Result<Box<dyn PlaybackDriver>> {
+        use crate::driver::rodio::RodioPlaybackDriver;
+        Ok(match kind {
+            DriverKind::Rodio => Box::new(RodioPlaybackDriver::new(volume)?),
+            DriverKind::Null { realtime } => {
+                Box::new(NullPlaybackDriver::new(clock(*realtime), volume))
+            }
+            DriverKind::Wav { path, realtime } => Box::new(WavPlaybackDriver::new(
+                path.clone(),
+                clock(*realtime),
+                volume,
+            )),
+        })
+    }
+}
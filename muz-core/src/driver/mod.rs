@@ -0,0 +1,74 @@
+pub mod factory;
+pub mod null;
+pub mod position;
+pub mod render;
+pub mod rodio;
+pub mod wav;
+
+use crate::{
+    dsp::DspSettings, equalizer::EqualizerSettings, playback::PlaybackEvent, track::Track,
+};
+use anyhow::Result;
+use position::PlaybackPosition;
+use serde::Serialize;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDevice {
+    pub name: String,
+    pub is_default: bool,
+    pub channels: Vec<u16>,
+    pub sample_rates: Vec<u32>,
+    pub sample_formats: Vec<String>,
+}
+
+pub trait PlaybackDriver: Send {
+    /// Start playing a track with progress events sent to the given channel
+    fn play(&mut self, track: Track, progress_sender: Sender<PlaybackEvent>) -> Result<()>;
+
+    /// Pause current playback
+    fn pause(&mut self) -> Result<()>;
+
+    /// Resume current playback
+    fn resume(&mut self) -> Result<()>;
+
+    /// Clear/stop current playback
+    fn clear(&mut self) -> Result<()>;
+
+    /// Set playback volume (0.0 to 1.0)
+    fn set_volume(&mut self, volume: f32) -> Result<()>;
+
+    /// Set the loudness normalization gain (linear factor) applied on top of the volume
+    fn set_gain(&mut self, gain: f32) -> Result<()>;
+
+    /// Replace the equalizer settings, applied live to the playing track
+    fn set_equalizer(&mut self, settings: EqualizerSettings) -> Result<()>;
+
+    /// Set the playback rate (1.0 is normal speed), keeping the pitch unchanged
+    fn set_speed(&mut self, speed: f32) -> Result<()>;
+
+    /// Shift the pitch by the given number of semitones, independently of the speed
+    fn set_pitch(&mut self, semitones: f32) -> Result<()>;
+
+    /// Enable, disable and reorder the DSP stages between the decoder and the output
+    fn set_dsp(&mut self, settings: DspSettings) -> Result<()>;
+
+    /// Seek to a specific position in the current track
+    fn seek(&mut self, position: Duration) -> Result<()>;
+
+    /// The position in the current track as heard, net of output buffering
+    fn position(&self) -> PlaybackPosition;
+
+    /// List the available audio output devices
+    fn output_devices(&self) -> Result<Vec<OutputDevice>>;
+
+    /// Switch to the named output device, or the system default with `None`, keeping the
+    /// current position. Falling back to the default device is reported on `event_sender`.
+    fn set_output_device(
+        &mut self,
+        device: Option<String>,
+        event_sender: Sender<PlaybackEvent>,
+    ) -> Result<()>;
+}
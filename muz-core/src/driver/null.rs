@@ -0,0 +1,33 @@
+use anyhow::Result;
+
+use crate::driver::{
+    render::{Clock, RenderDriver, RenderOutput},
+    OutputDevice,
+};
+
+/// Discards the output stream. Decoding, DSP and playback events work as with a sound card.
+pub struct NullOutput;
+
+impl RenderOutput for NullOutput {
+    fn device(&self) -> OutputDevice {
+        OutputDevice {
+            name: "Null output".to_string(),
+            is_default: true,
+            channels: Vec::new(),
+            sample_rates: Vec::new(),
+            sample_formats: vec!["f32".to_string()],
+        }
+    }
+
+    fn write(&mut self, _samples: &[f32], _sample_rate: u32, _channels: usize) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub type NullPlaybackDriver = RenderDriver<NullOutput>;
+
+impl NullPlaybackDriver {
+    pub fn new(clock: Clock, volume: f32) -> Self {
+        Self::with_output(NullOutput, clock, volume)
+    }
+}
@@ -0,0 +1,77 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Position in the current track as heard, in milliseconds and source frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackPosition {
+    pub ms: u64,
+    pub frames: u64,
+}
+
+impl PlaybackPosition {
+    pub fn from_frames(frames: u64, sample_rate: u32) -> Self {
+        Self {
+            ms: (frames * 1000).checked_div(sample_rate as u64).unwrap_or(0),
+            frames,
+        }
+    }
+}
+
+/// Shared between a driver and the thread producing its audio, which advances it as source
+/// frames are handed to the output.
+#[derive(Debug, Default)]
+pub struct PositionTracker {
+    /// Frames handed to the output
+    frames: AtomicU64,
+    /// Frames handed over but still buffered ahead of the speaker
+    latency_frames: AtomicU64,
+    /// Seek target, the position never reads earlier while the buffer refills
+    floor_frames: AtomicU64,
+    sample_rate: AtomicU32,
+}
+
+impl PositionTracker {
+    /// Starts a new track at its beginning.
+    pub fn reset(&self, sample_rate: u32) {
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+        self.seek_to(0);
+    }
+
+    pub fn seek_to(&self, frames: u64) {
+        self.floor_frames.store(frames, Ordering::Relaxed);
+        self.frames.store(frames, Ordering::Relaxed);
+    }
+
+    /// Seeks at the sample rate of the current track.
+    pub fn seek_to_time(&self, position: Duration) {
+        let frames = position.as_millis() as u64 * self.sample_rate() as u64 / 1000;
+        self.seek_to(frames);
+    }
+
+    pub fn advance_to(&self, frames: u64) {
+        self.frames.store(frames, Ordering::Relaxed);
+    }
+
+    pub fn set_latency(&self, frames: u64) {
+        self.latency_frames.store(frames, Ordering::Relaxed);
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate.load(Ordering::Relaxed)
+    }
+
+    pub fn position(&self) -> PlaybackPosition {
+        let heard = self
+            .frames
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.latency_frames.load(Ordering::Relaxed));
+        let frames = heard.max(self.floor_frames.load(Ordering::Relaxed));
+        PlaybackPosition::from_frames(frames, self.sample_rate())
+    }
+}
+
+#[cfg(test)]
+#[path = "./position.tests.rs"]
+mod tests;
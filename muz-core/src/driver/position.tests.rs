@@ -0,0 +1,41 @@
+use super::*;
+
+#[test]
+fn test_position_excludes_buffered_frames() {
+    let tracker = PositionTracker::default();
+    tracker.reset(48_000);
+    tracker.set_latency(4_800);
+    tracker.advance_to(96_000);
+
+    assert_eq!(
+        tracker.position(),
+        PlaybackPosition {
+            ms: 1_900,
+            frames: 91_200
+        }
+    );
+}
+
+#[test]
+fn test_position_does_not_go_back_after_seeking() {
+    let tracker = PositionTracker::default();
+    tracker.reset(44_100);
+    tracker.set_latency(1_024);
+    tracker.seek_to(441_000);
+    assert_eq!(tracker.position().ms, 10_000);
+
+    tracker.advance_to(441_512);
+    assert_eq!(tracker.position().frames, 441_000);
+    tracker.advance_to(442_124);
+    assert_eq!(tracker.position().frames, 441_100);
+}
+
+#[test]
+fn test_reset_starts_over() {
+    let tracker = PositionTracker::default();
+    tracker.reset(44_100);
+    tracker.advance_to(44_100);
+    tracker.reset(48_000);
+
+    assert_eq!(tracker.position(), PlaybackPosition::default());
+}
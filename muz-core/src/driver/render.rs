@@ -0,0 +1,433 @@
+use anyhow::{anyhow, Result};
+use std::marker::PhantomData;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{
+    decoder::TrackDecoder,
+    driver::{
+        position::{PlaybackPosition, PositionTracker},
+        OutputDevice, PlaybackDriver,
+    },
+    dsp::{
+        processors::{EqualizerProcessor, GainProcessor, SpectrumProcessor},
+        DspChain, DspSettings,
+    },
+    equalizer::EqualizerSettings,
+    playback::PlaybackEvent,
+    time_stretch::TimeStretcher,
+    track::Track,
+};
+
+/// Progress and spectrum are reported every this many milliseconds of audio.
+const PROGRESS_INTERVAL_MS: u64 = 100;
+
+/// Destination of the output stream for drivers that render it themselves.
+pub trait RenderOutput: Send + 'static {
+    /// Describes the output, as listed by `output_devices`
+    fn device(&self) -> OutputDevice;
+
+    /// Receives the final output stream, after DSP, time stretching and volume
+    fn write(&mut self, samples: &[f32], sample_rate: u32, channels: usize) -> Result<()>;
+
+    /// Called whenever the stream stops: at the end of a track, on pause and on clear
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clock {
+    /// Paced like a real device, one second of audio per second
+    RealTime,
+    /// As fast as decoding allows; positions and events follow the audio time
+    Virtual,
+}
+
+enum RenderCommand {
+    Play(Track, Sender<PlaybackEvent>),
+    Pause,
+    Resume,
+    Clear,
+    SetVolume(f32),
+    SetGain(f32),
+    SetEqualizer(EqualizerSettings),
+    SetDsp(DspSettings),
+    SetSpeed(f32),
+    SetPitch(f32),
+    Seek(Duration),
+    Exit,
+}
+
+/// Decodes and processes tracks on its own thread, like the rodio driver, and hands the result
+/// to a `RenderOutput` instead of a sound card.
+pub struct RenderDriver<O: RenderOutput> {
+    command_sender: Sender<RenderCommand>,
+    device: OutputDevice,
+    position: Arc<PositionTracker>,
+    _output: PhantomData<fn() -> O>,
+}
+
+impl<O: RenderOutput> RenderDriver<O> {
+    pub fn with_output(output: O, clock: Clock, volume: f32) -> Self {
+        let (command_sender, command_receiver) = mpsc::channel();
+        let device = output.device();
+        let position = Arc::new(PositionTracker::default());
+        let renderer = Renderer::new(output, clock, volume, position.clone());
+        thread::spawn(move || renderer.run(command_receiver));
+        Self {
+            command_sender,
+            device,
+            position,
+            _output: PhantomData,
+        }
+    }
+
+    fn send(&self, command: RenderCommand) -> Result<()> {
+        self.command_sender
+            .send(command)
+            .map_err(|e| anyhow!("Failed to send command to render thread: {}", e))
+    }
+}
+
+impl<O: RenderOutput> PlaybackDriver for RenderDriver<O> {
+    fn play(&mut self, track: Track, progress_sender: Sender<PlaybackEvent>) -> Result<()> {
+        self.position.seek_to(0);
+        self.send(RenderCommand::Play(track, progress_sender))
+    }
+
+    fn pause(&mut self) -> Result<()> {
+        self.send(RenderCommand::Pause)
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        self.send(RenderCommand::Resume)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.position.seek_to(0);
+        self.send(RenderCommand::Clear)
+    }
+
+    fn set_volume(&mut self, volume: f32) -> Result<()> {
+        self.send(RenderCommand::SetVolume(volume))
+    }
+
+    fn set_gain(&mut self, gain: f32) -> Result<()> {
+        self.send(RenderCommand::SetGain(gain))
+    }
+
+    fn set_equalizer(&mut self, settings: EqualizerSettings) -> Result<()> {
+        self.send(RenderCommand::SetEqualizer(settings))
+    }
+
+    fn set_speed(&mut self, speed: f32) -> Result<()> {
+        self.send(RenderCommand::SetSpeed(speed))
+    }
+
+    fn set_pitch(&mut self, semitones: f32) -> Result<()> {
+        self.send(RenderCommand::SetPitch(semitones))
+    }
+
+    fn set_dsp(&mut self, settings: DspSettings) -> Result<()> {
+        self.send(RenderCommand::SetDsp(settings))
+    }
+
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        self.position.seek_to_time(position);
+        self.send(RenderCommand::Seek(position))
+    }
+
+    fn position(&self) -> PlaybackPosition {
+        self.position.position()
+    }
+
+    fn output_devices(&self) -> Result<Vec<OutputDevice>> {
+        Ok(vec![self.device.clone()])
+    }
+
+    fn set_output_device(
+        &mut self,
+        device: Option<String>,
+        event_sender: Sender<PlaybackEvent>,
+    ) -> Result<()> {
+        if let Some(name) = device.filter(|name| *name != self.device.name) {
+            let reason = format!("Only {} is available", self.device.name);
+            let _ = event_sender.send(PlaybackEvent::OutputDeviceFallback(name, reason));
+        }
+        Ok(())
+    }
+}
+
+impl<O: RenderOutput> Drop for RenderDriver<O> {
+    fn drop(&mut self) {
+        let _ = self.command_sender.send(RenderCommand::Exit);
+    }
+}
+
+struct ActiveTrack {
+    track: Track,
+    progress_sender: Sender<PlaybackEvent>,
+    decoder: TrackDecoder,
+    stretcher: TimeStretcher,
+    /// Position in the track, in source frames
+    frames_played: u64,
+    next_report: u64,
+    /// Output samples to drop so the chain latency doesn't delay the track
+    skip_samples: usize,
+}
+
+/// State owned by the render thread.
+struct Renderer<O: RenderOutput> {
+    output: O,
+    clock: Clock,
+    volume: f32,
+    chain: DspChain,
+    speed: f32,
+    pitch_semitones: f32,
+    current: Option<ActiveTrack>,
+    position: Arc<PositionTracker>,
+    paused: bool,
+    /// Real-time pacing: when the clock started, and the output frames written since
+    clock_start: Instant,
+    clock_frames: u64,
+    block: Vec<f32>,
+    stretched: Vec<f32>,
+}
+
+impl<O: RenderOutput> Renderer<O> {
+    fn new(output: O, clock: Clock, volume: f32, position: Arc<PositionTracker>) -> Self {
+        Self {
+            output,
+            clock,
+            volume: volume.clamp(0.0, 1.0),
+            chain: DspChain::from_settings(&DspSettings::default()),
+            speed: 1.0,
+            pitch_semitones: 0.0,
+            current: None,
+            position,
+            paused: false,
+            clock_start: Instant::now(),
+            clock_frames: 0,
+            block: Vec::new(),
+            stretched: Vec::new(),
+        }
+    }
+
+    fn run(mut self, command_receiver: Receiver<RenderCommand>) {
+        loop {
+            let command = if self.current.is_some() && !self.paused {
+                match command_receiver.try_recv() {
+                    Ok(command) => Some(command),
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            } else {
+                match command_receiver.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => break,
+                }
+            };
+            match command {
+                Some(RenderCommand::Exit) => break,
+                Some(command) => self.handle(command),
+                None => self.render_block(),
+            }
+        }
+        self.flush();
+    }
+
+    fn handle(&mut self, command: RenderCommand) {
+        match command {
+            RenderCommand::Play(track, progress_sender) => {
+                self.current = None;
+                match TrackDecoder::open(&track) {
+                    Ok(decoder) => {
+                        let stretcher =
+                            TimeStretcher::new(decoder.sample_rate(), decoder.channels());
+                        self.position.reset(decoder.sample_rate());
+                        self.current = Some(ActiveTrack {
+                            track,
+                            progress_sender,
+                            decoder,
+                            stretcher,
+                            frames_played: 0,
+                            next_report: 0,
+                            skip_samples: 0,
+                        });
+                        self.rewind();
+                    }
+                    Err(e) => {
+                        let _ = progress_sender.send(PlaybackEvent::FailedOpeningFile(track, e));
+                    }
+                }
+            }
+            RenderCommand::Pause => {
+                self.paused = true;
+                self.flush();
+            }
+            RenderCommand::Resume => {
+                self.paused = false;
+                self.reset_clock();
+            }
+            RenderCommand::Clear => {
+                self.current = None;
+                self.position.seek_to(0);
+                self.flush();
+            }
+            RenderCommand::SetVolume(volume) => self.volume = volume.clamp(0.0, 1.0),
+            RenderCommand::SetGain(factor) => {
+                self.chain.stage_mut::<GainProcessor>().set_gain(factor);
+            }
+            RenderCommand::SetEqualizer(settings) => {
+                self.chain
+                    .stage_mut::<EqualizerProcessor>()
+                    .set_settings(settings);
+            }
+            RenderCommand::SetDsp(settings) => self.chain.apply_settings(&settings),
+            RenderCommand::SetSpeed(speed) => self.speed = speed,
+            RenderCommand::SetPitch(semitones) => self.pitch_semitones = semitones,
+            RenderCommand::Seek(position) => {
+                let Some(active) = self.current.as_mut() else {
+                    return;
+                };
+                match active.decoder.seek(position) {
+                    Ok(_) => {
+                        let sample_rate = active.decoder.sample_rate() as u64;
+                        active.frames_played = position.as_millis() as u64 * sample_rate / 1000;
+                        active.next_report = active.frames_played;
+                        self.position.seek_to(active.frames_played);
+                        self.rewind();
+                    }
+                    Err(e) => tracing::error!("Failed to seek: {:?}", e),
+                }
+            }
+            RenderCommand::Exit => {}
+        }
+    }
+
+    /// Clears the DSP and time stretch state after starting or seeking.
+    fn rewind(&mut self) {
+        self.chain.reset();
+        if let Some(active) = self.current.as_mut() {
+            active.skip_samples = self.chain.latency_frames() * active.decoder.channels();
+            active.stretcher.reset();
+        }
+        self.reset_clock();
+    }
+
+    fn reset_clock(&mut self) {
+        self.clock_start = Instant::now();
+        self.clock_frames = 0;
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.output.flush() {
+            tracing::error!("Failed to flush output: {e}");
+        }
+    }
+
+    fn render_block(&mut self) {
+        let Some(active) = self.current.as_mut() else {
+            return;
+        };
+        let sample_rate = active.decoder.sample_rate();
+        let channels = active.decoder.channels();
+
+        self.block.clear();
+        match active.decoder.next_block() {
+            Ok(Some(samples)) => self.block.extend_from_slice(samples),
+            Ok(None) => {}
+            Err(e) => {
+                let error = e.context(format!("Failed to decode {}", active.track.path.display()));
+                let _ = active
+                    .progress_sender
+                    .send(PlaybackEvent::FailedOpeningFile(
+                        active.track.clone(),
+                        error,
+                    ));
+                self.current = None;
+                self.flush();
+                return;
+            }
+        }
+        let decoded_frames = (self.block.len() / channels) as u64;
+        let ended = self.block.is_empty();
+        if ended {
+            // Push silence through so delayed samples still reach the output
+            self.block
+                .resize(self.chain.latency_frames() * channels, 0.0);
+        }
+        self.chain.process(&mut self.block, sample_rate, channels);
+        let skipped = active.skip_samples.min(self.block.len());
+        active.skip_samples -= skipped;
+
+        active.stretcher.set_speed(self.speed);
+        active.stretcher.set_pitch_semitones(self.pitch_semitones);
+        self.stretched.clear();
+        active
+            .stretcher
+            .process(&self.block[skipped..], &mut self.stretched);
+        if ended {
+            active.stretcher.finish(&mut self.stretched);
+        }
+        if self.volume != 1.0 {
+            self.stretched.iter_mut().for_each(|s| *s *= self.volume);
+        }
+
+        let written = self.output.write(&self.stretched, sample_rate, channels);
+        if let Err(e) = &written {
+            tracing::error!("Failed to write output for {:?}: {e}", active.track.path);
+        }
+
+        active.frames_played += decoded_frames;
+        self.position.advance_to(active.frames_played);
+        if active.frames_played >= active.next_report {
+            active.next_report =
+                active.frames_played + PROGRESS_INTERVAL_MS * sample_rate as u64 / 1000;
+            let total_frames = active.track.total_frames;
+            let percent_completed = if total_frames > 0 {
+                active.frames_played as f64 / total_frames as f64
+            } else {
+                0.0
+            };
+            let percent_completed = (percent_completed * 1000.0).round() / 1000.0;
+            let _ = active.progress_sender.send(PlaybackEvent::Progress(
+                percent_completed,
+                active.frames_played,
+            ));
+            let spectrum = self.chain.stage_mut::<SpectrumProcessor>().spectrum();
+            let spectrum_data = spectrum.lock().map(|s| s.clone()).unwrap_or_default();
+            let _ = active
+                .progress_sender
+                .send(PlaybackEvent::Spectrum(spectrum_data));
+        }
+
+        if ended || written.is_err() {
+            let progress_sender = active.progress_sender.clone();
+            self.current = None;
+            self.flush();
+            let _ = progress_sender.send(PlaybackEvent::TrackCompleted);
+            return;
+        }
+        self.pace(self.stretched.len() / channels, sample_rate);
+    }
+
+    fn pace(&mut self, frames: usize, sample_rate: u32) {
+        if self.clock != Clock::RealTime {
+            return;
+        }
+        self.clock_frames += frames as u64;
+        let due = Duration::from_secs_f64(self.clock_frames as f64 / sample_rate as f64);
+        let elapsed = self.clock_start.elapsed();
+        if due > elapsed {
+            thread::sleep(due - elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./render.tests.rs"]
+mod tests;
@@ -0,0 +1,163 @@
+use super::*;
+use crate::decoder::TrackDecoder;
+use crate::driver::{null::NullPlaybackDriver, wav::WavOutput, wav::WavPlaybackDriver};
+use std::f32::consts::PI;
+use std::path::PathBuf;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+fn temp_wav(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("muz-{name}-{}.wav", uuid::Uuid::new_v4()))
+}
+
+/// Writes one second of a stereo 440 Hz sine.
+fn sine_track() -> Track {
+    let samples: Vec<f32> = (0..SAMPLE_RATE)
+        .flat_map(|i| {
+            let s = 0.25 * (2.0 * PI * 440.0 * i as f32 / SAMPLE_RATE as f32).sin();
+            [s, s]
+        })
+        .collect();
+    let path = temp_wav("sine");
+    let mut output = WavOutput::new(&path);
+    output.write(&samples, SAMPLE_RATE, 2).unwrap();
+    drop(output);
+    Track::new(path)
+}
+
+fn decode(path: &std::path::Path) -> Vec<f32> {
+    let mut decoder = TrackDecoder::open(&Track::new(path)).unwrap();
+    let mut samples = Vec::new();
+    while let Some(block) = decoder.next_block().unwrap() {
+        samples.extend_from_slice(block);
+    }
+    samples
+}
+
+/// Collects events until the track completes or fails.
+fn play_to_end(driver: &mut dyn PlaybackDriver, track: Track) -> Vec<PlaybackEvent> {
+    let (sender, receiver) = mpsc::channel();
+    driver.play(track, sender).unwrap();
+    let mut events = Vec::new();
+    while let Ok(event) = receiver.recv_timeout(Duration::from_secs(10)) {
+        let completed = matches!(
+            event,
+            PlaybackEvent::TrackCompleted | PlaybackEvent::FailedOpeningFile(..)
+        );
+        events.push(event);
+        if completed {
+            break;
+        }
+    }
+    events
+}
+
+fn progress(events: &[PlaybackEvent]) -> Vec<(f64, u64)> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            PlaybackEvent::Progress(percent, frames) => Some((*percent, *frames)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_null_driver_decodes_and_reports_progress() {
+    let track = sine_track();
+    let mut driver = NullPlaybackDriver::new(Clock::Virtual, 1.0);
+    let events = play_to_end(&mut driver, track.clone());
+
+    assert!(matches!(events.last(), Some(PlaybackEvent::TrackCompleted)));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, PlaybackEvent::Spectrum(s) if !s.is_empty())));
+    let progress = progress(&events);
+    // One report per 100 ms of audio
+    assert!(progress.len() >= 9, "{} progress events", progress.len());
+    assert!(progress.windows(2).all(|w| w[1].1 > w[0].1));
+    let (percent, frames) = progress.last().copied().unwrap();
+    assert!(percent > 0.9);
+    assert!(frames <= track.total_frames);
+    let _ = std::fs::remove_file(&track.path);
+}
+
+#[test]
+fn test_wav_driver_renders_the_output_stream() {
+    let track = sine_track();
+    let path = temp_wav("render");
+    let mut driver = WavPlaybackDriver::new(&path, Clock::Virtual, 0.5);
+    driver
+        .set_dsp(DspSettings {
+            stages: Vec::new(),
+            ..Default::default()
+        })
+        .unwrap();
+    play_to_end(&mut driver, track.clone());
+
+    let input = decode(&track.path);
+    let rendered = decode(&path);
+    assert_eq!(rendered.len(), input.len());
+    assert!(rendered
+        .iter()
+        .zip(&input)
+        .all(|(r, i)| (r - i * 0.5).abs() < 1e-6));
+    let _ = std::fs::remove_file(&track.path);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_chain_latency_does_not_shift_the_output() {
+    let track = sine_track();
+    let path = temp_wav("latency");
+    let mut driver = WavPlaybackDriver::new(&path, Clock::Virtual, 1.0);
+    play_to_end(&mut driver, track.clone());
+
+    // The default chain includes the look-ahead limiter
+    let input = decode(&track.path);
+    let rendered = decode(&path);
+    assert_eq!(rendered.len(), input.len());
+    assert!(rendered
+        .iter()
+        .zip(&input)
+        .all(|(r, i)| (r - i).abs() < 1e-3));
+    let _ = std::fs::remove_file(&track.path);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_seek_moves_progress_to_the_position() {
+    let track = sine_track();
+    let mut driver = NullPlaybackDriver::new(Clock::RealTime, 1.0);
+    let (sender, receiver) = mpsc::channel();
+    driver.play(track.clone(), sender).unwrap();
+    driver.seek(Duration::from_millis(800)).unwrap();
+
+    let mut frames_after_seek = Vec::new();
+    while let Ok(event) = receiver.recv_timeout(Duration::from_secs(10)) {
+        match event {
+            PlaybackEvent::Progress(_, frames) => frames_after_seek.push(frames),
+            PlaybackEvent::TrackCompleted => break,
+            _ => {}
+        }
+    }
+    // Only the last 200 ms are left to play
+    assert!(frames_after_seek.len() <= 4);
+    assert!(frames_after_seek
+        .iter()
+        .skip(1)
+        .all(|&frames| frames >= SAMPLE_RATE as u64 * 8 / 10));
+    let _ = std::fs::remove_file(&track.path);
+}
+
+#[test]
+fn test_missing_file_is_reported() {
+    let mut driver = NullPlaybackDriver::new(Clock::Virtual, 1.0);
+    let track = Track::new(temp_wav("missing"));
+    let events = play_to_end(&mut driver, track.clone());
+
+    assert!(matches!(
+        events.as_slice(),
+        [PlaybackEvent::FailedOpeningFile(failed, _)] if failed.id == track.id
+    ));
+}
@@ -0,0 +1,1009 @@
+pub mod rodio_impl {
+    use anyhow::{anyhow, Context, Result};
+    use rodio::cpal::{
+        self,
+        traits::{DeviceTrait, HostTrait, StreamTrait},
+        FromSample, OutputCallbackInfo, SizedSample,
+    };
+    use rodio::dynamic_mixer::{self, DynamicMixer, DynamicMixerController};
+    use rodio::source::SeekError;
+    use rodio::{Decoder, Sink, Source};
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::mpsc::{RecvTimeoutError, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::{
+        driver::{
+            position::{PlaybackPosition, PositionTracker},
+            OutputDevice, PlaybackDriver,
+        },
+        dsp::{
+            processors::{EqualizerProcessor, GainProcessor, SpectrumProcessor},
+            DspChain, DspSettings,
+        },
+        equalizer::EqualizerSettings,
+        playback::PlaybackEvent,
+        time_stretch::TimeStretcher,
+        track::Track,
+    };
+
+    enum AudioCommand {
+        Play(Track, Sender<PlaybackEvent>),
+        Pause,
+        Resume,
+        Clear,
+        SetVolume(f32),
+        SetGain(f32),
+        SetEqualizer(EqualizerSettings),
+        SetDsp(DspSettings),
+        SetSpeed(f32),
+        SetPitch(f32),
+        SetOutputDevice(Option<String>, Sender<PlaybackEvent>),
+        Seek(Duration),
+        Exit,
+    }
+
+    const DSP_BLOCK_FRAMES: usize = 512;
+
+    const COMMON_SAMPLE_RATES: [u32; 6] = [44_100, 48_000, 88_200, 96_000, 176_400, 192_000];
+
+    /// How often the audio thread checks that the selected output device is still there.
+    const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+    /// How often the playing source reports its progress and spectrum.
+    const PROGRESS_INTERVAL_MS: u64 = 100;
+
+    pub struct RodioPlaybackDriver {
+        command_sender: Sender<AudioCommand>,
+        position: Arc<PositionTracker>,
+    }
+
+    impl RodioPlaybackDriver {
+        /// Fails when the default output can't be opened, so that callers can play
+        /// without a sound card instead.
+        pub fn new(volume: f32) -> Result<Self> {
+            use std::sync::mpsc;
+            let (command_sender, command_receiver) = mpsc::channel();
+            let (opened_sender, opened_receiver) = mpsc::channel();
+            let position = Arc::new(PositionTracker::default());
+            let audio_position = position.clone();
+
+            // NOTE: Cpal Backend is not Send, using a dedicated thread as a workaround
+            thread::spawn(move || {
+                let mut audio = match AudioThread::new(volume, audio_position) {
+                    Ok(audio) => {
+                        let _ = opened_sender.send(Ok(()));
+                        audio
+                    }
+                    Err(e) => {
+                        let _ = opened_sender.send(Err(e));
+                        return;
+                    }
+                };
+                loop {
+                    let cmd = match command_receiver.recv_timeout(DEVICE_CHECK_INTERVAL) {
+                        Ok(cmd) => cmd,
+                        Err(RecvTimeoutError::Timeout) => {
+                            audio.check_output_device();
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    };
+                    match cmd {
+                        AudioCommand::Play(track, progress_sender) => {
+                            audio.current = Some((track, progress_sender));
+                            audio.play_current(None);
+                        }
+                        AudioCommand::Pause => {
+                            if let Some(ref s) = audio.sink {
+                                s.pause();
+                            }
+                        }
+                        AudioCommand::Resume => match audio.sink {
+                            Some(ref s) => s.play(),
+                            // The track never started, for want of an output
+                            None => {
+                                let position = audio.position.position().ms;
+                                audio.play_current(Some(Duration::from_millis(position)));
+                            }
+                        },
+                        AudioCommand::Clear => {
+                            audio.current = None;
+                            audio.position.seek_to(0);
+                            if let Some(old_sink) = audio.sink.take() {
+                                old_sink.stop();
+                            }
+                        }
+                        AudioCommand::SetVolume(vol) => {
+                            audio.volume = vol.clamp(0.0, 1.0);
+                            if let Some(ref s) = audio.sink {
+                                s.set_volume(audio.volume);
+                            }
+                        }
+                        AudioCommand::SetGain(factor) => {
+                            if let Ok(mut chain) = audio.dsp_chain.lock() {
+                                chain.stage_mut::<GainProcessor>().set_gain(factor);
+                            }
+                        }
+                        AudioCommand::SetEqualizer(settings) => {
+                            if let Ok(mut chain) = audio.dsp_chain.lock() {
+                                chain
+                                    .stage_mut::<EqualizerProcessor>()
+                                    .set_settings(settings);
+                            }
+                        }
+                        AudioCommand::SetDsp(settings) => {
+                            if let Ok(mut chain) = audio.dsp_chain.lock() {
+                                chain.apply_settings(&settings);
+                            }
+                        }
+                        AudioCommand::SetSpeed(speed) => {
+                            audio.rate.speed.store(speed.to_bits(), Ordering::Relaxed);
+                        }
+                        AudioCommand::SetPitch(semitones) => {
+                            audio
+                                .rate
+                                .pitch_semitones
+                                .store(semitones.to_bits(), Ordering::Relaxed);
+                        }
+                        AudioCommand::SetOutputDevice(device, event_sender) => {
+                            audio.device_events = Some(event_sender);
+                            audio.select_output_device(device);
+                        }
+                        AudioCommand::Seek(position) => audio.seek(position),
+                        AudioCommand::Exit => break,
+                    }
+                }
+            });
+            opened_receiver
+                .recv()
+                .map_err(|_| anyhow!("The audio thread stopped before opening the output"))?
+                .context("Failed to open the default audio output")?;
+            Ok(Self {
+                command_sender,
+                position,
+            })
+        }
+    }
+
+    struct AudioOutput {
+        _stream: cpal::Stream,
+        mixer: Arc<DynamicMixerController<f32>>,
+        device_name: Option<String>,
+    }
+
+    impl AudioOutput {
+        /// Opens the named device, or the system default with `None`. The stream keeps
+        /// the latency of `position` up to date from the device timestamps.
+        fn open(
+            device_name: Option<&str>,
+            position: Arc<PositionTracker>,
+            rate: PlaybackRate,
+        ) -> Result<Self> {
+            let device = match device_name {
+                Some(name) => find_output_device(name)
+                    .ok_or_else(|| anyhow!("Output device not found: {name}"))?,
+                None => cpal::default_host()
+                    .default_output_device()
+                    .ok_or_else(|| anyhow!("No default output device"))?,
+            };
+            let config = device.default_output_config()?;
+            let (mixer, source) =
+                dynamic_mixer::mixer::<f32>(config.channels(), config.sample_rate().0);
+            let output = OutputCallback {
+                source,
+                position,
+                rate,
+                sample_rate: config.sample_rate().0,
+                channels: config.channels(),
+            };
+            let stream_config = config.config();
+            let stream = match config.sample_format() {
+                cpal::SampleFormat::F32 => output.build_stream::<f32>(&device, &stream_config),
+                cpal::SampleFormat::F64 => output.build_stream::<f64>(&device, &stream_config),
+                cpal::SampleFormat::I16 => output.build_stream::<i16>(&device, &stream_config),
+                cpal::SampleFormat::I32 => output.build_stream::<i32>(&device, &stream_config),
+                cpal::SampleFormat::U16 => output.build_stream::<u16>(&device, &stream_config),
+                format => return Err(anyhow!("Unsupported output sample format: {format}")),
+            }?;
+            stream.play()?;
+            Ok(Self {
+                _stream: stream,
+                mixer,
+                device_name: device_name.map(str::to_string),
+            })
+        }
+
+        /// A sink playing on this output.
+        fn sink(&self) -> Sink {
+            let (sink, queue) = Sink::new_idle();
+            self.mixer.add(queue);
+            sink
+        }
+    }
+
+    /// Fills the device buffers from the mixer.
+    struct OutputCallback {
+        source: DynamicMixer<f32>,
+        position: Arc<PositionTracker>,
+        rate: PlaybackRate,
+        sample_rate: u32,
+        channels: u16,
+    }
+
+    impl OutputCallback {
+        fn build_stream<T>(
+            mut self,
+            device: &cpal::Device,
+            config: &cpal::StreamConfig,
+        ) -> Result<cpal::Stream>
+        where
+            T: SizedSample + FromSample<f32>,
+        {
+            let stream = device.build_output_stream(
+                config,
+                move |data: &mut [T], info: &OutputCallbackInfo| {
+                    for sample in data.iter_mut() {
+                        *sample = T::from_sample(self.source.next().unwrap_or(0.0));
+                    }
+                    self.update_latency(data.len(), info);
+                },
+                |e| tracing::error!("Audio output stream error: {e}"),
+                None,
+            )?;
+            Ok(stream)
+        }
+
+        /// Everything handed over in this callback is heard once the device played the
+        /// buffer, from the time the device reports for it on.
+        fn update_latency(&self, samples: usize, info: &OutputCallbackInfo) {
+            let timestamp = info.timestamp();
+            let delay = timestamp
+                .playback
+                .duration_since(&timestamp.callback)
+                .unwrap_or_default();
+            let buffered = samples as f64 / self.channels as f64 / self.sample_rate as f64;
+            // Track time runs at the playback speed
+            let speed = f32::from_bits(self.rate.speed.load(Ordering::Relaxed)) as f64;
+            let seconds = (delay.as_secs_f64() + buffered) * speed;
+            self.position
+                .set_latency((seconds * self.position.sample_rate() as f64) as u64);
+        }
+    }
+
+    fn find_output_device(name: &str) -> Option<rodio::Device> {
+        cpal::default_host()
+            .output_devices()
+            .ok()?
+            .find(|d| d.name().is_ok_and(|n| n == name))
+    }
+
+    /// State owned by the audio thread.
+    struct AudioThread {
+        output: Option<AudioOutput>,
+        /// Device picked by the user, `None` for the system default
+        requested_device: Option<String>,
+        device_events: Option<Sender<PlaybackEvent>>,
+        sink: Option<Sink>,
+        current: Option<(Track, Sender<PlaybackEvent>)>,
+        /// Position in the current track, kept up to date by the playing source
+        position: Arc<PositionTracker>,
+        volume: f32,
+        // Shared with the playing source so settings changes apply live
+        dsp_chain: Arc<Mutex<DspChain>>,
+        rate: PlaybackRate,
+    }
+
+    impl AudioThread {
+        fn new(volume: f32, position: Arc<PositionTracker>) -> Result<Self> {
+            let rate = PlaybackRate::default();
+            let output = AudioOutput::open(None, position.clone(), rate.clone())?;
+            Ok(Self {
+                output: Some(output),
+                requested_device: None,
+                device_events: None,
+                sink: None,
+                current: None,
+                position,
+                volume: volume.clamp(0.0, 1.0),
+                dsp_chain: Arc::new(Mutex::new(DspChain::from_settings(&DspSettings::default()))),
+                rate,
+            })
+        }
+
+        /// Starts the current track, reporting whether the file or the output failed.
+        fn play_current(&mut self, position: Option<Duration>) {
+            let Some((track, sender)) = self.current.clone() else {
+                return;
+            };
+            if self.output.is_none() {
+                // Kept current, to start once an output comes back
+                let error = anyhow!("No audio output device available");
+                let _ = sender.send(PlaybackEvent::OutputUnavailable(error));
+                return;
+            }
+            if let Err(e) = self.start_track(position, false) {
+                self.current = None;
+                let _ = sender.send(PlaybackEvent::FailedOpeningFile(track, e));
+            }
+        }
+
+        /// Builds the source chain for the current track and starts it at `position`.
+        fn start_track(&mut self, position: Option<Duration>, paused: bool) -> Result<()> {
+            if let Some(old_sink) = self.sink.take() {
+                old_sink.stop();
+            }
+            let Some((track, progress_sender)) = self.current.as_ref() else {
+                return Ok(());
+            };
+            let output = self
+                .output
+                .as_ref()
+                .ok_or_else(|| anyhow!("No audio output device available"))?;
+
+            let start_offset = track.start_offset();
+            let position = position.unwrap_or_default();
+            let source = FileSource::open_at(&track.path, start_offset + position)?;
+            let sink = output.sink();
+            sink.set_volume(self.volume);
+            if paused {
+                sink.pause();
+            }
+            let spectrum = self
+                .dsp_chain
+                .lock()
+                .map(|mut chain| chain.stage_mut::<SpectrumProcessor>().spectrum())
+                .unwrap_or_default();
+            let source = DspSource::new(source, self.dsp_chain.clone());
+            let mut progress_source = ProgressAndSpectrumSource::new(
+                source,
+                track.total_frames,
+                start_offset,
+                track.segment.as_ref().is_some_and(|s| s.end_ms.is_some()),
+                spectrum,
+                self.position.clone(),
+                progress_sender.clone(),
+            );
+            self.position.reset(progress_source.sample_rate());
+            progress_source.start_at(position);
+            // Stretch after progress reporting so positions stay in track time
+            let source = TimeStretchSource::new(progress_source, self.rate.clone());
+            sink.append(source);
+            self.sink = Some(sink);
+            Ok(())
+        }
+
+        /// Seeks in the playing source, or restarts the track at `position` when its file
+        /// can't seek, so that decoding up to it doesn't hold up the output.
+        fn seek(&mut self, position: Duration) {
+            let Some(sink) = self.sink.as_ref() else {
+                return;
+            };
+            match sink.try_seek(position) {
+                Ok(()) => tracing::debug!("Successfully seeked to {:?}", position),
+                Err(e) => {
+                    tracing::debug!("Restarting the track to seek: {:?}", e);
+                    let paused = sink.is_paused();
+                    if let Err(e) = self.start_track(Some(position), paused) {
+                        tracing::error!("Failed to seek: {e}");
+                    }
+                }
+            }
+        }
+
+        fn open_output(&self, device_name: Option<&str>) -> Result<AudioOutput> {
+            AudioOutput::open(device_name, self.position.clone(), self.rate.clone())
+        }
+
+        /// Moves playback to a new output, resuming the current track where it was.
+        fn replace_output(&mut self, output: AudioOutput) {
+            // Without a sink the track was waiting for an output, paused meanwhile
+            let paused = self.sink.as_ref().is_none_or(|s| s.is_paused());
+            let position = Duration::from_millis(self.position.position().ms);
+            if let Some(old_sink) = self.sink.take() {
+                old_sink.stop();
+            }
+            self.output = Some(output);
+            if let Err(e) = self.start_track(Some(position), paused) {
+                tracing::error!("Failed to resume playback on new output: {e}");
+            }
+        }
+
+        fn select_output_device(&mut self, device: Option<String>) {
+            self.requested_device = device.clone();
+            match self.open_output(device.as_deref()) {
+                Ok(output) => self.replace_output(output),
+                Err(e) => self.fall_back_to_default(e.to_string()),
+            }
+        }
+
+        fn fall_back_to_default(&mut self, reason: String) {
+            let requested = self.requested_device.clone().unwrap_or_default();
+            match self.open_output(None) {
+                Ok(output) => self.replace_output(output),
+                Err(e) => {
+                    tracing::error!("Failed to open default audio output: {e}");
+                    self.output = None;
+                }
+            }
+            if let Some(sender) = self.device_events.as_ref() {
+                let _ = sender.send(PlaybackEvent::OutputDeviceFallback(requested, reason));
+            }
+        }
+
+        /// Falls back to the default device when the selected one disappears, and retries
+        /// opening an output if none was available.
+        fn check_output_device(&mut self) {
+            let active_device = self.output.as_ref().map(|o| o.device_name.clone());
+            match active_device {
+                Some(Some(name)) => {
+                    if find_output_device(&name).is_none() {
+                        self.fall_back_to_default(format!("Output device {name} disconnected"));
+                    }
+                }
+                Some(None) => {}
+                None => {
+                    if let Ok(output) = self.open_output(None) {
+                        self.replace_output(output);
+                    }
+                }
+            }
+        }
+    }
+
+    impl PlaybackDriver for RodioPlaybackDriver {
+        fn play(&mut self, track: Track, progress_sender: Sender<PlaybackEvent>) -> Result<()> {
+            self.position.seek_to(0);
+            self.command_sender
+                .send(AudioCommand::Play(track, progress_sender))
+                .map_err(|e| anyhow!("Failed to send play command: {}", e))
+        }
+
+        fn pause(&mut self) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::Pause)
+                .map_err(|e| anyhow!("Failed to send pause command: {}", e))
+        }
+
+        fn resume(&mut self) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::Resume)
+                .map_err(|e| anyhow!("Failed to send resume command: {}", e))
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            self.position.seek_to(0);
+            self.command_sender
+                .send(AudioCommand::Clear)
+                .map_err(|e| anyhow!("Failed to send clear command: {}", e))
+        }
+
+        fn set_volume(&mut self, volume: f32) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::SetVolume(volume))
+                .map_err(|e| anyhow!("Failed to send volume command: {}", e))
+        }
+
+        fn set_gain(&mut self, gain: f32) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::SetGain(gain))
+                .map_err(|e| anyhow!("Failed to send gain command: {}", e))
+        }
+
+        fn set_equalizer(&mut self, settings: EqualizerSettings) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::SetEqualizer(settings))
+                .map_err(|e| anyhow!("Failed to send equalizer command: {}", e))
+        }
+
+        fn set_speed(&mut self, speed: f32) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::SetSpeed(speed))
+                .map_err(|e| anyhow!("Failed to send speed command: {}", e))
+        }
+
+        fn set_pitch(&mut self, semitones: f32) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::SetPitch(semitones))
+                .map_err(|e| anyhow!("Failed to send pitch command: {}", e))
+        }
+
+        fn set_dsp(&mut self, settings: DspSettings) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::SetDsp(settings))
+                .map_err(|e| anyhow!("Failed to send DSP command: {}", e))
+        }
+
+        fn seek(&mut self, position: Duration) -> Result<()> {
+            self.position.seek_to_time(position);
+            self.command_sender
+                .send(AudioCommand::Seek(position))
+                .map_err(|e| anyhow!("Failed to send seek command: {}", e))
+        }
+
+        fn position(&self) -> PlaybackPosition {
+            self.position.position()
+        }
+
+        fn output_devices(&self) -> Result<Vec<OutputDevice>> {
+            let host = cpal::default_host();
+            let default_name = host.default_output_device().and_then(|d| d.name().ok());
+            let devices = host.output_devices()?.filter_map(|device| {
+                let name = device.name().ok()?;
+                let mut channels = Vec::new();
+                let mut sample_rates = Vec::new();
+                let mut sample_formats = Vec::new();
+                for config in device.supported_output_configs().ok()? {
+                    channels.push(config.channels());
+                    let range = config.min_sample_rate().0..=config.max_sample_rate().0;
+                    sample_rates.extend(COMMON_SAMPLE_RATES.iter().filter(|r| range.contains(r)));
+                    sample_formats.push(config.sample_format().to_string());
+                }
+                channels.sort_unstable();
+                channels.dedup();
+                sample_rates.sort_unstable();
+                sample_rates.dedup();
+                sample_formats.sort();
+                sample_formats.dedup();
+                Some(OutputDevice {
+                    is_default: default_name.as_ref() == Some(&name),
+                    name,
+                    channels,
+                    sample_rates,
+                    sample_formats,
+                })
+            });
+            Ok(devices.collect())
+        }
+
+        fn set_output_device(
+            &mut self,
+            device: Option<String>,
+            event_sender: Sender<PlaybackEvent>,
+        ) -> Result<()> {
+            self.command_sender
+                .send(AudioCommand::SetOutputDevice(device, event_sender))
+                .map_err(|e| anyhow!("Failed to send output device command: {}", e))
+        }
+    }
+    impl Drop for RodioPlaybackDriver {
+        fn drop(&mut self) {
+            let _ = self.command_sender.send(AudioCommand::Exit);
+        }
+    }
+
+    /// Decodes a file. Seeking while playing is left to the demuxer, as it runs on the
+    /// output thread.
+    pub(super) struct FileSource {
+        decoder: Decoder<BufReader<File>>,
+    }
+
+    impl FileSource {
+        pub(super) fn open(path: &Path) -> Result<Self> {
+            let file =
+                File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+            let decoder = Decoder::new(BufReader::new(file))
+                .with_context(|| format!("Failed to decode {}", path.display()))?;
+            Ok(Self { decoder })
+        }
+
+        /// Opens the file at `pos`, decoding from the start up to it when the demuxer can't
+        /// seek, e.g. in files without a frame count or seek index.
+        pub(super) fn open_at(path: &Path, pos: Duration) -> Result<Self> {
+            let mut source = Self::open(path)?;
+            if pos.is_zero() {
+                return Ok(source);
+            }
+            if let Err(e) = source.try_seek(pos) {
+                tracing::debug!("Seeking {:?} by decoding from the start: {e}", path);
+                // The failed seek may have moved the demuxer anywhere
+                source = Self::open(path)?;
+                source.skip_to(pos);
+            }
+            Ok(source)
+        }
+
+        pub(super) fn skip_to(&mut self, pos: Duration) {
+            let frames = (pos.as_secs_f64() * self.sample_rate() as f64) as usize;
+            let samples = frames * self.channels() as usize;
+            self.decoder.by_ref().take(samples).for_each(drop);
+        }
+    }
+
+    impl Iterator for FileSource {
+        type Item = i16;
+
+        fn next(&mut self) -> Option<i16> {
+            self.decoder.next()
+        }
+    }
+
+    impl Source for FileSource {
+        fn current_frame_len(&self) -> Option<usize> {
+            self.decoder.current_frame_len()
+        }
+
+        fn channels(&self) -> u16 {
+            self.decoder.channels()
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.decoder.sample_rate()
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            self.decoder.total_duration()
+        }
+
+        fn try_seek(&mut self, pos: Duration) -> std::result::Result<(), SeekError> {
+            self.decoder.try_seek(pos)
+        }
+    }
+
+    /// Runs the decoded samples through the shared DSP chain, one block at a time.
+    struct DspSource<S: Source<Item = i16>> {
+        inner: S,
+        chain: Arc<Mutex<DspChain>>,
+        block: Vec<f32>,
+        position: usize,
+        /// Output samples to drop so the chain latency doesn't delay the track
+        skip_samples: usize,
+        flushed: bool,
+    }
+
+    impl<S: Source<Item = i16>> DspSource<S> {
+        fn new(inner: S, chain: Arc<Mutex<DspChain>>) -> Self {
+            let mut source = Self {
+                inner,
+                chain,
+                block: Vec::new(),
+                position: 0,
+                skip_samples: 0,
+                flushed: false,
+            };
+            source.reset();
+            source
+        }
+
+        fn reset(&mut self) {
+            let latency = match self.chain.lock() {
+                Ok(mut chain) => {
+                    chain.reset();
+                    chain.latency_frames()
+                }
+                Err(_) => 0,
+            };
+            self.skip_samples = latency * self.inner.channels() as usize;
+            self.block.clear();
+            self.position = 0;
+            self.flushed = false;
+        }
+
+        fn fill_block(&mut self) -> bool {
+            let channels = self.inner.channels().max(1) as usize;
+            let sample_rate = self.inner.sample_rate();
+            self.block.clear();
+            self.position = 0;
+            self.block.extend(
+                self.inner
+                    .by_ref()
+                    .take(DSP_BLOCK_FRAMES * channels)
+                    .map(|s| s as f32 / i16::MAX as f32),
+            );
+
+            let Ok(mut chain) = self.chain.lock() else {
+                return !self.block.is_empty();
+            };
+            if self.block.len() < DSP_BLOCK_FRAMES * channels && !self.flushed {
+                // Push silence through so delayed samples still reach the output
+                self.flushed = true;
+                let tail = chain.latency_frames() * channels;
+                self.block.resize(self.block.len() + tail, 0.0);
+            }
+            if self.block.is_empty() {
+                return false;
+            }
+            chain.process(&mut self.block, sample_rate, channels);
+
+            let skipped = self.skip_samples.min(self.block.len());
+            self.position = skipped;
+            self.skip_samples -= skipped;
+            true
+        }
+    }
+
+    impl<S: Source<Item = i16>> Iterator for DspSource<S> {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            while self.position >= self.block.len() {
+                if !self.fill_block() {
+                    return None;
+                }
+            }
+            let sample = self.block[self.position];
+            self.position += 1;
+            Some(sample)
+        }
+    }
+
+    impl<S: Source<Item = i16>> Source for DspSource<S> {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> u16 {
+            self.inner.channels()
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.inner.sample_rate()
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            self.inner.total_duration()
+        }
+
+        fn try_seek(&mut self, pos: Duration) -> std::result::Result<(), rodio::source::SeekError> {
+            self.inner.try_seek(pos)?;
+            self.reset();
+            Ok(())
+        }
+    }
+
+    /// Speed and pitch shared with the playing source, stored as f32 bits.
+    #[derive(Clone)]
+    struct PlaybackRate {
+        speed: Arc<AtomicU32>,
+        pitch_semitones: Arc<AtomicU32>,
+    }
+
+    impl Default for PlaybackRate {
+        fn default() -> Self {
+            Self {
+                speed: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+                pitch_semitones: Arc::new(AtomicU32::new(0.0f32.to_bits())),
+            }
+        }
+    }
+
+    struct TimeStretchSource<S: Source<Item = f32>> {
+        inner: S,
+        rate: PlaybackRate,
+        stretcher: TimeStretcher,
+        input: Vec<f32>,
+        output: Vec<f32>,
+        position: usize,
+        finished: bool,
+    }
+
+    impl<S: Source<Item = f32>> TimeStretchSource<S> {
+        fn new(inner: S, rate: PlaybackRate) -> Self {
+            let stretcher = TimeStretcher::new(inner.sample_rate(), inner.channels() as usize);
+            Self {
+                inner,
+                rate,
+                stretcher,
+                input: Vec::new(),
+                output: Vec::new(),
+                position: 0,
+                finished: false,
+            }
+        }
+
+        fn fill_output(&mut self) -> bool {
+            self.output.clear();
+            self.position = 0;
+            if self.finished {
+                return false;
+            }
+
+            self.stretcher
+                .set_speed(f32::from_bits(self.rate.speed.load(Ordering::Relaxed)));
+            self.stretcher.set_pitch_semitones(f32::from_bits(
+                self.rate.pitch_semitones.load(Ordering::Relaxed),
+            ));
+
+            let channels = self.inner.channels().max(1) as usize;
+            self.input.clear();
+            self.input
+                .extend(self.inner.by_ref().take(DSP_BLOCK_FRAMES * channels));
+            if self.input.is_empty() {
+                self.finished = true;
+                self.stretcher.finish(&mut self.output);
+            } else {
+                self.stretcher.process(&self.input, &mut self.output);
+            }
+            true
+        }
+    }
+
+    impl<S: Source<Item = f32>> Iterator for TimeStretchSource<S> {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            while self.position >= self.output.len() {
+                if !self.fill_output() {
+                    return None;
+                }
+            }
+            let sample = self.output[self.position];
+            self.position += 1;
+            Some(sample)
+        }
+    }
+
+    impl<S: Source<Item = f32>> Source for TimeStretchSource<S> {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> u16 {
+            self.inner.channels()
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.inner.sample_rate()
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            self.inner.total_duration()
+        }
+
+        fn try_seek(&mut self, pos: Duration) -> std::result::Result<(), rodio::source::SeekError> {
+            self.inner.try_seek(pos)?;
+            self.stretcher.reset();
+            self.output.clear();
+            self.position = 0;
+            self.finished = false;
+            Ok(())
+        }
+    }
+
+    /// Reports progress and the latest spectrum from the DSP chain, and ends cue segments.
+    struct ProgressAndSpectrumSource<S: Source<Item = f32>> {
+        inner: S,
+        total_frames: u64,
+        samples_played: u64,
+        start_offset: Duration,
+        stop_at_end: bool,
+        completed: bool,
+        playback_sender: Sender<PlaybackEvent>,
+        spectrum: Arc<Mutex<Vec<f32>>>,
+        position: Arc<PositionTracker>,
+        /// Samples pulled since progress was last reported
+        samples_since_update: u64,
+        sample_rate: u32,
+        channels: u16,
+    }
+
+    impl<S: Source<Item = f32>> ProgressAndSpectrumSource<S> {
+        fn new(
+            inner: S,
+            total_frames: u64,
+            start_offset: Duration,
+            stop_at_end: bool,
+            spectrum: Arc<Mutex<Vec<f32>>>,
+            position: Arc<PositionTracker>,
+            playback_sender: Sender<PlaybackEvent>,
+        ) -> Self {
+            let sample_rate = inner.sample_rate();
+            let channels = inner.channels();
+
+            Self {
+                inner,
+                total_frames,
+                samples_played: 0,
+                start_offset,
+                stop_at_end,
+                completed: false,
+                playback_sender,
+                spectrum,
+                position,
+                samples_since_update: 0,
+                sample_rate,
+                channels,
+            }
+        }
+
+        /// Counts the track as played up to `pos`, where the inner source now is.
+        fn start_at(&mut self, pos: Duration) {
+            let channels = self.channels as u64;
+            self.samples_played =
+                (pos.as_secs_f64() * self.sample_rate as f64 * channels as f64) as u64;
+            self.position.seek_to(self.samples_played / channels.max(1));
+        }
+    }
+
+    impl<S: Source<Item = f32>> Iterator for ProgressAndSpectrumSource<S> {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.completed {
+                return None;
+            }
+
+            let total_samples = self.total_frames * self.channels as u64;
+            // Segments of a larger file (cue tracks) end before the decoder does
+            let segment_ended = self.stop_at_end && self.samples_played >= total_samples;
+
+            let next_sample = if segment_ended {
+                None
+            } else {
+                self.inner.next()
+            };
+            if let Some(sample) = next_sample {
+                self.samples_played += 1;
+
+                let frames_played = self.samples_played / self.channels as u64;
+
+                self.position.advance_to(frames_played);
+
+                self.samples_since_update += 1;
+                let update_interval =
+                    self.sample_rate as u64 * self.channels as u64 * PROGRESS_INTERVAL_MS / 1000;
+                if self.samples_since_update >= update_interval {
+                    self.samples_since_update = 0;
+                    // Report what is heard rather than what was handed to the output
+                    let frames_heard = self.position.position().frames;
+                    let percent_completed = if self.total_frames > 0 {
+                        frames_heard as f64 / self.total_frames as f64
+                    } else {
+                        0.0
+                    };
+                    let percent_completed = (percent_completed * 1000.0).round() / 1000.0;
+                    let _ = self
+                        .playback_sender
+                        .send(PlaybackEvent::Progress(percent_completed, frames_heard));
+                    let spectrum_data = self.spectrum.lock().map(|s| s.clone()).unwrap_or_default();
+                    let _ = self
+                        .playback_sender
+                        .send(PlaybackEvent::Spectrum(spectrum_data));
+                }
+
+                Some(sample)
+            } else {
+                self.completed = true;
+                let _ = self.playback_sender.send(PlaybackEvent::TrackCompleted);
+                None
+            }
+        }
+    }
+
+    impl<S: Source<Item = f32>> Source for ProgressAndSpectrumSource<S> {
+        fn current_frame_len(&self) -> Option<usize> {
+            self.inner.current_frame_len()
+        }
+
+        fn channels(&self) -> u16 {
+            self.channels
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            self.inner.total_duration()
+        }
+
+        fn try_seek(&mut self, pos: Duration) -> std::result::Result<(), rodio::source::SeekError> {
+            let result = self.inner.try_seek(pos + self.start_offset);
+            if result.is_ok() {
+                self.start_at(pos);
+            }
+            result
+        }
+    }
+}
+
+pub use rodio_impl::*;
+
+#[cfg(test)]
+#[path = "./rodio.tests.rs"]
+mod tests;
@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Result};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use crate::driver::{
+    render::{Clock, RenderDriver, RenderOutput},
+    OutputDevice,
+};
+
+const WAV_HEADER_BYTES: u32 = 44;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+
+/// Writes the output stream to a 32-bit float WAV file. The first track sets the format, and
+/// tracks in another format are not written.
+pub struct WavOutput {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    format: Option<(u32, usize)>,
+    data_bytes: u32,
+}
+
+impl WavOutput {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            writer: None,
+            format: None,
+            data_bytes: 0,
+        }
+    }
+
+    fn create(&mut self, sample_rate: u32, channels: usize) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        let block_align = channels as u16 * 4;
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(WAV_HEADER_BYTES - 8).to_le_bytes())?;
+        writer.write_all(b"WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&WAVE_FORMAT_IEEE_FLOAT.to_le_bytes())?;
+        writer.write_all(&(channels as u16).to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&32u16.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        self.writer = Some(writer);
+        self.format = Some((sample_rate, channels));
+        self.data_bytes = 0;
+        Ok(())
+    }
+}
+
+impl RenderOutput for WavOutput {
+    fn device(&self) -> OutputDevice {
+        OutputDevice {
+            name: format!("WAV file {}", self.path.display()),
+            is_default: true,
+            channels: Vec::new(),
+            sample_rates: Vec::new(),
+            sample_formats: vec!["f32".to_string()],
+        }
+    }
+
+    fn write(&mut self, samples: &[f32], sample_rate: u32, channels: usize) -> Result<()> {
+        match self.format {
+            None => self.create(sample_rate, channels)?,
+            Some(format) if format != (sample_rate, channels) => {
+                return Err(anyhow!(
+                    "Cannot write {channels} channels at {sample_rate} Hz to a file with {} channels at {} Hz",
+                    format.1,
+                    format.0
+                ));
+            }
+            Some(_) => {}
+        }
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+        for sample in samples {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_bytes = self.data_bytes.saturating_add((samples.len() * 4) as u32);
+        Ok(())
+    }
+
+    /// Updates the header sizes so the file is valid whenever playback stops.
+    fn flush(&mut self) -> Result<()> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+        writer.seek(SeekFrom::Start(4))?;
+        let riff_bytes = (WAV_HEADER_BYTES - 8).saturating_add(self.data_bytes);
+        writer.write_all(&riff_bytes.to_le_bytes())?;
+        writer.seek(SeekFrom::Start(WAV_HEADER_BYTES as u64 - 4))?;
+        writer.write_all(&self.data_bytes.to_le_bytes())?;
+        writer.seek(SeekFrom::End(0))?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for WavOutput {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+pub type WavPlaybackDriver = RenderDriver<WavOutput>;
+
+impl WavPlaybackDriver {
+    pub fn new(path: impl Into<PathBuf>, clock: Clock, volume: f32) -> Self {
+        Self::with_output(WavOutput::new(path), clock, volume)
+    }
+}
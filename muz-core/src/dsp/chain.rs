@@ -0,0 +1,137 @@
+use crate::dsp::processors::{BalanceProcessor, LimiterProcessor};
+use crate::dsp::{DspProcessor, DspSettings};
+
+/// Ordered list of processors applied between the decoder and the output.
+///
+/// Stages removed from the chain are parked rather than dropped, so their settings
+/// survive being disabled and re-enabled.
+#[derive(Default)]
+pub struct DspChain {
+    active: Vec<Box<dyn DspProcessor>>,
+    parked: Vec<Box<dyn DspProcessor>>,
+    format: Option<(u32, usize)>,
+}
+
+impl DspChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_settings(settings: &DspSettings) -> Self {
+        let mut chain = Self::new();
+        chain.apply_settings(settings);
+        chain
+    }
+
+    pub fn push(&mut self, processor: Box<dyn DspProcessor>) {
+        self.insert(self.active.len(), processor);
+    }
+
+    /// Inserts a processor at `index`, replacing any stage with the same name.
+    pub fn insert(&mut self, index: usize, mut processor: Box<dyn DspProcessor>) {
+        self.remove(processor.name());
+        if let Some((sample_rate, channels)) = self.format {
+            processor.prepare(sample_rate, channels);
+        }
+        let index = index.min(self.active.len());
+        self.active.insert(index, processor);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn DspProcessor>> {
+        let index = self.active.iter().position(|p| p.name() == name)?;
+        Some(self.active.remove(index))
+    }
+
+    pub fn move_to(&mut self, name: &str, index: usize) -> bool {
+        match self.remove(name) {
+            Some(processor) => {
+                let index = index.min(self.active.len());
+                self.active.insert(index, processor);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.active.iter().map(|p| p.name()).collect()
+    }
+
+    /// Activates exactly the given built-in stages in order, and applies their parameters.
+    /// Stages that stay active keep their state, so changing settings doesn't click.
+    pub fn apply_settings(&mut self, settings: &DspSettings) {
+        let mut active = std::mem::take(&mut self.active);
+        for stage in &settings.stages {
+            if self.active.iter().any(|p| p.name() == stage.name()) {
+                continue;
+            }
+            let processor = match active.iter().position(|p| p.name() == stage.name()) {
+                Some(index) => active.remove(index),
+                None => {
+                    let mut processor =
+                        match self.parked.iter().position(|p| p.name() == stage.name()) {
+                            Some(index) => self.parked.remove(index),
+                            None => stage.create(),
+                        };
+                    if let Some((sample_rate, channels)) = self.format {
+                        processor.prepare(sample_rate, channels);
+                    }
+                    processor
+                }
+            };
+            self.active.push(processor);
+        }
+        self.parked.append(&mut active);
+
+        if let Some(balance) = self.get_mut::<BalanceProcessor>() {
+            balance.set_balance(settings.balance);
+        }
+        if let Some(limiter) = self.get_mut::<LimiterProcessor>() {
+            limiter.set_ceiling_db(settings.limiter_ceiling_db);
+        }
+    }
+
+    /// Finds a stage by type, whether it is active or parked.
+    pub fn get_mut<T: DspProcessor + 'static>(&mut self) -> Option<&mut T> {
+        self.active
+            .iter_mut()
+            .chain(self.parked.iter_mut())
+            .find_map(|p| p.as_any_mut().downcast_mut::<T>())
+    }
+
+    /// Gets the stage of type `T`, creating a parked one if needed so parameters can be set
+    /// before the stage is enabled.
+    pub fn stage_mut<T: DspProcessor + Default + 'static>(&mut self) -> &mut T {
+        if self.get_mut::<T>().is_none() {
+            self.parked.push(Box::new(T::default()));
+        }
+        self.get_mut::<T>().expect("stage was just added")
+    }
+
+    pub fn latency_frames(&self) -> usize {
+        self.active.iter().map(|p| p.latency_frames()).sum()
+    }
+
+    pub fn reset(&mut self) {
+        for processor in self.active.iter_mut() {
+            processor.reset();
+        }
+    }
+
+    pub fn process(&mut self, block: &mut [f32], sample_rate: u32, channels: usize) {
+        let format = (sample_rate, channels.max(1));
+        if self.format != Some(format) {
+            self.format = Some(format);
+            for processor in self.active.iter_mut().chain(self.parked.iter_mut()) {
+                processor.prepare(format.0, format.1);
+            }
+        }
+        for processor in self.active.iter_mut() {
+            processor.process(block);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./chain.tests.rs"]
+mod tests;
@@ -0,0 +1,162 @@
+use std::any::Any;
+
+use super::*;
+use crate::dsp::processors::GainProcessor;
+use crate::dsp::DspStage;
+
+/// Adds a constant to every sample, so the order of stages shows in the output.
+struct Offset {
+    name: &'static str,
+    offset: f32,
+    prepared: Option<(u32, usize)>,
+}
+
+impl Offset {
+    fn boxed(name: &'static str, offset: f32) -> Box<dyn DspProcessor> {
+        Box::new(Offset {
+            name,
+            offset,
+            prepared: None,
+        })
+    }
+}
+
+impl DspProcessor for Offset {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn prepare(&mut self, sample_rate: u32, channels: usize) {
+        self.prepared = Some((sample_rate, channels));
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        block.iter_mut().for_each(|s| *s = *s * 2.0 + self.offset);
+    }
+
+    fn latency_frames(&self) -> usize {
+        3
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn run(chain: &mut DspChain, value: f32) -> f32 {
+    let mut block = [value];
+    chain.process(&mut block, 44_100, 1);
+    block[0]
+}
+
+#[test]
+fn test_processors_run_in_order() {
+    let mut chain = DspChain::new();
+    chain.push(Offset::boxed("a", 1.0));
+    chain.push(Offset::boxed("b", 10.0));
+    assert_eq!(run(&mut chain, 0.0), 12.0);
+
+    assert!(chain.move_to("b", 0));
+    assert_eq!(chain.names(), vec!["b", "a"]);
+    assert_eq!(run(&mut chain, 0.0), 21.0);
+}
+
+#[test]
+fn test_insert_replaces_and_remove() {
+    let mut chain = DspChain::new();
+    chain.push(Offset::boxed("a", 1.0));
+    chain.push(Offset::boxed("b", 10.0));
+    chain.insert(0, Offset::boxed("b", 5.0));
+    assert_eq!(chain.names(), vec!["b", "a"]);
+
+    assert!(chain.remove("a").is_some());
+    assert!(chain.remove("a").is_none());
+    assert!(!chain.move_to("a", 0));
+    assert_eq!(run(&mut chain, 1.0), 7.0);
+}
+
+#[test]
+fn test_latency_is_summed() {
+    let mut chain = DspChain::new();
+    assert_eq!(chain.latency_frames(), 0);
+    chain.push(Offset::boxed("a", 0.0));
+    chain.push(Offset::boxed("b", 0.0));
+    assert_eq!(chain.latency_frames(), 6);
+}
+
+#[test]
+fn test_processors_are_prepared_with_stream_format() {
+    let mut chain = DspChain::new();
+    chain.push(Offset::boxed("a", 0.0));
+    let mut block = [0.0; 4];
+    chain.process(&mut block, 48_000, 2);
+    chain.push(Offset::boxed("b", 0.0));
+
+    for name in ["a", "b"] {
+        let mut processor = chain.remove(name).unwrap();
+        let offset = processor.as_any_mut().downcast_mut::<Offset>().unwrap();
+        assert_eq!(offset.prepared, Some((48_000, 2)));
+    }
+}
+
+#[test]
+fn test_apply_settings_parks_disabled_stages() {
+    let mut chain = DspChain::from_settings(&DspSettings::default());
+    assert_eq!(
+        chain.names(),
+        vec![
+            "equalizer",
+            "normalization",
+            "balance",
+            "limiter",
+            "spectrum"
+        ]
+    );
+    chain.stage_mut::<GainProcessor>().set_gain(0.5);
+
+    let settings = DspSettings {
+        stages: vec![DspStage::Spectrum, DspStage::Equalizer],
+        ..Default::default()
+    };
+    chain.apply_settings(&settings);
+    assert_eq!(chain.names(), vec!["spectrum", "equalizer"]);
+
+    chain.apply_settings(&DspSettings::default());
+    assert_eq!(chain.get_mut::<GainProcessor>().unwrap().target_gain(), 0.5);
+}
+
+#[test]
+fn test_stage_mut_creates_parked_stage() {
+    let mut chain = DspChain::new();
+    chain.stage_mut::<GainProcessor>().set_gain(0.25);
+    assert!(chain.names().is_empty());
+    assert_eq!(run(&mut chain, 1.0), 1.0);
+
+    chain.apply_settings(&DspSettings {
+        stages: vec![DspStage::Normalization],
+        ..Default::default()
+    });
+    let mut block = vec![1.0; 1024];
+    chain.process(&mut block, 44_100, 1);
+    assert_eq!(run(&mut chain, 1.0), 0.25);
+}
+
+#[test]
+fn test_apply_settings_keeps_the_state_of_active_stages() {
+    let mut chain = DspChain::from_settings(&DspSettings {
+        stages: vec![DspStage::Limiter],
+        ..Default::default()
+    });
+    let mut block = vec![0.5; 1024];
+    chain.process(&mut block, 44_100, 1);
+    let latency = chain.latency_frames();
+    assert!(block[latency..].iter().all(|s| *s == 0.5));
+
+    // The limiter still holds the end of the last block
+    chain.apply_settings(&DspSettings {
+        stages: vec![DspStage::Balance, DspStage::Limiter],
+        balance: 0.5,
+        ..Default::default()
+    });
+    assert_eq!(run(&mut chain, 0.0), 0.5);
+}
@@ -0,0 +1,87 @@
+pub mod chain;
+pub mod processors;
+
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+
+pub use chain::DspChain;
+
+/// A processing stage working on blocks of interleaved f32 samples.
+pub trait DspProcessor: Send {
+    /// Unique name of the stage within a chain
+    fn name(&self) -> &str;
+
+    /// Called before the first block and whenever the stream format changes
+    fn prepare(&mut self, sample_rate: u32, channels: usize);
+
+    /// Process a block of interleaved samples in place
+    fn process(&mut self, block: &mut [f32]);
+
+    /// Delay introduced by the stage, in frames
+    fn latency_frames(&self) -> usize {
+        0
+    }
+
+    /// Drop any internal state, e.g. after a seek
+    fn reset(&mut self) {}
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Built-in stages that can be enabled and ordered from the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DspStage {
+    Equalizer,
+    Normalization,
+    Balance,
+    Limiter,
+    Spectrum,
+}
+
+impl DspStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DspStage::Equalizer => processors::EqualizerProcessor::NAME,
+            DspStage::Normalization => processors::GainProcessor::NAME,
+            DspStage::Balance => processors::BalanceProcessor::NAME,
+            DspStage::Limiter => processors::LimiterProcessor::NAME,
+            DspStage::Spectrum => processors::SpectrumProcessor::NAME,
+        }
+    }
+
+    pub fn create(&self) -> Box<dyn DspProcessor> {
+        match self {
+            DspStage::Equalizer => Box::new(processors::EqualizerProcessor::default()),
+            DspStage::Normalization => Box::new(processors::GainProcessor::default()),
+            DspStage::Balance => Box::new(processors::BalanceProcessor::default()),
+            DspStage::Limiter => Box::new(processors::LimiterProcessor::default()),
+            DspStage::Spectrum => Box::new(processors::SpectrumProcessor::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DspSettings {
+    /// Active stages, in processing order.
+    pub stages: Vec<DspStage>,
+    /// Stereo balance from -1.0 (left) to 1.0 (right).
+    pub balance: f32,
+    pub limiter_ceiling_db: f32,
+}
+
+impl Default for DspSettings {
+    fn default() -> Self {
+        Self {
+            stages: vec![
+                DspStage::Equalizer,
+                DspStage::Normalization,
+                DspStage::Balance,
+                DspStage::Limiter,
+                DspStage::Spectrum,
+            ],
+            balance: 0.0,
+            limiter_ceiling_db: -0.1,
+        }
+    }
+}
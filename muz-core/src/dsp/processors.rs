@@ -0,0 +1,343 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::dsp::DspProcessor;
+use crate::equalizer::{Equalizer, EqualizerSettings};
+use crate::spectrum::SpectrumAnalyzer;
+
+/// Linear gain, ramped over one block on change to avoid clicks. Used for normalization.
+#[derive(Debug, Clone)]
+pub struct GainProcessor {
+    gain: f32,
+    target: f32,
+    channels: usize,
+}
+
+impl Default for GainProcessor {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            target: 1.0,
+            channels: 2,
+        }
+    }
+}
+
+impl GainProcessor {
+    pub const NAME: &'static str = "normalization";
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.target = gain.max(0.0);
+    }
+
+    pub fn target_gain(&self) -> f32 {
+        self.target
+    }
+}
+
+impl DspProcessor for GainProcessor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn prepare(&mut self, _sample_rate: u32, channels: usize) {
+        self.channels = channels;
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        if self.gain == self.target {
+            if self.gain != 1.0 {
+                block.iter_mut().for_each(|s| *s *= self.gain);
+            }
+            return;
+        }
+
+        let frames = (block.len() / self.channels).max(1);
+        let step = (self.target - self.gain) / frames as f32;
+        for frame in block.chunks_mut(self.channels) {
+            self.gain += step;
+            frame.iter_mut().for_each(|s| *s *= self.gain);
+        }
+        self.gain = self.target;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EqualizerProcessor {
+    settings: EqualizerSettings,
+    equalizer: Option<Equalizer>,
+    format: (u32, usize),
+}
+
+impl EqualizerProcessor {
+    pub const NAME: &'static str = "equalizer";
+
+    pub fn set_settings(&mut self, settings: EqualizerSettings) {
+        if let Some(equalizer) = self.equalizer.as_mut() {
+            equalizer.update(&settings);
+        }
+        self.settings = settings;
+    }
+}
+
+impl DspProcessor for EqualizerProcessor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn prepare(&mut self, sample_rate: u32, channels: usize) {
+        self.format = (sample_rate, channels);
+        self.equalizer = Some(Equalizer::new(&self.settings, sample_rate, channels));
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        if let Some(equalizer) = self.equalizer.as_mut().filter(|e| e.is_enabled()) {
+            equalizer.process(block);
+        }
+    }
+
+    fn reset(&mut self) {
+        if self.equalizer.is_some() {
+            let (sample_rate, channels) = self.format;
+            self.equalizer = Some(Equalizer::new(&self.settings, sample_rate, channels));
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Stereo balance, attenuating the opposite channel. Other layouts pass through.
+#[derive(Debug, Clone, Default)]
+pub struct BalanceProcessor {
+    balance: f32,
+    channels: usize,
+}
+
+impl BalanceProcessor {
+    pub const NAME: &'static str = "balance";
+
+    pub fn set_balance(&mut self, balance: f32) {
+        self.balance = balance.clamp(-1.0, 1.0);
+    }
+}
+
+impl DspProcessor for BalanceProcessor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn prepare(&mut self, _sample_rate: u32, channels: usize) {
+        self.channels = channels;
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        if self.channels != 2 || self.balance == 0.0 {
+            return;
+        }
+        let left = (1.0 - self.balance).min(1.0);
+        let right = (1.0 + self.balance).min(1.0);
+        for frame in block.chunks_exact_mut(2) {
+            frame[0] *= left;
+            frame[1] *= right;
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+const LIMITER_LOOKAHEAD_MS: f32 = 5.0;
+const LIMITER_RELEASE_MS: f32 = 80.0;
+
+/// Look-ahead peak limiter keeping the output under `ceiling`.
+#[derive(Debug, Clone)]
+pub struct LimiterProcessor {
+    ceiling: f32,
+    channels: usize,
+    lookahead: usize,
+    release_coefficient: f32,
+    delay: VecDeque<f32>,
+    gain: f32,
+    target: f32,
+    step: f32,
+    hold: usize,
+}
+
+impl Default for LimiterProcessor {
+    fn default() -> Self {
+        let mut limiter = Self {
+            ceiling: 1.0,
+            channels: 2,
+            lookahead: 0,
+            release_coefficient: 0.0,
+            delay: VecDeque::new(),
+            gain: 1.0,
+            target: 1.0,
+            step: 0.0,
+            hold: 0,
+        };
+        limiter.set_ceiling_db(-0.1);
+        limiter.prepare(44_100, 2);
+        limiter
+    }
+}
+
+impl LimiterProcessor {
+    pub const NAME: &'static str = "limiter";
+
+    pub fn set_ceiling_db(&mut self, ceiling_db: f32) {
+        self.ceiling = 10f32.powf(ceiling_db.min(0.0) / 20.0);
+    }
+}
+
+impl DspProcessor for LimiterProcessor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn prepare(&mut self, sample_rate: u32, channels: usize) {
+        self.channels = channels;
+        self.lookahead = ((sample_rate as f32 * LIMITER_LOOKAHEAD_MS / 1000.0) as usize).max(1);
+        self.release_coefficient =
+            1.0 - (-1.0 / (sample_rate as f32 * LIMITER_RELEASE_MS / 1000.0)).exp();
+        self.reset();
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        for frame in block.chunks_mut(self.channels) {
+            let peak = frame.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            let required = if peak > self.ceiling {
+                self.ceiling / peak
+            } else {
+                1.0
+            };
+
+            // Reach the required gain by the time this frame leaves the delay line, and hold it
+            // until the frame is out
+            if required <= self.target {
+                if required < self.target {
+                    self.target = required;
+                    self.step = (self.gain - required).max(0.0) / self.lookahead as f32;
+                }
+                self.hold = self.lookahead;
+            } else if self.hold > 0 {
+                self.hold -= 1;
+            } else {
+                self.target = required;
+            }
+
+            if self.gain > self.target {
+                self.gain = (self.gain - self.step).max(self.target);
+            } else {
+                self.gain += (self.target - self.gain) * self.release_coefficient;
+            }
+
+            for sample in frame.iter_mut() {
+                self.delay.push_back(*sample);
+                let delayed = self.delay.pop_front().unwrap_or_default();
+                *sample = (delayed * self.gain).clamp(-self.ceiling, self.ceiling);
+            }
+        }
+    }
+
+    fn latency_frames(&self) -> usize {
+        self.lookahead
+    }
+
+    fn reset(&mut self) {
+        self.delay.clear();
+        self.delay.resize(self.lookahead * self.channels, 0.0);
+        self.gain = 1.0;
+        self.target = 1.0;
+        self.step = 0.0;
+        self.hold = 0;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+const SPECTRUM_FFT_SIZE: usize = 4096;
+/// The drivers send spectrum events this often, there is no use analyzing more
+const SPECTRUM_INTERVAL_MS: usize = 100;
+
+/// Feeds the spectrum analyzer and publishes the latest spectrum for the UI.
+pub struct SpectrumProcessor {
+    analyzer: SpectrumAnalyzer,
+    spectrum: Arc<Mutex<Vec<f32>>>,
+    channels: usize,
+    /// Frames between two analyses, and since the last one
+    interval_frames: usize,
+    pending_frames: usize,
+}
+
+impl Default for SpectrumProcessor {
+    fn default() -> Self {
+        let mut processor = Self {
+            analyzer: SpectrumAnalyzer::new(SPECTRUM_FFT_SIZE, 44_100.0),
+            spectrum: Arc::new(Mutex::new(Vec::new())),
+            channels: 2,
+            interval_frames: 0,
+            pending_frames: 0,
+        };
+        processor.prepare(44_100, 2);
+        processor
+    }
+}
+
+impl SpectrumProcessor {
+    pub const NAME: &'static str = "spectrum";
+
+    /// Latest spectrum, updated at most every `SPECTRUM_INTERVAL_MS`.
+    pub fn spectrum(&self) -> Arc<Mutex<Vec<f32>>> {
+        self.spectrum.clone()
+    }
+}
+
+impl DspProcessor for SpectrumProcessor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn prepare(&mut self, sample_rate: u32, channels: usize) {
+        self.analyzer = SpectrumAnalyzer::new(SPECTRUM_FFT_SIZE, sample_rate as f32);
+        self.channels = channels.max(1);
+        self.interval_frames = sample_rate as usize * SPECTRUM_INTERVAL_MS / 1000;
+        // The first block is analyzed right away
+        self.pending_frames = self.interval_frames;
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        self.analyzer.add_samples(block);
+        self.pending_frames += block.len() / self.channels;
+        if self.pending_frames < self.interval_frames {
+            return;
+        }
+        self.pending_frames = 0;
+        if let Ok(mut spectrum) = self.spectrum.lock() {
+            *spectrum = self.analyzer.get_spectrum();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.analyzer.reset();
+        self.pending_frames = self.interval_frames;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+#[path = "./processors.tests.rs"]
+mod tests;
@@ -0,0 +1,118 @@
+use super::*;
+
+fn prepared<T: DspProcessor + Default>(sample_rate: u32, channels: usize) -> T {
+    let mut processor = T::default();
+    processor.prepare(sample_rate, channels);
+    processor
+}
+
+#[test]
+fn test_gain_ramps_to_target() {
+    let mut gain: GainProcessor = prepared(44_100, 1);
+    gain.set_gain(0.5);
+    let mut block = vec![1.0; 4];
+    gain.process(&mut block);
+    assert_eq!(block, vec![0.875, 0.75, 0.625, 0.5]);
+
+    let mut block = vec![1.0; 2];
+    gain.process(&mut block);
+    assert_eq!(block, vec![0.5, 0.5]);
+}
+
+#[test]
+fn test_balance() {
+    let mut balance: BalanceProcessor = prepared(44_100, 2);
+    balance.set_balance(-0.5);
+    let mut block = vec![1.0, 1.0, 0.5, 0.5];
+    balance.process(&mut block);
+    assert_eq!(block, vec![1.0, 0.5, 0.5, 0.25]);
+
+    let mut mono: BalanceProcessor = prepared(44_100, 1);
+    mono.set_balance(1.0);
+    let mut block = vec![1.0, 1.0];
+    mono.process(&mut block);
+    assert_eq!(block, vec![1.0, 1.0]);
+}
+
+#[test]
+fn test_limiter_keeps_peaks_under_ceiling() {
+    let mut limiter: LimiterProcessor = prepared(44_100, 2);
+    limiter.set_ceiling_db(-6.0);
+    let ceiling = 10f32.powf(-6.0 / 20.0);
+    let latency = limiter.latency_frames();
+    assert_eq!(latency, 220);
+
+    let input: Vec<f32> = (0..44_100)
+        .flat_map(|i| {
+            let s = (i as f32 * 0.05).sin() * if i > 10_000 { 1.5 } else { 0.25 };
+            [s, -s]
+        })
+        .collect();
+    let mut output = input.clone();
+    for block in output.chunks_mut(1024) {
+        limiter.process(block);
+    }
+
+    assert!(output.iter().all(|s| s.abs() <= ceiling + 1e-6));
+    // Quiet passages come out unchanged, only delayed
+    for frame in 0..5_000 {
+        assert!((output[(frame + latency) * 2] - input[frame * 2]).abs() < 1e-6);
+    }
+    // The look-ahead lowers the gain before loud peaks instead of clipping them
+    let loud = &output[30_000 * 2..31_000 * 2];
+    let peak = loud.iter().fold(0.0f32, |p, s| p.max(s.abs()));
+    assert!(peak > ceiling * 0.9);
+}
+
+#[test]
+fn test_limiter_reset_clears_delay() {
+    let mut limiter: LimiterProcessor = prepared(1_000, 1);
+    let mut block = vec![0.5; 10];
+    limiter.process(&mut block);
+    limiter.reset();
+    let mut block = vec![0.0; 10];
+    limiter.process(&mut block);
+    assert!(block.iter().all(|s| *s == 0.0));
+}
+
+#[test]
+fn test_equalizer_processor_applies_settings() {
+    let mut equalizer: EqualizerProcessor = prepared(44_100, 1);
+    let settings = EqualizerSettings {
+        enabled: true,
+        preamp_db: -6.0,
+        ..Default::default()
+    };
+    equalizer.set_settings(settings);
+    let mut block = vec![1.0];
+    equalizer.process(&mut block);
+    assert!((block[0] - 10f32.powf(-6.0 / 20.0)).abs() < 1e-6);
+}
+
+#[test]
+fn test_spectrum_processor_publishes_spectrum() {
+    let mut spectrum: SpectrumProcessor = prepared(44_100, 1);
+    let output = spectrum.spectrum();
+    let mut block: Vec<f32> = (0..SPECTRUM_FFT_SIZE)
+        .map(|i| (i as f32 * 0.3).sin())
+        .collect();
+    spectrum.process(&mut block);
+    let data = output.lock().unwrap();
+    assert!(!data.is_empty());
+    assert!(data.iter().any(|v| *v > 0.0));
+}
+
+#[test]
+fn test_spectrum_processor_analyzes_at_the_event_rate() {
+    let mut spectrum: SpectrumProcessor = prepared(1_000, 1);
+    let output = spectrum.spectrum();
+    let mut block = vec![0.5; 60];
+    spectrum.process(&mut block);
+    assert!(!output.lock().unwrap().is_empty());
+
+    output.lock().unwrap().clear();
+    spectrum.process(&mut block);
+    assert!(output.lock().unwrap().is_empty());
+    spectrum.process(&mut block);
+    assert!(!output.lock().unwrap().is_empty());
+}
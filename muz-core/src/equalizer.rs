@@ -0,0 +1,314 @@
+use std::f64::consts::PI;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Center frequencies of the graphic equalizer, one band per octave.
+pub const GRAPHIC_BANDS_HZ: [f32; 10] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1_000.0, 2_000.0, 4_000.0, 8_000.0, 16_000.0,
+];
+
+const GRAPHIC_BAND_Q: f32 = std::f32::consts::SQRT_2;
+const MAX_GAIN_DB: f32 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterType {
+    Peaking,
+    LowShelf,
+    HighShelf,
+    LowPass,
+    HighPass,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EqualizerBand {
+    pub filter_type: FilterType,
+    pub frequency_hz: f32,
+    /// Ignored by the pass filters.
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EqualizerPreset {
+    pub name: String,
+    pub preamp_db: f32,
+    pub graphic_gains_db: [f32; 10],
+    #[serde(default)]
+    pub parametric_bands: Vec<EqualizerBand>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EqualizerSettings {
+    pub enabled: bool,
+    /// Name of the preset the current values came from, if unchanged since.
+    pub preset: Option<String>,
+    pub preamp_db: f32,
+    pub graphic_gains_db: [f32; 10],
+    pub parametric_bands: Vec<EqualizerBand>,
+    pub custom_presets: Vec<EqualizerPreset>,
+}
+
+impl Default for EqualizerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            preset: Some("Flat".to_string()),
+            preamp_db: 0.0,
+            graphic_gains_db: [0.0; 10],
+            parametric_bands: Vec::new(),
+            custom_presets: Vec::new(),
+        }
+    }
+}
+
+fn builtin(name: &str, preamp_db: f32, graphic_gains_db: [f32; 10]) -> EqualizerPreset {
+    EqualizerPreset {
+        name: name.to_string(),
+        preamp_db,
+        graphic_gains_db,
+        parametric_bands: Vec::new(),
+    }
+}
+
+pub fn builtin_presets() -> Vec<EqualizerPreset> {
+    vec![
+        builtin("Flat", 0.0, [0.0; 10]),
+        builtin(
+            "Bass Boost",
+            -6.0,
+            [6.0, 5.0, 4.0, 2.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0],
+        ),
+        builtin(
+            "Treble Boost",
+            -5.0,
+            [0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 2.0, 3.5, 4.5, 5.0],
+        ),
+        builtin(
+            "Vocal",
+            -3.0,
+            [-2.0, -2.0, -1.0, 1.0, 3.0, 3.0, 2.5, 1.5, 0.0, -1.0],
+        ),
+        builtin(
+            "Rock",
+            -4.0,
+            [4.0, 3.0, 2.0, 0.5, -1.0, -0.5, 1.0, 2.5, 3.5, 4.0],
+        ),
+        builtin(
+            "Classical",
+            -3.0,
+            [3.0, 2.0, 1.0, 0.0, 0.0, 0.0, -1.0, -1.0, 1.0, 2.5],
+        ),
+        builtin(
+            "Electronic",
+            -5.0,
+            [5.0, 4.0, 1.5, 0.0, -1.5, 1.0, 0.5, 1.5, 4.0, 4.5],
+        ),
+    ]
+}
+
+impl EqualizerSettings {
+    pub fn presets(&self) -> Vec<EqualizerPreset> {
+        let mut presets = builtin_presets();
+        presets.extend(self.custom_presets.iter().cloned());
+        presets
+    }
+
+    pub fn apply_preset(&mut self, name: &str) -> Result<()> {
+        let preset = self
+            .presets()
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| anyhow!("Unknown equalizer preset: {name}"))?;
+        self.preamp_db = preset.preamp_db;
+        self.graphic_gains_db = preset.graphic_gains_db;
+        self.parametric_bands = preset.parametric_bands;
+        self.preset = Some(preset.name);
+        Ok(())
+    }
+
+    /// Stores the current values as a custom preset, replacing one with the same name.
+    pub fn save_preset(&mut self, name: &str) -> Result<()> {
+        if builtin_presets().iter().any(|p| p.name == name) {
+            return Err(anyhow!("Cannot overwrite built-in preset: {name}"));
+        }
+        let preset = EqualizerPreset {
+            name: name.to_string(),
+            preamp_db: self.preamp_db,
+            graphic_gains_db: self.graphic_gains_db,
+            parametric_bands: self.parametric_bands.clone(),
+        };
+        match self.custom_presets.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = preset,
+            None => self.custom_presets.push(preset),
+        }
+        self.preset = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn delete_preset(&mut self, name: &str) {
+        self.custom_presets.retain(|p| p.name != name);
+        if self.preset.as_deref() == Some(name) {
+            self.preset = None;
+        }
+    }
+
+    fn bands(&self) -> impl Iterator<Item = EqualizerBand> + '_ {
+        let graphic = GRAPHIC_BANDS_HZ
+            .iter()
+            .zip(self.graphic_gains_db)
+            .map(|(&frequency_hz, gain_db)| EqualizerBand {
+                filter_type: FilterType::Peaking,
+                frequency_hz,
+                gain_db,
+                q: GRAPHIC_BAND_Q,
+            });
+        graphic.chain(self.parametric_bands.iter().copied())
+    }
+}
+
+/// Biquad coefficients normalized by a0, following the RBJ audio EQ cookbook.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Coefficients {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Coefficients {
+    /// Returns `None` for bands that leave the signal unchanged.
+    fn for_band(band: &EqualizerBand, sample_rate: u32) -> Option<Self> {
+        let boosts = !matches!(band.filter_type, FilterType::LowPass | FilterType::HighPass);
+        let gain_db = band.gain_db.clamp(-MAX_GAIN_DB, MAX_GAIN_DB) as f64;
+        if boosts && gain_db.abs() < 0.01 {
+            return None;
+        }
+
+        let nyquist = sample_rate as f64 / 2.0;
+        let frequency = (band.frequency_hz as f64).clamp(1.0, nyquist * 0.98);
+        let q = (band.q as f64).max(0.05);
+        let w0 = 2.0 * PI * frequency / sample_rate as f64;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a = 10f64.powf(gain_db / 40.0);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        let (b, a) = match band.filter_type {
+            FilterType::Peaking => (
+                [1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a],
+                [1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a],
+            ),
+            FilterType::LowShelf => (
+                [
+                    a * ((a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha),
+                ],
+                [
+                    (a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha,
+                ],
+            ),
+            FilterType::HighShelf => (
+                [
+                    a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha),
+                ],
+                [
+                    (a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha,
+                ],
+            ),
+            FilterType::LowPass => (
+                [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+            FilterType::HighPass => (
+                [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+        };
+
+        Some(Self {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+        })
+    }
+}
+
+/// Applies the equalizer settings to interleaved f32 samples.
+#[derive(Debug, Clone)]
+pub struct Equalizer {
+    sample_rate: u32,
+    channels: usize,
+    enabled: bool,
+    preamp: f32,
+    filters: Vec<Option<Coefficients>>,
+    /// Transposed direct form II state, per band and channel.
+    state: Vec<Vec<[f64; 2]>>,
+}
+
+impl Equalizer {
+    pub fn new(settings: &EqualizerSettings, sample_rate: u32, channels: usize) -> Self {
+        let mut equalizer = Self {
+            sample_rate,
+            channels: channels.max(1),
+            enabled: false,
+            preamp: 1.0,
+            filters: Vec::new(),
+            state: Vec::new(),
+        };
+        equalizer.update(settings);
+        equalizer
+    }
+
+    /// Recomputes the filters, keeping the state of existing bands so playback doesn't click.
+    pub fn update(&mut self, settings: &EqualizerSettings) {
+        self.enabled = settings.enabled;
+        self.preamp = 10f32.powf(settings.preamp_db.clamp(-MAX_GAIN_DB, MAX_GAIN_DB) / 20.0);
+        self.filters = settings
+            .bands()
+            .map(|band| Coefficients::for_band(&band, self.sample_rate))
+            .collect();
+        self.state
+            .resize(self.filters.len(), vec![[0.0; 2]; self.channels]);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn process_sample(&mut self, channel: usize, sample: f32) -> f32 {
+        if !self.enabled {
+            return sample;
+        }
+        let channel = channel % self.channels;
+        let mut x = (sample * self.preamp) as f64;
+        for (filter, state) in self.filters.iter().zip(self.state.iter_mut()) {
+            let Some(c) = filter else {
+                continue;
+            };
+            let z = &mut state[channel];
+            let y = c.b[0] * x + z[0];
+            z[0] = c.b[1] * x - c.a[0] * y + z[1];
+            z[1] = c.b[2] * x - c.a[1] * y;
+            x = y;
+        }
+        x as f32
+    }
+
+    pub fn process(&mut self, interleaved: &mut [f32]) {
+        for (index, sample) in interleaved.iter_mut().enumerate() {
+            *sample = self.process_sample(index % self.channels, *sample);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./equalizer.tests.rs"]
+mod tests;
@@ -0,0 +1,124 @@
+use super::*;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+fn enabled() -> EqualizerSettings {
+    EqualizerSettings {
+        enabled: true,
+        ..Default::default()
+    }
+}
+
+/// Steady-state RMS gain of the equalizer for a mono sine at `frequency`.
+fn gain_at(settings: &EqualizerSettings, frequency: f32) -> f32 {
+    let mut equalizer = Equalizer::new(settings, SAMPLE_RATE, 1);
+    let mut samples: Vec<f32> = (0..SAMPLE_RATE / 2)
+        .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+        .collect();
+    let input_rms = rms(&samples[samples.len() / 2..]);
+    equalizer.process(&mut samples);
+    rms(&samples[samples.len() / 2..]) / input_rms
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+fn db(gain: f32) -> f32 {
+    20.0 * gain.log10()
+}
+
+#[test]
+fn test_flat_equalizer_is_transparent() {
+    let mut equalizer = Equalizer::new(&enabled(), SAMPLE_RATE, 2);
+    let mut samples = vec![0.5, -0.25, 0.1, 0.9];
+    equalizer.process(&mut samples);
+    assert_eq!(samples, vec![0.5, -0.25, 0.1, 0.9]);
+}
+
+#[test]
+fn test_disabled_equalizer_ignores_bands() {
+    let settings = EqualizerSettings {
+        graphic_gains_db: [12.0; 10],
+        preamp_db: -6.0,
+        ..Default::default()
+    };
+    assert_eq!(gain_at(&settings, 1_000.0), 1.0);
+}
+
+#[test]
+fn test_graphic_band_boosts_its_frequency() {
+    let mut settings = enabled();
+    settings.graphic_gains_db[5] = 6.0;
+    assert!((db(gain_at(&settings, 1_000.0)) - 6.0).abs() < 0.2);
+    assert!(db(gain_at(&settings, 100.0)).abs() < 0.5);
+}
+
+#[test]
+fn test_preamp() {
+    let mut settings = enabled();
+    settings.preamp_db = -6.0;
+    assert!((db(gain_at(&settings, 1_000.0)) + 6.0).abs() < 0.05);
+}
+
+#[test]
+fn test_parametric_filters() {
+    let band = |filter_type, frequency_hz, gain_db| EqualizerBand {
+        filter_type,
+        frequency_hz,
+        gain_db,
+        q: std::f32::consts::FRAC_1_SQRT_2,
+    };
+
+    let mut settings = enabled();
+    settings.parametric_bands = vec![band(FilterType::LowPass, 1_000.0, 0.0)];
+    assert!(db(gain_at(&settings, 100.0)).abs() < 0.5);
+    assert!(db(gain_at(&settings, 8_000.0)) < -30.0);
+
+    settings.parametric_bands = vec![band(FilterType::HighPass, 1_000.0, 0.0)];
+    assert!(db(gain_at(&settings, 100.0)) < -30.0);
+
+    settings.parametric_bands = vec![band(FilterType::LowShelf, 200.0, -6.0)];
+    assert!((db(gain_at(&settings, 40.0)) + 6.0).abs() < 0.5);
+    assert!(db(gain_at(&settings, 5_000.0)).abs() < 0.2);
+
+    settings.parametric_bands = vec![band(FilterType::HighShelf, 4_000.0, 6.0)];
+    assert!((db(gain_at(&settings, 15_000.0)) - 6.0).abs() < 0.5);
+}
+
+#[test]
+fn test_apply_builtin_preset() {
+    let mut settings = enabled();
+    settings.apply_preset("Bass Boost").unwrap();
+    assert_eq!(settings.preset.as_deref(), Some("Bass Boost"));
+    assert_eq!(settings.preamp_db, -6.0);
+    assert_eq!(settings.graphic_gains_db[0], 6.0);
+    assert!(settings.apply_preset("Missing").is_err());
+}
+
+#[test]
+fn test_save_and_delete_custom_preset() {
+    let mut settings = enabled();
+    settings.graphic_gains_db[3] = 2.0;
+    settings.save_preset("Mine").unwrap();
+    settings.graphic_gains_db[3] = 4.0;
+    settings.save_preset("Mine").unwrap();
+    assert_eq!(settings.custom_presets.len(), 1);
+    assert_eq!(settings.custom_presets[0].graphic_gains_db[3], 4.0);
+
+    settings.apply_preset("Flat").unwrap();
+    settings.apply_preset("Mine").unwrap();
+    assert_eq!(settings.graphic_gains_db[3], 4.0);
+    assert!(settings.save_preset("Flat").is_err());
+
+    settings.delete_preset("Mine");
+    assert!(settings.custom_presets.is_empty());
+    assert_eq!(settings.preset, None);
+}
+
+#[test]
+fn test_settings_deserialize_with_missing_fields() {
+    let settings: EqualizerSettings = serde_json::from_str(r#"{"enabled": true}"#).unwrap();
+    assert!(settings.enabled);
+    assert_eq!(settings.graphic_gains_db, [0.0; 10]);
+}
@@ -0,0 +1,116 @@
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// A stable, machine-readable identifier for an error the UI can branch on.
+pub trait ErrorCode {
+    fn code(&self) -> &'static str;
+
+    /// Structured context for the error, beyond its message.
+    fn details(&self) -> Option<Value> {
+        None
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PlaybackError {
+    #[error("No track to play")]
+    NothingToPlay,
+    #[error("No track to resume playback")]
+    NotPaused,
+    #[error("Playback speed must be between {min} and {max}, got {speed}")]
+    SpeedOutOfRange { speed: f32, min: f32, max: f32 },
+    #[error("Pitch shift must be within ±{max} semitones, got {semitones}")]
+    PitchOutOfRange { semitones: f32, max: f32 },
+    /// The driver failed to carry out an action, e.g. "seek"
+    #[error("Failed to {0}")]
+    Driver(&'static str),
+}
+
+impl ErrorCode for PlaybackError {
+    fn code(&self) -> &'static str {
+        match self {
+            PlaybackError::NothingToPlay => "NOTHING_TO_PLAY",
+            PlaybackError::NotPaused => "NOT_PAUSED",
+            PlaybackError::SpeedOutOfRange { .. } => "SPEED_OUT_OF_RANGE",
+            PlaybackError::PitchOutOfRange { .. } => "PITCH_OUT_OF_RANGE",
+            PlaybackError::Driver(_) => "PLAYBACK_DRIVER_FAILED",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            PlaybackError::SpeedOutOfRange { speed, min, max } => {
+                Some(json!({ "value": speed, "min": min, "max": max }))
+            }
+            PlaybackError::PitchOutOfRange { semitones, max } => {
+                Some(json!({ "value": semitones, "min": -max, "max": max }))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QueueError {
+    #[error("No queue available")]
+    Unavailable,
+    #[error("Track not found in queue")]
+    TrackNotInQueue(String),
+    #[error("Index {index} out of bounds for a queue of {len} tracks")]
+    IndexOutOfBounds { index: usize, len: usize },
+}
+
+impl ErrorCode for QueueError {
+    fn code(&self) -> &'static str {
+        match self {
+            QueueError::Unavailable => "QUEUE_UNAVAILABLE",
+            QueueError::TrackNotInQueue(_) => "TRACK_NOT_IN_QUEUE",
+            QueueError::IndexOutOfBounds { .. } => "QUEUE_INDEX_OUT_OF_BOUNDS",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            QueueError::TrackNotInQueue(track_id) => Some(json!({ "trackId": track_id })),
+            QueueError::IndexOutOfBounds { index, len } => {
+                Some(json!({ "index": index, "len": len }))
+            }
+            QueueError::Unavailable => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LibraryError {
+    #[error("Track not found")]
+    TrackNotFound(String),
+    #[error("Album not found")]
+    AlbumNotFound { album: String, artist: String },
+    #[error("Library path {path:?} {reason}")]
+    PathInvalid { path: PathBuf, reason: &'static str },
+    #[error("Loudness analysis is already running")]
+    AnalysisRunning,
+}
+
+impl ErrorCode for LibraryError {
+    fn code(&self) -> &'static str {
+        match self {
+            LibraryError::TrackNotFound(_) => "TRACK_NOT_FOUND",
+            LibraryError::AlbumNotFound { .. } => "ALBUM_NOT_FOUND",
+            LibraryError::PathInvalid { .. } => "LIBRARY_PATH_INVALID",
+            LibraryError::AnalysisRunning => "ANALYSIS_ALREADY_RUNNING",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            LibraryError::TrackNotFound(track_id) => Some(json!({ "trackId": track_id })),
+            LibraryError::AlbumNotFound { album, artist } => {
+                Some(json!({ "album": album, "artist": artist }))
+            }
+            LibraryError::PathInvalid { path, .. } => Some(json!({ "path": path })),
+            LibraryError::AnalysisRunning => None,
+        }
+    }
+}
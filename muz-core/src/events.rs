@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::playback::{PlaybackState, RepeatMode};
+use crate::track::Track;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryUpdateEvent {
+    pub has_history: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackChangedEvent {
+    pub track: Option<Track>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueChangedEvent {
+    pub queue: Vec<Track>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEvent {
+    pub position: f64,
+    pub frames_played: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lyric_line: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SpectrumEvent {
+    pub spectrum_data: Vec<f32>,
+}
+
+/// The playback state or one of the player settings changed.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StateChangedEvent {
+    pub state: PlaybackState,
+    pub volume: f32,
+    pub muted: bool,
+    pub speed: f32,
+    pub pitch_semitones: f32,
+    pub repeat: RepeatMode,
+    pub shuffle: bool,
+}
+
+/// The library was rescanned.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryChangedEvent {
+    pub track_count: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LoudnessAnalysisProgressEvent {
+    pub processed: usize,
+    pub total: usize,
+    pub track_id: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LoudnessAnalysisFinishedEvent {
+    pub analyzed: usize,
+    pub cancelled: bool,
+}
+
+/// A track failed to open or decode and was skipped.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackErrorEvent {
+    pub track_id: String,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// There was no output to play on, so playback paused on the current track.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputUnavailableEvent {
+    pub reason: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDeviceFallbackEvent {
+    pub requested_device: String,
+    pub reason: String,
+}
+
+/// Everything the player and the library report, published on the `EventBus`.
+#[derive(Clone, Debug)]
+pub enum PlayerEvent {
+    Progress(ProgressEvent),
+    Spectrum(SpectrumEvent),
+    HistoryUpdate(HistoryUpdateEvent),
+    // Boxed, as every event on the bus is as large as the largest
+    TrackChanged(Box<TrackChangedEvent>),
+    QueueChanged(QueueChangedEvent),
+    StateChanged(StateChangedEvent),
+    OutputDeviceFallback(OutputDeviceFallbackEvent),
+    OutputUnavailable(OutputUnavailableEvent),
+    PlaybackError(PlaybackErrorEvent),
+    LibraryChanged(LibraryChangedEvent),
+    LoudnessAnalysisProgress(LoudnessAnalysisProgressEvent),
+    LoudnessAnalysisFinished(LoudnessAnalysisFinishedEvent),
+}
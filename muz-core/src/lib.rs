@@ -0,0 +1,24 @@
+//! The playback engine, library and settings of muz, shared by its frontends.
+
+pub mod artwork;
+pub mod bus;
+pub mod command;
+pub mod config;
+pub mod cue;
+pub mod decoder;
+pub mod driver;
+pub mod dsp;
+pub mod equalizer;
+pub mod error;
+pub mod events;
+pub mod library;
+pub mod loudness;
+pub mod lyrics;
+pub mod normalization;
+pub mod playback;
+pub mod queue;
+pub mod services;
+pub mod spectrum;
+pub mod tag_writer;
+pub mod time_stretch;
+pub mod track;
@@ -0,0 +1,325 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::cue::{CueSheet, EMBEDDED_CUE_EXTENSIONS};
+use crate::loudness::TrackLoudness;
+use crate::track::{Track, SUPPORTED_EXTENSIONS};
+use std::boxed::Box;
+use std::ffi::OsStr;
+use tokio::fs;
+
+/// Where the library keeps what it learned about the files, next to the config.
+pub const CACHE_FILE: &str = "library-cache.json";
+
+pub struct Library {
+    pub path: PathBuf,
+    pub name: String,
+    pub tracks: Vec<Track>,
+    /// Analysis results keyed by file and segment start, since track ids change on rescan.
+    loudness: HashMap<(PathBuf, u64), TrackLoudness>,
+    /// Lengths found by scanning files whose headers have none, valid while unmodified.
+    durations: HashMap<PathBuf, ScannedDuration>,
+    cache_path: Option<PathBuf>,
+}
+
+/// The analysis results and scanned lengths as saved, since JSON maps only take string keys.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Cache {
+    loudness: Vec<CachedLoudness>,
+    durations: Vec<CachedDuration>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedLoudness {
+    path: PathBuf,
+    start_ms: u64,
+    loudness: TrackLoudness,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedDuration {
+    path: PathBuf,
+    #[serde(flatten)]
+    duration: ScannedDuration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScannedDuration {
+    modified: Option<SystemTime>,
+    total_frames: u64,
+    duration_ms: u64,
+}
+
+impl Library {
+    pub fn new(path: PathBuf, name: String) -> Self {
+        Self {
+            path,
+            name,
+            tracks: Vec::new(),
+            loudness: HashMap::new(),
+            durations: HashMap::new(),
+            cache_path: None,
+        }
+    }
+
+    /// Keeps the analysis results and scanned lengths in `path` across runs, taking those
+    /// saved there before.
+    pub fn use_cache(&mut self, path: PathBuf) {
+        if path.exists() {
+            let cache = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<Cache>(&content)?));
+            match cache {
+                Ok(cache) => {
+                    for cached in cache.loudness {
+                        self.loudness
+                            .insert((cached.path, cached.start_ms), cached.loudness);
+                    }
+                    for cached in cache.durations {
+                        self.durations.insert(cached.path, cached.duration);
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring the library cache {path:?}: {e}"),
+            }
+        }
+        self.cache_path = Some(path);
+    }
+
+    /// Writes the analysis results and scanned lengths to the cache, when the library has one.
+    pub fn save_cache(&self) -> Result<()> {
+        let Some(path) = &self.cache_path else {
+            return Ok(());
+        };
+        let cache = Cache {
+            loudness: self
+                .loudness
+                .iter()
+                .map(|((path, start_ms), loudness)| CachedLoudness {
+                    path: path.clone(),
+                    start_ms: *start_ms,
+                    loudness: *loudness,
+                })
+                .collect(),
+            durations: self
+                .durations
+                .iter()
+                .map(|(path, duration)| CachedDuration {
+                    path: path.clone(),
+                    duration: *duration,
+                })
+                .collect(),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(&cache)?)
+            .with_context(|| format!("Failed to save the library cache {path:?}"))
+    }
+
+    pub async fn initialize(&mut self) {
+        self.build_tracks().await;
+    }
+
+    async fn build_tracks(&mut self) {
+        self.tracks.clear();
+        let path = self.path.clone();
+        self.scan_directory_recursive(&path).await;
+
+        let loudness = std::mem::take(&mut self.loudness);
+        for track in &mut self.tracks {
+            if let Some(result) = loudness.get(&Self::loudness_key(track)) {
+                track.replay_gain = track.replay_gain.or(result.replay_gain());
+            }
+        }
+        self.loudness = loudness;
+
+        if let Err(e) = self.save_cache() {
+            tracing::warn!("{e:#}");
+        }
+    }
+
+    fn loudness_key(track: &Track) -> (PathBuf, u64) {
+        let start_ms = track.segment.as_ref().map_or(0, |s| s.start_ms);
+        (track.path.clone(), start_ms)
+    }
+
+    pub fn loudness(&self, track: &Track) -> Option<&TrackLoudness> {
+        self.loudness.get(&Self::loudness_key(track))
+    }
+
+    /// Stores an analysis result, using it as ReplayGain info when the file has no tags.
+    pub fn set_loudness(&mut self, track_id: &str, loudness: TrackLoudness) -> Option<Track> {
+        let track = self.tracks.iter_mut().find(|t| t.id == track_id)?;
+        track.replay_gain = track.replay_gain.or(loudness.replay_gain());
+        let track = track.clone();
+        self.loudness.insert(Self::loudness_key(&track), loudness);
+        Some(track)
+    }
+
+    /// Creates the track for a file, scanning its packets when the header has no length.
+    async fn new_track(&mut self, path: &Path) -> Track {
+        let mut track = Track::new(path);
+        if track.total_frames == 0 && track.metadata.is_some() {
+            self.complete_duration(&mut track).await;
+        }
+        track
+    }
+
+    async fn complete_duration(&mut self, track: &mut Track) {
+        let modified = std::fs::metadata(&track.path)
+            .and_then(|m| m.modified())
+            .ok();
+        let scanned = match self.durations.get(&track.path) {
+            Some(cached) if cached.modified == modified => *cached,
+            _ => match Self::scan_duration(track.path.clone()).await {
+                Ok((total_frames, duration_ms)) => {
+                    let scanned = ScannedDuration {
+                        modified,
+                        total_frames,
+                        duration_ms,
+                    };
+                    self.durations.insert(track.path.clone(), scanned);
+                    scanned
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to scan the length of {:?}: {e}", track.path);
+                    return;
+                }
+            },
+        };
+        track.total_frames = scanned.total_frames;
+        track.duration_ms = scanned.duration_ms;
+    }
+
+    /// Decodes every packet of the file, off the runtime as it reads the whole file.
+    async fn scan_duration(path: PathBuf) -> Result<(u64, u64)> {
+        tokio::task::spawn_blocking(move || Track::scan_duration(&path))
+            .await
+            .context("Length scan task failed")?
+    }
+
+    /// Flags a track that failed during playback. Rescanning clears the flag.
+    pub fn mark_unplayable(&mut self, track_id: &str) -> Option<Track> {
+        let track = self.tracks.iter_mut().find(|t| t.id == track_id)?;
+        track.unplayable = true;
+        Some(track.clone())
+    }
+
+    async fn scan_directory_recursive(&mut self, dir_path: &PathBuf) {
+        let mut audio_files = Vec::new();
+        let mut cue_files = Vec::new();
+        let mut sub_dirs = Vec::new();
+
+        if let Ok(mut entries) = fs::read_dir(dir_path).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                if path.is_file() {
+                    if let Some(ext) = path.extension().and_then(OsStr::to_str) {
+                        let ext = ext.to_lowercase();
+                        if ext == "cue" {
+                            cue_files.push(path);
+                        } else if SUPPORTED_EXTENSIONS.contains(&ext.as_str()) {
+                            audio_files.push(path);
+                        }
+                    }
+                } else if path.is_dir() {
+                    sub_dirs.push(path);
+                }
+            }
+        }
+
+        // Files described by a sidecar cue sheet are replaced by their virtual tracks
+        let mut covered_files = HashSet::new();
+        for cue_path in cue_files {
+            let sheet = match CueSheet::read(&cue_path) {
+                Ok(sheet) => sheet,
+                Err(e) => {
+                    tracing::warn!("Failed to parse cue sheet {:?}: {e}", cue_path);
+                    continue;
+                }
+            };
+            for file in &sheet.files {
+                if let Some(audio_path) = CueSheet::resolve_file(dir_path, file) {
+                    if covered_files.insert(audio_path.clone()) {
+                        let source = self.new_track(&audio_path).await;
+                        self.tracks.extend(sheet.split_track(file, &source));
+                    }
+                }
+            }
+        }
+
+        for path in audio_files {
+            if covered_files.contains(&path) {
+                continue;
+            }
+            let track = self.new_track(&path).await;
+            match Self::embedded_cue_sheet(&path) {
+                Some(sheet) if !sheet.files.is_empty() => {
+                    self.tracks
+                        .extend(sheet.split_track(&sheet.files[0], &track));
+                }
+                _ => self.tracks.push(track),
+            }
+        }
+
+        for path in sub_dirs {
+            Box::pin(self.scan_directory_recursive(&path)).await;
+        }
+    }
+
+    fn embedded_cue_sheet(path: &Path) -> Option<CueSheet> {
+        let ext = path.extension().and_then(OsStr::to_str)?.to_lowercase();
+        if EMBEDDED_CUE_EXTENSIONS.contains(&ext.as_str()) {
+            CueSheet::read_embedded(path)
+        } else {
+            None
+        }
+    }
+
+    pub fn create(&mut self) -> Result<()> {
+        todo!("Implement library creation logic");
+    }
+
+    pub fn delete(&mut self) -> Result<()> {
+        todo!("Implement library deletion logic");
+    }
+
+    pub fn tracks_cloned(&self) -> Vec<Track> {
+        self.tracks.clone()
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn track_by_id(&self, track_id: &str) -> Option<Track> {
+        self.tracks
+            .iter()
+            .find(|track| track.id == track_id)
+            .cloned()
+    }
+
+    pub fn update(&mut self, path: Option<PathBuf>, name: Option<String>) {
+        if let Some(p) = path {
+            self.path = p;
+        }
+        if let Some(n) = name {
+            self.name = n;
+        }
+    }
+
+    pub async fn rescan(&mut self) {
+        self.build_tracks().await;
+    }
+}
+
+#[cfg(test)]
+#[path = "./library.tests.rs"]
+mod tests;
@@ -0,0 +1,102 @@
+use super::*;
+use std::path::PathBuf;
+
+#[test]
+fn test_new_library_initialization() {
+    let path = PathBuf::from("/some/path/to/library");
+    let name = "My Library".to_string();
+    let library = Library::new(path.clone(), name.clone());
+    assert_eq!(library.path, path);
+    assert_eq!(library.name, name);
+    assert!(library.tracks.is_empty());
+}
+
+#[test]
+fn test_update_library() {
+    let mut library = Library::new(
+        PathBuf::from("/some/path/to/library"),
+        "My Library".to_string(),
+    );
+    library.update(
+        Some(PathBuf::from("/some/path/to/another/library")),
+        Some("New Library".to_string()),
+    );
+    assert_eq!(library.path, PathBuf::from("/some/path/to/another/library"));
+    assert_eq!(library.name, "New Library");
+}
+
+#[test]
+fn test_update_only_path() {
+    let mut library = Library::new(PathBuf::from("/some/path/to/library"), "Lib".to_string());
+    library.update(Some(PathBuf::from("/other_music")), None);
+    assert_eq!(library.path, PathBuf::from("/other_music"));
+    assert_eq!(library.name, "Lib");
+}
+
+#[test]
+fn test_update_only_name() {
+    let mut library = Library::new(PathBuf::from("/some/path/to/library"), "Lib".to_string());
+    library.update(None, Some("Renamed".to_string()));
+    assert_eq!(library.path, PathBuf::from("/some/path/to/library"));
+    assert_eq!(library.name, "Renamed");
+}
+
+#[tokio::test]
+async fn test_missing_length_is_scanned_and_cached() {
+    use crate::driver::{render::RenderOutput, wav::WavOutput};
+
+    let path = std::env::temp_dir().join(format!("muz-length-{}.wav", uuid::Uuid::new_v4()));
+    let mut output = WavOutput::new(&path);
+    output.write(&vec![0.0; 2 * 22_050], 44_100, 2).unwrap();
+    drop(output);
+
+    let mut library = Library::new(std::env::temp_dir(), "Scan".to_string());
+    let mut track = Track::new(&path);
+    track.total_frames = 0;
+    track.duration_ms = 0;
+    library.complete_duration(&mut track).await;
+    assert_eq!((track.total_frames, track.duration_ms), (22_050, 500));
+
+    // An unmodified file is not scanned again
+    library.durations.get_mut(&path).unwrap().duration_ms = 1;
+    library.complete_duration(&mut track).await;
+    assert_eq!(track.duration_ms, 1);
+
+    // Nor after a restart
+    let cache_path = path.with_extension("json");
+    library.use_cache(cache_path.clone());
+    library.save_cache().unwrap();
+    let mut library = Library::new(std::env::temp_dir(), "Scan".to_string());
+    library.use_cache(cache_path.clone());
+    library.complete_duration(&mut track).await;
+    assert_eq!(track.duration_ms, 1);
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(cache_path);
+}
+
+#[test]
+fn test_loudness_is_kept_across_runs() {
+    use crate::loudness::LoudnessResult;
+
+    let cache_path = std::env::temp_dir().join(format!("muz-cache-{}.json", uuid::Uuid::new_v4()));
+    let track = Track::new("/music/song.mp3");
+    let loudness = TrackLoudness {
+        track: LoudnessResult {
+            integrated_lufs: Some(-12.0),
+            loudness_range_lu: 4.0,
+            true_peak: 0.9,
+        },
+        album: None,
+    };
+
+    let mut library = Library::new(PathBuf::from("/music"), "Lib".to_string());
+    library.use_cache(cache_path.clone());
+    library.tracks.push(track.clone());
+    library.set_loudness(&track.id, loudness).unwrap();
+    library.save_cache().unwrap();
+
+    let mut library = Library::new(PathBuf::from("/music"), "Lib".to_string());
+    library.use_cache(cache_path.clone());
+    assert_eq!(library.loudness(&track), Some(&loudness));
+    let _ = std::fs::remove_file(cache_path);
+}
@@ -0,0 +1,352 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::decoder::TrackDecoder;
+use crate::normalization::ReplayGain;
+use crate::track::Track;
+
+/// ReplayGain 2.0 reference level.
+pub const REPLAY_GAIN_REFERENCE_LUFS: f64 = -18.0;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const INTEGRATED_RELATIVE_GATE_LU: f64 = -10.0;
+const RANGE_RELATIVE_GATE_LU: f64 = -20.0;
+const MOMENTARY_SUB_BLOCKS: usize = 4;
+const SHORT_TERM_SUB_BLOCKS: usize = 30;
+const TRUE_PEAK_OVERSAMPLING: usize = 4;
+const TRUE_PEAK_TAPS_PER_PHASE: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoudnessResult {
+    /// `None` when everything is below the absolute gate (e.g. silence).
+    pub integrated_lufs: Option<f64>,
+    pub loudness_range_lu: f64,
+    pub true_peak: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackLoudness {
+    pub track: LoudnessResult,
+    pub album: Option<LoudnessResult>,
+}
+
+impl TrackLoudness {
+    pub fn replay_gain(&self) -> Option<ReplayGain> {
+        let track_gain_db = self.track.replay_gain_db()?;
+        Some(ReplayGain {
+            track_gain_db: Some(track_gain_db),
+            track_peak: Some(self.track.true_peak as f32),
+            album_gain_db: self.album.and_then(|a| a.replay_gain_db()),
+            album_peak: self.album.map(|a| a.true_peak as f32),
+        })
+    }
+}
+
+impl LoudnessResult {
+    pub fn replay_gain_db(&self) -> Option<f32> {
+        self.integrated_lufs
+            .map(|lufs| (REPLAY_GAIN_REFERENCE_LUFS - lufs) as f32)
+    }
+}
+
+/// Handle on a running background analysis, shared with the worker to request cancellation.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisJob {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl AnalysisJob {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// BS.1770 K-weighting (high shelf followed by high pass) for any sample rate.
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / sample_rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Channel weights for L, R, C, LFE, Ls, Rs ordering.
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channel, channels >= 5) {
+        (3, true) => 0.0,
+        (4 | 5, true) => 1.41,
+        _ => 1.0,
+    }
+}
+
+fn energy_to_lufs(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// Measures the inter-sample peak by upsampling with a windowed-sinc polyphase filter.
+#[derive(Debug, Clone)]
+struct TruePeakMeter {
+    phases: Vec<[f64; TRUE_PEAK_TAPS_PER_PHASE]>,
+    history: Vec<[f64; TRUE_PEAK_TAPS_PER_PHASE]>,
+    peak: f64,
+}
+
+impl TruePeakMeter {
+    fn new(channels: usize) -> Self {
+        let taps = TRUE_PEAK_OVERSAMPLING * TRUE_PEAK_TAPS_PER_PHASE;
+        let center = (taps - 1) as f64 / 2.0;
+        let coefficients: Vec<f64> = (0..taps)
+            .map(|n| {
+                let x = (n as f64 - center) / TRUE_PEAK_OVERSAMPLING as f64;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (PI * x).sin() / (PI * x)
+                };
+                let window = 0.5 - 0.5 * (2.0 * PI * (n as f64 + 0.5) / taps as f64).cos();
+                sinc * window
+            })
+            .collect();
+
+        let phases = (0..TRUE_PEAK_OVERSAMPLING)
+            .map(|phase| {
+                let mut taps = [0.0; TRUE_PEAK_TAPS_PER_PHASE];
+                for (k, tap) in taps.iter_mut().enumerate() {
+                    *tap = coefficients[phase + k * TRUE_PEAK_OVERSAMPLING];
+                }
+                let sum: f64 = taps.iter().sum();
+                taps.iter_mut().for_each(|t| *t /= sum);
+                taps
+            })
+            .collect();
+
+        Self {
+            phases,
+            history: vec![[0.0; TRUE_PEAK_TAPS_PER_PHASE]; channels],
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, channel: usize, sample: f64) {
+        let history = &mut self.history[channel];
+        history.copy_within(0..TRUE_PEAK_TAPS_PER_PHASE - 1, 1);
+        history[0] = sample;
+        self.peak = self.peak.max(sample.abs());
+        for taps in &self.phases {
+            let mut value = 0.0;
+            for k in 0..TRUE_PEAK_TAPS_PER_PHASE {
+                value += taps[k] * history[k];
+            }
+            self.peak = self.peak.max(value.abs());
+        }
+    }
+}
+
+/// Streaming EBU R128 meter: feed interleaved frames, then read the result.
+#[derive(Debug, Clone)]
+pub struct LoudnessAnalyzer {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    sub_block_frames: usize,
+    sub_block_position: usize,
+    sub_block_energy: f64,
+    sub_blocks: VecDeque<f64>,
+    momentary_blocks: Vec<f64>,
+    short_term_blocks: Vec<f64>,
+    true_peak: TruePeakMeter,
+}
+
+impl LoudnessAnalyzer {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            channels,
+            filters: vec![k_weighting(sample_rate as f64); channels],
+            // Blocks overlap by 75%, so everything is built from 100 ms sub-blocks
+            sub_block_frames: (sample_rate as usize / 10).max(1),
+            sub_block_position: 0,
+            sub_block_energy: 0.0,
+            sub_blocks: VecDeque::with_capacity(SHORT_TERM_SUB_BLOCKS),
+            momentary_blocks: Vec::new(),
+            short_term_blocks: Vec::new(),
+            true_peak: TruePeakMeter::new(channels),
+        }
+    }
+
+    pub fn add_frames(&mut self, interleaved: &[f32]) {
+        for frame in interleaved.chunks_exact(self.channels) {
+            for (channel, &sample) in frame.iter().enumerate() {
+                let sample = sample as f64;
+                self.true_peak.process(channel, sample);
+                let [shelf, high_pass] = &mut self.filters[channel];
+                let filtered = high_pass.process(shelf.process(sample));
+                self.sub_block_energy +=
+                    channel_weight(channel, self.channels) * filtered * filtered;
+            }
+
+            self.sub_block_position += 1;
+            if self.sub_block_position == self.sub_block_frames {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    fn finish_sub_block(&mut self) {
+        let energy = self.sub_block_energy / self.sub_block_frames as f64;
+        self.sub_block_energy = 0.0;
+        self.sub_block_position = 0;
+
+        if self.sub_blocks.len() == SHORT_TERM_SUB_BLOCKS {
+            self.sub_blocks.pop_front();
+        }
+        self.sub_blocks.push_back(energy);
+
+        let mean_of_last = |n: usize| self.sub_blocks.iter().rev().take(n).sum::<f64>() / n as f64;
+        if self.sub_blocks.len() >= MOMENTARY_SUB_BLOCKS {
+            self.momentary_blocks
+                .push(mean_of_last(MOMENTARY_SUB_BLOCKS));
+        }
+        if self.sub_blocks.len() == SHORT_TERM_SUB_BLOCKS {
+            self.short_term_blocks
+                .push(mean_of_last(SHORT_TERM_SUB_BLOCKS));
+        }
+    }
+
+    pub fn result(&self) -> LoudnessResult {
+        Self::combined(std::slice::from_ref(self))
+    }
+
+    /// Album loudness is measured over the blocks of all tracks as if they were one stream.
+    pub fn combined(analyzers: &[LoudnessAnalyzer]) -> LoudnessResult {
+        let momentary: Vec<f64> = analyzers
+            .iter()
+            .flat_map(|a| a.momentary_blocks.iter().copied())
+            .collect();
+        let short_term: Vec<f64> = analyzers
+            .iter()
+            .flat_map(|a| a.short_term_blocks.iter().copied())
+            .collect();
+
+        LoudnessResult {
+            integrated_lufs: integrated_loudness(&momentary),
+            loudness_range_lu: loudness_range(&short_term),
+            true_peak: analyzers
+                .iter()
+                .map(|a| a.true_peak.peak)
+                .fold(0.0, f64::max),
+        }
+    }
+}
+
+/// Blocks above both the absolute and relative gates, as loudness values.
+fn gated_loudness(blocks: &[f64], relative_gate_lu: f64) -> Vec<f64> {
+    let above_absolute: Vec<f64> = blocks
+        .iter()
+        .copied()
+        .filter(|&e| energy_to_lufs(e) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if above_absolute.is_empty() {
+        return Vec::new();
+    }
+    let mean = above_absolute.iter().sum::<f64>() / above_absolute.len() as f64;
+    let relative_gate = energy_to_lufs(mean) + relative_gate_lu;
+    above_absolute
+        .into_iter()
+        .map(energy_to_lufs)
+        .filter(|&l| l > relative_gate)
+        .collect()
+}
+
+fn integrated_loudness(blocks: &[f64]) -> Option<f64> {
+    let gated = gated_loudness(blocks, INTEGRATED_RELATIVE_GATE_LU);
+    if gated.is_empty() {
+        return None;
+    }
+    let mean_energy = gated
+        .iter()
+        .map(|l| 10f64.powf((l + 0.691) / 10.0))
+        .sum::<f64>()
+        / gated.len() as f64;
+    Some(energy_to_lufs(mean_energy))
+}
+
+/// EBU Tech 3342: spread between the 10th and 95th percentiles of gated short-term loudness.
+fn loudness_range(blocks: &[f64]) -> f64 {
+    let mut gated = gated_loudness(blocks, RANGE_RELATIVE_GATE_LU);
+    if gated.is_empty() {
+        return 0.0;
+    }
+    gated.sort_by(f64::total_cmp);
+    let percentile = |p: f64| gated[((gated.len() - 1) as f64 * p).round() as usize];
+    percentile(0.95) - percentile(0.10)
+}
+
+/// Decodes a track (or its cue segment) and measures it.
+pub fn analyze_track(track: &Track, job: &AnalysisJob) -> Result<LoudnessAnalyzer> {
+    let mut decoder = TrackDecoder::open(track)?;
+    let mut analyzer = LoudnessAnalyzer::new(decoder.sample_rate(), decoder.channels());
+    loop {
+        if job.is_cancelled() {
+            return Err(anyhow!("Loudness analysis cancelled"));
+        }
+        match decoder.next_block()? {
+            Some(samples) => analyzer.add_frames(samples),
+            None => break,
+        }
+    }
+
+    Ok(analyzer)
+}
+
+#[cfg(test)]
+#[path = "./loudness.tests.rs"]
+mod tests;
@@ -0,0 +1,127 @@
+use super::*;
+
+const SAMPLE_RATE: u32 = 48_000;
+
+/// Interleaved stereo sine with the same signal on both channels.
+fn stereo_sine(frequency: f64, amplitude: f64, seconds: f64) -> Vec<f32> {
+    let frames = (seconds * SAMPLE_RATE as f64) as usize;
+    (0..frames)
+        .flat_map(|n| {
+            let value = amplitude * (2.0 * PI * frequency * n as f64 / SAMPLE_RATE as f64).sin();
+            [value as f32, value as f32]
+        })
+        .collect()
+}
+
+fn analyze(samples: &[f32]) -> LoudnessAnalyzer {
+    let mut analyzer = LoudnessAnalyzer::new(SAMPLE_RATE, 2);
+    analyzer.add_frames(samples);
+    analyzer
+}
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+#[test]
+fn test_integrated_loudness_of_sine() {
+    // A 1 kHz sine at -23 dBFS on both stereo channels measures -23 LUFS
+    let amplitude = 10f64.powf(-23.0 / 20.0);
+    let result = analyze(&stereo_sine(1000.0, amplitude, 3.0)).result();
+    assert_close(result.integrated_lufs.unwrap(), -23.0, 0.1);
+}
+
+#[test]
+fn test_integrated_loudness_tracks_level() {
+    let quiet = analyze(&stereo_sine(1000.0, 0.05, 3.0)).result();
+    let loud = analyze(&stereo_sine(1000.0, 0.5, 3.0)).result();
+    let difference = loud.integrated_lufs.unwrap() - quiet.integrated_lufs.unwrap();
+    assert_close(difference, 20.0, 0.05);
+}
+
+#[test]
+fn test_silence_is_gated() {
+    let result = analyze(&vec![0.0; SAMPLE_RATE as usize * 2 * 3]).result();
+    assert_eq!(result.integrated_lufs, None);
+    assert_eq!(result.loudness_range_lu, 0.0);
+    assert_eq!(result.true_peak, 0.0);
+
+    let mut samples = vec![0.0; SAMPLE_RATE as usize * 2 * 3];
+    samples.extend(stereo_sine(1000.0, 0.1, 3.0));
+    // Blocks straddling the onset pass the relative gate and pull the result down slightly
+    let result = analyze(&samples).result();
+    assert_close(result.integrated_lufs.unwrap(), -20.0, 0.3);
+}
+
+#[test]
+fn test_loudness_range() {
+    let steady = analyze(&stereo_sine(1000.0, 0.1, 6.0)).result();
+    assert_close(steady.loudness_range_lu, 0.0, 0.1);
+
+    let mut samples = stereo_sine(1000.0, 0.1, 6.0);
+    samples.extend(stereo_sine(1000.0, 0.01, 6.0));
+    let varying = analyze(&samples).result();
+    assert_close(varying.loudness_range_lu, 20.0, 1.0);
+}
+
+#[test]
+fn test_true_peak_exceeds_sample_peak() {
+    // At fs/4 with a 45° phase offset every sample sits at ±0.707 of the real peak
+    let frames = SAMPLE_RATE as usize;
+    let samples: Vec<f32> = (0..frames)
+        .flat_map(|n| {
+            let value = 0.5 * (PI / 2.0 * n as f64 + PI / 4.0).sin();
+            [value as f32, value as f32]
+        })
+        .collect();
+    let sample_peak = samples.iter().fold(0.0f32, |p, s| p.max(s.abs())) as f64;
+    let result = analyze(&samples).result();
+    assert_close(sample_peak, 0.354, 0.001);
+    assert_close(result.true_peak, 0.5, 0.02);
+}
+
+#[test]
+fn test_album_loudness_combines_tracks() {
+    let quiet = analyze(&stereo_sine(1000.0, 0.01, 3.0));
+    let loud = analyze(&stereo_sine(1000.0, 0.1, 3.0));
+    let album = LoudnessAnalyzer::combined(&[quiet.clone(), loud.clone()]);
+
+    let quiet_lufs = quiet.result().integrated_lufs.unwrap();
+    let loud_lufs = loud.result().integrated_lufs.unwrap();
+    let album_lufs = album.integrated_lufs.unwrap();
+    assert!(album_lufs > quiet_lufs && album_lufs <= loud_lufs);
+    assert_close(album.true_peak, loud.result().true_peak, 1e-9);
+}
+
+#[test]
+fn test_replay_gain_from_loudness() {
+    let loudness = TrackLoudness {
+        track: LoudnessResult {
+            integrated_lufs: Some(-23.0),
+            loudness_range_lu: 0.0,
+            true_peak: 0.1,
+        },
+        album: Some(LoudnessResult {
+            integrated_lufs: Some(-10.0),
+            loudness_range_lu: 4.0,
+            true_peak: 0.9,
+        }),
+    };
+    let replay_gain = loudness.replay_gain().unwrap();
+    assert_eq!(replay_gain.track_gain_db, Some(5.0));
+    assert_eq!(replay_gain.track_peak, Some(0.1));
+    assert_eq!(replay_gain.album_gain_db, Some(-8.0));
+    assert_eq!(replay_gain.album_peak, Some(0.9));
+}
+
+#[test]
+fn test_cancelled_job_stops_analysis() {
+    let job = AnalysisJob::default();
+    job.cancel();
+    assert!(job.is_cancelled());
+    let track = Track::new("/music/missing.flac");
+    assert!(analyze_track(&track, &job).is_err());
+}
@@ -0,0 +1,297 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
+use symphonia::core::probe::Hint;
+
+use crate::track::Track;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricLine {
+    pub time_ms: Option<u64>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lyrics {
+    pub synced: bool,
+    pub lines: Vec<LyricLine>,
+}
+
+impl Lyrics {
+    /// Looks for lyrics in a sidecar `.lrc` file first, then in the file's own tags.
+    pub fn load(track: &Track) -> Option<Self> {
+        let lrc_path = track.path.with_extension("lrc");
+        if lrc_path.is_file() {
+            if let Ok(bytes) = std::fs::read(&lrc_path) {
+                return Some(Self::parse(&String::from_utf8_lossy(&bytes)));
+            }
+        }
+        Self::read_id3_synced(&track.path).or_else(|| Self::read_embedded(&track.path))
+    }
+
+    /// Parses LRC content. Text without any timestamp is treated as plain lyrics.
+    pub fn parse(content: &str) -> Self {
+        let mut offset_ms: i64 = 0;
+        let mut synced_lines = Vec::new();
+        let mut plain_lines = Vec::new();
+
+        for line in content.lines() {
+            let line = line.trim_start_matches('\u{feff}').trim();
+            let mut rest = line;
+            let mut timestamps = Vec::new();
+
+            while let Some(tag_end) = rest.strip_prefix('[').and_then(|r| r.find(']')) {
+                let tag = &rest[1..tag_end + 1];
+                if let Some(time_ms) = parse_lrc_timestamp(tag) {
+                    timestamps.push(time_ms);
+                } else if let Some(value) = tag.strip_prefix("offset:") {
+                    offset_ms = value.trim().parse().unwrap_or(0);
+                } else if !timestamps.is_empty() {
+                    break;
+                }
+                rest = &rest[tag_end + 2..];
+            }
+
+            if timestamps.is_empty() {
+                let is_id_tag = line.starts_with('[') && line.ends_with(']') && line.contains(':');
+                if !is_id_tag {
+                    plain_lines.push(line.to_string());
+                }
+                continue;
+            }
+            for time_ms in timestamps {
+                synced_lines.push((time_ms, rest.trim().to_string()));
+            }
+        }
+
+        if synced_lines.is_empty() {
+            while plain_lines.last().is_some_and(|l| l.is_empty()) {
+                plain_lines.pop();
+            }
+            return Self {
+                synced: false,
+                lines: plain_lines
+                    .into_iter()
+                    .map(|text| LyricLine {
+                        time_ms: None,
+                        text,
+                    })
+                    .collect(),
+            };
+        }
+
+        // A positive offset makes lyrics show up sooner
+        Self::from_synced(
+            synced_lines
+                .into_iter()
+                .map(|(time_ms, text)| ((time_ms as i64 - offset_ms).max(0) as u64, text))
+                .collect(),
+        )
+    }
+
+    fn from_synced(mut lines: Vec<(u64, String)>) -> Self {
+        lines.sort_by_key(|(time_ms, _)| *time_ms);
+        Self {
+            synced: true,
+            lines: lines
+                .into_iter()
+                .map(|(time_ms, text)| LyricLine {
+                    time_ms: Some(time_ms),
+                    text,
+                })
+                .collect(),
+        }
+    }
+
+    /// Index of the line being sung at the given position, if the lyrics are synced.
+    pub fn line_index_at(&self, position_ms: u64) -> Option<usize> {
+        if !self.synced {
+            return None;
+        }
+        self.lines
+            .partition_point(|line| line.time_ms.is_some_and(|t| t <= position_ms))
+            .checked_sub(1)
+    }
+
+    /// Reads USLT (ID3) or LYRICS (Vorbis comment) tags.
+    fn read_embedded(path: &Path) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext_str) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext_str);
+        }
+
+        let mut probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .ok()?;
+
+        let find_lyrics = |tags: &[Tag]| {
+            tags.iter()
+                .find(|tag| tag.std_key == Some(StandardTagKey::Lyrics))
+                .map(|tag| tag.value.to_string())
+        };
+
+        let mut content = probed
+            .format
+            .metadata()
+            .skip_to_latest()
+            .and_then(|rev| find_lyrics(rev.tags()));
+        if content.is_none() {
+            if let Some(mut m) = probed.metadata.get() {
+                content = m.skip_to_latest().and_then(|rev| find_lyrics(rev.tags()));
+            }
+        }
+
+        content
+            .map(|c| Self::parse(&c))
+            .filter(|lyrics| !lyrics.lines.is_empty())
+    }
+
+    /// Symphonia skips ID3 SYLT frames, so synchronized lyrics are read from the tag directly.
+    fn read_id3_synced(path: &Path) -> Option<Self> {
+        let mut file = File::open(path).ok()?;
+        let mut header = [0u8; 10];
+        file.read_exact(&mut header).ok()?;
+        if &header[0..3] != b"ID3" || !(3..=4).contains(&header[3]) {
+            return None;
+        }
+        let version = header[3];
+        let mut tag = vec![0u8; syncsafe_u32(&header[6..10]) as usize];
+        file.read_exact(&mut tag).ok()?;
+
+        let frame_size = |bytes: &[u8]| {
+            if version == 4 {
+                syncsafe_u32(bytes) as usize
+            } else {
+                u32::from_be_bytes(bytes.try_into().unwrap_or_default()) as usize
+            }
+        };
+
+        let mut pos = 0;
+        if header[5] & 0x40 != 0 {
+            let size = frame_size(tag.get(0..4)?);
+            // The extended header size excludes its own size field in ID3v2.3
+            pos = if version == 4 { size } else { size + 4 };
+        }
+
+        while pos + 10 <= tag.len() && tag[pos] != 0 {
+            let id = &tag[pos..pos + 4];
+            let size = frame_size(&tag[pos + 4..pos + 8]);
+            let body = tag.get(pos + 10..pos + 10 + size)?;
+            if id == b"SYLT" {
+                return parse_sylt_frame(body);
+            }
+            pos += 10 + size;
+        }
+        None
+    }
+}
+
+/// Parses `mm:ss`, `mm:ss.xx`, `mm:ss.xxx` or `mm:ss:xx` into milliseconds.
+fn parse_lrc_timestamp(tag: &str) -> Option<u64> {
+    let (minutes, rest) = tag.split_once(':')?;
+    let minutes = minutes.parse::<u64>().ok()?;
+    let (seconds, fraction) = match rest.find(['.', ':']) {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, ""),
+    };
+    let seconds = seconds.parse::<u64>().ok()?;
+    if seconds >= 60 || fraction.len() > 3 {
+        return None;
+    }
+    let fraction_ms = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u64>().ok()? * 10u64.pow(3 - fraction.len() as u32)
+    };
+    Some((minutes * 60 + seconds) * 1000 + fraction_ms)
+}
+
+fn syncsafe_u32(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .take(4)
+        .fold(0, |acc, &b| (acc << 7) | (b & 0x7f) as u32)
+}
+
+fn parse_sylt_frame(body: &[u8]) -> Option<Lyrics> {
+    let encoding = *body.first()?;
+    // Only absolute millisecond timestamps are supported, not MPEG frame counts
+    if body.get(4) != Some(&2) {
+        return None;
+    }
+
+    let (_, descriptor_len) = read_id3_text(body.get(6..)?, encoding)?;
+    let mut data = &body[6 + descriptor_len..];
+    let mut lines = Vec::new();
+
+    while !data.is_empty() {
+        let (text, text_len) = read_id3_text(data, encoding)?;
+        let time_bytes = data.get(text_len..text_len + 4)?;
+        let time_ms = u32::from_be_bytes(time_bytes.try_into().ok()?) as u64;
+        lines.push((time_ms, text.trim().to_string()));
+        data = &data[text_len + 4..];
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(Lyrics::from_synced(lines))
+    }
+}
+
+/// Reads a null-terminated ID3 string, returning it with the number of bytes consumed.
+fn read_id3_text(data: &[u8], encoding: u8) -> Option<(String, usize)> {
+    match encoding {
+        0 | 3 => {
+            let end = data.iter().position(|&b| b == 0)?;
+            let text = if encoding == 0 {
+                data[..end].iter().map(|&b| b as char).collect()
+            } else {
+                String::from_utf8_lossy(&data[..end]).to_string()
+            };
+            Some((text, end + 1))
+        }
+        1 | 2 => {
+            let end = data.chunks_exact(2).position(|c| c == [0, 0])? * 2;
+            let mut units = data[..end].chunks_exact(2);
+            let mut big_endian = encoding == 2;
+            let mut code_units = Vec::with_capacity(end / 2);
+            if encoding == 1 {
+                match units.next() {
+                    Some([0xfe, 0xff]) => big_endian = true,
+                    Some([0xff, 0xfe]) => big_endian = false,
+                    Some(unit) => code_units.push(u16::from_le_bytes([unit[0], unit[1]])),
+                    None => {}
+                }
+            }
+            code_units.extend(units.map(|unit| {
+                if big_endian {
+                    u16::from_be_bytes([unit[0], unit[1]])
+                } else {
+                    u16::from_le_bytes([unit[0], unit[1]])
+                }
+            }));
+            Some((String::from_utf16_lossy(&code_units), end + 2))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[path = "./lyrics.tests.rs"]
+mod tests;
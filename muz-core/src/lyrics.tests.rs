@@ -0,0 +1,133 @@
+use super::*;
+
+fn times(lyrics: &Lyrics) -> Vec<Option<u64>> {
+    lyrics.lines.iter().map(|l| l.time_ms).collect()
+}
+
+#[test]
+fn test_parse_synced_lyrics() {
+    let lyrics = Lyrics::parse("[ar:Someone]\n[00:01.50]First line\n[00:04.00]Second line\n");
+    assert!(lyrics.synced);
+    assert_eq!(lyrics.lines.len(), 2);
+    assert_eq!(lyrics.lines[0].text, "First line");
+    assert_eq!(times(&lyrics), vec![Some(1_500), Some(4_000)]);
+}
+
+#[test]
+fn test_parse_multiple_timestamps_per_line() {
+    let lyrics = Lyrics::parse("[00:10.00]Verse\n[00:05.00][00:20.00]Chorus\n");
+    assert_eq!(times(&lyrics), vec![Some(5_000), Some(10_000), Some(20_000)]);
+    assert_eq!(lyrics.lines[0].text, "Chorus");
+    assert_eq!(lyrics.lines[1].text, "Verse");
+    assert_eq!(lyrics.lines[2].text, "Chorus");
+}
+
+#[test]
+fn test_parse_offset() {
+    let lyrics = Lyrics::parse("[offset:+500]\n[00:00.20]Intro\n[00:02.00]Line\n");
+    assert_eq!(times(&lyrics), vec![Some(0), Some(1_500)]);
+
+    let lyrics = Lyrics::parse("[offset:-250]\n[00:02.00]Line\n");
+    assert_eq!(times(&lyrics), vec![Some(2_250)]);
+}
+
+#[test]
+fn test_parse_timestamp_formats() {
+    assert_eq!(parse_lrc_timestamp("01:02"), Some(62_000));
+    assert_eq!(parse_lrc_timestamp("01:02.5"), Some(62_500));
+    assert_eq!(parse_lrc_timestamp("01:02.34"), Some(62_340));
+    assert_eq!(parse_lrc_timestamp("01:02.345"), Some(62_345));
+    assert_eq!(parse_lrc_timestamp("01:02:34"), Some(62_340));
+    assert_eq!(parse_lrc_timestamp("ti:Title"), None);
+    assert_eq!(parse_lrc_timestamp("00:75.00"), None);
+}
+
+#[test]
+fn test_parse_plain_lyrics() {
+    let lyrics = Lyrics::parse("First line\n[Chorus]\nSecond line\n\n");
+    assert!(!lyrics.synced);
+    assert_eq!(lyrics.lines.len(), 3);
+    assert_eq!(lyrics.lines[1].text, "[Chorus]");
+    assert!(lyrics.lines.iter().all(|l| l.time_ms.is_none()));
+}
+
+#[test]
+fn test_line_index_at() {
+    let lyrics = Lyrics::parse("[00:01.00]One\n[00:03.00]Two\n[00:05.00]Three\n");
+    assert_eq!(lyrics.line_index_at(0), None);
+    assert_eq!(lyrics.line_index_at(1_000), Some(0));
+    assert_eq!(lyrics.line_index_at(2_999), Some(0));
+    assert_eq!(lyrics.line_index_at(3_000), Some(1));
+    assert_eq!(lyrics.line_index_at(60_000), Some(2));
+}
+
+#[test]
+fn test_line_index_at_plain_lyrics() {
+    let lyrics = Lyrics::parse("Just words\n");
+    assert_eq!(lyrics.line_index_at(1_000), None);
+}
+
+fn sylt_frame(encoding: u8, entries: &[(&str, u32)]) -> Vec<u8> {
+    let mut body = vec![encoding, b'e', b'n', b'g', 2, 1];
+    body.push(0); // empty content descriptor
+    for (text, time_ms) in entries {
+        body.extend_from_slice(text.as_bytes());
+        body.push(0);
+        body.extend_from_slice(&time_ms.to_be_bytes());
+    }
+    body
+}
+
+#[test]
+fn test_parse_sylt_frame() {
+    let body = sylt_frame(3, &[("Hello", 1_000), ("World", 2_500)]);
+    let lyrics = parse_sylt_frame(&body).unwrap();
+    assert!(lyrics.synced);
+    assert_eq!(lyrics.lines[1].text, "World");
+    assert_eq!(times(&lyrics), vec![Some(1_000), Some(2_500)]);
+}
+
+#[test]
+fn test_parse_sylt_frame_utf16() {
+    let mut body = vec![1, b'e', b'n', b'g', 2, 1, 0xff, 0xfe, 0, 0];
+    body.extend_from_slice(&[0xff, 0xfe, b'H', 0, b'i', 0, 0, 0]);
+    body.extend_from_slice(&750u32.to_be_bytes());
+    let lyrics = parse_sylt_frame(&body).unwrap();
+    assert_eq!(lyrics.lines[0].text, "Hi");
+    assert_eq!(lyrics.lines[0].time_ms, Some(750));
+}
+
+#[test]
+fn test_parse_sylt_frame_mpeg_frames_unsupported() {
+    let mut body = sylt_frame(3, &[("Hello", 10)]);
+    body[4] = 1;
+    assert!(parse_sylt_frame(&body).is_none());
+}
+
+#[test]
+fn test_read_id3_synced() {
+    let body = sylt_frame(0, &[("Line", 3_000)]);
+    let mut frame = b"SYLT".to_vec();
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend_from_slice(&body);
+
+    let size = frame.len() as u32;
+    let mut file = b"ID3\x03\x00\x00".to_vec();
+    file.extend_from_slice(&[
+        ((size >> 21) & 0x7f) as u8,
+        ((size >> 14) & 0x7f) as u8,
+        ((size >> 7) & 0x7f) as u8,
+        (size & 0x7f) as u8,
+    ]);
+    file.extend_from_slice(&frame);
+
+    let path = std::env::temp_dir().join(format!("muz-sylt-{}.mp3", uuid::Uuid::new_v4()));
+    std::fs::write(&path, file).unwrap();
+    let lyrics = Lyrics::read_id3_synced(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let lyrics = lyrics.unwrap();
+    assert_eq!(lyrics.lines[0].text, "Line");
+    assert_eq!(lyrics.lines[0].time_ms, Some(3_000));
+}
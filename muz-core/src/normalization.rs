@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use symphonia::core::meta::{StandardTagKey, Tag};
+
+/// Loudness information read from ReplayGain or R128 tags, gains in dB relative to -18 LUFS.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayGain {
+    pub track_gain_db: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain_db: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalizationMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+    /// Album gain while playing a whole album, track gain otherwise.
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizationSettings {
+    pub mode: NormalizationMode,
+    pub preamp_db: f32,
+    pub prevent_clipping: bool,
+}
+
+impl Default for NormalizationSettings {
+    fn default() -> Self {
+        Self {
+            mode: NormalizationMode::Off,
+            preamp_db: 0.0,
+            prevent_clipping: true,
+        }
+    }
+}
+
+/// R128 gains are Q7.8 fixed point relative to -23 LUFS, ReplayGain uses -18 LUFS.
+const R128_TO_REPLAY_GAIN_DB: f32 = 5.0;
+
+impl ReplayGain {
+    pub fn from_tags(tags: &[Tag]) -> Option<Self> {
+        let mut gain = ReplayGain::default();
+
+        for tag in tags {
+            let value = tag.value.to_string();
+            match tag.std_key {
+                Some(StandardTagKey::ReplayGainTrackGain) => gain.track_gain_db = parse_db(&value),
+                Some(StandardTagKey::ReplayGainTrackPeak) => gain.track_peak = parse_db(&value),
+                Some(StandardTagKey::ReplayGainAlbumGain) => gain.album_gain_db = parse_db(&value),
+                Some(StandardTagKey::ReplayGainAlbumPeak) => gain.album_peak = parse_db(&value),
+                _ => {
+                    if tag.key.eq_ignore_ascii_case("R128_TRACK_GAIN") {
+                        gain.track_gain_db = gain.track_gain_db.or(parse_r128(&value));
+                    } else if tag.key.eq_ignore_ascii_case("R128_ALBUM_GAIN") {
+                        gain.album_gain_db = gain.album_gain_db.or(parse_r128(&value));
+                    }
+                }
+            }
+        }
+
+        if gain == ReplayGain::default() {
+            None
+        } else {
+            Some(gain)
+        }
+    }
+
+    pub fn merge(self, other: ReplayGain) -> ReplayGain {
+        ReplayGain {
+            track_gain_db: self.track_gain_db.or(other.track_gain_db),
+            track_peak: self.track_peak.or(other.track_peak),
+            album_gain_db: self.album_gain_db.or(other.album_gain_db),
+            album_peak: self.album_peak.or(other.album_peak),
+        }
+    }
+}
+
+impl NormalizationSettings {
+    /// Linear gain factor to apply to a track. Tracks without loudness info are left untouched.
+    pub fn gain_factor(&self, replay_gain: Option<&ReplayGain>, playing_album: bool) -> f32 {
+        let Some(replay_gain) = replay_gain else {
+            return 1.0;
+        };
+
+        let prefer_album = match self.mode {
+            NormalizationMode::Off => return 1.0,
+            NormalizationMode::Track => false,
+            NormalizationMode::Album => true,
+            NormalizationMode::Auto => playing_album,
+        };
+
+        let track = (replay_gain.track_gain_db, replay_gain.track_peak);
+        let album = (replay_gain.album_gain_db, replay_gain.album_peak);
+        let (gain_db, peak) = match (prefer_album, track, album) {
+            (true, _, (Some(gain), peak)) | (false, (Some(gain), peak), _) => (gain, peak),
+            (true, (Some(gain), peak), _) | (false, _, (Some(gain), peak)) => (gain, peak),
+            _ => return 1.0,
+        };
+
+        let factor = 10f32.powf((gain_db + self.preamp_db) / 20.0);
+        match peak.filter(|p| self.prevent_clipping && *p > 0.0) {
+            Some(peak) => factor.min(1.0 / peak),
+            None => factor,
+        }
+    }
+}
+
+/// Parses values such as `-6.48 dB` or `0.988312`.
+fn parse_db(value: &str) -> Option<f32> {
+    value
+        .split_whitespace()
+        .next()
+        .and_then(|v| v.parse::<f32>().ok())
+        .filter(|v| v.is_finite())
+}
+
+fn parse_r128(value: &str) -> Option<f32> {
+    value
+        .trim()
+        .parse::<i16>()
+        .ok()
+        .map(|q| q as f32 / 256.0 + R128_TO_REPLAY_GAIN_DB)
+}
+
+#[cfg(test)]
+#[path = "./normalization.tests.rs"]
+mod tests;
@@ -0,0 +1,147 @@
+use super::*;
+use symphonia::core::meta::Value;
+
+fn tag(std_key: Option<StandardTagKey>, key: &str, value: &str) -> Tag {
+    Tag::new(std_key, key, Value::String(value.to_string()))
+}
+
+fn replay_gain() -> ReplayGain {
+    ReplayGain {
+        track_gain_db: Some(-6.0),
+        track_peak: Some(0.5),
+        album_gain_db: Some(-3.0),
+        album_peak: Some(0.9),
+    }
+}
+
+fn settings(mode: NormalizationMode) -> NormalizationSettings {
+    NormalizationSettings {
+        mode,
+        preamp_db: 0.0,
+        prevent_clipping: true,
+    }
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-4,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn test_replay_gain_from_tags() {
+    let tags = vec![
+        tag(
+            Some(StandardTagKey::ReplayGainTrackGain),
+            "REPLAYGAIN_TRACK_GAIN",
+            "-7.12 dB",
+        ),
+        tag(
+            Some(StandardTagKey::ReplayGainTrackPeak),
+            "REPLAYGAIN_TRACK_PEAK",
+            "0.988312",
+        ),
+        tag(
+            Some(StandardTagKey::ReplayGainAlbumGain),
+            "REPLAYGAIN_ALBUM_GAIN",
+            "+1.50 dB",
+        ),
+    ];
+    let gain = ReplayGain::from_tags(&tags).unwrap();
+    assert_eq!(gain.track_gain_db, Some(-7.12));
+    assert_eq!(gain.track_peak, Some(0.988312));
+    assert_eq!(gain.album_gain_db, Some(1.5));
+    assert_eq!(gain.album_peak, None);
+}
+
+#[test]
+fn test_replay_gain_from_r128_tags() {
+    let tags = vec![
+        tag(None, "R128_TRACK_GAIN", "-512"),
+        tag(None, "R128_ALBUM_GAIN", "256"),
+    ];
+    let gain = ReplayGain::from_tags(&tags).unwrap();
+    assert_eq!(gain.track_gain_db, Some(3.0));
+    assert_eq!(gain.album_gain_db, Some(6.0));
+}
+
+#[test]
+fn test_replay_gain_from_tags_without_gain() {
+    let tags = vec![tag(Some(StandardTagKey::Artist), "ARTIST", "Someone")];
+    assert_eq!(ReplayGain::from_tags(&tags), None);
+}
+
+#[test]
+fn test_replay_gain_merge_keeps_existing_values() {
+    let gain = ReplayGain {
+        track_gain_db: Some(-2.0),
+        ..Default::default()
+    }
+    .merge(replay_gain());
+    assert_eq!(gain.track_gain_db, Some(-2.0));
+    assert_eq!(gain.album_gain_db, Some(-3.0));
+}
+
+#[test]
+fn test_gain_factor_off() {
+    let gain = settings(NormalizationMode::Off).gain_factor(Some(&replay_gain()), true);
+    assert_eq!(gain, 1.0);
+}
+
+#[test]
+fn test_gain_factor_without_replay_gain() {
+    let gain = settings(NormalizationMode::Track).gain_factor(None, false);
+    assert_eq!(gain, 1.0);
+}
+
+#[test]
+fn test_gain_factor_track_and_album() {
+    let rg = replay_gain();
+    assert_close(
+        settings(NormalizationMode::Track).gain_factor(Some(&rg), true),
+        10f32.powf(-6.0 / 20.0),
+    );
+    assert_close(
+        settings(NormalizationMode::Album).gain_factor(Some(&rg), false),
+        10f32.powf(-3.0 / 20.0),
+    );
+}
+
+#[test]
+fn test_gain_factor_auto_follows_album_context() {
+    let rg = replay_gain();
+    let auto = settings(NormalizationMode::Auto);
+    assert_close(auto.gain_factor(Some(&rg), false), 10f32.powf(-6.0 / 20.0));
+    assert_close(auto.gain_factor(Some(&rg), true), 10f32.powf(-3.0 / 20.0));
+}
+
+#[test]
+fn test_gain_factor_falls_back_to_other_gain() {
+    let rg = ReplayGain {
+        track_gain_db: Some(-4.0),
+        ..Default::default()
+    };
+    assert_close(
+        settings(NormalizationMode::Album).gain_factor(Some(&rg), true),
+        10f32.powf(-4.0 / 20.0),
+    );
+}
+
+#[test]
+fn test_gain_factor_preamp_and_clipping_prevention() {
+    let rg = ReplayGain {
+        track_gain_db: Some(6.0),
+        track_peak: Some(0.8),
+        ..Default::default()
+    };
+    let mut settings = settings(NormalizationMode::Track);
+    settings.preamp_db = 2.0;
+    assert_close(settings.gain_factor(Some(&rg), false), 1.0 / 0.8);
+
+    settings.prevent_clipping = false;
+    assert_close(
+        settings.gain_factor(Some(&rg), false),
+        10f32.powf(8.0 / 20.0),
+    );
+}
@@ -0,0 +1,813 @@
+use crate::bus::EventBus;
+use crate::command::PlaybackCommand;
+use crate::driver::{position::PlaybackPosition, OutputDevice, PlaybackDriver};
+use crate::dsp::DspSettings;
+use crate::equalizer::EqualizerSettings;
+use crate::error::{PlaybackError, QueueError};
+use crate::events::{
+    HistoryUpdateEvent, OutputDeviceFallbackEvent, OutputUnavailableEvent, PlaybackErrorEvent, PlayerEvent, ProgressEvent,
+    QueueChangedEvent, SpectrumEvent, StateChangedEvent, TrackChangedEvent,
+};
+use crate::normalization::{NormalizationSettings, ReplayGain};
+use crate::time_stretch::{MAX_PITCH_SEMITONES, MAX_SPEED, MIN_SPEED};
+use crate::{lyrics::Lyrics, queue::Queue, track::Track};
+
+use anyhow::{anyhow, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How far into a track `previous` restarts it instead of going back.
+const RESTART_THRESHOLD_MS: u64 = 3000;
+
+pub enum PlaybackEvent {
+    HistoryUpdate,
+    FailedOpeningFile(Track, Error),
+    TrackCompleted,
+    Shutdown,
+    Progress(f64, u64), // percent completed, frames played
+    Spectrum(Vec<f32>), // spectrum data
+    TrackChanged(Option<Track>),
+    QueueChanged(Vec<Track>),
+    StateChanged(StateChangedEvent),
+    OutputDeviceFallback(String, String), // requested device, reason
+    /// The track is fine but there is nothing to play it on
+    OutputUnavailable(Error),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    /// Replays the current track when it completes
+    One,
+    /// Restarts from the first played track when the queue runs out
+    All,
+}
+
+/// Everything a client needs to render the player.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackSnapshot {
+    pub state: PlaybackState,
+    pub track: Option<Track>,
+    pub position_ms: u64,
+    pub position_frames: u64,
+    pub volume: f32,
+    pub muted: bool,
+    pub speed: f32,
+    pub pitch_semitones: f32,
+    pub repeat: RepeatMode,
+    pub shuffle: bool,
+}
+
+pub struct Playback {
+    driver: Box<dyn PlaybackDriver>,
+    pub state: PlaybackState,
+    current_track: Option<Track>,
+    queue: Option<Queue>,
+    pub history: Vec<Track>,
+    event_sender: mpsc::Sender<PlaybackEvent>,
+    progress: f64,
+    lyrics: Option<Lyrics>,
+    normalization: NormalizationSettings,
+    playing_album: bool,
+    speed: f32,
+    pitch_semitones: f32,
+    volume: f32,
+    muted: bool,
+    repeat: RepeatMode,
+    shuffle: bool,
+}
+
+impl Playback {
+    /// Starts the event loop of a stopped player, which reports on `events`.
+    pub fn create(driver: Box<dyn PlaybackDriver>, events: EventBus) -> Arc<Mutex<Self>> {
+        let (event_sender, event_receiver) = mpsc::channel();
+
+        let playback = Arc::new(Mutex::new(Self {
+            driver,
+            queue: None,
+            history: Vec::new(),
+            current_track: None,
+            state: PlaybackState::Stopped,
+            event_sender,
+            progress: 0.0,
+            lyrics: None,
+            normalization: NormalizationSettings::default(),
+            playing_album: false,
+            speed: 1.0,
+            pitch_semitones: 0.0,
+            volume: 1.0,
+            muted: false,
+            repeat: RepeatMode::Off,
+            shuffle: false,
+        }));
+        if let Ok(playback) = playback.lock() {
+            events.publish(PlayerEvent::StateChanged(playback.state_event()));
+        }
+
+        let playback_clone = Arc::clone(&playback);
+
+        thread::spawn(move || {
+            for event in event_receiver {
+                match event {
+                    PlaybackEvent::TrackCompleted => {
+                        tracing::info!("Track completed event received");
+                        if let Ok(mut playback) = playback_clone.lock() {
+                            playback
+                                .track_completed()
+                                .map_err(|e| {
+                                    tracing::error!("Error moving to next track: {e}");
+                                    e
+                                })
+                                .ok();
+                        }
+                    }
+                    PlaybackEvent::FailedOpeningFile(track, err) => {
+                        tracing::error!("Failed to open {:?}: {err}", track.path);
+                        events.publish(PlayerEvent::PlaybackError(PlaybackErrorEvent {
+                            track_id: track.id.clone(),
+                            path: track.path.clone(),
+                            message: format!("{err:#}"),
+                        }));
+                        if let Ok(mut playback) = playback_clone.lock() {
+                            // Ignore failures of a track that was already skipped
+                            let is_current = playback
+                                .current_track
+                                .as_ref()
+                                .is_some_and(|t| t.id == track.id);
+                            if is_current {
+                                // Flagged so that repeating the queue doesn't retry it
+                                if let Some(current) = playback.current_track.as_mut() {
+                                    current.unplayable = true;
+                                }
+                                playback
+                                    .next()
+                                    .map_err(|e| {
+                                        tracing::error!("Error skipping unplayable track: {e}");
+                                        e
+                                    })
+                                    .ok();
+                            }
+                        }
+                    }
+                    PlaybackEvent::HistoryUpdate => {
+                        if let Ok(playback) = playback_clone.lock() {
+                            let history = &playback.history;
+                            let has_history = history.len() > 1
+                                || (history.len() == 1
+                                    && history.last() != playback.current_track.as_ref());
+                            events.publish(PlayerEvent::HistoryUpdate(HistoryUpdateEvent {
+                                has_history,
+                            }));
+                        }
+                    }
+                    PlaybackEvent::TrackChanged(track) => {
+                        if let Ok(mut playback) = playback_clone.lock() {
+                            playback.lyrics = None;
+                        }
+                        if let Some(track) = track.clone() {
+                            load_lyrics(Arc::clone(&playback_clone), track);
+                        }
+                        events.publish(PlayerEvent::TrackChanged(Box::new(TrackChangedEvent {
+                            track,
+                        })));
+                    }
+                    PlaybackEvent::QueueChanged(queue) => {
+                        events.publish(PlayerEvent::QueueChanged(QueueChangedEvent { queue }));
+                    }
+                    PlaybackEvent::Progress(percent, frames_played) => {
+                        if let Ok(mut playback) = playback_clone.lock() {
+                            if playback.state != PlaybackState::Stopped {
+                                playback.progress = percent;
+                                let lyric_line = playback.lyric_line_at(frames_played);
+                                events.publish(PlayerEvent::Progress(ProgressEvent {
+                                    position: percent,
+                                    frames_played,
+                                    lyric_line,
+                                }));
+                            }
+                        }
+                    }
+                    PlaybackEvent::Spectrum(spectrum_data) => {
+                        if let Ok(playback) = playback_clone.lock() {
+                            if playback.state == PlaybackState::Playing {
+                                events.publish(PlayerEvent::Spectrum(SpectrumEvent {
+                                    spectrum_data,
+                                }));
+                            }
+                        }
+                    }
+                    PlaybackEvent::StateChanged(state) => {
+                        events.publish(PlayerEvent::StateChanged(state));
+                    }
+                    PlaybackEvent::OutputDeviceFallback(device, reason) => {
+                        tracing::warn!("Output device {device} unavailable: {reason}");
+                        events.publish(PlayerEvent::OutputDeviceFallback(
+                            OutputDeviceFallbackEvent {
+                                requested_device: device,
+                                reason,
+                            },
+                        ));
+                    }
+                    PlaybackEvent::OutputUnavailable(err) => {
+                        tracing::error!("No audio output to play on: {err:#}");
+                        events.publish(PlayerEvent::OutputUnavailable(OutputUnavailableEvent {
+                            reason: format!("{err:#}"),
+                        }));
+                        // Paused rather than skipped, as every other track would fail too
+                        if let Ok(mut playback) = playback_clone.lock() {
+                            if playback.state == PlaybackState::Playing {
+                                if let Err(e) = playback.pause() {
+                                    tracing::error!("Error pausing without an output: {e}");
+                                }
+                                events.publish(PlayerEvent::StateChanged(playback.state_event()));
+                            }
+                        }
+                    }
+                    PlaybackEvent::Shutdown => break,
+                }
+            }
+            tracing::info!("Playback event loop shutting down");
+        });
+
+        playback
+    }
+
+    pub fn current_track(&self) -> Option<&Track> {
+        match self.state {
+            PlaybackState::Playing | PlaybackState::Paused => self.current_track.as_ref(),
+            PlaybackState::Stopped => None,
+        }
+    }
+
+    pub fn enqueue(&mut self, track: Track) {
+        self.push_to_queue(track);
+        self.event_sender
+            .send(PlaybackEvent::QueueChanged(self.queue()))
+            .ok();
+    }
+
+    fn push_to_queue(&mut self, track: Track) {
+        self.playing_album = false;
+        if let Some(queue) = &mut self.queue {
+            queue.enqueue(track);
+        } else {
+            tracing::debug!("Creating queue");
+            let mut queue = Queue::new();
+            queue.enqueue(track);
+            self.queue = Some(queue);
+        }
+    }
+
+    pub fn prepend(&mut self, track: Track) {
+        self.playing_album = false;
+        if let Some(queue) = &mut self.queue {
+            queue.prepend(track);
+        } else {
+            tracing::debug!("Creating queue");
+            let mut queue = Queue::new();
+            queue.enqueue(track);
+            self.queue = Some(queue);
+        }
+    }
+
+    /// Reports the queue once rather than after every track.
+    pub fn enqueue_multiple(&mut self, tracks: Vec<Track>) {
+        for track in tracks {
+            self.push_to_queue(track);
+        }
+        self.event_sender
+            .send(PlaybackEvent::QueueChanged(self.queue()))
+            .ok();
+    }
+
+    pub fn clear_queue(&mut self) {
+        self.playing_album = false;
+        if let Some(queue) = &mut self.queue {
+            queue.clear();
+        }
+        self.event_sender
+            .send(PlaybackEvent::QueueChanged(self.queue()))
+            .ok();
+    }
+
+    pub fn play(&mut self) -> Result<PlaybackState> {
+        if self.current_track.is_some() && self.state == PlaybackState::Paused {
+            return self.resume_play();
+        }
+
+        let track_changed = self.current_track.is_none();
+        if self.current_track.is_none() {
+            if let Some(queue) = &mut self.queue {
+                self.current_track = queue.dequeue();
+                if track_changed {
+                    self.event_sender
+                        .send(PlaybackEvent::TrackChanged(self.current_track.clone()))
+                        .ok();
+                    self.event_sender
+                        .send(PlaybackEvent::QueueChanged(self.queue()))
+                        .ok();
+                }
+            };
+        }
+
+        let track = self
+            .current_track
+            .clone()
+            .ok_or(PlaybackError::NothingToPlay)?;
+        self.driver.set_gain(self.normalization_gain())?;
+        self.driver.play(track, self.event_sender.clone())?;
+        self.state = PlaybackState::Playing;
+        self.progress = 0.0;
+
+        Ok(self.state.clone())
+    }
+
+    pub fn next(&mut self) -> Result<PlaybackState> {
+        if let Some(current_track) = self.current_track.clone() {
+            self.history.push(current_track);
+            self.event_sender.send(PlaybackEvent::HistoryUpdate)?;
+        }
+        if self.repeat == RepeatMode::All && self.queue.as_ref().is_none_or(Queue::is_empty) {
+            self.requeue_history();
+        }
+        self.stop()?;
+        self.play()
+    }
+
+    /// Moves on when the driver finished the track, replaying it under `RepeatMode::One`.
+    fn track_completed(&mut self) -> Result<PlaybackState> {
+        match self.current_track.clone() {
+            Some(track) if self.repeat == RepeatMode::One => {
+                tracing::info!("Repeating the current track");
+                self.driver.clear()?;
+                self.driver.play(track, self.event_sender.clone())?;
+                self.state = PlaybackState::Playing;
+                self.progress = 0.0;
+                Ok(self.state.clone())
+            }
+            _ => {
+                tracing::info!("Playing next track");
+                self.next()
+            }
+        }
+    }
+
+    /// Queues the played tracks again, in the order they were played, leaving out those
+    /// that failed to open.
+    fn requeue_history(&mut self) {
+        let queue = self.queue.get_or_insert_with(Queue::new);
+        for track in self.history.drain(..).filter(|track| !track.unplayable) {
+            queue.enqueue(track);
+        }
+        if self.shuffle {
+            queue.shuffle();
+        }
+        self.event_sender.send(PlaybackEvent::HistoryUpdate).ok();
+        self.event_sender
+            .send(PlaybackEvent::QueueChanged(self.queue()))
+            .ok();
+    }
+
+    pub fn previous(&mut self) -> Result<PlaybackState> {
+        if self.current_track().is_some() && self.driver.position().ms > RESTART_THRESHOLD_MS {
+            return self.seek(Duration::ZERO);
+        }
+
+        self.state = PlaybackState::Stopped;
+        self.driver.pause()?;
+        self.driver.clear()?;
+
+        if let Some(current_track) = self.current_track.clone() {
+            self.prepend(current_track);
+            self.event_sender
+                .send(PlaybackEvent::QueueChanged(self.queue()))
+                .ok();
+        }
+
+        let mut history_changed = false;
+        loop {
+            if self.history.is_empty() {
+                break;
+            }
+            if let Some(last) = self.history.pop() {
+                history_changed = true;
+                self.current_track = Some(last);
+                break;
+            }
+        }
+        if history_changed {
+            self.event_sender.send(PlaybackEvent::HistoryUpdate)?;
+        }
+        let result = self.play();
+        self.event_sender
+            .send(PlaybackEvent::TrackChanged(self.current_track.clone()))
+            .ok();
+        result
+    }
+
+    pub fn resume_play(&mut self) -> Result<PlaybackState> {
+        match &self.state {
+            PlaybackState::Paused => {
+                self.state = PlaybackState::Playing;
+                self.driver
+                    .resume()
+                    .context(PlaybackError::Driver("resume playback"))?;
+                Ok(self.state.clone())
+            }
+            _ => Err(PlaybackError::NotPaused.into()),
+        }
+    }
+
+    pub fn pause(&mut self) -> Result<PlaybackState> {
+        if let PlaybackState::Playing = self.state {
+            self.state = PlaybackState::Paused;
+            self.driver
+                .pause()
+                .context(PlaybackError::Driver("pause playback"))?;
+            self.report_position();
+        }
+        Ok(self.state.clone())
+    }
+
+    pub fn stop(&mut self) -> Result<PlaybackState> {
+        self.state = PlaybackState::Stopped;
+        self.current_track = None;
+        self.progress = 0.0;
+        self.driver
+            .pause()
+            .context(PlaybackError::Driver("stop playback"))?;
+        self.driver
+            .clear()
+            .context(PlaybackError::Driver("stop playback"))?;
+        Ok(self.state.clone())
+    }
+
+    pub fn seek(&mut self, position: Duration) -> Result<PlaybackState> {
+        self.driver
+            .seek(position)
+            .context(PlaybackError::Driver("seek"))?;
+        self.report_position();
+        Ok(self.state.clone())
+    }
+
+    /// Seeks by `delta_ms` from the current position, staying within the track.
+    pub fn seek_relative(&mut self, delta_ms: i64) -> Result<PlaybackState> {
+        let duration_ms = self
+            .current_track()
+            .ok_or(PlaybackError::NothingToPlay)?
+            .duration_ms;
+        let position_ms = self.driver.position().ms.saturating_add_signed(delta_ms);
+        self.seek(Duration::from_millis(position_ms.min(duration_ms)))
+    }
+
+    /// Sets the volume, applied once unmuted when muted.
+    pub fn set_volume(&mut self, volume: f32) -> Result<PlaybackState> {
+        self.volume = volume.clamp(0.0, 1.0);
+        if !self.muted {
+            self.driver
+                .set_volume(self.volume)
+                .context(PlaybackError::Driver("set volume"))?;
+        }
+        Ok(self.state.clone())
+    }
+
+    pub fn set_muted(&mut self, muted: bool) -> Result<PlaybackState> {
+        let volume = if muted { 0.0 } else { self.volume };
+        self.driver
+            .set_volume(volume)
+            .context(PlaybackError::Driver("set volume"))?;
+        self.muted = muted;
+        Ok(self.state.clone())
+    }
+
+    pub fn set_repeat(&mut self, repeat: RepeatMode) -> Result<PlaybackState> {
+        self.repeat = repeat;
+        Ok(self.state.clone())
+    }
+
+    /// Shuffles the upcoming tracks when enabled, as well as the tracks queued again under
+    /// `RepeatMode::All`.
+    pub fn set_shuffle(&mut self, shuffle: bool) -> Result<PlaybackState> {
+        if shuffle && !self.shuffle {
+            if let Some(queue) = &mut self.queue {
+                queue.shuffle();
+                self.event_sender
+                    .send(PlaybackEvent::QueueChanged(self.queue()))
+                    .ok();
+            }
+        }
+        self.shuffle = shuffle;
+        Ok(self.state.clone())
+    }
+
+    /// Runs `command`, announcing the change when it touched the state or the settings.
+    pub fn execute(&mut self, command: PlaybackCommand) -> Result<PlaybackSnapshot> {
+        let before = self.state_event();
+        match command {
+            PlaybackCommand::Play => self.play(),
+            PlaybackCommand::Pause => self.pause(),
+            PlaybackCommand::TogglePlayPause => match self.state {
+                PlaybackState::Playing => self.pause(),
+                PlaybackState::Paused | PlaybackState::Stopped => self.play(),
+            },
+            PlaybackCommand::Stop => self.stop().inspect(|_| {
+                self.event_sender
+                    .send(PlaybackEvent::TrackChanged(None))
+                    .ok();
+            }),
+            PlaybackCommand::Next => self.next(),
+            PlaybackCommand::Previous => self.previous(),
+            PlaybackCommand::Seek { ms } => self.seek(Duration::from_millis(ms)),
+            PlaybackCommand::SeekRelative { delta_ms } => self.seek_relative(delta_ms),
+            PlaybackCommand::SetVolume { volume } => self.set_volume(volume.get()),
+            PlaybackCommand::SetMuted { muted } => self.set_muted(muted),
+            PlaybackCommand::SetSpeed { speed } => self.set_speed(speed.get()),
+            PlaybackCommand::SetPitch { semitones } => self.set_pitch(semitones.get()),
+            PlaybackCommand::SetRepeat { mode } => self.set_repeat(mode),
+            PlaybackCommand::SetShuffle { shuffle } => self.set_shuffle(shuffle),
+        }?;
+        let after = self.state_event();
+        if after != before {
+            self.event_sender
+                .send(PlaybackEvent::StateChanged(after))
+                .ok();
+        }
+        Ok(self.snapshot())
+    }
+
+    fn state_event(&self) -> StateChangedEvent {
+        StateChangedEvent {
+            state: self.state.clone(),
+            volume: self.volume,
+            muted: self.muted,
+            speed: self.speed,
+            pitch_semitones: self.pitch_semitones,
+            repeat: self.repeat,
+            shuffle: self.shuffle,
+        }
+    }
+
+    pub fn snapshot(&self) -> PlaybackSnapshot {
+        let position = self.position();
+        PlaybackSnapshot {
+            state: self.state.clone(),
+            track: self.current_track().cloned(),
+            position_ms: position.ms,
+            position_frames: position.frames,
+            volume: self.volume,
+            muted: self.muted,
+            speed: self.speed,
+            pitch_semitones: self.pitch_semitones,
+            repeat: self.repeat,
+            shuffle: self.shuffle,
+        }
+    }
+
+    pub fn queue(&self) -> Vec<Track> {
+        self.queue
+            .as_ref()
+            .map(|q| q.tracks_cloned())
+            .unwrap_or_else(Vec::new)
+    }
+
+    pub fn set_speed(&mut self, speed: f32) -> Result<PlaybackState> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(PlaybackError::SpeedOutOfRange {
+                speed,
+                min: MIN_SPEED,
+                max: MAX_SPEED,
+            }
+            .into());
+        }
+        self.driver
+            .set_speed(speed)
+            .context(PlaybackError::Driver("set speed"))?;
+        self.speed = speed;
+        Ok(self.state.clone())
+    }
+
+    pub fn set_pitch(&mut self, semitones: f32) -> Result<PlaybackState> {
+        if !(-MAX_PITCH_SEMITONES..=MAX_PITCH_SEMITONES).contains(&semitones) {
+            return Err(PlaybackError::PitchOutOfRange {
+                semitones,
+                max: MAX_PITCH_SEMITONES,
+            }
+            .into());
+        }
+        self.driver
+            .set_pitch(semitones)
+            .context(PlaybackError::Driver("set pitch"))?;
+        self.pitch_semitones = semitones;
+        Ok(self.state.clone())
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn pitch_semitones(&self) -> f32 {
+        self.pitch_semitones
+    }
+
+    pub fn current_track_cloned(&self) -> Option<Track> {
+        self.current_track.clone()
+    }
+
+    pub fn set_normalization(&mut self, settings: NormalizationSettings) -> Result<()> {
+        self.normalization = settings;
+        self.driver
+            .set_gain(self.normalization_gain())
+            .context(PlaybackError::Driver("set normalization gain"))
+    }
+
+    pub fn set_equalizer(&mut self, settings: EqualizerSettings) -> Result<()> {
+        self.driver
+            .set_equalizer(settings)
+            .context(PlaybackError::Driver("set equalizer"))
+    }
+
+    pub fn output_devices(&self) -> Result<Vec<OutputDevice>> {
+        self.driver.output_devices()
+    }
+
+    pub fn set_output_device(&mut self, device: Option<String>) -> Result<()> {
+        self.driver
+            .set_output_device(device, self.event_sender.clone())
+            .context(PlaybackError::Driver("set output device"))
+    }
+
+    pub fn set_dsp(&mut self, settings: DspSettings) -> Result<()> {
+        self.driver
+            .set_dsp(settings)
+            .context(PlaybackError::Driver("configure DSP chain"))
+    }
+
+    /// Applies loudness info computed after the track was queued.
+    pub fn update_replay_gain(&mut self, track_id: &str, replay_gain: ReplayGain) -> Result<()> {
+        let queued = self.queue.iter_mut().flat_map(|q| q.iter_mut());
+        for track in queued.chain(self.current_track.as_mut()) {
+            if track.id == track_id {
+                track.replay_gain = Some(replay_gain);
+            }
+        }
+        if self
+            .current_track
+            .as_ref()
+            .is_some_and(|t| t.id == track_id)
+        {
+            self.driver.set_gain(self.normalization_gain())?;
+        }
+        Ok(())
+    }
+
+    /// Whether the queue holds a whole album, used by the `Auto` normalization mode.
+    /// Adding tracks or clearing the queue resets it, so set it once the album is queued.
+    pub fn set_playing_album(&mut self, playing_album: bool) {
+        self.playing_album = playing_album;
+    }
+
+    fn normalization_gain(&self) -> f32 {
+        let replay_gain = self
+            .current_track
+            .as_ref()
+            .and_then(|t| t.replay_gain.as_ref());
+        self.normalization
+            .gain_factor(replay_gain, self.playing_album)
+    }
+
+    /// The position in the current track, zero when stopped.
+    pub fn position(&self) -> PlaybackPosition {
+        match self.current_track() {
+            Some(_) => self.driver.position(),
+            None => PlaybackPosition::default(),
+        }
+    }
+
+    /// Reports the position right away, as the driver only does so while playing.
+    fn report_position(&self) {
+        let Some(track) = self.current_track() else {
+            return;
+        };
+        let frames = self.driver.position().frames;
+        let percent = if track.total_frames > 0 {
+            frames as f64 / track.total_frames as f64
+        } else {
+            0.0
+        };
+        self.event_sender
+            .send(PlaybackEvent::Progress(percent, frames))
+            .ok();
+    }
+
+    fn lyric_line_at(&self, frames_played: u64) -> Option<usize> {
+        let track = self.current_track.as_ref()?;
+        let position_ms = (frames_played * track.duration_ms).checked_div(track.total_frames)?;
+        self.lyrics.as_ref()?.line_index_at(position_ms)
+    }
+
+    pub fn select_track_from_queue(&mut self, track_id: &str) -> Result<PlaybackState> {
+        if let Some(queue) = &mut self.queue {
+            let queue_tracks = queue.tracks();
+            if let Some(track_index) = queue_tracks.iter().position(|t| t.id == track_id) {
+                queue.move_item(track_index, 0);
+
+                if let Some(current_track) = self.current_track.clone() {
+                    self.history.push(current_track);
+                    self.event_sender.send(PlaybackEvent::HistoryUpdate)?;
+                }
+
+                self.stop()?;
+                self.play()?;
+
+                self.event_sender
+                    .send(PlaybackEvent::QueueChanged(self.queue()))
+                    .ok();
+
+                return Ok(self.state.clone());
+            }
+        }
+        Err(QueueError::TrackNotInQueue(track_id.to_string()).into())
+    }
+
+    pub fn remove_from_queue(&mut self, track_id: &str) -> Result<()> {
+        let queue = self.queue.as_mut().ok_or(QueueError::Unavailable)?;
+        let track = queue
+            .iter()
+            .find(|t| t.id == track_id)
+            .cloned()
+            .ok_or_else(|| QueueError::TrackNotInQueue(track_id.to_string()))?;
+        queue.remove(&track);
+        self.event_sender
+            .send(PlaybackEvent::QueueChanged(self.queue()))
+            .ok();
+        Ok(())
+    }
+
+    pub fn reorder_queue(&mut self, old_index: usize, new_index: usize) -> Result<()> {
+        if let Some(queue) = &mut self.queue {
+            let queue_len = queue.len();
+
+            if let Some(index) = [old_index, new_index].into_iter().find(|&i| i >= queue_len) {
+                return Err(QueueError::IndexOutOfBounds {
+                    index,
+                    len: queue_len,
+                }
+                .into());
+            }
+
+            if old_index != new_index {
+                queue.move_item(old_index, new_index);
+
+                // Emit queue changed event
+                self.event_sender
+                    .send(PlaybackEvent::QueueChanged(self.queue()))
+                    .map_err(|e| anyhow!("Failed to send queue changed event: {}", e))?;
+            }
+
+            Ok(())
+        } else {
+            Err(QueueError::Unavailable.into())
+        }
+    }
+}
+
+impl Drop for Playback {
+    fn drop(&mut self) {
+        tracing::info!("Playback is being dropped, sending shutdown event");
+        if let Err(e) = self.event_sender.send(PlaybackEvent::Shutdown) {
+            tracing::error!("Failed to send shutdown event during drop: {}", e);
+        }
+    }
+}
+
+/// Reads the lyrics of `track` off the event loop, as sidecar files and tags may be on
+/// slow storage. They are dropped if another track started in the meantime.
+fn load_lyrics(playback: Arc<Mutex<Playback>>, track: Track) {
+    thread::spawn(move || {
+        let lyrics = Lyrics::load(&track);
+        if let Ok(mut playback) = playback.lock() {
+            let is_current = playback
+                .current_track
+                .as_ref()
+                .is_some_and(|t| t.id == track.id);
+            if is_current {
+                playback.lyrics = lyrics;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+#[path = "./playback.tests.rs"]
+mod tests;
@@ -0,0 +1,906 @@
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+use super::*;
+use crate::bus::BusEvent;
+use crate::events::PlayerEvent;
+use crate::track::Track;
+use anyhow::Result;
+
+/// Volume and seek requests received by `TestPlaybackDriver`, which plays at 1 kHz and only
+/// moves when seeking
+#[derive(Default)]
+pub struct DriverLog {
+    volume: Option<f32>,
+    seeks: Vec<Duration>,
+    position_ms: u64,
+}
+
+#[derive(Default)]
+pub struct TestPlaybackDriver {
+    log: Arc<Mutex<DriverLog>>,
+}
+
+impl TestPlaybackDriver {
+    #[allow(clippy::new_ret_no_self)]
+    fn new() -> impl PlaybackDriver {
+        Self::default()
+    }
+}
+
+impl PlaybackDriver for TestPlaybackDriver {
+    fn play(&mut self, track: Track, progress_sender: Sender<PlaybackEvent>) -> Result<()> {
+        self.log.lock().unwrap().position_ms = 0;
+        if track.path.ends_with("missing.mp3") {
+            let error = anyhow!("No such file");
+            progress_sender.send(PlaybackEvent::FailedOpeningFile(track, error))?;
+        } else if track.path.ends_with("unheard.mp3") {
+            let error = anyhow!("No audio output device available");
+            progress_sender.send(PlaybackEvent::OutputUnavailable(error))?;
+        }
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.log.lock().unwrap().position_ms = 0;
+        Ok(())
+    }
+
+    fn set_volume(&mut self, volume: f32) -> Result<()> {
+        self.log.lock().unwrap().volume = Some(volume);
+        Ok(())
+    }
+
+    fn set_gain(&mut self, _gain: f32) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_equalizer(&mut self, _settings: EqualizerSettings) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_speed(&mut self, _speed: f32) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_pitch(&mut self, _semitones: f32) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_dsp(&mut self, _settings: DspSettings) -> Result<()> {
+        Ok(())
+    }
+
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        let mut log = self.log.lock().unwrap();
+        log.seeks.push(position);
+        log.position_ms = position.as_millis() as u64;
+        Ok(())
+    }
+
+    fn position(&self) -> PlaybackPosition {
+        let position_ms = self.log.lock().unwrap().position_ms;
+        PlaybackPosition::from_frames(position_ms, 1000)
+    }
+
+    fn output_devices(&self) -> Result<Vec<OutputDevice>> {
+        Ok(Vec::new())
+    }
+
+    fn set_output_device(
+        &mut self,
+        _device: Option<String>,
+        _event_sender: Sender<PlaybackEvent>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn create_playback() -> Arc<Mutex<Playback>> {
+    create_logged_playback().0
+}
+
+fn create_logged_playback() -> (Arc<Mutex<Playback>>, Arc<Mutex<DriverLog>>) {
+    let playback_driver = TestPlaybackDriver::default();
+    let log = playback_driver.log.clone();
+    let playback = Playback::create(Box::new(playback_driver), EventBus::new());
+    (playback, log)
+}
+
+/// The next event that `pick` accepts, waiting up to a second for it.
+fn next_event<T>(
+    events: &mut broadcast::Receiver<BusEvent>,
+    pick: impl Fn(&PlayerEvent) -> Option<T>,
+) -> T {
+    let deadline = Instant::now() + Duration::from_secs(1);
+    loop {
+        match events.try_recv() {
+            Ok(BusEvent { event, .. }) => {
+                if let Some(picked) = pick(&event) {
+                    return picked;
+                }
+            }
+            Err(TryRecvError::Empty) => {
+                assert!(Instant::now() < deadline, "the event never came");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(e) => panic!("Failed to receive an event: {e}"),
+        }
+    }
+}
+
+/// A playing one minute track.
+fn play_minute_long_track(playback: &mut Playback) -> Track {
+    let mut track = Track::new("/music/song.mp3");
+    track.duration_ms = 60_000;
+    playback.enqueue(track.clone());
+    playback.play().unwrap();
+    track
+}
+
+#[test]
+fn test_play_track() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let track = Track::new("/music/song.mp3");
+    playback.enqueue(track.clone());
+    let _ = playback.play();
+    assert_eq!(playback.current_track(), Some(&track));
+}
+
+#[test]
+fn test_stop() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let track = Track::new("/music/song.mp3");
+    playback.enqueue(track.clone());
+    let _ = playback.play();
+    playback.stop();
+    assert!(playback.current_track().is_none());
+}
+
+#[test]
+fn test_queue_next_track() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    let _ = playback.play();
+    assert_eq!(playback.current_track(), Some(&track1));
+    let _ = playback.next();
+    assert_eq!(playback.current_track(), Some(&track2));
+}
+
+#[test]
+fn test_pause_and_resume() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let track = Track::new("/music/song.mp3");
+    playback.enqueue(track.clone());
+    let _ = playback.play();
+    playback.pause();
+    match playback.state {
+        PlaybackState::Paused => (),
+        _ => panic!("playback should be paused"),
+    }
+    let result = playback.resume_play();
+    assert!(result.is_ok());
+    match playback.state {
+        PlaybackState::Playing => (),
+        _ => panic!("playback should be playing after resume"),
+    }
+}
+
+#[test]
+fn test_play_with_empty_queue() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    playback.queue = Some(Queue::new());
+    let error = playback.play().unwrap_err();
+    assert!(error.to_string().contains("No track to play"));
+}
+
+#[test]
+fn test_play_without_queue() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let error = playback.play().unwrap_err();
+    assert_eq!(error.to_string(), "No track to play");
+}
+
+#[test]
+fn test_previous_with_no_history() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let error = playback.previous().unwrap_err();
+    assert_eq!(error.to_string(), "No track to play");
+}
+#[test]
+fn test_play_appends_to_history() {
+    let playback_arc = create_playback();
+    let track = Track::new("/music/song.mp3");
+    let mut playback = playback_arc.lock().unwrap();
+    playback.enqueue(track.clone());
+    let _ = playback.play();
+    let _ = playback.next();
+    assert_eq!(playback.history.len(), 1);
+    assert_eq!(playback.history[0], track.clone());
+}
+
+#[test]
+fn test_play_multiple_tracks_appends_to_history() {
+    let playback_arc = create_playback();
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    let mut playback = playback_arc.lock().unwrap();
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    let _ = playback.play();
+    let _ = playback.next();
+    let _ = playback.next();
+    assert_eq!(playback.history.len(), 2);
+    assert_eq!(playback.history[0], track1.clone());
+    assert_eq!(playback.history[1], track2.clone());
+}
+
+#[test]
+fn test_previous_prepends_current_track_to_queue() {
+    let playback_arc = create_playback();
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    let mut playback = playback_arc.lock().unwrap();
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    let _ = playback.play();
+    let _ = playback.next();
+    let initial_queue_length = playback.queue().len();
+    let _ = playback.previous();
+    let final_queue_length = playback.queue().len();
+    assert_eq!(final_queue_length, initial_queue_length + 1);
+    let queue_tracks = playback.queue();
+    assert_eq!(queue_tracks.first(), Some(&track2));
+    assert_eq!(playback.current_track(), Some(&track1));
+}
+
+#[test]
+fn test_previous_with_empty_queue_prepends_current_track() {
+    let playback_arc = create_playback();
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    let mut playback = playback_arc.lock().unwrap();
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    let _ = playback.play();
+    let _ = playback.next();
+    playback.clear_queue();
+    let _ = playback.previous();
+    let queue_tracks = playback.queue();
+    assert_eq!(queue_tracks.len(), 1);
+    assert_eq!(queue_tracks[0], track2);
+    assert_eq!(playback.current_track(), Some(&track1));
+}
+
+#[test]
+fn test_previous_with_no_current_track_does_not_prepend_to_queue() {
+    let playback_arc = create_playback();
+
+    let track1 = Track::new("/music/song1.mp3");
+
+    let mut playback = playback_arc.lock().unwrap();
+    playback.enqueue(track1.clone());
+    let _ = playback.play();
+
+    playback.stop();
+    let _ = playback.previous();
+
+    let queue_tracks = playback.queue();
+
+    assert_eq!(queue_tracks.len(), 0);
+}
+
+#[test]
+fn test_multiple_previous_calls_prepend_correctly() {
+    let playback_arc = create_playback();
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    let track3 = Track::new("/music/song3.mp3");
+    let mut playback = playback_arc.lock().unwrap();
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    playback.enqueue(track3.clone());
+    let _ = playback.play();
+    let _ = playback.next();
+    let _ = playback.next();
+    let initial_queue_length = playback.queue().len();
+    let _ = playback.previous();
+    let _ = playback.previous();
+    let final_queue_length = playback.queue().len();
+    let queue_tracks = playback.queue();
+    assert_eq!(final_queue_length, initial_queue_length + 2);
+    assert_eq!(queue_tracks[0], track2);
+    assert_eq!(queue_tracks[1], track3);
+    assert_eq!(playback.current_track(), Some(&track1));
+}
+
+#[test]
+fn test_select_track_from_queue_success() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    let track3 = Track::new("/music/song3.mp3");
+
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    playback.enqueue(track3.clone());
+
+    let _ = playback.play();
+    assert_eq!(playback.current_track(), Some(&track1));
+
+    let result = playback.select_track_from_queue(&track3.id);
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), PlaybackState::Playing);
+
+    // Should now be playing track3
+    assert_eq!(playback.current_track(), Some(&track3));
+
+    // track1 should be in history
+    assert_eq!(playback.history.len(), 1);
+    assert_eq!(playback.history[0], track1);
+
+    // track3 should be removed from queue and track2 should be first
+    let queue = playback.queue();
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue[0], track2);
+}
+
+#[test]
+fn test_select_track_from_queue_with_no_current_track() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+
+    let result = playback.select_track_from_queue(&track2.id);
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), PlaybackState::Playing);
+
+    // Should now be playing track2
+    assert_eq!(playback.current_track(), Some(&track2));
+
+    // History should be empty since no track was playing
+    assert_eq!(playback.history.len(), 0);
+
+    // track1 should be the only track left in queue
+    let queue = playback.queue();
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue[0], track1);
+}
+
+#[test]
+fn test_select_track_from_queue_moves_to_front() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    let track3 = Track::new("/music/song3.mp3");
+    let track4 = Track::new("/music/song4.mp3");
+
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    playback.enqueue(track3.clone());
+    playback.enqueue(track4.clone());
+
+    let result = playback.select_track_from_queue(&track3.id);
+    assert!(result.is_ok());
+
+    // Should now be playing track3
+    assert_eq!(playback.current_track(), Some(&track3));
+
+    // Queue should have track3 removed and other tracks in original order
+    let queue = playback.queue();
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue[0], track1);
+    assert_eq!(queue[1], track2);
+    assert_eq!(queue[2], track4);
+}
+
+#[test]
+fn test_select_track_from_queue_nonexistent_track() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let track1 = Track::new("/music/song1.mp3");
+    playback.enqueue(track1.clone());
+
+    let result = playback.select_track_from_queue("nonexistent-id");
+    assert!(result.is_err());
+    assert_eq!(result.unwrap_err().to_string(), "Track not found in queue");
+}
+
+#[test]
+fn test_select_track_from_queue_empty_queue() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let result = playback.select_track_from_queue("any-id");
+    assert!(result.is_err());
+    assert_eq!(result.unwrap_err().to_string(), "Track not found in queue");
+}
+
+#[test]
+fn test_select_track_from_queue_no_queue() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    // Don't create any queue
+    let result = playback.select_track_from_queue("any-id");
+    assert!(result.is_err());
+    assert_eq!(result.unwrap_err().to_string(), "Track not found in queue");
+}
+
+#[test]
+fn test_select_track_from_queue_first_track() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    let track3 = Track::new("/music/song3.mp3");
+
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    playback.enqueue(track3.clone());
+
+    let _ = playback.play();
+    assert_eq!(playback.current_track(), Some(&track1));
+
+    // Select the first track in queue (track2)
+    let result = playback.select_track_from_queue(&track2.id);
+    assert!(result.is_ok());
+
+    // Should now be playing track2
+    assert_eq!(playback.current_track(), Some(&track2));
+
+    // track1 should be in history
+    assert_eq!(playback.history.len(), 1);
+    assert_eq!(playback.history[0], track1);
+
+    // Queue should have track3 only
+    let queue = playback.queue();
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue[0], track3);
+}
+
+#[test]
+fn test_select_track_from_queue_with_paused_state() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+
+    let _ = playback.play();
+    let _ = playback.pause();
+    assert_eq!(playback.state, PlaybackState::Paused);
+
+    let result = playback.select_track_from_queue(&track2.id);
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), PlaybackState::Playing);
+
+    // Should now be playing track2
+    assert_eq!(playback.current_track(), Some(&track2));
+    assert_eq!(playback.state, PlaybackState::Playing);
+}
+
+#[test]
+fn test_normalization_gain_follows_album_context() {
+    use crate::normalization::{NormalizationMode, NormalizationSettings, ReplayGain};
+
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let mut track = Track::new("/music/song.mp3");
+    track.replay_gain = Some(ReplayGain {
+        track_gain_db: Some(-20.0),
+        album_gain_db: Some(0.0),
+        ..Default::default()
+    });
+    playback.enqueue(track);
+    playback
+        .set_normalization(NormalizationSettings {
+            mode: NormalizationMode::Auto,
+            preamp_db: 0.0,
+            prevent_clipping: true,
+        })
+        .unwrap();
+    let _ = playback.play();
+
+    assert!((playback.normalization_gain() - 0.1).abs() < 1e-4);
+    playback.set_playing_album(true);
+    assert_eq!(playback.normalization_gain(), 1.0);
+}
+
+#[test]
+fn test_adding_tracks_leaves_album_mode() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    playback.enqueue_multiple(vec![Track::new("/music/a.mp3"), Track::new("/music/b.mp3")]);
+    playback.set_playing_album(true);
+    playback.enqueue(Track::new("/music/other.mp3"));
+    assert!(!playback.playing_album);
+
+    playback.set_playing_album(true);
+    playback.prepend(Track::new("/music/other.mp3"));
+    assert!(!playback.playing_album);
+
+    playback.set_playing_album(true);
+    playback.clear_queue();
+    assert!(!playback.playing_album);
+}
+
+#[test]
+fn test_speed_and_pitch_are_validated() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    assert!(playback.set_speed(1.5).is_ok());
+    assert_eq!(playback.speed(), 1.5);
+    assert!(playback.set_speed(0.25).is_err());
+    assert!(playback.set_speed(3.5).is_err());
+    assert_eq!(playback.speed(), 1.5);
+
+    assert!(playback.set_pitch(-3.0).is_ok());
+    assert_eq!(playback.pitch_semitones(), -3.0);
+    assert!(playback.set_pitch(13.0).is_err());
+    assert_eq!(playback.pitch_semitones(), -3.0);
+}
+
+#[test]
+fn test_unplayable_track_is_reported_and_skipped() {
+    let bus = EventBus::new();
+    let (_, mut events) = bus.subscribe();
+    let playback_arc = Playback::create(Box::new(TestPlaybackDriver::new()), bus);
+    let missing = Track::new("/music/missing.mp3");
+    let next = Track::new("/music/song.mp3");
+    {
+        let mut playback = playback_arc.lock().unwrap();
+        playback.enqueue(missing.clone());
+        playback.enqueue(next.clone());
+        let _ = playback.play();
+    }
+
+    let failed = next_event(&mut events, |event| match event {
+        PlayerEvent::PlaybackError(error) => Some(error.track_id.clone()),
+        _ => None,
+    });
+    assert_eq!(failed, missing.id);
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while playback_arc.lock().unwrap().current_track() != Some(&next) {
+        assert!(
+            Instant::now() < deadline,
+            "unplayable track was not skipped"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let history = playback_arc.lock().unwrap().history.clone();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, missing.id);
+    assert!(history[0].unplayable);
+}
+
+#[test]
+fn test_missing_output_pauses_on_the_track() {
+    let bus = EventBus::new();
+    let (_, mut events) = bus.subscribe();
+    let playback_arc = Playback::create(Box::new(TestPlaybackDriver::new()), bus);
+    let unheard = Track::new("/music/unheard.mp3");
+    let next = Track::new("/music/song.mp3");
+    {
+        let mut playback = playback_arc.lock().unwrap();
+        playback.enqueue(unheard.clone());
+        playback.enqueue(next.clone());
+        let _ = playback.play();
+    }
+
+    next_event(&mut events, |event| match event {
+        PlayerEvent::OutputUnavailable(_) => Some(()),
+        PlayerEvent::PlaybackError(_) => panic!("the track was reported unplayable"),
+        _ => None,
+    });
+    next_event(&mut events, |event| match event {
+        PlayerEvent::StateChanged(state) if state.state == PlaybackState::Paused => Some(()),
+        _ => None,
+    });
+    let playback = playback_arc.lock().unwrap();
+    assert_eq!(playback.current_track(), Some(&unheard));
+    assert_eq!(playback.queue(), [next]);
+}
+
+#[test]
+fn test_repeat_all_stops_when_no_track_opens() {
+    let bus = EventBus::new();
+    let (_, mut events) = bus.subscribe();
+    let playback_arc = Playback::create(Box::new(TestPlaybackDriver::new()), bus);
+    let missing = Track::new("/music/missing.mp3");
+    {
+        let mut playback = playback_arc.lock().unwrap();
+        playback.enqueue(missing.clone());
+        let _ = playback.set_repeat(RepeatMode::All);
+        let _ = playback.play();
+    }
+
+    next_event(&mut events, |event| match event {
+        PlayerEvent::PlaybackError(_) => Some(()),
+        _ => None,
+    });
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while playback_arc.lock().unwrap().current_track().is_some() {
+        assert!(Instant::now() < deadline, "unplayable track was retried");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    std::thread::sleep(Duration::from_millis(50));
+    while let Ok(BusEvent { event, .. }) = events.try_recv() {
+        assert!(!matches!(event, PlayerEvent::PlaybackError(_)));
+    }
+    let snapshot = playback_arc.lock().unwrap().snapshot();
+    assert_eq!(snapshot.state, PlaybackState::Stopped);
+}
+
+#[test]
+fn test_execute_returns_a_snapshot() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let track = Track::new("/music/song.mp3");
+    playback.enqueue(track.clone());
+
+    let snapshot = playback.execute(PlaybackCommand::Play).unwrap();
+    assert_eq!(snapshot.state, PlaybackState::Playing);
+    assert_eq!(snapshot.track, Some(track));
+    assert_eq!(snapshot.position_ms, 0);
+
+    let snapshot = playback
+        .execute(PlaybackCommand::SetRepeat {
+            mode: RepeatMode::All,
+        })
+        .unwrap();
+    assert_eq!(snapshot.repeat, RepeatMode::All);
+
+    let snapshot = playback.execute(PlaybackCommand::TogglePlayPause).unwrap();
+    assert_eq!(snapshot.state, PlaybackState::Paused);
+    let snapshot = playback.execute(PlaybackCommand::TogglePlayPause).unwrap();
+    assert_eq!(snapshot.state, PlaybackState::Playing);
+}
+
+#[test]
+fn test_execute_announces_state_changes() {
+    let bus = EventBus::new();
+    let (_, mut events) = bus.subscribe();
+    let playback_arc = Playback::create(Box::new(TestPlaybackDriver::new()), bus);
+    {
+        let mut playback = playback_arc.lock().unwrap();
+        playback
+            .execute(PlaybackCommand::SetRepeat {
+                mode: RepeatMode::All,
+            })
+            .unwrap();
+        // Nothing changes
+        playback
+            .execute(PlaybackCommand::SetRepeat {
+                mode: RepeatMode::All,
+            })
+            .unwrap();
+        playback
+            .execute(PlaybackCommand::SetMuted { muted: true })
+            .unwrap();
+    }
+
+    let pick = |event: &PlayerEvent| match event {
+        PlayerEvent::StateChanged(state) => Some(state.clone()),
+        _ => None,
+    };
+    // The player starts by announcing its initial state
+    let changed = next_event(&mut events, pick);
+    assert_eq!(changed.repeat, RepeatMode::Off);
+    let changed = next_event(&mut events, pick);
+    assert_eq!(changed.repeat, RepeatMode::All);
+    assert!(!changed.muted);
+    let changed = next_event(&mut events, pick);
+    assert!(changed.muted);
+    assert_eq!(changed.state, PlaybackState::Stopped);
+}
+
+#[test]
+fn test_repeat_one_replays_the_completed_track() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    let _ = playback.play();
+    let _ = playback.set_repeat(RepeatMode::One);
+
+    let _ = playback.track_completed();
+    assert_eq!(playback.current_track(), Some(&track1));
+    assert!(playback.history.is_empty());
+
+    // Skipping still moves on
+    let _ = playback.next();
+    assert_eq!(playback.current_track(), Some(&track2));
+}
+
+#[test]
+fn test_repeat_all_requeues_played_tracks() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let track1 = Track::new("/music/song1.mp3");
+    let track2 = Track::new("/music/song2.mp3");
+    playback.enqueue(track1.clone());
+    playback.enqueue(track2.clone());
+    let _ = playback.set_repeat(RepeatMode::All);
+    let _ = playback.play();
+
+    let _ = playback.track_completed();
+    assert_eq!(playback.current_track(), Some(&track2));
+    let _ = playback.track_completed();
+    assert_eq!(playback.current_track(), Some(&track1));
+    assert_eq!(playback.queue(), vec![track2]);
+    assert!(playback.history.is_empty());
+}
+
+#[test]
+fn test_shuffle_keeps_the_queued_tracks() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let tracks: Vec<Track> = (0..20)
+        .map(|i| Track::new(format!("/music/song{i}.mp3")))
+        .collect();
+    playback.enqueue_multiple(tracks.clone());
+
+    let _ = playback.set_shuffle(true);
+    let mut shuffled = playback.queue();
+    assert_ne!(shuffled, tracks);
+    shuffled.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut sorted = tracks;
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(shuffled, sorted);
+}
+
+#[test]
+fn test_remove_from_queue() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let tracks: Vec<Track> = (0..3)
+        .map(|i| Track::new(format!("/music/song{i}.mp3")))
+        .collect();
+    playback.enqueue_multiple(tracks.clone());
+
+    playback.remove_from_queue(&tracks[1].id).unwrap();
+    assert_eq!(playback.queue(), vec![tracks[0].clone(), tracks[2].clone()]);
+    assert!(playback.remove_from_queue(&tracks[1].id).is_err());
+}
+
+#[test]
+fn test_stop_command_clears_the_current_track() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    play_minute_long_track(&mut playback);
+
+    let snapshot = playback.execute(PlaybackCommand::Stop).unwrap();
+    assert_eq!(snapshot.state, PlaybackState::Stopped);
+    assert_eq!(snapshot.track, None);
+    assert_eq!(snapshot.position_ms, 0);
+}
+
+#[test]
+fn test_unmute_restores_the_previous_volume() {
+    let (playback_arc, log) = create_logged_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    playback.set_volume(0.6).unwrap();
+
+    playback.set_muted(true).unwrap();
+    assert_eq!(log.lock().unwrap().volume, Some(0.0));
+    // Volume changes while muted apply on unmute
+    playback.set_volume(0.3).unwrap();
+    assert_eq!(log.lock().unwrap().volume, Some(0.0));
+    assert!(playback.snapshot().muted);
+
+    playback.set_muted(false).unwrap();
+    assert_eq!(log.lock().unwrap().volume, Some(0.3));
+    assert_eq!(playback.snapshot().volume, 0.3);
+    assert!(!playback.snapshot().muted);
+}
+
+#[test]
+fn test_relative_seek_is_clamped_to_the_track() {
+    let (playback_arc, log) = create_logged_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    play_minute_long_track(&mut playback);
+
+    playback.seek_relative(5_000).unwrap();
+    playback.seek_relative(-10_000).unwrap();
+    playback.seek(Duration::from_secs(50)).unwrap();
+    playback.seek_relative(30_000).unwrap();
+
+    assert_eq!(
+        log.lock().unwrap().seeks,
+        [5, 0, 50, 60].map(Duration::from_secs)
+    );
+    assert_eq!(playback.snapshot().position_ms, 60_000);
+}
+
+#[test]
+fn test_relative_seek_needs_a_track() {
+    let playback_arc = create_playback();
+    let mut playback = playback_arc.lock().unwrap();
+
+    let error = playback.seek_relative(5_000).unwrap_err();
+    assert_eq!(error.to_string(), "No track to play");
+}
+
+#[test]
+fn test_previous_restarts_the_track_after_three_seconds() {
+    let (playback_arc, log) = create_logged_playback();
+    let mut playback = playback_arc.lock().unwrap();
+    let first = play_minute_long_track(&mut playback);
+    let second = Track::new("/music/other.mp3");
+    playback.enqueue(second.clone());
+    playback.next().unwrap();
+
+    playback.seek(Duration::from_secs(4)).unwrap();
+    playback.previous().unwrap();
+    assert_eq!(playback.current_track(), Some(&second));
+    assert_eq!(log.lock().unwrap().seeks.last(), Some(&Duration::ZERO));
+    assert_eq!(playback.snapshot().position_ms, 0);
+
+    // Within the first seconds it goes back
+    playback.previous().unwrap();
+    assert_eq!(playback.current_track(), Some(&first));
+}
+
+#[test]
+fn test_seek_and_pause_report_the_position() {
+    let bus = EventBus::new();
+    let (_, mut events) = bus.subscribe();
+    let playback_arc = Playback::create(Box::new(TestPlaybackDriver::new()), bus);
+    {
+        let mut playback = playback_arc.lock().unwrap();
+        play_minute_long_track(&mut playback);
+        playback.seek(Duration::from_secs(20)).unwrap();
+        playback.seek_relative(-5_000).unwrap();
+        playback.pause().unwrap();
+        assert_eq!(playback.snapshot().position_ms, 15_000);
+    }
+
+    let reported: Vec<u64> = (0..3)
+        .map(|_| {
+            next_event(&mut events, |event| match event {
+                PlayerEvent::Progress(progress) => Some(progress.frames_played),
+                _ => None,
+            })
+        })
+        .collect();
+    assert_eq!(reported, [20_000, 15_000, 15_000]);
+}
@@ -1,4 +1,6 @@
-use crate::player::track::Track;
+use crate::track::Track;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
 
 pub struct Queue {
     tracks: Vec<Track>,
@@ -21,6 +23,10 @@ impl Queue {
         self.tracks.iter()
     }
 
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Track> {
+        self.tracks.iter_mut()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.tracks.is_empty()
     }
@@ -61,6 +67,18 @@ impl Queue {
         self.tracks.clear();
     }
 
+    /// Shuffles the tracks in place.
+    pub fn shuffle(&mut self) {
+        let mut state = RandomState::new().build_hasher().finish() | 1;
+        for i in (1..self.tracks.len()).rev() {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            self.tracks.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+    }
+
     pub fn move_item(&mut self, from: usize, to: usize) {
         if from < self.tracks.len() && to < self.tracks.len() {
             let track = self.tracks.remove(from);
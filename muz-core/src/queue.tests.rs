@@ -1,5 +1,5 @@
 use super::*;
-use crate::player::track::Track;
+use crate::track::Track;
 
 #[test]
 fn test_enqueue_dequeue() {
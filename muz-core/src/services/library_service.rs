@@ -0,0 +1,329 @@
+use crate::bus::EventBus;
+use crate::error::LibraryError;
+use crate::events::{
+    LibraryChangedEvent, LoudnessAnalysisFinishedEvent, LoudnessAnalysisProgressEvent, PlayerEvent,
+};
+use crate::loudness::{self, AnalysisJob, LoudnessAnalyzer, TrackLoudness};
+use crate::{library::Library, tag_writer, track::Track};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Clone)]
+pub struct LibraryService {
+    library: Arc<Mutex<Library>>,
+    events: EventBus,
+}
+
+impl LibraryService {
+    pub fn new(library: Arc<Mutex<Library>>, events: EventBus) -> Self {
+        Self { library, events }
+    }
+
+    pub async fn library_path(&self) -> Result<String> {
+        let library = self.library.lock().await;
+        Ok(library.path.to_string_lossy().to_string())
+    }
+
+    pub async fn set_library_path(&self, path: PathBuf) -> Result<()> {
+        let mut library = self.library.lock().await;
+        library.update(Some(path), None);
+        library.rescan().await;
+        self.library_changed(&library);
+        Ok(())
+    }
+
+    pub async fn rescan_library(&self) -> Result<()> {
+        let mut library = self.library.lock().await;
+        library.rescan().await;
+        self.library_changed(&library);
+        Ok(())
+    }
+
+    fn library_changed(&self, library: &Library) {
+        self.events
+            .publish(PlayerEvent::LibraryChanged(LibraryChangedEvent {
+                track_count: library.tracks().len(),
+            }));
+    }
+
+    pub async fn tracks(&self) -> Vec<Track> {
+        let library = self.library.lock().await;
+        library.tracks_cloned()
+    }
+
+    /// Tracks whose title, artist, album artist or album contain every word of the
+    /// query, ignoring case.
+    pub async fn search(&self, query: &str) -> Vec<Track> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let library = self.library.lock().await;
+        library
+            .tracks()
+            .iter()
+            .filter(|track| {
+                let Some(m) = &track.metadata else {
+                    return false;
+                };
+                let fields: Vec<String> = [&m.title, &m.artist, &m.album_artist, &m.album]
+                    .into_iter()
+                    .flatten()
+                    .map(|field| field.to_lowercase())
+                    .collect();
+                words
+                    .iter()
+                    .all(|word| fields.iter().any(|field| field.contains(word)))
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub async fn library_tracks(&self) -> Result<HashMap<String, Vec<Track>>> {
+        let library = self.library.lock().await;
+        let tracks = library.tracks();
+
+        let mut grouped: HashMap<String, Vec<Track>> = HashMap::new();
+        for track in tracks {
+            let album = track
+                .metadata
+                .as_ref()
+                .and_then(|m| m.album.clone())
+                .unwrap_or_else(|| "Unknown Album".to_string());
+            grouped
+                .entry(album)
+                .or_insert_with(Vec::new)
+                .push(track.clone());
+        }
+
+        Ok(grouped)
+    }
+
+    pub async fn albums_by_artist(&self) -> Result<HashMap<String, HashMap<String, Vec<Track>>>> {
+        let library = self.library.lock().await;
+        let tracks = library.tracks();
+
+        let mut grouped: HashMap<String, HashMap<String, Vec<Track>>> = HashMap::new();
+
+        for track in tracks {
+            let artist = track
+                .metadata
+                .as_ref()
+                .and_then(|m| m.album_artist.as_ref().or(m.artist.as_ref()))
+                .cloned()
+                .unwrap_or_else(|| "Unknown Artist".to_string());
+
+            let album = track
+                .metadata
+                .as_ref()
+                .and_then(|m| m.album.clone())
+                .unwrap_or_else(|| "Unknown Album".to_string());
+
+            grouped
+                .entry(artist)
+                .or_insert_with(HashMap::new)
+                .entry(album)
+                .or_insert_with(Vec::new)
+                .push(track.clone());
+        }
+
+        for artist_albums in grouped.values_mut() {
+            for album_tracks in artist_albums.values_mut() {
+                album_tracks.sort_by(|a, b| {
+                    let a_track_num = a
+                        .metadata
+                        .as_ref()
+                        .and_then(|m| m.track_number)
+                        .unwrap_or(0);
+                    let b_track_num = b
+                        .metadata
+                        .as_ref()
+                        .and_then(|m| m.track_number)
+                        .unwrap_or(0);
+                    a_track_num.cmp(&b_track_num)
+                });
+            }
+        }
+
+        Ok(grouped)
+    }
+
+    pub async fn track_by_id(&self, track_id: &str) -> Result<Track> {
+        let library = self.library.lock().await;
+        library
+            .track_by_id(track_id)
+            .ok_or_else(|| LibraryError::TrackNotFound(track_id.to_string()).into())
+    }
+
+    pub async fn mark_unplayable(&self, track_id: &str) -> Option<Track> {
+        let mut library = self.library.lock().await;
+        library.mark_unplayable(track_id)
+    }
+
+    pub async fn tracks_by_album(&self, album_name: &str, artist_name: &str) -> Result<Vec<Track>> {
+        let library = self.library.lock().await;
+        let mut tracks: Vec<Track> = library
+            .tracks()
+            .iter()
+            .cloned()
+            .filter(|track| {
+                let unknown_album = "Unknown Album".to_string();
+                let unknown_artist = "Unknown Artist".to_string();
+
+                let track_album = track
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.album.as_ref())
+                    .unwrap_or(&unknown_album);
+                let track_artist = track
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.album_artist.as_ref().or(m.artist.as_ref()))
+                    .unwrap_or(&unknown_artist);
+
+                track_album == album_name && track_artist == artist_name
+            })
+            .collect();
+
+        if tracks.is_empty() {
+            return Err(LibraryError::AlbumNotFound {
+                album: album_name.to_string(),
+                artist: artist_name.to_string(),
+            }
+            .into());
+        }
+
+        tracks.sort_by(|a, b| {
+            let a_track_num = a
+                .metadata
+                .as_ref()
+                .and_then(|m| m.track_number)
+                .unwrap_or(0);
+            let b_track_num = b
+                .metadata
+                .as_ref()
+                .and_then(|m| m.track_number)
+                .unwrap_or(0);
+            a_track_num.cmp(&b_track_num)
+        });
+
+        Ok(tracks)
+    }
+
+    /// Measures every track lacking ReplayGain info, album by album, until done or cancelled,
+    /// reporting the progress on the bus. Returns the number of tracks analyzed.
+    pub async fn analyze_loudness(
+        &self,
+        job: &AnalysisJob,
+        write_tags: bool,
+        on_track_analyzed: impl Fn(&Track),
+    ) -> Result<usize> {
+        let result = self
+            .measure_loudness(job, write_tags, on_track_analyzed)
+            .await;
+        self.events.publish(PlayerEvent::LoudnessAnalysisFinished(
+            LoudnessAnalysisFinishedEvent {
+                analyzed: *result.as_ref().unwrap_or(&0),
+                cancelled: job.is_cancelled(),
+            },
+        ));
+        result
+    }
+
+    async fn measure_loudness(
+        &self,
+        job: &AnalysisJob,
+        write_tags: bool,
+        on_track_analyzed: impl Fn(&Track),
+    ) -> Result<usize> {
+        let albums = {
+            let library = self.library.lock().await;
+            let mut albums: Vec<Vec<Track>> = Vec::new();
+            let mut album_indices: HashMap<(String, String), usize> = HashMap::new();
+            for track in library.tracks() {
+                if track.replay_gain.is_some() || library.loudness(track).is_some() {
+                    continue;
+                }
+                let album_key = track.metadata.as_ref().and_then(|m| {
+                    let artist = m.album_artist.as_ref().or(m.artist.as_ref())?;
+                    Some((artist.clone(), m.album.clone()?))
+                });
+                match album_key.map(|key| album_indices.get(&key).copied().ok_or(key)) {
+                    Some(Ok(index)) => albums[index].push(track.clone()),
+                    Some(Err(key)) => {
+                        album_indices.insert(key, albums.len());
+                        albums.push(vec![track.clone()]);
+                    }
+                    None => albums.push(vec![track.clone()]),
+                }
+            }
+            albums
+        };
+
+        let total = albums.iter().map(Vec::len).sum();
+        let mut processed = 0;
+
+        for album in albums {
+            let mut analyzed = Vec::new();
+            for track in album {
+                if job.is_cancelled() {
+                    return Ok(processed);
+                }
+                self.events.publish(PlayerEvent::LoudnessAnalysisProgress(
+                    LoudnessAnalysisProgressEvent {
+                        processed,
+                        total,
+                        track_id: track.id.clone(),
+                    },
+                ));
+
+                let worker_job = job.clone();
+                let worker_track = track.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    loudness::analyze_track(&worker_track, &worker_job)
+                })
+                .await
+                .map_err(|e| anyhow!("Loudness analysis task failed: {e}"))?;
+
+                processed += 1;
+                match result {
+                    Ok(analyzer) => analyzed.push((track, analyzer)),
+                    Err(_) if job.is_cancelled() => return Ok(processed - 1),
+                    Err(e) => tracing::warn!("Failed to analyze {:?}: {e}", track.path),
+                }
+            }
+
+            let analyzers: Vec<LoudnessAnalyzer> =
+                analyzed.iter().map(|(_, a)| a.clone()).collect();
+            let album_result =
+                (analyzers.len() > 1).then(|| LoudnessAnalyzer::combined(&analyzers));
+
+            for (track, analyzer) in analyzed {
+                let track_loudness = TrackLoudness {
+                    track: analyzer.result(),
+                    album: album_result,
+                };
+                if write_tags && track.segment.is_none() {
+                    if let Some(replay_gain) = track_loudness.replay_gain() {
+                        if let Err(e) = tag_writer::write_replay_gain(&track.path, &replay_gain) {
+                            tracing::warn!("Failed to write ReplayGain tags: {e}");
+                        }
+                    }
+                }
+                let updated = self
+                    .library
+                    .lock()
+                    .await
+                    .set_loudness(&track.id, track_loudness);
+                if let Some(updated) = updated {
+                    on_track_analyzed(&updated);
+                }
+            }
+            if let Err(e) = self.library.lock().await.save_cache() {
+                tracing::warn!("{e:#}");
+            }
+        }
+
+        Ok(processed)
+    }
+}
@@ -0,0 +1,159 @@
+use crate::command::PlaybackCommand;
+use crate::dsp::DspSettings;
+use crate::equalizer::EqualizerSettings;
+use crate::normalization::{NormalizationSettings, ReplayGain};
+use crate::driver::OutputDevice;
+use crate::error::LibraryError;
+use crate::playback::{Playback, PlaybackSnapshot, PlaybackState};
+use crate::track::Track;
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone)]
+pub struct PlaybackService {
+    playback: Arc<Mutex<Playback>>,
+}
+
+impl PlaybackService {
+    pub fn new(playback: Arc<Mutex<Playback>>) -> Self {
+        Self { playback }
+    }
+
+    pub fn control_playback(&self, command: PlaybackCommand) -> Result<PlaybackSnapshot> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        tracing::debug!("Playback command: {:?}", command);
+        playback.execute(command)
+    }
+
+    pub fn status(&self) -> Result<PlaybackSnapshot> {
+        let playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        Ok(playback.snapshot())
+    }
+
+    /// The upcoming tracks, excluding the current one.
+    pub fn queue(&self) -> Result<Vec<Track>> {
+        let playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        Ok(playback.queue())
+    }
+
+    /// The current track followed by the queue.
+    pub fn tracks(&self) -> Result<Vec<Track>> {
+        let playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        Ok(playback.current_track_cloned().into_iter().chain(playback.queue()).collect())
+    }
+
+    pub fn play_single_track(&self, track: Track) -> Result<PlaybackState> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+
+        playback.set_playing_album(false);
+        playback.prepend(track);
+        if playback.state == PlaybackState::Playing || playback.state == PlaybackState::Paused {
+            playback.next()
+        } else {
+            playback.play()
+        }
+    }
+
+    pub fn play_album_tracks(
+        &self,
+        album_tracks: Vec<Track>,
+        track_id: &str,
+    ) -> Result<PlaybackState> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+
+        let selected_track_id = album_tracks
+            .iter()
+            .find(|track| track.id == track_id)
+            .map(|track| track.id.clone())
+            .ok_or_else(|| LibraryError::TrackNotFound(track_id.to_string()))?;
+
+        playback.clear_queue();
+        playback.enqueue_multiple(album_tracks);
+        playback.set_playing_album(true);
+
+        playback.select_track_from_queue(&selected_track_id)
+    }
+
+    pub fn select_from_queue(&self, track_id: &str) -> Result<PlaybackState> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.select_track_from_queue(track_id)
+    }
+
+    pub fn clear_queue_and_enqueue(&self, tracks: Vec<Track>) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.clear_queue();
+        playback.enqueue_multiple(tracks);
+        playback.set_playing_album(false);
+        Ok(())
+    }
+
+    pub fn set_normalization(&self, settings: NormalizationSettings) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.set_normalization(settings)
+    }
+
+    pub fn set_equalizer(&self, settings: EqualizerSettings) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.set_equalizer(settings)
+    }
+
+    pub fn set_dsp(&self, settings: DspSettings) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.set_dsp(settings)
+    }
+
+    pub fn output_devices(&self) -> Result<Vec<OutputDevice>> {
+        let playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.output_devices()
+    }
+
+    pub fn set_output_device(&self, device: Option<String>) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.set_output_device(device)
+    }
+
+    pub fn update_replay_gain(&self, track_id: &str, replay_gain: ReplayGain) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.update_replay_gain(track_id, replay_gain)
+    }
+
+    pub fn enqueue(&self, tracks: Vec<Track>) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.enqueue_multiple(tracks);
+        Ok(())
+    }
+
+    pub fn remove_from_queue(&self, track_id: &str) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.remove_from_queue(track_id)
+    }
+
+    pub fn clear_queue(&self) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.clear_queue();
+        Ok(())
+    }
+
+    pub fn reorder_queue(&self, old_index: usize, new_index: usize) -> Result<()> {
+        let mut playback = self.playback.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
+        playback.reorder_queue(old_index, new_index)
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::normalization::ReplayGain;
+
+const FLAC_MARKER: &[u8; 4] = b"fLaC";
+const STREAMINFO_BLOCK: u8 = 0;
+const VORBIS_COMMENT_BLOCK: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MetadataBlock {
+    block_type: u8,
+    data: Vec<u8>,
+}
+
+/// Writes ReplayGain tags into the file. Only FLAC Vorbis comments are supported for now.
+pub fn write_replay_gain(path: &Path, replay_gain: &ReplayGain) -> Result<()> {
+    let is_flac = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("flac"));
+    if !is_flac {
+        return Err(anyhow!(
+            "Writing ReplayGain tags is not supported for {:?}",
+            path
+        ));
+    }
+
+    let mut comments = Vec::new();
+    if let Some(gain) = replay_gain.track_gain_db {
+        comments.push(format!("REPLAYGAIN_TRACK_GAIN={gain:.2} dB"));
+    }
+    if let Some(peak) = replay_gain.track_peak {
+        comments.push(format!("REPLAYGAIN_TRACK_PEAK={peak:.6}"));
+    }
+    if let Some(gain) = replay_gain.album_gain_db {
+        comments.push(format!("REPLAYGAIN_ALBUM_GAIN={gain:.2} dB"));
+    }
+    if let Some(peak) = replay_gain.album_peak {
+        comments.push(format!("REPLAYGAIN_ALBUM_PEAK={peak:.6}"));
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut blocks = read_flac_metadata(&mut reader)?;
+    set_comments(&mut blocks, "REPLAYGAIN_", comments);
+
+    // Rewrite into a sibling file and swap it in, so a failure never truncates the original
+    let temp_path = path.with_extension("flac.muz-tmp");
+    let result = (|| -> Result<()> {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        write_flac_metadata(&mut writer, &blocks)?;
+        io::copy(&mut reader, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    })();
+    drop(reader);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+fn read_flac_metadata(reader: &mut impl Read) -> Result<Vec<MetadataBlock>> {
+    let mut marker = [0u8; 4];
+    reader.read_exact(&mut marker)?;
+    if &marker != FLAC_MARKER {
+        return Err(anyhow!("Not a FLAC file"));
+    }
+
+    let mut blocks = Vec::new();
+    loop {
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+        let is_last = header[0] & 0x80 != 0;
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut data = vec![0u8; length];
+        reader.read_exact(&mut data)?;
+        blocks.push(MetadataBlock {
+            block_type: header[0] & 0x7f,
+            data,
+        });
+        if is_last {
+            return Ok(blocks);
+        }
+    }
+}
+
+fn write_flac_metadata(writer: &mut impl Write, blocks: &[MetadataBlock]) -> Result<()> {
+    writer.write_all(FLAC_MARKER)?;
+    for (index, block) in blocks.iter().enumerate() {
+        let length = u32::try_from(block.data.len())
+            .ok()
+            .filter(|l| *l < 1 << 24)
+            .ok_or_else(|| anyhow!("Metadata block too large"))?;
+        let last_flag = if index == blocks.len() - 1 { 0x80 } else { 0 };
+        let [_, l1, l2, l3] = length.to_be_bytes();
+        writer.write_all(&[last_flag | block.block_type, l1, l2, l3])?;
+        writer.write_all(&block.data)?;
+    }
+    Ok(())
+}
+
+/// Replaces every comment whose key starts with `prefix` by `comments`.
+fn set_comments(blocks: &mut Vec<MetadataBlock>, prefix: &str, comments: Vec<String>) {
+    let index = match blocks
+        .iter()
+        .position(|b| b.block_type == VORBIS_COMMENT_BLOCK)
+    {
+        Some(index) => index,
+        None => {
+            let after_streaminfo = blocks
+                .iter()
+                .position(|b| b.block_type == STREAMINFO_BLOCK)
+                .map_or(0, |i| i + 1);
+            blocks.insert(
+                after_streaminfo,
+                MetadataBlock {
+                    block_type: VORBIS_COMMENT_BLOCK,
+                    data: encode_vorbis_comments("muz", &[]),
+                },
+            );
+            after_streaminfo
+        }
+    };
+
+    let (vendor, existing) = decode_vorbis_comments(&blocks[index].data);
+    let mut kept: Vec<String> = existing
+        .into_iter()
+        .filter(|c| {
+            let key = c.split('=').next().unwrap_or_default();
+            !key.to_uppercase().starts_with(prefix)
+        })
+        .collect();
+    kept.extend(comments);
+    blocks[index].data = encode_vorbis_comments(&vendor, &kept);
+}
+
+fn decode_vorbis_comments(data: &[u8]) -> (String, Vec<String>) {
+    let mut pos = 0;
+    let read_string = |pos: &mut usize| -> Option<String> {
+        let length = u32::from_le_bytes(data.get(*pos..*pos + 4)?.try_into().ok()?) as usize;
+        let value = data.get(*pos + 4..*pos + 4 + length)?;
+        *pos += 4 + length;
+        Some(String::from_utf8_lossy(value).to_string())
+    };
+
+    let vendor = read_string(&mut pos).unwrap_or_default();
+    let count = data
+        .get(pos..pos + 4)
+        .and_then(|b| b.try_into().ok())
+        .map_or(0, u32::from_le_bytes);
+    pos += 4;
+    let comments = (0..count).map_while(|_| read_string(&mut pos)).collect();
+    (vendor, comments)
+}
+
+fn encode_vorbis_comments(vendor: &str, comments: &[String]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    data.extend_from_slice(vendor.as_bytes());
+    data.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        data.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        data.extend_from_slice(comment.as_bytes());
+    }
+    data
+}
+
+#[cfg(test)]
+#[path = "./tag_writer.tests.rs"]
+mod tests;
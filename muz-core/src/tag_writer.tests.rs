@@ -0,0 +1,106 @@
+use super::*;
+
+fn flac_bytes(blocks: &[MetadataBlock], audio: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_flac_metadata(&mut bytes, blocks).unwrap();
+    bytes.extend_from_slice(audio);
+    bytes
+}
+
+fn streaminfo() -> MetadataBlock {
+    MetadataBlock {
+        block_type: STREAMINFO_BLOCK,
+        data: vec![0; 34],
+    }
+}
+
+fn replay_gain() -> ReplayGain {
+    ReplayGain {
+        track_gain_db: Some(-7.5),
+        track_peak: Some(0.95),
+        album_gain_db: None,
+        album_peak: None,
+    }
+}
+
+fn temp_flac(bytes: &[u8]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("muz-tags-{}.flac", uuid::Uuid::new_v4()));
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+#[test]
+fn test_metadata_round_trip() {
+    let blocks = vec![
+        streaminfo(),
+        MetadataBlock {
+            block_type: VORBIS_COMMENT_BLOCK,
+            data: encode_vorbis_comments("vendor", &["TITLE=Song".to_string()]),
+        },
+    ];
+    let bytes = flac_bytes(&blocks, b"audio");
+    let mut reader = bytes.as_slice();
+    assert_eq!(read_flac_metadata(&mut reader).unwrap(), blocks);
+    assert_eq!(reader, b"audio");
+}
+
+#[test]
+fn test_write_replay_gain_replaces_existing_tags() {
+    let blocks = vec![
+        streaminfo(),
+        MetadataBlock {
+            block_type: VORBIS_COMMENT_BLOCK,
+            data: encode_vorbis_comments(
+                "vendor",
+                &[
+                    "TITLE=Song".to_string(),
+                    "replaygain_track_gain=+1.00 dB".to_string(),
+                ],
+            ),
+        },
+    ];
+    let path = temp_flac(&flac_bytes(&blocks, b"audio frames"));
+
+    write_replay_gain(&path, &replay_gain()).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut reader = bytes.as_slice();
+    let blocks = read_flac_metadata(&mut reader).unwrap();
+    assert_eq!(reader, b"audio frames");
+    let (vendor, comments) = decode_vorbis_comments(&blocks[1].data);
+    assert_eq!(vendor, "vendor");
+    assert_eq!(
+        comments,
+        vec![
+            "TITLE=Song",
+            "REPLAYGAIN_TRACK_GAIN=-7.50 dB",
+            "REPLAYGAIN_TRACK_PEAK=0.950000"
+        ]
+    );
+}
+
+#[test]
+fn test_write_replay_gain_adds_comment_block() {
+    let padding = MetadataBlock {
+        block_type: 1,
+        data: vec![0; 16],
+    };
+    let path = temp_flac(&flac_bytes(&[streaminfo(), padding.clone()], b"audio"));
+
+    write_replay_gain(&path, &replay_gain()).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let blocks = read_flac_metadata(&mut bytes.as_slice()).unwrap();
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[1].block_type, VORBIS_COMMENT_BLOCK);
+    assert_eq!(blocks[2], padding);
+    assert_eq!(decode_vorbis_comments(&blocks[1].data).1.len(), 2);
+}
+
+#[test]
+fn test_write_replay_gain_rejects_other_formats() {
+    let result = write_replay_gain(Path::new("/music/song.mp3"), &replay_gain());
+    assert!(result.is_err());
+}
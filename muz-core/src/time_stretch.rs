@@ -0,0 +1,268 @@
+use std::f32::consts::PI;
+
+pub const MIN_SPEED: f32 = 0.5;
+pub const MAX_SPEED: f32 = 3.0;
+pub const MAX_PITCH_SEMITONES: f32 = 12.0;
+
+const SEGMENT_MS: u32 = 40;
+const SEEK_WINDOW_MS: u32 = 10;
+
+/// Changes tempo without affecting pitch (WSOLA), and shifts pitch by resampling the result.
+///
+/// Works on interleaved f32 samples. At normal speed and pitch the input passes through untouched.
+pub struct TimeStretcher {
+    channels: usize,
+    segment: usize,
+    hop: usize,
+    tolerance: usize,
+    window: Vec<f32>,
+    speed: f64,
+    pitch_ratio: f64,
+    /// Buffered input frames, interleaved
+    input: Vec<f32>,
+    /// Nominal position of the next analysis segment, in frames from the start of `input`
+    nominal: f64,
+    /// Where the previously used segment naturally continues, `None` until the first one is taken
+    continuation: Option<usize>,
+    /// Fading-out half of the previous windowed segment
+    overlap: Vec<f32>,
+    resampler: LinearResampler,
+}
+
+impl TimeStretcher {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        let hop = ((sample_rate * SEGMENT_MS / 2000) as usize).max(1);
+        let segment = hop * 2;
+        let window = (0..segment)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / segment as f32).cos())
+            .collect();
+        Self {
+            channels,
+            segment,
+            hop,
+            tolerance: (sample_rate * SEEK_WINDOW_MS / 1000) as usize,
+            window,
+            speed: 1.0,
+            pitch_ratio: 1.0,
+            input: Vec::new(),
+            nominal: 0.0,
+            continuation: None,
+            overlap: vec![0.0; hop * channels],
+            resampler: LinearResampler::new(channels),
+        }
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED) as f64;
+    }
+
+    pub fn set_pitch_semitones(&mut self, semitones: f32) {
+        let semitones = semitones.clamp(-MAX_PITCH_SEMITONES, MAX_PITCH_SEMITONES);
+        self.pitch_ratio = 2f64.powf(semitones as f64 / 12.0);
+        self.resampler.ratio = self.pitch_ratio;
+    }
+
+    fn is_identity(&self) -> bool {
+        self.speed == 1.0 && self.pitch_ratio == 1.0
+    }
+
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.is_identity() {
+            if self.continuation.is_some() || !self.input.is_empty() {
+                self.finish(output);
+            }
+            output.extend_from_slice(input);
+            return;
+        }
+
+        self.input.extend_from_slice(input);
+        let mut stretched = Vec::new();
+        self.stretch(&mut stretched);
+        self.resampler.process(&stretched, output);
+    }
+
+    /// Emits everything still buffered, cross-fading out of the last segment.
+    pub fn finish(&mut self, output: &mut Vec<f32>) {
+        let channels = self.channels;
+        let frames = self.input.len() / channels;
+        // Resume at the tempo-adjusted position, aligned to the last segment when possible
+        let nominal = (self.nominal.round() as usize).min(frames);
+        let start = match self.continuation {
+            Some(target) if frames >= nominal + self.tolerance + self.hop => self.best_match(
+                target,
+                nominal.saturating_sub(self.tolerance),
+                nominal + self.tolerance,
+            ),
+            _ => nominal,
+        };
+
+        let mut tail = Vec::new();
+        if self.continuation.is_some() {
+            for i in 0..self.hop {
+                for c in 0..channels {
+                    let sample = self
+                        .input
+                        .get((start + i) * channels + c)
+                        .map_or(0.0, |s| s * self.window[i]);
+                    tail.push(self.overlap[i * channels + c] + sample);
+                }
+            }
+        }
+        let rest = match self.continuation {
+            Some(_) => start + self.hop,
+            None => start,
+        };
+        tail.extend_from_slice(&self.input[(rest * channels).min(self.input.len())..]);
+        self.resampler.process(&tail, output);
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.input.clear();
+        self.nominal = 0.0;
+        self.continuation = None;
+        self.overlap.fill(0.0);
+        self.resampler.reset();
+    }
+
+    fn stretch(&mut self, output: &mut Vec<f32>) {
+        let channels = self.channels;
+        let tempo = self.speed / self.pitch_ratio;
+        loop {
+            let frames = self.input.len() / channels;
+            let nominal = self.nominal.round() as usize;
+            let search_start = nominal.saturating_sub(self.tolerance);
+            let search_end = nominal + self.tolerance;
+            if frames < search_end + self.segment {
+                break;
+            }
+
+            let best = match self.continuation {
+                Some(target) => self.best_match(target, search_start, search_end),
+                None => nominal,
+            };
+
+            for i in 0..self.hop {
+                for c in 0..channels {
+                    let fade_in = self.input[(best + i) * channels + c] * self.window[i];
+                    output.push(self.overlap[i * channels + c] + fade_in);
+                    self.overlap[i * channels + c] = self.input
+                        [(best + self.hop + i) * channels + c]
+                        * self.window[self.hop + i];
+                }
+            }
+            let continuation = best + self.hop;
+            self.nominal += self.hop as f64 * tempo;
+
+            let consumed =
+                continuation.min((self.nominal.floor() as usize).saturating_sub(self.tolerance));
+            self.input.drain(..consumed * channels);
+            self.continuation = Some(continuation - consumed);
+            self.nominal -= consumed as f64;
+        }
+    }
+
+    /// Finds the segment start in the search range that best continues the signal at `target`.
+    fn best_match(&self, target: usize, search_start: usize, search_end: usize) -> usize {
+        let channels = self.channels;
+        let mono = |frame: usize| -> f32 {
+            self.input[frame * channels..(frame + 1) * channels]
+                .iter()
+                .sum()
+        };
+        let similarity = |candidate: usize, step: usize| -> f32 {
+            let (mut correlation, mut energy) = (0.0, 0.0);
+            for i in (0..self.hop).step_by(step) {
+                let sample = mono(candidate + i);
+                correlation += sample * mono(target + i);
+                energy += sample * sample;
+            }
+            correlation / energy.max(1e-9).sqrt()
+        };
+
+        // Coarse search, then refine around the best candidate
+        let best_of = |candidates: &mut dyn Iterator<Item = usize>, step: usize| {
+            candidates
+                .map(|candidate| (candidate, similarity(candidate, step)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(candidate, _)| candidate)
+        };
+        let coarse = best_of(&mut (search_start..=search_end).step_by(2), 2).unwrap_or(target);
+        let refine = coarse.saturating_sub(1).max(search_start)..=(coarse + 1).min(search_end);
+        best_of(&mut refine.into_iter(), 1).unwrap_or(coarse)
+    }
+}
+
+/// Streaming linear-interpolation resampler, reading `ratio` input frames per output frame.
+struct LinearResampler {
+    channels: usize,
+    ratio: f64,
+    /// Position of the next output frame, relative to `last`
+    position: f64,
+    last: Option<Vec<f32>>,
+}
+
+impl LinearResampler {
+    fn new(channels: usize) -> Self {
+        Self {
+            channels,
+            ratio: 1.0,
+            position: 0.0,
+            last: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.position = 0.0;
+        self.last = None;
+    }
+
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.ratio == 1.0 && self.last.is_none() {
+            output.extend_from_slice(input);
+            return;
+        }
+
+        let channels = self.channels;
+        let mut input = input;
+        let last = match self.last.take() {
+            Some(last) => last,
+            None if input.len() >= channels => {
+                let (first, rest) = input.split_at(channels);
+                input = rest;
+                first.to_vec()
+            }
+            None => return,
+        };
+
+        // Frame 0 is `last`, frames 1..=n come from the input
+        let frames = input.len() / channels;
+        let frame = |index: usize, c: usize| -> f32 {
+            if index == 0 {
+                last[c]
+            } else {
+                input[(index - 1) * channels + c]
+            }
+        };
+        while self.position + 1.0 <= frames as f64 {
+            let index = self.position.floor() as usize;
+            let fraction = (self.position - index as f64) as f32;
+            for c in 0..channels {
+                let a = frame(index, c);
+                let b = frame(index + 1, c);
+                output.push(a + (b - a) * fraction);
+            }
+            self.position += self.ratio;
+        }
+        self.position -= frames as f64;
+        self.last = Some(match frames {
+            0 => last,
+            _ => input[(frames - 1) * channels..frames * channels].to_vec(),
+        });
+    }
+}
+
+#[cfg(test)]
+#[path = "./time_stretch.tests.rs"]
+mod tests;
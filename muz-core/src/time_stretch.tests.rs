@@ -0,0 +1,105 @@
+use super::*;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+fn sine(frequency: f32, frames: usize, channels: usize) -> Vec<f32> {
+    (0..frames)
+        .flat_map(|i| {
+            let s = 0.5 * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin();
+            std::iter::repeat_n(s, channels)
+        })
+        .collect()
+}
+
+fn run(stretcher: &mut TimeStretcher, input: &[f32], block: usize) -> Vec<f32> {
+    let mut output = Vec::new();
+    for chunk in input.chunks(block) {
+        stretcher.process(chunk, &mut output);
+    }
+    stretcher.finish(&mut output);
+    output
+}
+
+/// Estimates the dominant frequency of a mono signal from its zero crossings.
+fn frequency(samples: &[f32]) -> f32 {
+    let crossings = samples
+        .windows(2)
+        .filter(|w| w[0] <= 0.0 && w[1] > 0.0)
+        .count();
+    crossings as f32 * SAMPLE_RATE as f32 / samples.len() as f32
+}
+
+#[test]
+fn test_identity_passes_through() {
+    let input = sine(440.0, 10_000, 2);
+    let mut stretcher = TimeStretcher::new(SAMPLE_RATE, 2);
+    assert_eq!(run(&mut stretcher, &input, 1_024), input);
+}
+
+#[test]
+fn test_speed_changes_duration_not_pitch() {
+    let input = sine(440.0, SAMPLE_RATE as usize / 2, 1);
+    for speed in [0.5, 1.5, 2.0] {
+        let mut stretcher = TimeStretcher::new(SAMPLE_RATE, 1);
+        stretcher.set_speed(speed);
+        let output = run(&mut stretcher, &input, 1_024);
+
+        let expected = input.len() as f32 / speed;
+        assert!(
+            (output.len() as f32 - expected).abs() < 0.05 * expected,
+            "speed {speed}: {} samples, expected {expected}",
+            output.len()
+        );
+        let middle = &output[output.len() / 4..output.len() * 3 / 4];
+        assert!((frequency(middle) - 440.0).abs() < 10.0, "speed {speed}");
+    }
+}
+
+#[test]
+fn test_pitch_shift_keeps_duration() {
+    let input = sine(440.0, SAMPLE_RATE as usize / 2, 1);
+    let mut stretcher = TimeStretcher::new(SAMPLE_RATE, 1);
+    stretcher.set_pitch_semitones(12.0);
+    let output = run(&mut stretcher, &input, 1_024);
+
+    assert!((output.len() as f32 - input.len() as f32).abs() < 0.05 * input.len() as f32);
+    let middle = &output[output.len() / 4..output.len() * 3 / 4];
+    assert!((frequency(middle) - 880.0).abs() < 20.0);
+}
+
+#[test]
+fn test_channels_stay_separate() {
+    let input: Vec<f32> = sine(440.0, 20_000, 1)
+        .into_iter()
+        .flat_map(|s| [s, 0.0])
+        .collect();
+    let mut stretcher = TimeStretcher::new(SAMPLE_RATE, 2);
+    stretcher.set_speed(1.25);
+    let output = run(&mut stretcher, &input, 512);
+    assert!(output.iter().skip(1).step_by(2).all(|s| *s == 0.0));
+    assert!(output.iter().step_by(2).any(|s| s.abs() > 0.4));
+}
+
+#[test]
+fn test_returning_to_normal_speed_keeps_stream_continuous() {
+    let input = sine(440.0, 20_000, 1);
+    let mut stretcher = TimeStretcher::new(SAMPLE_RATE, 1);
+    stretcher.set_speed(2.0);
+    let mut output = Vec::new();
+    stretcher.process(&input[..10_000], &mut output);
+    stretcher.set_speed(1.0);
+    stretcher.process(&input[10_000..], &mut output);
+
+    // No sample-to-sample jump larger than the sine's own slope allows
+    let max_step = 0.5 * 2.0 * PI * 440.0 / SAMPLE_RATE as f32;
+    assert!(output.windows(2).all(|w| (w[1] - w[0]).abs() < max_step * 3.0));
+}
+
+#[test]
+fn test_settings_are_clamped() {
+    let mut stretcher = TimeStretcher::new(SAMPLE_RATE, 1);
+    stretcher.set_speed(10.0);
+    assert_eq!(stretcher.speed, MAX_SPEED as f64);
+    stretcher.set_pitch_semitones(-48.0);
+    assert_eq!(stretcher.pitch_ratio, 0.5);
+}
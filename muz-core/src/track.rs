@@ -1,14 +1,18 @@
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use symphonia::core::errors::Error as SymphoniaError;
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::{MetadataOptions, StandardTagKey};
 use symphonia::core::probe::{Hint, ProbeResult};
 use uuid::Uuid;
 
+use crate::normalization::ReplayGain;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TrackMetadata {
@@ -22,14 +26,29 @@ pub struct TrackMetadata {
     pub year: Option<String>,
 }
 
+/// Portion of a file a virtual track covers, e.g. one entry of a cue sheet.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct TrackSegment {
+    pub start_ms: u64,
+    pub end_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Track {
     pub id: String,
     pub path: PathBuf,
     pub total_frames: u64,
     pub duration_ms: u64,
     pub metadata: Option<TrackMetadata>,
+    #[serde(default)]
+    pub segment: Option<TrackSegment>,
+    #[serde(default)]
+    pub replay_gain: Option<ReplayGain>,
+    /// Set when the file failed to open or decode during playback
+    #[serde(default)]
+    pub unplayable: bool,
 }
 
 pub static SUPPORTED_EXTENSIONS: &[&str] = &[
@@ -40,9 +59,10 @@ pub static SUPPORTED_EXTENSIONS: &[&str] = &[
 
 impl Track {
     pub fn new<P: Into<PathBuf> + AsRef<Path>>(path: P) -> Self {
-        let (total_frames, duration_ms, mut metadata) = Self::get_metadata(path.as_ref());
+        let (total_frames, duration_ms, mut metadata, replay_gain) =
+            Self::get_metadata(path.as_ref());
         if total_frames.is_none() {
-            tracing::error!("Failed to get total frames for track: {:?}", path.as_ref());
+            tracing::debug!("No frame count in the header of {:?}", path.as_ref());
         }
         if let Some(metadata) = metadata.as_mut() {
             if metadata.title.is_none() {
@@ -55,9 +75,16 @@ impl Track {
             total_frames: total_frames.unwrap_or(0),
             duration_ms: duration_ms.unwrap_or(0),
             metadata,
+            segment: None,
+            replay_gain,
+            unplayable: false,
         }
     }
 
+    pub fn start_offset(&self) -> Duration {
+        Duration::from_millis(self.segment.as_ref().map_or(0, |s| s.start_ms))
+    }
+
     pub fn default_title(path: &Path) -> String {
         path.file_name()
             .and_then(|s| s.to_str())
@@ -65,7 +92,7 @@ impl Track {
             .to_string()
     }
 
-    fn get_metadata_from_probe(mut probed: ProbeResult) -> TrackMetadata {
+    fn get_metadata_from_probe(mut probed: ProbeResult) -> (TrackMetadata, Option<ReplayGain>) {
         let mut meta = TrackMetadata {
             title: None,
             album: None,
@@ -101,16 +128,25 @@ impl Track {
             }
         };
 
+        let mut replay_gain: Option<ReplayGain> = None;
+        let mut fill_gain_from_tags = |tags: &[symphonia::core::meta::Tag]| {
+            if let Some(gain) = ReplayGain::from_tags(tags) {
+                replay_gain = Some(replay_gain.map_or(gain, |g| g.merge(gain)));
+            }
+        };
+
         if let Some(metadata_rev) = probed.format.metadata().skip_to_latest() {
             fill_from_tags(metadata_rev.tags());
+            fill_gain_from_tags(metadata_rev.tags());
         }
         let mut metadata = probed.metadata;
         if let Some(mut m) = metadata.get() {
             if let Some(metadata_rev) = m.skip_to_latest() {
                 fill_from_tags(metadata_rev.tags());
+                fill_gain_from_tags(metadata_rev.tags());
             }
         }
-        meta
+        (meta, replay_gain)
     }
 
     fn get_audio_track_from_probe(
@@ -144,14 +180,8 @@ impl Track {
         }
     }
 
-    pub fn get_metadata(path: &Path) -> (Option<u64>, Option<u64>, Option<TrackMetadata>) {
-        let file = match File::open(path) {
-            Ok(f) => f,
-            Err(_) => {
-                tracing::error!("Failed to open file: {:?}", path);
-                return (None, None, None);
-            }
-        };
+    pub(crate) fn probe(path: &Path) -> Result<ProbeResult> {
+        let file = File::open(path)?;
         let mss = MediaSourceStream::new(Box::new(file), Default::default());
 
         let mut hint = Hint::new();
@@ -161,23 +191,73 @@ impl Track {
             }
         }
 
-        let probed = match symphonia::default::get_probe().format(
+        Ok(symphonia::default::get_probe().format(
             &hint,
             mss,
             &FormatOptions::default(),
             &MetadataOptions::default(),
-        ) {
+        )?)
+    }
+
+    /// Reads every packet of a file whose header doesn't state its length, returning the
+    /// exact `(total_frames, duration_ms)` from the end of the last packet.
+    pub fn scan_duration(path: &Path) -> Result<(u64, u64)> {
+        let mut probed = Self::probe(path)?;
+        let track = Self::get_audio_track_from_probe(&probed)?;
+        let track_id = track.id;
+        let time_base = track.codec_params.time_base;
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or_else(|| anyhow::anyhow!("Unknown sample rate"))?;
+
+        let mut end_ts = 0;
+        loop {
+            match probed.format.next_packet() {
+                Ok(packet) if packet.track_id() == track_id => {
+                    end_ts = end_ts.max(packet.ts() + packet.dur());
+                }
+                Ok(_) => {}
+                Err(SymphoniaError::IoError(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break
+                }
+                Err(SymphoniaError::ResetRequired) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let total_frames = match time_base {
+            Some(time_base) => {
+                let time = time_base.calc_time(end_ts);
+                ((time.seconds as f64 + time.frac) * sample_rate as f64).round() as u64
+            }
+            None => end_ts,
+        };
+        Ok((total_frames, total_frames * 1000 / sample_rate as u64))
+    }
+
+    pub fn get_metadata(
+        path: &Path,
+    ) -> (
+        Option<u64>,
+        Option<u64>,
+        Option<TrackMetadata>,
+        Option<ReplayGain>,
+    ) {
+        let probed = match Self::probe(path) {
             Ok(p) => p,
-            Err(_) => {
-                tracing::error!("Failed to probe file: {:?}", path);
-                return (None, None, None);
+            Err(e) => {
+                tracing::error!("Failed to probe file {:?}: {e}", path);
+                return (None, None, None, None);
             }
         };
 
         let total_frames = Self::get_total_frames_from_probe(&probed).ok();
         let duration_ms = Self::get_duration_from_probe(&probed).ok();
-        let metadata = Some(Self::get_metadata_from_probe(probed));
-        (total_frames, duration_ms, metadata)
+        let (metadata, replay_gain) = Self::get_metadata_from_probe(probed);
+        (total_frames, duration_ms, Some(metadata), replay_gain)
     }
 }
 
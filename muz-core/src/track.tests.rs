@@ -1,4 +1,5 @@
 use super::*;
+use crate::driver::{render::RenderOutput, wav::WavOutput};
 use std::path::PathBuf;
 
 #[test]
@@ -54,3 +55,20 @@ fn test_path_handling() {
         assert!(!track.id.is_empty());
     }
 }
+
+#[test]
+fn test_scan_duration_matches_the_header() {
+    let path = std::env::temp_dir().join(format!("muz-scan-{}.wav", Uuid::new_v4()));
+    let mut output = WavOutput::new(&path);
+    output.write(&vec![0.0; 2 * 66_150], 44_100, 2).unwrap();
+    drop(output);
+
+    let track = Track::new(&path);
+    assert_eq!(track.total_frames, 66_150);
+    assert_eq!(
+        Track::scan_duration(&path).unwrap(),
+        (track.total_frames, track.duration_ms)
+    );
+    assert_eq!(track.duration_ms, 1_500);
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,136 @@
+//! Controls a running `muzd`, e.g. `muzctl play` or `muzctl queue add boards of canada`.
+
+#[cfg(unix)]
+const USAGE: &str = "Usage: muzctl [--socket PATH] [--json] <command>
+
+Commands:
+  play | pause | toggle | stop | next | previous
+  seek <seconds> | seek +<seconds> | seek -<seconds>
+  volume <0-100> | mute | unmute
+  repeat off|one|all | shuffle on|off
+  status
+  queue [list] | queue add <query> | queue clear
+  scan";
+
+#[cfg(unix)]
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    use muz_server::control::{self, ControlRequest};
+
+    let mut socket = None;
+    let mut json = false;
+    let mut command = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--socket" => socket = args.next().map(std::path::PathBuf::from),
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => command.push(arg),
+        }
+    }
+
+    let request = match ControlRequest::from_args(&command) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let socket = socket.unwrap_or_else(control::socket_path);
+    match control::request(&socket, &request).await {
+        Ok(value) if json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&value).unwrap_or_default()
+            );
+        }
+        Ok(value) => print_human(&request, &value),
+        Err(e) => {
+            eprintln!("muzctl: {e:#}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn print_human(request: &muz_server::control::ControlRequest, value: &serde_json::Value) {
+    use muz_server::control::ControlRequest;
+
+    match request {
+        ControlRequest::Status | ControlRequest::Playback { .. } => {
+            let track = match &value["track"] {
+                serde_json::Value::Null => "Nothing playing".to_string(),
+                track => format!(
+                    "{} ({}/{})",
+                    describe(track),
+                    format_ms(value["positionMs"].as_u64().unwrap_or(0)),
+                    format_ms(track["durationMs"].as_u64().unwrap_or(0)),
+                ),
+            };
+            println!("{}: {track}", value["state"].as_str().unwrap_or("Unknown"));
+            let volume = (value["volume"].as_f64().unwrap_or(0.0) * 100.0).round();
+            let muted = if value["muted"].as_bool() == Some(true) {
+                " (muted)"
+            } else {
+                ""
+            };
+            println!(
+                "Volume: {volume}%{muted}, repeat: {}, shuffle: {}",
+                value["repeat"].as_str().unwrap_or("Off").to_lowercase(),
+                if value["shuffle"].as_bool() == Some(true) {
+                    "on"
+                } else {
+                    "off"
+                },
+            );
+        }
+        ControlRequest::Queue | ControlRequest::QueueAdd { .. } => {
+            let tracks = value.as_array().map(Vec::as_slice).unwrap_or_default();
+            for (i, track) in tracks.iter().enumerate() {
+                println!("{:>4}. {}", i + 1, describe(track));
+            }
+            if matches!(request, ControlRequest::QueueAdd { .. }) {
+                println!("Queued {} tracks", tracks.len());
+            }
+        }
+        ControlRequest::QueueClear => println!("Cleared the queue"),
+        ControlRequest::Scan => {
+            println!("Found {} tracks", value["tracks"].as_u64().unwrap_or(0));
+        }
+    }
+}
+
+/// `Artist - Title`, falling back to the file name.
+#[cfg(unix)]
+fn describe(track: &serde_json::Value) -> String {
+    let metadata = &track["metadata"];
+    let title = metadata["title"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let path = std::path::Path::new(track["path"].as_str().unwrap_or_default());
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+    match metadata["artist"].as_str() {
+        Some(artist) => format!("{artist} - {title}"),
+        None => title,
+    }
+}
+
+#[cfg(unix)]
+fn format_ms(ms: u64) -> String {
+    let seconds = ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("muzctl is only supported on Unix");
+    std::process::exit(1);
+}
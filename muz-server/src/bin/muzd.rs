@@ -0,0 +1,38 @@
+//! Runs the player without a window, controlled with `muzctl`.
+
+#[cfg(unix)]
+const USAGE: &str = "Usage: muzd [--data-dir DIR] [--socket PATH]";
+
+#[cfg(unix)]
+#[tokio::main]
+async fn main() {
+    let mut data_dir = None;
+    let mut socket = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--data-dir" => data_dir = args.next().map(Into::into),
+            "--socket" => socket = args.next().map(Into::into),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            other => {
+                eprintln!("Unknown argument: {other}\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    muz_server::init_tracing();
+    if let Err(e) = muz_server::daemon::run(data_dir, socket).await {
+        tracing::error!("{e:#}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("muzd is only supported on Unix");
+    std::process::exit(1);
+}
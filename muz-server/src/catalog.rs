@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use muz_core::track::Track;
+
+/// The library by artist and album, as the media servers browse it, with ids that survive
+/// rescans. Track ids change on every scan, so songs are known by file and segment instead.
+pub struct Catalog {
+    pub artists: Vec<Artist>,
+}
+
+pub struct Artist {
+    pub id: String,
+    pub name: String,
+    pub albums: Vec<Album>,
+}
+
+pub struct Album {
+    pub id: String,
+    pub name: String,
+    pub artist: String,
+    pub artist_id: String,
+    pub songs: Vec<Song>,
+}
+
+pub struct Song {
+    pub id: String,
+    pub track: Track,
+}
+
+impl Catalog {
+    /// Sorts the `LibraryService::albums_by_artist` grouping by name.
+    pub fn new(albums_by_artist: HashMap<String, HashMap<String, Vec<Track>>>) -> Self {
+        let mut artists: Vec<Artist> = albums_by_artist
+            .into_iter()
+            .map(|(artist, albums)| {
+                let artist_id = artist_id(&artist);
+                let mut albums: Vec<Album> = albums
+                    .into_iter()
+                    .map(|(album, tracks)| Album {
+                        id: album_id(&artist, &album),
+                        name: album,
+                        artist: artist.clone(),
+                        artist_id: artist_id.clone(),
+                        songs: tracks
+                            .into_iter()
+                            .map(|track| Song {
+                                id: song_id(&track),
+                                track,
+                            })
+                            .collect(),
+                    })
+                    .collect();
+                albums.sort_by_key(|album| album.name.to_lowercase());
+                Artist {
+                    id: artist_id,
+                    name: artist,
+                    albums,
+                }
+            })
+            .collect();
+        artists.sort_by_key(|artist| artist.name.to_lowercase());
+        Self { artists }
+    }
+
+    pub fn artist(&self, id: &str) -> Option<&Artist> {
+        self.artists.iter().find(|artist| artist.id == id)
+    }
+
+    pub fn albums(&self) -> impl Iterator<Item = &Album> {
+        self.artists.iter().flat_map(|artist| &artist.albums)
+    }
+
+    pub fn album(&self, id: &str) -> Option<&Album> {
+        self.albums().find(|album| album.id == id)
+    }
+
+    pub fn songs(&self) -> impl Iterator<Item = (&Album, &Song)> {
+        self.albums()
+            .flat_map(|album| album.songs.iter().map(move |song| (album, song)))
+    }
+
+    pub fn song(&self, id: &str) -> Option<(&Album, &Song)> {
+        self.songs().find(|(_, song)| song.id == id)
+    }
+
+    /// A track to show the cover of an artist, album or song.
+    pub fn cover_track(&self, id: &str) -> Option<&Track> {
+        let album = match id.split_once('-') {
+            Some(("ar", _)) => self.artist(id)?.albums.first()?,
+            Some(("al", _)) => self.album(id)?,
+            _ => return self.song(id).map(|(_, song)| &song.track),
+        };
+        album.songs.first().map(|song| &song.track)
+    }
+}
+
+impl Album {
+    pub fn duration_ms(&self) -> u64 {
+        self.songs.iter().map(|song| song.track.duration_ms).sum()
+    }
+
+    /// The first tag of the album's songs that has it.
+    pub fn tag(&self, tag: impl Fn(&Track) -> Option<&String>) -> Option<&String> {
+        self.songs.iter().find_map(|song| tag(&song.track))
+    }
+}
+
+fn digest(kind: &str, parts: &[&str]) -> String {
+    format!("{kind}-{:x}", md5::compute(parts.join("\0")))
+}
+
+pub fn artist_id(artist: &str) -> String {
+    digest("ar", &[artist])
+}
+
+pub fn album_id(artist: &str, album: &str) -> String {
+    digest("al", &[artist, album])
+}
+
+pub fn song_id(track: &Track) -> String {
+    let start_ms = track.segment.as_ref().map_or(0, |s| s.start_ms);
+    digest(
+        "tr",
+        &[&track.path.to_string_lossy(), &start_ms.to_string()],
+    )
+}
@@ -0,0 +1,231 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::error::CommandError;
+use muz_core::command::{PlaybackCommand, Volume};
+use muz_core::error::LibraryError;
+use muz_core::playback::RepeatMode;
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+
+/// Overrides the default socket path
+const SOCKET_ENV_VAR: &str = "MUZ_SOCKET";
+
+/// A request to the daemon, sent as one line of JSON and answered by one line holding
+/// `{ "ok": ... }` or `{ "error": { code, message } }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+pub enum ControlRequest {
+    Playback {
+        command: PlaybackCommand,
+    },
+    Status,
+    Queue,
+    /// Queues the library tracks matching a search
+    QueueAdd {
+        query: String,
+    },
+    QueueClear,
+    /// Rescans the library and queues all of it, like the desktop app does
+    Scan,
+}
+
+impl ControlRequest {
+    /// Parses `muzctl` arguments, e.g. `["queue", "add", "boards", "of"]`.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let playback = |command| Ok(ControlRequest::Playback { command });
+        match args.as_slice() {
+            ["play"] => playback(PlaybackCommand::Play),
+            ["pause"] => playback(PlaybackCommand::Pause),
+            ["toggle"] => playback(PlaybackCommand::TogglePlayPause),
+            ["stop"] => playback(PlaybackCommand::Stop),
+            ["next"] => playback(PlaybackCommand::Next),
+            ["previous" | "prev"] => playback(PlaybackCommand::Previous),
+            ["seek", time] => playback(Self::seek(time)?),
+            ["volume", percent] => {
+                let percent: f32 = percent
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid volume: {percent}"))?;
+                let volume = Volume::try_from(percent / 100.0).map_err(|e| anyhow!(e))?;
+                playback(PlaybackCommand::SetVolume { volume })
+            }
+            ["mute"] => playback(PlaybackCommand::SetMuted { muted: true }),
+            ["unmute"] => playback(PlaybackCommand::SetMuted { muted: false }),
+            ["repeat", mode] => {
+                let mode = match *mode {
+                    "off" => RepeatMode::Off,
+                    "one" => RepeatMode::One,
+                    "all" => RepeatMode::All,
+                    other => bail!("Unknown repeat mode: {other}, expected off, one or all"),
+                };
+                playback(PlaybackCommand::SetRepeat { mode })
+            }
+            ["shuffle", toggle @ ("on" | "off")] => playback(PlaybackCommand::SetShuffle {
+                shuffle: *toggle == "on",
+            }),
+            ["status"] => Ok(ControlRequest::Status),
+            ["queue"] | ["queue", "list"] => Ok(ControlRequest::Queue),
+            ["queue", "add", query @ ..] if !query.is_empty() => Ok(ControlRequest::QueueAdd {
+                query: query.join(" "),
+            }),
+            ["queue", "clear"] => Ok(ControlRequest::QueueClear),
+            ["scan"] => Ok(ControlRequest::Scan),
+            [] => bail!("No command given"),
+            _ => bail!("Unknown command: {}", args.join(" ")),
+        }
+    }
+
+    /// Seconds from the start, or relative to the position with a sign.
+    fn seek(time: &str) -> Result<PlaybackCommand> {
+        let seconds: f64 = time.parse().map_err(|_| anyhow!("Invalid time: {time}"))?;
+        let ms = (seconds * 1000.0).round();
+        Ok(if time.starts_with(['+', '-']) {
+            PlaybackCommand::SeekRelative {
+                delta_ms: ms as i64,
+            }
+        } else {
+            PlaybackCommand::Seek { ms: ms as u64 }
+        })
+    }
+}
+
+/// The socket of the running daemon: `MUZ_SOCKET`, or `muzd.sock` in the runtime directory.
+pub fn socket_path() -> PathBuf {
+    match std::env::var_os(SOCKET_ENV_VAR) {
+        Some(path) => PathBuf::from(path),
+        None => dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("muzd.sock"),
+    }
+}
+
+/// Runs control requests against the services, the same way the desktop commands do.
+pub struct Controller {
+    playback: PlaybackService,
+    library: LibraryService,
+}
+
+impl Controller {
+    pub fn new(playback: PlaybackService, library: LibraryService) -> Self {
+        Self { playback, library }
+    }
+
+    pub async fn execute(&self, request: ControlRequest) -> Result<Value> {
+        Ok(match request {
+            ControlRequest::Playback { command } => {
+                serde_json::to_value(self.playback.control_playback(command)?)?
+            }
+            ControlRequest::Status => serde_json::to_value(self.playback.status()?)?,
+            ControlRequest::Queue => serde_json::to_value(self.playback.queue()?)?,
+            ControlRequest::QueueAdd { query } => {
+                let tracks = self.library.search(&query).await;
+                if tracks.is_empty() {
+                    let error = anyhow::Error::from(LibraryError::TrackNotFound(query.clone()));
+                    return Err(error.context(format!("Nothing matches {query:?}")));
+                }
+                self.playback.enqueue(tracks.clone())?;
+                serde_json::to_value(tracks)?
+            }
+            ControlRequest::QueueClear => {
+                self.playback.clear_queue()?;
+                json!([])
+            }
+            ControlRequest::Scan => {
+                self.library.rescan_library().await?;
+                let tracks = self.library.tracks().await;
+                let count = tracks.len();
+                self.playback.clear_queue_and_enqueue(tracks)?;
+                json!({ "tracks": count })
+            }
+        })
+    }
+}
+
+/// Listens at `path`, replacing the socket a previous daemon left behind. Only the
+/// current user may connect.
+pub async fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            bail!("Another daemon is already listening on {path:?}");
+        }
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove {path:?}"))?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(path).with_context(|| format!("Failed to bind {path:?}"))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Answers clients until the listener fails.
+pub async fn serve(listener: UnixListener, controller: Arc<Controller>) -> Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let controller = controller.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_client(stream, &controller).await {
+                tracing::debug!("Control client disconnected: {e}");
+            }
+        });
+    }
+}
+
+async fn handle_client(stream: UnixStream, controller: &Controller) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let result = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => controller
+                .execute(request)
+                .await
+                .map_err(CommandError::from),
+            Err(e) => Err(CommandError {
+                code: "INVALID_REQUEST",
+                message: format!("Invalid request: {e}"),
+                details: None,
+            }),
+        };
+        let response = match result {
+            Ok(value) => json!({ "ok": value }),
+            Err(error) => json!({ "error": error }),
+        };
+        writer.write_all(format!("{response}\n").as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Sends one request to the daemon at `path`, returning its result.
+pub async fn request(path: &Path, request: &ControlRequest) -> Result<Value> {
+    let stream = UnixStream::connect(path)
+        .await
+        .with_context(|| format!("Failed to connect to muzd at {path:?}, is it running?"))?;
+    let (reader, mut writer) = stream.into_split();
+    writer
+        .write_all(format!("{}\n", serde_json::to_string(request)?).as_bytes())
+        .await?;
+    let line = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow!("muzd closed the connection without answering"))?;
+    let mut response: Value = serde_json::from_str(&line)?;
+    if let Some(error) = response.get("error") {
+        bail!(
+            "{} ({})",
+            error["message"].as_str().unwrap_or("Unknown error"),
+            error["code"].as_str().unwrap_or("UNKNOWN")
+        );
+    }
+    Ok(response["ok"].take())
+}
+
+#[cfg(test)]
+#[path = "./control.tests.rs"]
+mod tests;
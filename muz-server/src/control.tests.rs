@@ -0,0 +1,84 @@
+use super::*;
+use crate::testing::TestLibrary;
+use muz_core::bus::EventBus;
+use muz_core::driver::{null::NullPlaybackDriver, render::Clock};
+use muz_core::playback::Playback;
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+#[test]
+fn test_arguments() {
+    assert_eq!(
+        ControlRequest::from_args(&args("play")).unwrap(),
+        ControlRequest::Playback {
+            command: PlaybackCommand::Play
+        }
+    );
+    assert_eq!(
+        ControlRequest::from_args(&args("seek -2.5")).unwrap(),
+        ControlRequest::Playback {
+            command: PlaybackCommand::SeekRelative { delta_ms: -2500 }
+        }
+    );
+    assert_eq!(
+        ControlRequest::from_args(&args("seek 90")).unwrap(),
+        ControlRequest::Playback {
+            command: PlaybackCommand::Seek { ms: 90_000 }
+        }
+    );
+    assert_eq!(
+        ControlRequest::from_args(&args("queue add boards of canada")).unwrap(),
+        ControlRequest::QueueAdd {
+            query: "boards of canada".to_string()
+        }
+    );
+    assert_eq!(
+        ControlRequest::from_args(&args("queue")).unwrap(),
+        ControlRequest::Queue
+    );
+
+    for invalid in ["", "queue add", "volume 150", "repeat twice", "rewind"] {
+        assert!(
+            ControlRequest::from_args(&args(invalid)).is_err(),
+            "{invalid}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_requests_over_the_socket() {
+    let test_library = TestLibrary::create();
+    let library = test_library.scan().await;
+    let events = EventBus::new();
+    let playback = Playback::create(
+        Box::new(NullPlaybackDriver::new(Clock::RealTime, 1.0)),
+        events.clone(),
+    );
+    let controller = Controller::new(
+        PlaybackService::new(playback),
+        LibraryService::new(Arc::new(tokio::sync::Mutex::new(library)), events),
+    );
+    let socket = test_library.root.join("muzd.sock");
+    tokio::spawn(serve(bind(&socket).await.unwrap(), Arc::new(controller)));
+    assert!(bind(&socket).await.is_err(), "the socket is in use");
+
+    let send = |line: &str| {
+        let socket = socket.clone();
+        let message = ControlRequest::from_args(&args(line)).unwrap();
+        async move { request(&socket, &message).await }
+    };
+    let added = send("queue add beta").await.unwrap();
+    assert_eq!(added[0]["metadata"]["title"], "Beta.wav");
+    let error = send("queue add gamma").await.unwrap_err();
+    assert!(error.to_string().contains("TRACK_NOT_FOUND"), "{error}");
+
+    let status = send("play").await.unwrap();
+    assert_eq!(status["state"], "Playing");
+    assert_eq!(status["track"]["metadata"]["title"], "Beta.wav");
+
+    assert_eq!(send("scan").await.unwrap()["tracks"], 2);
+    assert_eq!(send("queue").await.unwrap().as_array().unwrap().len(), 2);
+    assert_eq!(send("queue clear").await.unwrap(), json!([]));
+}
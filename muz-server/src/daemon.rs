@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::Mutex;
+
+use crate::control::{self, Controller};
+use crate::integrations;
+use muz_core::bus::{BusEvent, EventBus};
+use muz_core::config::AppConfig;
+use muz_core::events::PlayerEvent;
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+use muz_core::{library::{Library, CACHE_FILE}, playback::Playback};
+
+/// Runs the player without the webview, controlled over `socket` and the integrations
+/// enabled in the config, until interrupted or terminated.
+pub async fn run(data_dir: Option<PathBuf>, socket: Option<PathBuf>) -> Result<()> {
+    let data_dir = match data_dir {
+        Some(dir) => dir,
+        None => AppConfig::default_data_dir()?,
+    };
+    let config = AppConfig::load(&data_dir)
+        .await
+        .with_context(|| format!("Failed to load the config in {data_dir:?}"))?;
+    tracing::info!("Using the config in {data_dir:?}");
+
+    let mut library = Library::new(config.library_path.clone(), "Library".to_string());
+    library.use_cache(data_dir.join(CACHE_FILE));
+    library.initialize().await;
+    let tracks = library.tracks_cloned();
+    let events = EventBus::new();
+    let library_service = LibraryService::new(Arc::new(Mutex::new(library)), events.clone());
+    tokio::spawn(log_events(events.subscribe().1, library_service.clone()));
+
+    let playback = Playback::create(config.create_driver(1.0), events.clone());
+    if let Ok(mut playback_guard) = playback.lock() {
+        playback_guard.enqueue_multiple(tracks);
+        config.apply_to(&mut playback_guard);
+    } else {
+        tracing::error!("Failed to lock playback");
+    }
+    let playback_service = PlaybackService::new(playback);
+    integrations::start(
+        &events,
+        &config,
+        Some(&data_dir),
+        &playback_service,
+        &library_service,
+    )
+    .await;
+
+    let socket = socket.unwrap_or_else(control::socket_path);
+    let listener = control::bind(&socket).await?;
+    tracing::info!("Listening for muzctl on {socket:?}");
+    let controller = Arc::new(Controller::new(playback_service, library_service));
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let result = tokio::select! {
+        result = control::serve(listener, controller) => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+        _ = terminate.recv() => Ok(()),
+    };
+    tracing::info!("Shutting down");
+    let _ = std::fs::remove_file(&socket);
+    result
+}
+
+/// Logs what the player does and marks the tracks it fails to play.
+async fn log_events(mut events: broadcast::Receiver<BusEvent>, library_service: LibraryService) {
+    loop {
+        let event = match events.recv().await {
+            Ok(BusEvent { event, .. }) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::debug!("Skipped {skipped} events");
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        match event {
+            PlayerEvent::TrackChanged(changed) => {
+                if let Some(track) = &changed.track {
+                    tracing::info!("Playing {:?}", track.path);
+                }
+            }
+            PlayerEvent::OutputDeviceFallback(fallback) => {
+                tracing::warn!(
+                    "Output device {:?} is unavailable: {}",
+                    fallback.requested_device,
+                    fallback.reason
+                );
+            }
+            PlayerEvent::OutputUnavailable(unavailable) => {
+                tracing::warn!("Paused, no audio output: {}", unavailable.reason);
+            }
+            PlayerEvent::PlaybackError(error) => {
+                tracing::warn!("Failed to play {:?}: {}", error.path, error.message);
+                library_service.mark_unplayable(&error.track_id).await;
+            }
+            PlayerEvent::LibraryChanged(changed) => {
+                tracing::info!("Library rescanned, {} tracks", changed.track_count);
+            }
+            _ => {}
+        }
+    }
+}
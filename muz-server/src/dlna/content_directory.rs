@@ -0,0 +1,326 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::catalog::{Album, Catalog, Song};
+use crate::dlna::escape;
+use crate::streaming;
+
+pub const ROOT: &str = "0";
+
+/// Lets renderers seek by byte range, with the `DLNA.ORG_FLAGS` of a streamed file.
+pub const DLNA_FEATURES: &str =
+    "DLNA.ORG_OP=01;DLNA.ORG_CI=0;DLNA.ORG_FLAGS=01700000000000000000000000000000";
+
+const STORAGE_FOLDER: &str = "object.container.storageFolder";
+const MUSIC_ARTIST: &str = "object.container.person.musicArtist";
+const MUSIC_ALBUM: &str = "object.container.album.musicAlbum";
+const MUSIC_GENRE: &str = "object.container.genre.musicGenre";
+
+/// A container or a song of the browse tree.
+pub enum Object<'a> {
+    Container {
+        id: String,
+        title: String,
+        class: &'static str,
+        child_count: usize,
+        /// Where the cover comes from, for albums
+        album: Option<&'a Album>,
+    },
+    Item {
+        id: String,
+        album: &'a Album,
+        song: &'a Song,
+    },
+}
+
+impl Object<'_> {
+    pub fn id(&self) -> &str {
+        match self {
+            Object::Container { id, .. } | Object::Item { id, .. } => id,
+        }
+    }
+}
+
+/// A directory of the library, as found in the paths of its songs.
+#[derive(Default)]
+struct Folder<'a> {
+    folders: BTreeSet<PathBuf>,
+    songs: Vec<(&'a Album, &'a Song)>,
+}
+
+/// Artists, albums, genres and folders, each down to the songs. Ids are the path of
+/// containers browsed through, e.g. `artists/ar-…/al-…`, so every object knows its parent
+/// and the same album can be reached in several ways.
+pub struct Tree<'a> {
+    catalog: &'a Catalog,
+    genres: BTreeMap<String, Vec<(&'a Album, &'a Song)>>,
+    folders: HashMap<PathBuf, Folder<'a>>,
+    folder_ids: HashMap<String, PathBuf>,
+}
+
+impl<'a> Tree<'a> {
+    /// Folders are relative to `root`, the library path.
+    pub fn new(catalog: &'a Catalog, root: &Path) -> Self {
+        let mut genres: BTreeMap<String, Vec<_>> = BTreeMap::new();
+        let mut folders: HashMap<PathBuf, Folder> = HashMap::new();
+        folders.insert(PathBuf::new(), Folder::default());
+        for (album, song) in catalog.songs() {
+            let track = &song.track;
+            if let Some(genre) = track.metadata.as_ref().and_then(|m| m.genre.clone()) {
+                genres.entry(genre).or_default().push((album, song));
+            }
+            let relative = track.path.strip_prefix(root).unwrap_or(&track.path);
+            let mut folder = relative.parent().unwrap_or(Path::new("")).to_path_buf();
+            folders
+                .entry(folder.clone())
+                .or_default()
+                .songs
+                .push((album, song));
+            while let Some(parent) = folder.parent().map(Path::to_path_buf) {
+                folders
+                    .entry(parent.clone())
+                    .or_default()
+                    .folders
+                    .insert(folder);
+                folder = parent;
+            }
+        }
+        let folder_ids = folders
+            .keys()
+            .map(|folder| (folder_id(folder), folder.clone()))
+            .collect();
+        Self {
+            catalog,
+            genres,
+            folders,
+            folder_ids,
+        }
+    }
+
+    /// The object itself, for `BrowseMetadata`.
+    pub fn object(&self, id: &str) -> Option<Object<'a>> {
+        if id == ROOT {
+            return Some(container(ROOT, "muz", STORAGE_FOLDER, 4));
+        }
+        self.children(parent_id(id))?
+            .into_iter()
+            .find(|object| object.id() == id)
+    }
+
+    /// The objects in a container, for `BrowseDirectChildren`.
+    pub fn children(&self, id: &str) -> Option<Vec<Object<'a>>> {
+        let catalog = self.catalog;
+        let child = |name: &str| format!("{id}/{name}");
+        let songs = |songs: &mut dyn Iterator<Item = (&'a Album, &'a Song)>| -> Vec<Object<'a>> {
+            songs
+                .map(|(album, song)| Object::Item {
+                    id: child(&song.id),
+                    album,
+                    song,
+                })
+                .collect()
+        };
+        let album = |album: &'a Album| Object::Container {
+            id: child(&album.id),
+            title: album.name.clone(),
+            class: MUSIC_ALBUM,
+            child_count: album.songs.len(),
+            album: Some(album),
+        };
+
+        let segments: Vec<&str> = id.split('/').collect();
+        let children = match segments.as_slice() {
+            [ROOT] => vec![
+                container("artists", "Artists", STORAGE_FOLDER, catalog.artists.len()),
+                container("albums", "Albums", STORAGE_FOLDER, catalog.albums().count()),
+                container("genres", "Genres", STORAGE_FOLDER, self.genres.len()),
+                container(
+                    "folders",
+                    "Folders",
+                    STORAGE_FOLDER,
+                    self.folder_size(Path::new("")),
+                ),
+            ],
+            ["artists"] => (catalog.artists.iter())
+                .map(|artist| {
+                    container(
+                        &child(&artist.id),
+                        &artist.name,
+                        MUSIC_ARTIST,
+                        artist.albums.len(),
+                    )
+                })
+                .collect(),
+            ["artists", artist] => catalog.artist(artist)?.albums.iter().map(album).collect(),
+            ["artists", artist, album] => {
+                let album = catalog.album(album).filter(|a| a.artist_id == *artist)?;
+                songs(&mut album.songs.iter().map(|song| (album, song)))
+            }
+            ["albums"] => catalog.albums().map(album).collect(),
+            ["albums", album] => {
+                let album = catalog.album(album)?;
+                songs(&mut album.songs.iter().map(|song| (album, song)))
+            }
+            ["genres"] => (self.genres.iter())
+                .map(|(genre, songs)| {
+                    container(&child(&genre_id(genre)), genre, MUSIC_GENRE, songs.len())
+                })
+                .collect(),
+            ["genres", genre] => {
+                let (_, genre_songs) = self.genres.iter().find(|(g, _)| genre_id(g) == *genre)?;
+                songs(&mut genre_songs.iter().copied())
+            }
+            ["folders", path @ ..] => {
+                let folder = match path.last() {
+                    Some(id) => self.folder_ids.get(*id)?.as_path(),
+                    None => Path::new(""),
+                };
+                let contents = self.folders.get(folder)?;
+                let mut children: Vec<Object> = (contents.folders.iter())
+                    .map(|sub| {
+                        let name = sub.file_name().unwrap_or_default().to_string_lossy();
+                        let count = self.folder_size(sub);
+                        container(&child(&folder_id(sub)), &name, STORAGE_FOLDER, count)
+                    })
+                    .collect();
+                children.extend(songs(&mut contents.songs.iter().copied()));
+                children
+            }
+            _ => return None,
+        };
+        Some(children)
+    }
+
+    fn folder_size(&self, folder: &Path) -> usize {
+        self.folders
+            .get(folder)
+            .map_or(0, |f| f.folders.len() + f.songs.len())
+    }
+}
+
+fn container<'a>(id: &str, title: &str, class: &'static str, child_count: usize) -> Object<'a> {
+    Object::Container {
+        id: id.to_string(),
+        title: title.to_string(),
+        class,
+        child_count,
+        album: None,
+    }
+}
+
+/// The container an object is in, `-1` for the root.
+pub fn parent_id(id: &str) -> &str {
+    match id {
+        ROOT => "-1",
+        _ => id.rsplit_once('/').map_or(ROOT, |(parent, _)| parent),
+    }
+}
+
+fn genre_id(genre: &str) -> String {
+    format!("ge-{:x}", md5::compute(genre))
+}
+
+fn folder_id(folder: &Path) -> String {
+    format!("fo-{:x}", md5::compute(folder.to_string_lossy().as_bytes()))
+}
+
+/// The objects as a DIDL-Lite document, with links to media and covers under `base_url`.
+pub fn didl(objects: &[Object], base_url: &str) -> String {
+    let mut didl = String::from(
+        r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/">"#,
+    );
+    for object in objects {
+        let id = object.id();
+        let parent = escape(parent_id(id));
+        match object {
+            Object::Container {
+                title,
+                class,
+                child_count,
+                album,
+                ..
+            } => {
+                didl.push_str(&format!(
+                    r#"<container id="{}" parentID="{parent}" restricted="1" childCount="{child_count}"><dc:title>{}</dc:title><upnp:class>{class}</upnp:class>"#,
+                    escape(id),
+                    escape(title)
+                ));
+                if let Some(album) = album {
+                    didl.push_str(&format!(
+                        "<upnp:artist>{}</upnp:artist><upnp:albumArtURI>{base_url}/art/{}</upnp:albumArtURI>",
+                        escape(&album.artist),
+                        album.id
+                    ));
+                }
+                didl.push_str("</container>");
+            }
+            Object::Item { album, song, .. } => {
+                didl.push_str(&format!(
+                    r#"<item id="{}" parentID="{parent}" restricted="1">"#,
+                    escape(id)
+                ));
+                item_metadata(&mut didl, album, song, base_url);
+                didl.push_str("</item>");
+            }
+        }
+    }
+    didl.push_str("</DIDL-Lite>");
+    didl
+}
+
+fn item_metadata(didl: &mut String, album: &Album, song: &Song, base_url: &str) {
+    let track = &song.track;
+    let metadata = track.metadata.as_ref();
+    let title = metadata
+        .and_then(|m| m.title.clone())
+        .unwrap_or_else(|| muz_core::track::Track::default_title(&track.path));
+    let artist = metadata
+        .and_then(|m| m.artist.as_ref())
+        .unwrap_or(&album.artist);
+    didl.push_str(&format!(
+        "<dc:title>{}</dc:title><upnp:class>object.item.audioItem.musicTrack</upnp:class>\
+         <dc:creator>{artist}</dc:creator><upnp:artist>{artist}</upnp:artist>\
+         <upnp:album>{}</upnp:album>",
+        escape(&title),
+        escape(&album.name),
+        artist = escape(artist),
+    ));
+    if let Some(genre) = metadata.and_then(|m| m.genre.as_ref()) {
+        didl.push_str(&format!("<upnp:genre>{}</upnp:genre>", escape(genre)));
+    }
+    if let Some(number) = metadata.and_then(|m| m.track_number) {
+        didl.push_str(&format!(
+            "<upnp:originalTrackNumber>{number}</upnp:originalTrackNumber>"
+        ));
+    }
+    let year = metadata.and_then(|m| m.year.as_ref()?.get(..4)?.parse::<u32>().ok());
+    if let Some(year) = year {
+        didl.push_str(&format!("<dc:date>{year:04}-01-01</dc:date>"));
+    }
+    didl.push_str(&format!(
+        "<upnp:albumArtURI>{base_url}/art/{}</upnp:albumArtURI>",
+        album.id
+    ));
+
+    // Songs that are part of a file are decoded to WAV, the length isn't known up front
+    let (media_type, size) = if track.segment.is_some() {
+        ("audio/wav", None)
+    } else {
+        let size = std::fs::metadata(&track.path).ok().map(|m| m.len());
+        (streaming::media_type(&track.path), size)
+    };
+    let size = size.map_or_else(String::new, |size| format!(r#" size="{size}""#));
+    let ms = track.duration_ms;
+    didl.push_str(&format!(
+        r#"<res protocolInfo="http-get:*:{media_type}:{DLNA_FEATURES}"{size} duration="{}:{:02}:{:02}.{:03}">{base_url}/media/{}</res>"#,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000,
+        song.id
+    ));
+}
+
+#[cfg(test)]
+#[path = "./content_directory.tests.rs"]
+mod tests;
@@ -0,0 +1,135 @@
+use super::*;
+use muz_core::track::{Track, TrackMetadata};
+
+fn track(path: &str, title: &str, number: i32, genre: &str) -> Track {
+    let mut track = Track::new(path);
+    track.duration_ms = 3_723_456;
+    track.metadata = Some(TrackMetadata {
+        title: Some(title.to_string()),
+        album: None,
+        artist: None,
+        album_artist: None,
+        track_number: Some(number),
+        disc_number: None,
+        genre: Some(genre.to_string()),
+        year: Some("1993".to_string()),
+    });
+    track
+}
+
+fn catalog() -> Catalog {
+    let debut = vec![
+        track("/music/Björk/Debut/01.flac", "Human Behaviour", 1, "Pop"),
+        track("/music/Björk/Debut/02.flac", "Crying", 2, "Pop"),
+    ];
+    let moon_safari = vec![track(
+        "/music/Air/Moon Safari/01.mp3",
+        "La femme d'argent",
+        1,
+        "Electronic",
+    )];
+    Catalog::new(HashMap::from([
+        (
+            "Björk".to_string(),
+            HashMap::from([("Debut".to_string(), debut)]),
+        ),
+        (
+            "Air".to_string(),
+            HashMap::from([("Moon Safari".to_string(), moon_safari)]),
+        ),
+    ]))
+}
+
+fn titles(objects: &[Object]) -> Vec<String> {
+    objects
+        .iter()
+        .map(|object| match object {
+            Object::Container { title, .. } => title.clone(),
+            Object::Item { song, .. } => {
+                song.track.metadata.as_ref().unwrap().title.clone().unwrap()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_browses_artists_down_to_songs() {
+    let catalog = catalog();
+    let tree = Tree::new(&catalog, Path::new("/music"));
+    let root = tree.children(ROOT).unwrap();
+    assert_eq!(titles(&root), ["Artists", "Albums", "Genres", "Folders"]);
+
+    let artists = tree.children("artists").unwrap();
+    assert_eq!(titles(&artists), ["Air", "Björk"]);
+    let albums = tree.children(artists[1].id()).unwrap();
+    assert_eq!(titles(&albums), ["Debut"]);
+    let songs = tree.children(albums[0].id()).unwrap();
+    assert_eq!(titles(&songs), ["Human Behaviour", "Crying"]);
+
+    let song = songs[1].id();
+    assert_eq!(parent_id(song), albums[0].id());
+    assert_eq!(parent_id("artists"), ROOT);
+    assert_eq!(parent_id(ROOT), "-1");
+    assert!(matches!(
+        tree.object(albums[0].id()),
+        Some(Object::Container { child_count: 2, .. })
+    ));
+    assert_eq!(tree.object(song).unwrap().id(), song);
+
+    // An album is only under its own artist
+    let air = artists[0].id();
+    let debut = albums[0].id().rsplit('/').next().unwrap();
+    assert!(tree.children(&format!("{air}/{debut}")).is_none());
+    assert!(tree.children("nowhere").is_none());
+}
+
+#[test]
+fn test_browses_albums_genres_and_folders() {
+    let catalog = catalog();
+    let tree = Tree::new(&catalog, Path::new("/music"));
+    assert_eq!(
+        titles(&tree.children("albums").unwrap()),
+        ["Moon Safari", "Debut"]
+    );
+
+    let genres = tree.children("genres").unwrap();
+    assert_eq!(titles(&genres), ["Electronic", "Pop"]);
+    assert_eq!(
+        titles(&tree.children(genres[1].id()).unwrap()),
+        ["Human Behaviour", "Crying"]
+    );
+
+    let folders = tree.children("folders").unwrap();
+    assert_eq!(titles(&folders), ["Air", "Björk"]);
+    let air = tree.children(folders[0].id()).unwrap();
+    assert_eq!(titles(&air), ["Moon Safari"]);
+    let moon_safari = tree.children(air[0].id()).unwrap();
+    assert_eq!(titles(&moon_safari), ["La femme d'argent"]);
+    assert_eq!(parent_id(moon_safari[0].id()), air[0].id());
+}
+
+#[test]
+fn test_didl() {
+    let catalog = catalog();
+    let tree = Tree::new(&catalog, Path::new("/music"));
+    let (album, song) = catalog
+        .songs()
+        .find(|(album, _)| album.name == "Moon Safari")
+        .unwrap();
+    let objects = tree.children(&format!("albums/{}", album.id)).unwrap();
+    let didl = didl(&objects, "http://10.0.0.2:8200");
+
+    assert!(didl.starts_with("<DIDL-Lite "));
+    assert!(didl.contains(&format!(
+        r#"<item id="albums/{}/{}" parentID="albums/{}""#,
+        album.id, song.id, album.id
+    )));
+    assert!(didl.contains("<dc:title>La femme d&apos;argent</dc:title>"));
+    assert!(didl.contains("<upnp:artist>Air</upnp:artist>"));
+    assert!(didl.contains("<dc:date>1993-01-01</dc:date>"));
+    assert!(didl.contains(&format!(
+        r#"protocolInfo="http-get:*:audio/mpeg:{DLNA_FEATURES}" duration="1:02:03.456">http://10.0.0.2:8200/media/{}</res>"#,
+        song.id
+    )));
+    assert!(didl.contains(&format!("http://10.0.0.2:8200/art/{}", album.id)));
+}
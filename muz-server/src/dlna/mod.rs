@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::dlna::routes::Server;
+use crate::dlna::ssdp::Announcement;
+use muz_core::bus::BusEvent;
+use muz_core::config::DlnaSettings;
+use muz_core::events::PlayerEvent;
+use muz_core::services::library_service::LibraryService;
+
+mod content_directory;
+mod description;
+mod routes;
+mod ssdp;
+
+/// The name and UDN the server is known by.
+#[derive(Clone, Debug)]
+pub struct Device {
+    pub uuid: String,
+    pub name: String,
+}
+
+impl Device {
+    /// The UUID must stay the same across restarts for renderers to remember the server,
+    /// so it comes from the host and server names.
+    pub fn new(name: &str) -> Self {
+        let hex = format!("{:x}", md5::compute(format!("{}\0{name}", hostname())));
+        Self {
+            uuid: format!(
+                "{}-{}-{}-{}-{}",
+                &hex[..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..]
+            ),
+            name: name.to_string(),
+        }
+    }
+}
+
+fn hostname() -> String {
+    std::fs::read_to_string("/etc/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Serves the library as a UPnP media server on the configured address and announces it
+/// over SSDP, returning the address actually bound.
+pub async fn serve(
+    settings: &DlnaSettings,
+    library: LibraryService,
+    events: broadcast::Receiver<BusEvent>,
+) -> Result<SocketAddr> {
+    let device = Device::new(&settings.name);
+    let socket = ssdp::bind().context("Failed to listen for SSDP searches")?;
+    let address = serve_http(&settings.address, device.clone(), library, events).await?;
+    tokio::spawn(ssdp::advertise(socket, Announcement::new(device, address)));
+    Ok(address)
+}
+
+/// Serves descriptions, browsing and media. Renderers are told about changes to the
+/// library through the system update id.
+async fn serve_http(
+    address: &str,
+    device: Device,
+    library: LibraryService,
+    mut events: broadcast::Receiver<BusEvent>,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(address).await?;
+    let local_address = listener.local_addr()?;
+    let update_id = Arc::new(AtomicU32::new(1));
+    let server = Arc::new(Server::new(library, device, update_id.clone()));
+
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(BusEvent {
+                    event: PlayerEvent::LibraryChanged(_),
+                    ..
+                })
+                | Err(RecvError::Lagged(_)) => {
+                    update_id.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(_) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!("Failed to accept a UPnP client: {e}");
+                    continue;
+                }
+            };
+            let server = server.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| server.clone().handle(request));
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    tracing::debug!("UPnP client {peer} disconnected: {e}");
+                }
+            });
+        }
+    });
+    Ok(local_address)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
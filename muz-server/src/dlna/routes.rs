@@ -0,0 +1,382 @@
+use anyhow::anyhow;
+use http_body_util::{BodyExt, Limited};
+use hyper::body::Incoming;
+use hyper::header::{self, HeaderName, HeaderValue};
+use hyper::{Request, Response, StatusCode};
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::catalog::Catalog;
+use crate::dlna::content_directory::{self, Tree, DLNA_FEATURES};
+use crate::dlna::description::{self, CONNECTION_MANAGER, CONTENT_DIRECTORY};
+use crate::dlna::{escape, Device};
+use crate::streaming::{self, full, Body};
+use muz_core::artwork::Artwork;
+use muz_core::services::library_service::LibraryService;
+
+/// SOAP requests are a few arguments
+const MAX_BODY: usize = 64 * 1024;
+/// What the ConnectionManager says can be served
+const SOURCE_PROTOCOLS: &[&str] = &[
+    "audio/mpeg",
+    "audio/flac",
+    "audio/ogg",
+    "audio/mp4",
+    "audio/aac",
+    "audio/wav",
+    "audio/aiff",
+    "audio/webm",
+    "audio/x-matroska",
+];
+
+/// A UPnP error, answered as a SOAP fault.
+struct Fault {
+    code: u32,
+    description: String,
+}
+
+impl Fault {
+    fn new(code: u32, description: impl Into<String>) -> Self {
+        Self {
+            code,
+            description: description.into(),
+        }
+    }
+
+    fn invalid_args(description: impl Into<String>) -> Self {
+        Self::new(402, description)
+    }
+
+    fn no_such_object() -> Self {
+        Self::new(701, "No such object")
+    }
+
+    fn into_response(self) -> Response<Body> {
+        let body = format!(
+            "<s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring>\
+             <detail><UPnPError xmlns=\"urn:schemas-upnp-org:control-1-0\">\
+             <errorCode>{}</errorCode><errorDescription>{}</errorDescription>\
+             </UPnPError></detail></s:Fault>",
+            self.code,
+            escape(&self.description)
+        );
+        let mut response = xml(envelope(&body));
+        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        response
+    }
+}
+
+impl From<anyhow::Error> for Fault {
+    fn from(error: anyhow::Error) -> Self {
+        Self::new(501, format!("{error:#}"))
+    }
+}
+
+/// The device and service descriptions, their control endpoints, and the media and covers
+/// the browse results link to.
+pub struct Server {
+    library: LibraryService,
+    device: Device,
+    update_id: Arc<AtomicU32>,
+}
+
+impl Server {
+    pub fn new(library: LibraryService, device: Device, update_id: Arc<AtomicU32>) -> Self {
+        Self {
+            library,
+            device,
+            update_id,
+        }
+    }
+
+    pub async fn handle(
+        self: Arc<Self>,
+        request: Request<Incoming>,
+    ) -> Result<Response<Body>, Infallible> {
+        let path = request.uri().path().to_string();
+        let method = request.method().as_str().to_string();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let response = match (method.as_str(), segments.as_slice()) {
+            ("GET", ["description.xml"]) => xml(description::device(&self.device)),
+            ("GET", ["ContentDirectory.xml"]) => xml(description::CONTENT_DIRECTORY_SCPD),
+            ("GET", ["ConnectionManager.xml"]) => xml(description::CONNECTION_MANAGER_SCPD),
+            ("POST", ["control", service]) => {
+                let service = service.to_string();
+                self.control(&service, request)
+                    .await
+                    .unwrap_or_else(Fault::into_response)
+            }
+            // Events aren't sent, but some control points won't browse without subscribing
+            ("SUBSCRIBE", ["events", _]) => {
+                let mut response = Response::new(full(""));
+                let headers = response.headers_mut();
+                let sid = format!("uuid:{}", uuid::Uuid::new_v4());
+                if let Ok(sid) = HeaderValue::from_str(&sid) {
+                    headers.insert(HeaderName::from_static("sid"), sid);
+                }
+                headers.insert(
+                    HeaderName::from_static("timeout"),
+                    HeaderValue::from_static("Second-1800"),
+                );
+                response
+            }
+            ("UNSUBSCRIBE", ["events", _]) => Response::new(full("")),
+            ("GET" | "HEAD", ["media", id]) => self.media(id, &request).await,
+            ("GET" | "HEAD", ["art", id]) => self.art(id).await,
+            _ => status(StatusCode::NOT_FOUND),
+        };
+        Ok(response)
+    }
+
+    async fn control(
+        &self,
+        service: &str,
+        request: Request<Incoming>,
+    ) -> Result<Response<Body>, Fault> {
+        let action = request
+            .headers()
+            .get("soapaction")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim_matches('"').rsplit_once('#'))
+            .map(|(_, action)| action.to_string())
+            .ok_or_else(|| Fault::new(401, "Invalid action"))?;
+        let base_url = request
+            .headers()
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(|host| format!("http://{host}"))
+            .ok_or_else(|| Fault::invalid_args("A Host header is required"))?;
+        let body = Limited::new(request.into_body(), MAX_BODY)
+            .collect()
+            .await
+            .map_err(|e| Fault::invalid_args(format!("Failed to read the request: {e}")))?
+            .to_bytes();
+        let body = String::from_utf8_lossy(&body);
+
+        let update_id = self.update_id.load(Ordering::Relaxed).to_string();
+        let (service_type, results) = match (service, action.as_str()) {
+            ("ContentDirectory", "Browse") => {
+                (CONTENT_DIRECTORY, self.browse(&body, &base_url).await?)
+            }
+            ("ContentDirectory", "GetSystemUpdateID") => {
+                (CONTENT_DIRECTORY, vec![("Id", update_id)])
+            }
+            ("ContentDirectory", "GetSearchCapabilities") => {
+                (CONTENT_DIRECTORY, vec![("SearchCaps", String::new())])
+            }
+            ("ContentDirectory", "GetSortCapabilities") => {
+                (CONTENT_DIRECTORY, vec![("SortCaps", String::new())])
+            }
+            ("ConnectionManager", "GetProtocolInfo") => {
+                let source: Vec<String> = SOURCE_PROTOCOLS
+                    .iter()
+                    .map(|media_type| format!("http-get:*:{media_type}:*"))
+                    .collect();
+                let source = ("Source", source.join(","));
+                (CONNECTION_MANAGER, vec![source, ("Sink", String::new())])
+            }
+            ("ConnectionManager", "GetCurrentConnectionIDs") => {
+                (CONNECTION_MANAGER, vec![("ConnectionIDs", "0".to_string())])
+            }
+            ("ConnectionManager", "GetCurrentConnectionInfo") => (
+                CONNECTION_MANAGER,
+                vec![
+                    ("RcsID", "-1".to_string()),
+                    ("AVTransportID", "-1".to_string()),
+                    ("ProtocolInfo", String::new()),
+                    ("PeerConnectionManager", String::new()),
+                    ("PeerConnectionID", "-1".to_string()),
+                    ("Direction", "Output".to_string()),
+                    ("Status", "OK".to_string()),
+                ],
+            ),
+            _ => return Err(Fault::new(401, format!("Invalid action: {action}"))),
+        };
+
+        let mut arguments = String::new();
+        for (name, value) in results {
+            arguments.push_str(&format!("<{name}>{}</{name}>", escape(&value)));
+        }
+        Ok(xml(envelope(&format!(
+            "<u:{action}Response xmlns:u=\"{service_type}\">{arguments}</u:{action}Response>"
+        ))))
+    }
+
+    /// Lists a container's children or describes one object, as a DIDL-Lite `Result`.
+    async fn browse(
+        &self,
+        body: &str,
+        base_url: &str,
+    ) -> Result<Vec<(&'static str, String)>, Fault> {
+        let object_id = argument(body, "ObjectID")
+            .ok_or_else(|| Fault::invalid_args("ObjectID is required"))?;
+        let number = |name: &str| {
+            argument(body, name)
+                .filter(|value| !value.is_empty())
+                .map_or(Ok(0), |value| value.trim().parse::<usize>())
+                .map_err(|_| Fault::invalid_args(format!("Invalid {name}")))
+        };
+        let (start, count) = (number("StartingIndex")?, number("RequestedCount")?);
+
+        let catalog = Catalog::new(self.library.albums_by_artist().await?);
+        let root = PathBuf::from(self.library.library_path().await?);
+        let tree = Tree::new(&catalog, &root);
+        let (objects, total) = match argument(body, "BrowseFlag").as_deref() {
+            Some("BrowseMetadata") => {
+                let object = tree.object(&object_id).ok_or_else(Fault::no_such_object)?;
+                (vec![object], 1)
+            }
+            Some("BrowseDirectChildren") => {
+                let children = tree
+                    .children(&object_id)
+                    .ok_or_else(Fault::no_such_object)?;
+                let total = children.len();
+                let count = if count == 0 { total } else { count };
+                (
+                    children.into_iter().skip(start).take(count).collect(),
+                    total,
+                )
+            }
+            _ => return Err(Fault::invalid_args("Invalid BrowseFlag")),
+        };
+        Ok(vec![
+            ("Result", content_directory::didl(&objects, base_url)),
+            ("NumberReturned", objects.len().to_string()),
+            ("TotalMatches", total.to_string()),
+            (
+                "UpdateID",
+                self.update_id.load(Ordering::Relaxed).to_string(),
+            ),
+        ])
+    }
+
+    /// The song's file with range requests, or decoded to WAV when it is part of a file.
+    async fn media(&self, id: &str, request: &Request<Incoming>) -> Response<Body> {
+        let song = match self.library.albums_by_artist().await {
+            Ok(albums) => Catalog::new(albums)
+                .song(id)
+                .map(|(_, song)| song.track.clone()),
+            Err(e) => {
+                tracing::warn!("Failed to list the library: {e}");
+                None
+            }
+        };
+        let Some(track) = song else {
+            return status(StatusCode::NOT_FOUND);
+        };
+        let range = request
+            .headers()
+            .get(header::RANGE)
+            .and_then(|value| value.to_str().ok());
+        let result = if track.segment.is_some() {
+            streaming::transcode_wav(track, Default::default()).await
+        } else {
+            streaming::serve_file(&track.path, range).await
+        };
+        match result {
+            Ok(mut response) => {
+                let headers = response.headers_mut();
+                headers.insert(
+                    HeaderName::from_static("transfermode.dlna.org"),
+                    HeaderValue::from_static("Streaming"),
+                );
+                headers.insert(
+                    HeaderName::from_static("contentfeatures.dlna.org"),
+                    HeaderValue::from_static(DLNA_FEATURES),
+                );
+                response
+            }
+            Err(e) => {
+                tracing::warn!("Failed to serve {id}: {e:#}");
+                status(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    async fn art(&self, id: &str) -> Response<Body> {
+        let track = match self.library.albums_by_artist().await {
+            Ok(albums) => Catalog::new(albums).cover_track(id).cloned(),
+            Err(_) => None,
+        };
+        let Some(track) = track else {
+            return status(StatusCode::NOT_FOUND);
+        };
+        let artwork = tokio::task::spawn_blocking(move || Artwork::load(&track))
+            .await
+            .map_err(|e| anyhow!("Failed to load the artwork: {e}"));
+        let Ok(Some(artwork)) = artwork else {
+            return status(StatusCode::NOT_FOUND);
+        };
+        let mut response = Response::new(full(artwork.data));
+        if let Ok(media_type) = HeaderValue::from_str(&artwork.media_type) {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, media_type);
+        }
+        response
+    }
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(full(""));
+    *response.status_mut() = status;
+    response
+}
+
+fn xml(body: impl Into<String>) -> Response<Body> {
+    let mut response = Response::new(full(body.into()));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/xml; charset=\"utf-8\""),
+    );
+    response
+}
+
+fn envelope(body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+         <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+         <s:Body>{body}</s:Body></s:Envelope>"
+    )
+}
+
+/// The text of the first `<name>` element of a SOAP body, unescaped. Arguments aren't
+/// namespaced, so a plain search is enough.
+fn argument(body: &str, name: &str) -> Option<String> {
+    let open = format!("<{name}");
+    let mut rest = body;
+    loop {
+        let start = rest.find(&open)?;
+        rest = &rest[start + open.len()..];
+        // Skip longer names with the same prefix, e.g. `<ObjectIDs>`
+        match rest.chars().next()? {
+            '>' => break,
+            ' ' | '\t' | '\r' | '\n' => {
+                let end = rest.find('>')?;
+                if rest[..end].ends_with('/') {
+                    return Some(String::new());
+                }
+                rest = &rest[end..];
+                break;
+            }
+            '/' => return Some(String::new()),
+            _ => continue,
+        }
+    }
+    let value = &rest[1..rest.find(&format!("</{name}>"))?];
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+#[cfg(test)]
+#[path = "./routes.tests.rs"]
+mod tests;
@@ -0,0 +1,156 @@
+use super::*;
+use crate::dlna::serve_http;
+use crate::testing::{request, TestLibrary};
+use muz_core::bus::EventBus;
+use muz_core::events::{LibraryChangedEvent, PlayerEvent};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+async fn start(test_library: &TestLibrary, events: &EventBus) -> SocketAddr {
+    let tracks = test_library.scan_tagged().await;
+    let library = LibraryService::new(Arc::new(tokio::sync::Mutex::new(tracks)), events.clone());
+    let device = Device::new("Test Server");
+    serve_http("127.0.0.1:0", device, library, events.subscribe().1)
+        .await
+        .unwrap()
+}
+
+/// Calls a ContentDirectory action, returning the status and the response body.
+async fn control(address: SocketAddr, action: &str, arguments: &str) -> (u16, String) {
+    let body = format!(
+        "<?xml version=\"1.0\"?><s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\">\
+         <s:Body><u:{action} xmlns:u=\"{CONTENT_DIRECTORY}\">{arguments}</u:{action}>\
+         </s:Body></s:Envelope>"
+    );
+    let headers = format!("SOAPACTION: \"{CONTENT_DIRECTORY}#{action}\"\r\n");
+    let (status, _, body) = request(
+        address,
+        "POST",
+        "/control/ContentDirectory",
+        &headers,
+        &body,
+    )
+    .await;
+    (status, String::from_utf8(body).unwrap())
+}
+
+/// The DIDL-Lite `Result` of browsing the children of `id`.
+async fn browse(address: SocketAddr, id: &str) -> String {
+    let arguments = format!(
+        "<ObjectID>{id}</ObjectID><BrowseFlag>BrowseDirectChildren</BrowseFlag>\
+         <Filter>*</Filter><StartingIndex>0</StartingIndex><RequestedCount>0</RequestedCount>\
+         <SortCriteria></SortCriteria>"
+    );
+    let (status, body) = control(address, "Browse", &arguments).await;
+    assert_eq!(status, 200, "{body}");
+    argument(&body, "Result").unwrap()
+}
+
+/// The first id in the DIDL-Lite document that starts with `prefix`.
+fn first_id(didl: &str, prefix: &str) -> String {
+    let start = didl.find(&format!("id=\"{prefix}")).unwrap() + 4;
+    didl[start..start + didl[start..].find('"').unwrap()].to_string()
+}
+
+#[tokio::test]
+async fn test_describes_the_device() {
+    let library = TestLibrary::create();
+    let address = start(&library, &EventBus::new()).await;
+
+    let (status, head, body) = request(address, "GET", "/description.xml", "", "").await;
+    let body = String::from_utf8(body).unwrap();
+    assert_eq!(status, 200);
+    assert!(head.contains("text/xml"));
+    assert!(body.contains("<friendlyName>Test Server</friendlyName>"));
+    assert!(body.contains(&format!(
+        "<UDN>uuid:{}</UDN>",
+        Device::new("Test Server").uuid
+    )));
+    assert!(body.contains("<controlURL>/control/ContentDirectory</controlURL>"));
+
+    let (status, _, body) = request(address, "GET", "/ContentDirectory.xml", "", "").await;
+    assert_eq!(status, 200);
+    assert!(String::from_utf8(body)
+        .unwrap()
+        .contains("<name>Browse</name>"));
+
+    let (status, head, _) = request(address, "SUBSCRIBE", "/events/ContentDirectory", "", "").await;
+    assert_eq!(status, 200);
+    assert!(head.to_lowercase().contains("sid: uuid:"));
+}
+
+#[tokio::test]
+async fn test_browses_to_songs_and_streams_them() {
+    let library = TestLibrary::create();
+    let address = start(&library, &EventBus::new()).await;
+
+    let root = browse(address, "0").await;
+    assert!(root.contains("<dc:title>Artists</dc:title>"));
+    let artists = browse(address, "artists").await;
+    assert!(artists.contains("<dc:title>The Rests</dc:title>"));
+    let albums = browse(address, &first_id(&artists, "artists/")).await;
+    assert!(albums.contains("<dc:title>Silence</dc:title>"));
+    assert!(albums.contains("childCount=\"2\""));
+    let songs = browse(address, &first_id(&albums, "artists/")).await;
+    assert!(songs.contains("<dc:title>Alpha</dc:title>"));
+    assert!(songs.contains("<upnp:originalTrackNumber>2</upnp:originalTrackNumber>"));
+
+    let url = format!("http://{address}/media/");
+    let start = songs.find(&url).unwrap() + url.len() - "/media/".len();
+    let media = &songs[start..start + songs[start..].find('<').unwrap()];
+    let file = std::fs::read(library.song("Alpha.wav")).unwrap();
+    let (status, head, body) = request(address, "GET", media, "Range: bytes=10-\r\n", "").await;
+    assert_eq!(status, 206);
+    assert!(head
+        .to_lowercase()
+        .contains("transfermode.dlna.org: streaming"));
+    assert_eq!(body, file[10..]);
+
+    let (status, head, body) = request(address, "HEAD", media, "", "").await;
+    assert_eq!(status, 200);
+    assert!(
+        head.contains(&format!("content-length: {}", file.len())),
+        "{head}"
+    );
+    assert!(body.is_empty());
+
+    let art = format!(
+        "/art/{}",
+        first_id(&albums, "artists/").rsplit('/').next().unwrap()
+    );
+    let (status, _, body) = request(address, "GET", &art, "", "").await;
+    assert_eq!(status, 200);
+    assert_eq!(body, b"jpeg");
+}
+
+#[tokio::test]
+async fn test_faults_and_update_ids() {
+    let library = TestLibrary::create();
+    let events = EventBus::new();
+    let address = start(&library, &events).await;
+
+    let arguments =
+        "<ObjectID>artists/ar-nobody</ObjectID><BrowseFlag>BrowseDirectChildren</BrowseFlag>";
+    let (status, body) = control(address, "Browse", arguments).await;
+    assert_eq!(status, 500);
+    assert!(body.contains("<errorCode>701</errorCode>"));
+
+    let (_, body) = control(address, "GetSystemUpdateID", "").await;
+    assert_eq!(argument(&body, "Id").as_deref(), Some("1"));
+    events.publish(PlayerEvent::LibraryChanged(LibraryChangedEvent {
+        track_count: 2,
+    }));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let (_, body) = control(address, "GetSystemUpdateID", "").await;
+    assert_eq!(argument(&body, "Id").as_deref(), Some("2"));
+}
+
+#[test]
+fn test_argument() {
+    let body =
+        "<ObjectIDs>no</ObjectIDs><ObjectID>a &amp; b</ObjectID><Filter/><Sort x=\"1\">s</Sort>";
+    assert_eq!(argument(body, "ObjectID").as_deref(), Some("a & b"));
+    assert_eq!(argument(body, "Filter").as_deref(), Some(""));
+    assert_eq!(argument(body, "Sort").as_deref(), Some("s"));
+    assert_eq!(argument(body, "Missing"), None);
+}
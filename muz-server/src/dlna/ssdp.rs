@@ -0,0 +1,181 @@
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+use crate::dlna::description::{CONNECTION_MANAGER, CONTENT_DIRECTORY, DEVICE_TYPE};
+use crate::dlna::Device;
+
+const GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const PORT: u16 = 1900;
+/// How long announcements hold, they are repeated at half of it
+const MAX_AGE: Duration = Duration::from_secs(1800);
+
+/// What the server announces: the root device, its UDN, its type and its services.
+pub struct Announcement {
+    device: Device,
+    http_address: SocketAddr,
+}
+
+impl Announcement {
+    pub fn new(device: Device, http_address: SocketAddr) -> Self {
+        Self {
+            device,
+            http_address,
+        }
+    }
+
+    /// Each notification type with its unique service name.
+    fn targets(&self) -> Vec<(String, String)> {
+        let udn = format!("uuid:{}", self.device.uuid);
+        let mut targets = vec![
+            (
+                "upnp:rootdevice".to_string(),
+                format!("{udn}::upnp:rootdevice"),
+            ),
+            (udn.clone(), udn.clone()),
+        ];
+        for target in [DEVICE_TYPE, CONTENT_DIRECTORY, CONNECTION_MANAGER] {
+            targets.push((target.to_string(), format!("{udn}::{target}")));
+        }
+        targets
+    }
+
+    /// The description URL, on the address `peer` reaches this host by when the server
+    /// listens on all of them.
+    fn location(&self, peer: SocketAddr) -> String {
+        let mut ip = self.http_address.ip();
+        if ip.is_unspecified() {
+            ip = local_ip(peer).unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        }
+        format!(
+            "http://{}/description.xml",
+            SocketAddr::new(ip, self.http_address.port())
+        )
+    }
+
+    /// The replies to a search for `search_target`, one per matching target.
+    pub fn responses(&self, search_target: &str, peer: SocketAddr) -> Vec<String> {
+        let location = self.location(peer);
+        self.targets()
+            .into_iter()
+            .filter(|(target, _)| search_target == "ssdp:all" || search_target == target)
+            .map(|(target, usn)| {
+                format!(
+                    "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age={}\r\nEXT:\r\n\
+                     LOCATION: {location}\r\nSERVER: {}\r\nST: {target}\r\nUSN: {usn}\r\n\r\n",
+                    MAX_AGE.as_secs(),
+                    server()
+                )
+            })
+            .collect()
+    }
+
+    /// The `ssdp:alive` notifications for every target.
+    pub fn notifications(&self) -> Vec<String> {
+        let group = SocketAddr::V4(SocketAddrV4::new(GROUP, PORT));
+        let location = self.location(group);
+        self.targets()
+            .into_iter()
+            .map(|(target, usn)| {
+                format!(
+                    "NOTIFY * HTTP/1.1\r\nHOST: {GROUP}:{PORT}\r\nCACHE-CONTROL: max-age={}\r\n\
+                     LOCATION: {location}\r\nNT: {target}\r\nNTS: ssdp:alive\r\n\
+                     SERVER: {}\r\nUSN: {usn}\r\n\r\n",
+                    MAX_AGE.as_secs(),
+                    server()
+                )
+            })
+            .collect()
+    }
+}
+
+fn server() -> String {
+    format!(
+        "{}/1.0 UPnP/1.0 muz/{}",
+        std::env::consts::OS,
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// The local address packets to `peer` leave from. Connecting a UDP socket sends nothing.
+fn local_ip(peer: SocketAddr) -> Option<IpAddr> {
+    let socket = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect(peer).ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+/// The search target of an `M-SEARCH` discovery request, `None` for anything else.
+pub fn search_target(message: &str) -> Option<&str> {
+    let mut lines = message.lines();
+    if !lines.next()?.starts_with("M-SEARCH ") {
+        return None;
+    }
+    let mut target = None;
+    let mut discover = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        match name.trim().to_ascii_uppercase().as_str() {
+            "ST" => target = Some(value.trim()),
+            "MAN" => discover = value.trim() == "\"ssdp:discover\"",
+            _ => {}
+        }
+    }
+    target.filter(|_| discover)
+}
+
+/// Joins the SSDP group on the standard port, sharing it with other UPnP software.
+pub fn bind() -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, PORT)).into())?;
+    socket.join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+/// Answers searches on `socket` and announces the server to the group until the task is
+/// dropped.
+pub async fn advertise(socket: UdpSocket, announcement: Announcement) {
+    let group = SocketAddr::V4(SocketAddrV4::new(GROUP, PORT));
+    let mut announce = tokio::time::interval(MAX_AGE / 2);
+    let mut buffer = [0; 2048];
+    loop {
+        tokio::select! {
+            _ = announce.tick() => {
+                for notification in announcement.notifications() {
+                    if let Err(e) = socket.send_to(notification.as_bytes(), group).await {
+                        tracing::debug!("Failed to announce the UPnP server: {e}");
+                        break;
+                    }
+                }
+            }
+            received = socket.recv_from(&mut buffer) => {
+                let (len, peer) = match received {
+                    Ok(received) => received,
+                    Err(e) => {
+                        tracing::warn!("Failed to receive an SSDP message: {e}");
+                        continue;
+                    }
+                };
+                let message = String::from_utf8_lossy(&buffer[..len]);
+                let Some(target) = search_target(&message) else {
+                    continue;
+                };
+                for response in announcement.responses(target, peer) {
+                    if let Err(e) = socket.send_to(response.as_bytes(), peer).await {
+                        tracing::debug!("Failed to answer the SSDP search from {peer}: {e}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./ssdp.tests.rs"]
+mod tests;
@@ -0,0 +1,64 @@
+use super::*;
+
+fn announcement() -> Announcement {
+    let device = Device {
+        uuid: "4d696e69-444c-164e-9d41-b827eb54e80e".to_string(),
+        name: "muz".to_string(),
+    };
+    Announcement::new(device, "127.0.0.1:8200".parse().unwrap())
+}
+
+#[test]
+fn test_search_target() {
+    let search = "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\
+                  MAN: \"ssdp:discover\"\r\nMX: 2\r\nST: ssdp:all\r\n\r\n";
+    assert_eq!(search_target(search), Some("ssdp:all"));
+    assert_eq!(
+        search_target(&search.replace("ssdp:discover", "other")),
+        None
+    );
+    let notify = "NOTIFY * HTTP/1.1\r\nNT: upnp:rootdevice\r\nNTS: ssdp:alive\r\n\r\n";
+    assert_eq!(search_target(notify), None);
+}
+
+#[test]
+fn test_answers_matching_targets() {
+    let announcement = announcement();
+    let peer = "127.0.0.1:5000".parse().unwrap();
+    assert_eq!(announcement.responses("ssdp:all", peer).len(), 5);
+    assert!(announcement
+        .responses("urn:schemas-upnp-org:device:Printer:1", peer)
+        .is_empty());
+
+    let responses = announcement.responses(DEVICE_TYPE, peer);
+    assert_eq!(responses.len(), 1);
+    assert!(responses[0].starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(responses[0].contains("LOCATION: http://127.0.0.1:8200/description.xml\r\n"));
+    assert!(responses[0].contains(&format!(
+        "USN: uuid:4d696e69-444c-164e-9d41-b827eb54e80e::{DEVICE_TYPE}\r\n"
+    )));
+    assert!(announcement
+        .notifications()
+        .iter()
+        .all(|n| n.contains("NTS: ssdp:alive")));
+}
+
+#[tokio::test]
+async fn test_replies_to_searches() {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let address = socket.local_addr().unwrap();
+    let advertiser = tokio::spawn(advertise(socket, announcement()));
+
+    let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let search = "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\
+                  MAN: \"ssdp:discover\"\r\nMX: 1\r\nST: upnp:rootdevice\r\n\r\n";
+    client.send_to(search.as_bytes(), address).await.unwrap();
+    let mut buffer = [0; 2048];
+    let (len, _) = tokio::time::timeout(Duration::from_secs(5), client.recv_from(&mut buffer))
+        .await
+        .unwrap()
+        .unwrap();
+    let response = String::from_utf8_lossy(&buffer[..len]);
+    assert!(response.contains("ST: upnp:rootdevice\r\n"), "{response}");
+    advertiser.abort();
+}
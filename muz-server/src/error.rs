@@ -0,0 +1,55 @@
+use muz_core::config::ConfigError;
+use muz_core::error::{ErrorCode, LibraryError, PlaybackError, QueueError};
+use serde::Serialize;
+use serde_json::Value;
+
+/// The error returned by commands, serialized as `{ code, message, details }`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl CommandError {
+    fn coded(error: &impl ErrorCode, message: String) -> Self {
+        CommandError {
+            code: error.code(),
+            message,
+            details: error.details(),
+        }
+    }
+}
+
+/// Takes the code from the typed error anywhere in the chain, keeping the full message.
+impl From<anyhow::Error> for CommandError {
+    fn from(error: anyhow::Error) -> Self {
+        let message = format!("{error:#}");
+        if let Some(e) = error.downcast_ref::<PlaybackError>() {
+            CommandError::coded(e, message)
+        } else if let Some(e) = error.downcast_ref::<QueueError>() {
+            CommandError::coded(e, message)
+        } else if let Some(e) = error.downcast_ref::<LibraryError>() {
+            CommandError::coded(e, message)
+        } else if let Some(e) = error.downcast_ref::<ConfigError>() {
+            CommandError::coded(e, message)
+        } else {
+            CommandError {
+                code: "INTERNAL_ERROR",
+                message,
+                details: None,
+            }
+        }
+    }
+}
+
+impl From<LibraryError> for CommandError {
+    fn from(error: LibraryError) -> Self {
+        CommandError::coded(&error, error.to_string())
+    }
+}
+
+#[cfg(test)]
+#[path = "./error.tests.rs"]
+mod tests;
@@ -0,0 +1,51 @@
+use super::*;
+use anyhow::{anyhow, Context};
+use serde_json::json;
+
+#[test]
+fn test_typed_error_keeps_its_code_and_details() {
+    let error: anyhow::Error = QueueError::IndexOutOfBounds { index: 5, len: 2 }.into();
+    let error = CommandError::from(error);
+
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({
+            "code": "QUEUE_INDEX_OUT_OF_BOUNDS",
+            "message": "Index 5 out of bounds for a queue of 2 tracks",
+            "details": { "index": 5, "len": 2 },
+        })
+    );
+}
+
+#[test]
+fn test_code_is_found_behind_context() {
+    let error = Err::<(), _>(LibraryError::TrackNotFound("abc".to_string()))
+        .context("Failed to load lyrics")
+        .unwrap_err();
+    let error = CommandError::from(error);
+
+    assert_eq!(error.code, "TRACK_NOT_FOUND");
+    assert_eq!(error.message, "Failed to load lyrics: Track not found");
+    assert_eq!(error.details, Some(json!({ "trackId": "abc" })));
+}
+
+#[test]
+fn test_typed_context_gives_the_code() {
+    let error = Err::<(), _>(anyhow!("device unplugged"))
+        .context(PlaybackError::Driver("seek"))
+        .unwrap_err();
+    let error = CommandError::from(error);
+
+    assert_eq!(error.code, "PLAYBACK_DRIVER_FAILED");
+    assert_eq!(error.message, "Failed to seek: device unplugged");
+}
+
+#[test]
+fn test_untyped_error_is_internal() {
+    let error = CommandError::from(anyhow!("something broke"));
+
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({ "code": "INTERNAL_ERROR", "message": "something broke" })
+    );
+}
@@ -0,0 +1,60 @@
+use crate::dlna;
+use crate::mpd;
+#[cfg(target_os = "linux")]
+use crate::mpris;
+use crate::remote;
+use crate::scrobble;
+use crate::subsonic;
+use muz_core::bus::EventBus;
+use muz_core::config::AppConfig;
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+use std::path::Path;
+
+/// Registers MPRIS and starts the servers and scrobblers enabled in `config`, each
+/// following the bus on its own. Shared by the desktop app and the daemon. Failures are
+/// logged, playback works without them. Those that keep state do so in `data_dir`.
+pub async fn start(
+    events: &EventBus,
+    config: &AppConfig,
+    data_dir: Option<&Path>,
+    playback: &PlaybackService,
+    library: &LibraryService,
+) {
+    #[cfg(target_os = "linux")]
+    if let Err(e) = mpris::serve(playback.clone(), events.subscribe().1, None) {
+        tracing::warn!("Failed to register the MPRIS interface: {e}");
+    }
+    if let Some(address) = &config.mpd_address {
+        let changes = events.subscribe().1;
+        match mpd::serve(address, playback.clone(), library.clone(), changes).await {
+            Ok(address) => tracing::info!("MPD server listening on {address}"),
+            Err(e) => tracing::error!("Failed to start the MPD server on {address}: {e}"),
+        }
+    }
+    if config.remote.enabled {
+        let events = events.clone();
+        match remote::serve(&config.remote, playback.clone(), library.clone(), events).await {
+            Ok(address) => tracing::info!("Remote API listening on {address}"),
+            Err(e) => tracing::error!("Failed to start the remote API: {e:#}"),
+        }
+    }
+    if config.subsonic.enabled {
+        match subsonic::serve(&config.subsonic, library.clone(), data_dir).await {
+            Ok(address) => tracing::info!("Subsonic API listening on {address}"),
+            Err(e) => tracing::error!("Failed to start the Subsonic API: {e:#}"),
+        }
+    }
+    if config.dlna.enabled {
+        let changes = events.subscribe().1;
+        match dlna::serve(&config.dlna, library.clone(), changes).await {
+            Ok(address) => tracing::info!("UPnP media server listening on {address}"),
+            Err(e) => tracing::error!("Failed to start the UPnP media server: {e:#}"),
+        }
+    }
+    let scrobbling = &config.scrobbling;
+    if scrobbling.listenbrainz.enabled || scrobbling.lastfm.enabled {
+        if let Err(e) = scrobble::start(scrobbling, events.subscribe().1, data_dir).await {
+            tracing::error!("Failed to start scrobbling: {e:#}");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// A value kept in a JSON file next to the config, written through on every change.
+/// Without a path it only lasts as long as the server.
+pub struct JsonFile<T> {
+    path: Option<PathBuf>,
+    /// What the file holds, for the logs and errors
+    name: &'static str,
+    value: Mutex<T>,
+}
+
+impl<T: Default + Serialize + DeserializeOwned> JsonFile<T> {
+    /// Reads the file at `path`, starting from the default when it is missing or unreadable.
+    pub async fn load(path: Option<PathBuf>, name: &'static str) -> Self {
+        let mut value = T::default();
+        if let Some(path) = path.as_ref().filter(|path| path.exists()) {
+            match tokio::fs::read_to_string(path).await {
+                Ok(content) => match serde_json::from_str(&content) {
+                    Ok(content) => value = content,
+                    Err(e) => tracing::warn!("Ignoring the invalid {name} {path:?}: {e}"),
+                },
+                Err(e) => tracing::warn!("Failed to read the {name} {path:?}: {e}"),
+            }
+        }
+        Self {
+            path,
+            name,
+            value: Mutex::new(value),
+        }
+    }
+
+    pub fn get(&self) -> MutexGuard<'_, T> {
+        self.value.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Applies `change` and saves the result before letting go of the value, so two
+    /// changes racing each other can't leave the older one on disk.
+    pub fn update<R>(&self, change: impl FnOnce(&mut T) -> R) -> Result<R> {
+        let mut value = self.get();
+        let result = change(&mut value);
+        if let Some(path) = &self.path {
+            write(path, &*value)
+                .with_context(|| format!("Failed to save the {} {path:?}", self.name))?;
+        }
+        Ok(result)
+    }
+}
+
+/// Replaces the file through a temporary one, only readable by the user since it tells
+/// what they listen to.
+fn write(path: &Path, value: &impl Serialize) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temporary = path.with_extension("json.tmp");
+    // A leftover from an interrupted save would keep its own mode
+    let _ = std::fs::remove_file(&temporary);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&temporary)?;
+    file.write_all(&serde_json::to_vec_pretty(value)?)?;
+    drop(file);
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "./json_file.tests.rs"]
+mod tests;
@@ -0,0 +1,36 @@
+use super::*;
+
+#[tokio::test]
+async fn test_saves_every_change_privately() {
+    let dir = std::env::temp_dir().join(format!("muz-json-file-{}", uuid::Uuid::new_v4()));
+    let path = dir.join("counts.json");
+    let file = JsonFile::<Vec<u32>>::load(Some(path.clone()), "counts").await;
+    file.update(|counts| counts.push(1)).unwrap();
+    let length = file.update(|counts| {
+        counts.push(2);
+        counts.len()
+    });
+    assert_eq!(length.unwrap(), 2);
+
+    let file = JsonFile::<Vec<u32>>::load(Some(path.clone()), "counts").await;
+    assert_eq!(*file.get(), [1, 2]);
+    assert!(!path.with_extension("json.tmp").exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_starts_over_from_an_invalid_file() {
+    let dir = std::env::temp_dir().join(format!("muz-json-file-{}", uuid::Uuid::new_v4()));
+    let path = dir.join("counts.json");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&path, "[1, ").unwrap();
+    let file = JsonFile::<Vec<u32>>::load(Some(path), "counts").await;
+    assert!(file.get().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,35 @@
+//! The MPD, remote API, Subsonic and DLNA servers, MPRIS and the scrobblers, started
+//! with `integrations::start` by any frontend without pulling in Tauri, as well as the
+//! `muzd` daemon and the socket `muzctl` controls it over.
+
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+mod catalog;
+#[cfg(unix)]
+pub mod control;
+#[cfg(unix)]
+pub mod daemon;
+mod dlna;
+pub mod error;
+pub mod integrations;
+mod json_file;
+mod mpd;
+#[cfg(target_os = "linux")]
+mod mpris;
+mod remote;
+mod scrobble;
+mod streaming;
+mod subsonic;
+#[cfg(test)]
+mod testing;
+
+/// Logs to stderr, filtered by `RUST_LOG`.
+pub fn init_tracing() {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "muz=debug,muz_server=debug,info".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+}
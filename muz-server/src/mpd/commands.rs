@@ -0,0 +1,808 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::sync::broadcast;
+
+use crate::mpd::filter::{Filter, Operator, Tag};
+use crate::mpd::protocol::{Ack, AckCode, Request, Response, Subsystem};
+use muz_core::command::{PlaybackCommand, Volume};
+use muz_core::playback::{PlaybackSnapshot, PlaybackState, RepeatMode};
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+use muz_core::track::Track;
+
+/// Commands listed by `commands`, besides the ones handled by the connection itself.
+const COMMANDS: &[&str] = &[
+    "add",
+    "addid",
+    "clear",
+    "clearerror",
+    "close",
+    "commands",
+    "count",
+    "currentsong",
+    "decoders",
+    "delete",
+    "deleteid",
+    "find",
+    "findadd",
+    "getvol",
+    "idle",
+    "list",
+    "listall",
+    "listallinfo",
+    "listplaylists",
+    "lsinfo",
+    "move",
+    "moveid",
+    "next",
+    "noidle",
+    "notcommands",
+    "outputs",
+    "password",
+    "pause",
+    "ping",
+    "play",
+    "playid",
+    "playlistid",
+    "playlistinfo",
+    "plchanges",
+    "plchangesposid",
+    "previous",
+    "random",
+    "repeat",
+    "replay_gain_status",
+    "rescan",
+    "search",
+    "searchadd",
+    "seek",
+    "seekcur",
+    "seekid",
+    "setvol",
+    "single",
+    "stats",
+    "status",
+    "stop",
+    "tagtypes",
+    "update",
+    "urlhandlers",
+    "volume",
+];
+
+/// Assigns the numeric song ids MPD clients expect to track ids.
+#[derive(Default)]
+struct SongIds {
+    ids: HashMap<String, u32>,
+    next: u32,
+}
+
+impl SongIds {
+    fn id(&mut self, track: &Track) -> u32 {
+        let next = &mut self.next;
+        *self.ids.entry(track.id.clone()).or_insert_with(|| {
+            *next += 1;
+            *next
+        })
+    }
+}
+
+/// State shared by all connections, and the commands run against the player.
+pub struct Mpd {
+    playback: PlaybackService,
+    library: LibraryService,
+    ids: Mutex<SongIds>,
+    playlist_version: AtomicU32,
+    /// The player as last reported, to tell which subsystems changed
+    last: Mutex<Option<PlaybackSnapshot>>,
+    changes: broadcast::Sender<Subsystem>,
+    started: Instant,
+}
+
+impl Mpd {
+    pub fn new(playback: PlaybackService, library: LibraryService) -> Self {
+        let (changes, _) = broadcast::channel(64);
+        Self {
+            last: Mutex::new(playback.status().ok()),
+            playback,
+            library,
+            ids: Mutex::default(),
+            playlist_version: AtomicU32::new(1),
+            changes,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Subsystem> {
+        self.changes.subscribe()
+    }
+
+    pub fn notify(&self, subsystem: Subsystem) {
+        if subsystem == Subsystem::Playlist {
+            self.playlist_version.fetch_add(1, Ordering::Relaxed);
+        }
+        let _ = self.changes.send(subsystem);
+    }
+
+    /// Notifies the subsystems that changed since the last refresh.
+    pub fn refresh(&self) {
+        let Ok(snapshot) = self.playback.status() else {
+            return;
+        };
+        let Some(last) = self
+            .last
+            .lock()
+            .ok()
+            .and_then(|mut last| last.replace(snapshot.clone()))
+        else {
+            return;
+        };
+        if last.track.as_ref().map(|t| &t.id) != snapshot.track.as_ref().map(|t| &t.id) {
+            // The current song is part of the playlist
+            self.notify(Subsystem::Playlist);
+            self.notify(Subsystem::Player);
+        } else if last.state != snapshot.state {
+            self.notify(Subsystem::Player);
+        }
+        if (last.volume, last.muted) != (snapshot.volume, snapshot.muted) {
+            self.notify(Subsystem::Mixer);
+        }
+        if (last.repeat, last.shuffle) != (snapshot.repeat, snapshot.shuffle) {
+            self.notify(Subsystem::Options);
+        }
+    }
+
+    pub async fn execute(&self, request: &Request) -> Result<Response, Ack> {
+        let mut response = Response::new();
+        match request.command.as_str() {
+            "ping" | "clearerror" | "password" => {}
+            "commands" => {
+                for command in COMMANDS {
+                    response.field("command", command);
+                }
+            }
+            "notcommands" | "urlhandlers" | "decoders" | "listplaylists" => {}
+            "tagtypes" => {
+                // Enabling or disabling tags is accepted, all of them are always sent
+                if request.args.is_empty() {
+                    for tag in Tag::SUPPORTED {
+                        response.field("tagtype", tag.name());
+                    }
+                }
+            }
+            "outputs" => {
+                response
+                    .field("outputid", 0)
+                    .field("outputname", "muz")
+                    .field("plugin", "muz")
+                    .field("outputenabled", 1);
+            }
+            "replay_gain_status" => {
+                response.field("replay_gain_mode", "off");
+            }
+            "status" => self.status(&mut response)?,
+            "stats" => self.stats(&mut response).await,
+            "currentsong" => {
+                if let Some(track) = self.playback.status()?.track {
+                    let root = self.root().await;
+                    self.song(&mut response, &root, &track);
+                    let id = self.id(&track);
+                    response.field("Pos", 0).field("Id", id);
+                }
+            }
+
+            "play" => match request.optional_arg::<usize>(0)? {
+                Some(position) => self.play_at(position)?,
+                None => self.control(PlaybackCommand::Play)?,
+            },
+            "playid" => match request.optional_arg::<u32>(0)? {
+                Some(id) => self.play_at(self.position_of(id)?)?,
+                None => self.control(PlaybackCommand::Play)?,
+            },
+            "pause" => match request.args.is_empty() {
+                true => self.control(PlaybackCommand::TogglePlayPause)?,
+                false if request.flag(0)? => self.control(PlaybackCommand::Pause)?,
+                false => self.control(PlaybackCommand::Play)?,
+            },
+            "stop" => self.control(PlaybackCommand::Stop)?,
+            "next" => self.control(PlaybackCommand::Next)?,
+            "previous" => self.control(PlaybackCommand::Previous)?,
+            "seek" => self.seek_at(request.parsed_arg(0)?, request.arg(1)?)?,
+            "seekid" => {
+                let position = self.position_of(request.parsed_arg(0)?)?;
+                self.seek_at(position, request.arg(1)?)?;
+            }
+            "seekcur" => {
+                let time = request.arg(0)?;
+                let command = match time.strip_prefix('+') {
+                    Some(delta) => PlaybackCommand::SeekRelative {
+                        delta_ms: Self::ms(delta)? as i64,
+                    },
+                    None => match time.strip_prefix('-') {
+                        Some(delta) => PlaybackCommand::SeekRelative {
+                            delta_ms: -(Self::ms(delta)? as i64),
+                        },
+                        None => PlaybackCommand::Seek {
+                            ms: Self::ms(time)?,
+                        },
+                    },
+                };
+                self.control(command)?;
+                self.notify(Subsystem::Player);
+            }
+
+            "setvol" => self.set_volume(request.parsed_arg(0)?)?,
+            "volume" => {
+                let status = self.playback.status()?;
+                let current = (status.volume * 100.0).round() as i32;
+                self.set_volume(current + request.parsed_arg::<i32>(0)?)?;
+            }
+            "getvol" => {
+                response.field("volume", Self::volume(&self.playback.status()?));
+            }
+            "repeat" => {
+                let status = self.playback.status()?;
+                let mode = match (request.flag(0)?, status.repeat) {
+                    (false, _) => RepeatMode::Off,
+                    (true, RepeatMode::One) => RepeatMode::One,
+                    (true, _) => RepeatMode::All,
+                };
+                self.control(PlaybackCommand::SetRepeat { mode })?;
+            }
+            "single" => {
+                let mode = match request.arg(0)? {
+                    "1" => RepeatMode::One,
+                    "0" if self.playback.status()?.repeat == RepeatMode::One => RepeatMode::All,
+                    "0" => self.playback.status()?.repeat,
+                    other => return Err(Ack::arg(format!("Boolean (0/1) expected: {other}"))),
+                };
+                self.control(PlaybackCommand::SetRepeat { mode })?;
+            }
+            "random" => self.control(PlaybackCommand::SetShuffle {
+                shuffle: request.flag(0)?,
+            })?,
+            // Played songs always leave the queue
+            "consume" => {
+                if !request.flag(0)? {
+                    return Err(Ack::arg("Only consume mode is supported"));
+                }
+            }
+
+            "playlistinfo" => {
+                let playlist = self.playlist()?;
+                let (start, end) = match request.args.is_empty() {
+                    true => (0, None),
+                    false => request.range(0)?,
+                };
+                if start >= playlist.len() && !(start == 0 && request.args.is_empty()) {
+                    return Err(Ack::arg("Bad song index"));
+                }
+                let end = end.unwrap_or(playlist.len()).min(playlist.len());
+                let root = self.root().await;
+                for (position, track) in playlist.iter().enumerate().take(end).skip(start) {
+                    self.playlist_song(&mut response, &root, track, position);
+                }
+            }
+            "playlistid" => {
+                let playlist = self.playlist()?;
+                let root = self.root().await;
+                match request.optional_arg::<u32>(0)? {
+                    Some(id) => {
+                        let position = self.position_of(id)?;
+                        self.playlist_song(&mut response, &root, &playlist[position], position);
+                    }
+                    None => {
+                        for (position, track) in playlist.iter().enumerate() {
+                            self.playlist_song(&mut response, &root, track, position);
+                        }
+                    }
+                }
+            }
+            // Changes aren't tracked per version, clients reload the whole playlist
+            "plchanges" => {
+                let root = self.root().await;
+                for (position, track) in self.playlist()?.iter().enumerate() {
+                    self.playlist_song(&mut response, &root, track, position);
+                }
+            }
+            "plchangesposid" => {
+                for (position, track) in self.playlist()?.iter().enumerate() {
+                    response.field("cpos", position).field("Id", self.id(track));
+                }
+            }
+            "add" => {
+                let tracks = self.tracks_under(request.arg(0)?).await?;
+                self.playback.enqueue(tracks)?;
+                self.notify(Subsystem::Playlist);
+            }
+            "addid" => {
+                let uri = request.arg(0)?;
+                let root = self.root().await;
+                let track = self
+                    .library
+                    .tracks()
+                    .await
+                    .into_iter()
+                    .find(|t| uri_of(&root, t) == uri)
+                    .ok_or_else(|| Ack::no_exist("No such song"))?;
+                self.playback.enqueue(vec![track.clone()])?;
+                if let Some(to) = request.optional_arg::<usize>(1)? {
+                    let last = self.playlist()?.len() - 1;
+                    self.move_range(last, last + 1, to)?;
+                }
+                self.notify(Subsystem::Playlist);
+                response.field("Id", self.id(&track));
+            }
+            "delete" => {
+                let playlist = self.playlist()?;
+                let (start, end) = request.range(0)?;
+                let end = end.unwrap_or(playlist.len());
+                if start >= end || end > playlist.len() {
+                    return Err(Ack::arg("Bad song index"));
+                }
+                self.delete(&playlist[start..end], start == 0)?;
+            }
+            "deleteid" => {
+                let position = self.position_of(request.parsed_arg(0)?)?;
+                let playlist = self.playlist()?;
+                self.delete(&playlist[position..=position], position == 0)?;
+            }
+            "move" => {
+                let (start, end) = request.range(0)?;
+                let end = end.unwrap_or(self.playlist()?.len());
+                self.move_range(start, end, request.parsed_arg(1)?)?;
+            }
+            "moveid" => {
+                let position = self.position_of(request.parsed_arg(0)?)?;
+                self.move_range(position, position + 1, request.parsed_arg(1)?)?;
+            }
+            "clear" => {
+                self.playback.clear_queue()?;
+                self.control(PlaybackCommand::Stop)?;
+                self.notify(Subsystem::Playlist);
+            }
+
+            "list" => self.list(request, &mut response).await?,
+            "find" | "search" | "findadd" | "searchadd" | "count" => {
+                let search = request.command.starts_with("search");
+                let (filter_args, sort, window) = Self::search_options(&request.args)?;
+                let operator = if search {
+                    Operator::Contains
+                } else {
+                    Operator::Equals
+                };
+                let filter = Filter::parse(filter_args, operator)?;
+                let root = self.root().await;
+                let mut tracks: Vec<Track> = self
+                    .library
+                    .tracks()
+                    .await
+                    .into_iter()
+                    .filter(|t| filter.matches(t, &uri_of(&root, t), search))
+                    .collect();
+                match sort {
+                    Some(tag) => tracks.sort_by_key(|t| tag.value(t)),
+                    None => tracks.sort_by_key(|t| uri_of(&root, t)),
+                }
+                if let Some((start, end)) = window {
+                    let end = end.unwrap_or(tracks.len()).min(tracks.len());
+                    tracks = tracks.into_iter().take(end).skip(start).collect();
+                }
+                match request.command.as_str() {
+                    "count" => {
+                        let playtime: u64 = tracks.iter().map(|t| t.duration_ms / 1000).sum();
+                        response
+                            .field("songs", tracks.len())
+                            .field("playtime", playtime);
+                    }
+                    "findadd" | "searchadd" => {
+                        self.playback.enqueue(tracks)?;
+                        self.notify(Subsystem::Playlist);
+                    }
+                    _ => {
+                        for track in &tracks {
+                            self.song(&mut response, &root, track);
+                        }
+                    }
+                }
+            }
+            "lsinfo" | "listall" | "listallinfo" => {
+                let prefix = request.args.first().map_or("", |uri| uri.trim_matches('/'));
+                let root = self.root().await;
+                let mut tracks = self.tracks_in(&root, prefix).await;
+                tracks.sort_by_key(|t| uri_of(&root, t));
+                if tracks.is_empty() && !prefix.is_empty() {
+                    return Err(Ack::no_exist("No such directory"));
+                }
+                if request.command == "lsinfo" {
+                    let mut directories = BTreeSet::new();
+                    let mut songs = Vec::new();
+                    for track in &tracks {
+                        let uri = uri_of(&root, track);
+                        let relative = uri[prefix.len()..].trim_start_matches('/');
+                        match relative.split_once('/') {
+                            Some((directory, _)) if prefix.is_empty() => {
+                                directories.insert(directory.to_string());
+                            }
+                            Some((directory, _)) => {
+                                directories.insert(format!("{prefix}/{directory}"));
+                            }
+                            None => songs.push(track),
+                        }
+                    }
+                    for directory in directories {
+                        response.field("directory", directory);
+                    }
+                    for track in songs {
+                        self.song(&mut response, &root, track);
+                    }
+                } else {
+                    for track in &tracks {
+                        if request.command == "listall" {
+                            response.field("file", uri_of(&root, track));
+                        } else {
+                            self.song(&mut response, &root, track);
+                        }
+                    }
+                }
+            }
+            "update" | "rescan" => {
+                self.library.rescan_library().await?;
+                self.notify(Subsystem::Database);
+                response.field("updating_db", 1);
+            }
+            other => {
+                return Err(Ack::new(
+                    AckCode::Unknown,
+                    format!("unknown command \"{other}\""),
+                ))
+            }
+        }
+        Ok(response)
+    }
+
+    fn control(&self, command: PlaybackCommand) -> Result<(), Ack> {
+        self.playback.control_playback(command)?;
+        self.refresh();
+        Ok(())
+    }
+
+    fn id(&self, track: &Track) -> u32 {
+        self.ids.lock().map(|mut ids| ids.id(track)).unwrap_or(0)
+    }
+
+    async fn root(&self) -> PathBuf {
+        PathBuf::from(self.library.library_path().await.unwrap_or_default())
+    }
+
+    /// The current song followed by the queue.
+    fn playlist(&self) -> Result<Vec<Track>, Ack> {
+        Ok(self.playback.tracks()?)
+    }
+
+    fn has_current(&self) -> Result<bool, Ack> {
+        Ok(self.playback.status()?.track.is_some())
+    }
+
+    fn position_of(&self, id: u32) -> Result<usize, Ack> {
+        self.playlist()?
+            .iter()
+            .position(|t| self.id(t) == id)
+            .ok_or_else(|| Ack::no_exist("No such song"))
+    }
+
+    /// Seconds, possibly fractional, as milliseconds.
+    fn ms(seconds: &str) -> Result<u64, Ack> {
+        seconds
+            .parse::<f64>()
+            .ok()
+            .filter(|s| *s >= 0.0)
+            .map(|s| (s * 1000.0) as u64)
+            .ok_or_else(|| Ack::arg(format!("Invalid time: {seconds}")))
+    }
+
+    fn volume(status: &PlaybackSnapshot) -> u32 {
+        if status.muted {
+            0
+        } else {
+            (status.volume * 100.0).round() as u32
+        }
+    }
+
+    fn set_volume(&self, volume: i32) -> Result<(), Ack> {
+        let volume = Volume::try_from(volume.clamp(0, 100) as f32 / 100.0).map_err(Ack::arg)?;
+        self.control(PlaybackCommand::SetVolume { volume })?;
+        self.control(PlaybackCommand::SetMuted { muted: false })?;
+        Ok(())
+    }
+
+    /// Plays the song at a playlist position, restarting it if it is the current one.
+    fn play_at(&self, position: usize) -> Result<(), Ack> {
+        let playlist = self.playlist()?;
+        let track = playlist
+            .get(position)
+            .ok_or_else(|| Ack::arg("Bad song index"))?;
+        if position == 0 && self.has_current()? {
+            self.control(PlaybackCommand::Seek { ms: 0 })?;
+            self.control(PlaybackCommand::Play)?;
+        } else {
+            self.playback.select_from_queue(&track.id)?;
+            self.refresh();
+        }
+        Ok(())
+    }
+
+    fn seek_at(&self, position: usize, time: &str) -> Result<(), Ack> {
+        let ms = Self::ms(time)?;
+        if !(position == 0 && self.has_current()?) {
+            self.play_at(position)?;
+        }
+        self.control(PlaybackCommand::Seek { ms })?;
+        self.notify(Subsystem::Player);
+        Ok(())
+    }
+
+    fn delete(&self, tracks: &[Track], includes_current: bool) -> Result<(), Ack> {
+        let has_current = self.has_current()?;
+        let queued = if includes_current && has_current {
+            &tracks[1..]
+        } else {
+            tracks
+        };
+        for track in queued {
+            self.playback.remove_from_queue(&track.id)?;
+        }
+        if includes_current && has_current {
+            match self.playback.queue()?.is_empty() {
+                true => self.control(PlaybackCommand::Stop)?,
+                false => self.control(PlaybackCommand::Next)?,
+            };
+        }
+        self.notify(Subsystem::Playlist);
+        Ok(())
+    }
+
+    /// Moves the songs in `start..end` so the first one ends up at `to`.
+    fn move_range(&self, start: usize, end: usize, to: usize) -> Result<(), Ack> {
+        let length = self.playlist()?.len();
+        let count = end.saturating_sub(start);
+        if count == 0 || end > length || to + count > length {
+            return Err(Ack::arg("Bad song index"));
+        }
+        // Queue indices are offset by the current song, which stays in place
+        let offset = usize::from(self.has_current()?);
+        if offset == 1 && (start == 0 || to == 0) {
+            return Err(Ack::arg("The current song can't be moved"));
+        }
+        let (start, to) = (start - offset, to - offset);
+        if to > start {
+            for _ in 0..count {
+                self.playback.reorder_queue(start, to + count - 1)?;
+            }
+        } else {
+            for i in 0..count {
+                self.playback.reorder_queue(start + i, to + i)?;
+            }
+        }
+        self.notify(Subsystem::Playlist);
+        Ok(())
+    }
+
+    /// Library tracks at `uri` or in the directory it names, the whole library for `/`.
+    async fn tracks_under(&self, uri: &str) -> Result<Vec<Track>, Ack> {
+        let root = self.root().await;
+        let prefix = uri.trim_matches('/');
+        let mut tracks = self.tracks_in(&root, prefix).await;
+        if tracks.is_empty() {
+            return Err(Ack::no_exist("No such directory"));
+        }
+        tracks.sort_by_key(|t| uri_of(&root, t));
+        Ok(tracks)
+    }
+
+    async fn tracks_in(&self, root: &Path, prefix: &str) -> Vec<Track> {
+        self.library
+            .tracks()
+            .await
+            .into_iter()
+            .filter(|t| {
+                let uri = uri_of(root, t);
+                prefix.is_empty()
+                    || uri == prefix
+                    || uri
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .collect()
+    }
+
+    /// Splits the trailing `sort TAG` and `window START:END` options from a filter.
+    #[allow(clippy::type_complexity)]
+    fn search_options(
+        args: &[String],
+    ) -> Result<(&[String], Option<Tag>, Option<(usize, Option<usize>)>), Ack> {
+        let mut args = args;
+        let mut sort = None;
+        let mut window = None;
+        while let [rest @ .., option, value] = args {
+            match option.as_str() {
+                "sort" => {
+                    let name = value.trim_start_matches('-');
+                    sort = Some(
+                        Tag::parse(name)
+                            .ok_or_else(|| Ack::arg(format!("Unknown sort tag: {name}")))?,
+                    );
+                }
+                "window" => {
+                    let request = Request {
+                        command: String::new(),
+                        args: vec![value.clone()],
+                    };
+                    window = Some(request.range(0)?);
+                }
+                _ => break,
+            }
+            args = rest;
+        }
+        Ok((args, sort, window))
+    }
+
+    /// `list TAG [FILTER] [group TAG]...`
+    async fn list(&self, request: &Request, response: &mut Response) -> Result<(), Ack> {
+        let tag = Tag::parse(request.arg(0)?)
+            .filter(|tag| *tag != Tag::Any)
+            .ok_or_else(|| Ack::arg(format!("Unknown tag type: {}", request.args[0])))?;
+        let mut args = &request.args[1..];
+        let mut groups = Vec::new();
+        while let [rest @ .., group, name] = args {
+            if group != "group" {
+                break;
+            }
+            groups.insert(
+                0,
+                Tag::parse(name).ok_or_else(|| Ack::arg(format!("Unknown tag type: {name}")))?,
+            );
+            args = rest;
+        }
+        // `list album ARTIST` from older clients
+        let filter = match args {
+            [artist] if tag == Tag::Album && !artist.starts_with('(') => Filter::Tag {
+                tag: Tag::Artist,
+                operator: Operator::Equals,
+                value: artist.clone(),
+            },
+            args => Filter::parse(args, Operator::Equals)?,
+        };
+
+        let root = self.root().await;
+        let mut rows = BTreeSet::new();
+        for track in self.library.tracks().await {
+            let uri = uri_of(&root, &track);
+            if !filter.matches(&track, &uri, false) {
+                continue;
+            }
+            let value = match tag {
+                Tag::File => Some(uri),
+                tag => tag.value(&track),
+            };
+            let Some(value) = value else {
+                continue;
+            };
+            let keys: Vec<String> = groups
+                .iter()
+                .map(|g| g.value(&track).unwrap_or_default())
+                .collect();
+            rows.insert((keys, value));
+        }
+
+        let mut last_keys: Option<Vec<String>> = None;
+        for (keys, value) in rows {
+            for (index, (group, key)) in groups.iter().zip(&keys).enumerate() {
+                if last_keys
+                    .as_ref()
+                    .is_none_or(|last| last[..=index] != keys[..=index])
+                {
+                    response.field(group.name(), key);
+                }
+            }
+            response.field(tag.name(), value);
+            last_keys = Some(keys);
+        }
+        Ok(())
+    }
+
+    fn status(&self, response: &mut Response) -> Result<(), Ack> {
+        let status = self.playback.status()?;
+        let playlist = self.playlist()?;
+        response
+            .field("volume", Self::volume(&status))
+            .field("repeat", u8::from(status.repeat != RepeatMode::Off))
+            .field("random", u8::from(status.shuffle))
+            .field("single", u8::from(status.repeat == RepeatMode::One))
+            .field("consume", 1)
+            .field("playlist", self.playlist_version.load(Ordering::Relaxed))
+            .field("playlistlength", playlist.len())
+            .field(
+                "state",
+                match status.state {
+                    PlaybackState::Playing => "play",
+                    PlaybackState::Paused => "pause",
+                    PlaybackState::Stopped => "stop",
+                },
+            );
+        if let Some(track) = &status.track {
+            let elapsed = status.position_ms as f64 / 1000.0;
+            let duration = track.duration_ms as f64 / 1000.0;
+            response
+                .field("song", 0)
+                .field("songid", self.id(track))
+                .field(
+                    "time",
+                    format!("{}:{}", elapsed as u64, track.duration_ms / 1000),
+                )
+                .field("elapsed", format!("{elapsed:.3}"))
+                .field("duration", format!("{duration:.3}"));
+        }
+        let next = usize::from(status.track.is_some());
+        if let Some(track) = playlist.get(next) {
+            response
+                .field("nextsong", next)
+                .field("nextsongid", self.id(track));
+        }
+        Ok(())
+    }
+
+    async fn stats(&self, response: &mut Response) {
+        let tracks = self.library.tracks().await;
+        let distinct = |tag: Tag| {
+            tracks
+                .iter()
+                .filter_map(|t| tag.value(t))
+                .collect::<BTreeSet<_>>()
+                .len()
+        };
+        let db_playtime: u64 = tracks.iter().map(|t| t.duration_ms / 1000).sum();
+        response
+            .field("artists", distinct(Tag::Artist))
+            .field("albums", distinct(Tag::Album))
+            .field("songs", tracks.len())
+            .field("uptime", self.started.elapsed().as_secs())
+            .field("db_playtime", db_playtime);
+    }
+
+    fn song(&self, response: &mut Response, root: &Path, track: &Track) {
+        response.field("file", uri_of(root, track));
+        for tag in Tag::SUPPORTED {
+            if let Some(value) = tag.value(track) {
+                response.field(tag.name(), value);
+            }
+        }
+        response.field("Time", track.duration_ms / 1000).field(
+            "duration",
+            format!("{:.3}", track.duration_ms as f64 / 1000.0),
+        );
+    }
+
+    fn playlist_song(&self, response: &mut Response, root: &Path, track: &Track, position: usize) {
+        self.song(response, root, track);
+        response.field("Pos", position).field("Id", self.id(track));
+    }
+}
+
+/// The song URI: the path relative to the library, with cue sheet entries as
+/// `file.flac/track0001` like MPD names them.
+pub fn uri_of(root: &Path, track: &Track) -> String {
+    let path = track.path.strip_prefix(root).unwrap_or(&track.path);
+    let uri = path.to_string_lossy().replace('\\', "/");
+    match &track.segment {
+        Some(_) => {
+            let number = track
+                .metadata
+                .as_ref()
+                .and_then(|m| m.track_number)
+                .unwrap_or(0);
+            format!("{uri}/track{number:04}")
+        }
+        None => uri,
+    }
+}
@@ -0,0 +1,268 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::mpd::protocol::Ack;
+use muz_core::track::Track;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tag {
+    Artist,
+    AlbumArtist,
+    Album,
+    Title,
+    Track,
+    Disc,
+    Genre,
+    Date,
+    /// The song URI
+    File,
+    /// Any tag or the URI
+    Any,
+}
+
+impl Tag {
+    /// Reported by `tagtypes`
+    pub const SUPPORTED: [Tag; 8] = [
+        Tag::Artist,
+        Tag::AlbumArtist,
+        Tag::Album,
+        Tag::Title,
+        Tag::Track,
+        Tag::Disc,
+        Tag::Genre,
+        Tag::Date,
+    ];
+
+    /// Tag names are case insensitive.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::SUPPORTED
+            .into_iter()
+            .chain([Tag::File, Tag::Any])
+            .find(|tag| tag.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tag::Artist => "Artist",
+            Tag::AlbumArtist => "AlbumArtist",
+            Tag::Album => "Album",
+            Tag::Title => "Title",
+            Tag::Track => "Track",
+            Tag::Disc => "Disc",
+            Tag::Genre => "Genre",
+            Tag::Date => "Date",
+            Tag::File => "file",
+            Tag::Any => "any",
+        }
+    }
+
+    pub fn value(self, track: &Track) -> Option<String> {
+        let metadata = track.metadata.as_ref();
+        match self {
+            Tag::Artist => metadata?.artist.clone(),
+            Tag::AlbumArtist => metadata?.album_artist.clone(),
+            Tag::Album => metadata?.album.clone(),
+            Tag::Title => Some(
+                metadata
+                    .and_then(|m| m.title.clone())
+                    .unwrap_or_else(|| Track::default_title(&track.path)),
+            ),
+            Tag::Track => metadata?.track_number.map(|n| n.to_string()),
+            Tag::Disc => metadata?.disc_number.map(|n| n.to_string()),
+            Tag::Genre => metadata?.genre.clone(),
+            Tag::Date => metadata?.year.clone(),
+            Tag::File | Tag::Any => None,
+        }
+    }
+
+    fn values(self, track: &Track, uri: &str) -> Vec<String> {
+        match self {
+            Tag::File => vec![uri.to_string()],
+            Tag::Any => Self::SUPPORTED
+                .iter()
+                .filter_map(|tag| tag.value(track))
+                .chain([uri.to_string()])
+                .collect(),
+            tag => tag.value(track).into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equals,
+    NotEquals,
+    Contains,
+    StartsWith,
+}
+
+/// Song filter of `find`, `search` and `list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Tag {
+        tag: Tag,
+        operator: Operator,
+        value: String,
+    },
+    Not(Box<Filter>),
+    And(Vec<Filter>),
+}
+
+impl Filter {
+    /// Parses either an expression like `((artist == 'X') AND (album == 'Y'))` or the
+    /// older `TAG VALUE` pairs, which compare with `pair_operator`.
+    pub fn parse(args: &[String], pair_operator: Operator) -> Result<Self, Ack> {
+        match args {
+            [expression] if expression.starts_with('(') => {
+                let mut parser = Parser {
+                    chars: expression.chars().peekable(),
+                };
+                let filter = parser.expression()?;
+                parser.skip_whitespace();
+                match parser.chars.next() {
+                    None => Ok(filter),
+                    Some(c) => Err(Ack::arg(format!("Unparsed garbage after expression: {c}"))),
+                }
+            }
+            pairs if pairs.len() % 2 == 0 => pairs
+                .chunks(2)
+                .map(|pair| {
+                    Ok(Filter::Tag {
+                        tag: Self::tag(&pair[0])?,
+                        operator: pair_operator,
+                        value: pair[1].clone(),
+                    })
+                })
+                .collect::<Result<_, Ack>>()
+                .map(Filter::And),
+            _ => Err(Ack::arg("Incorrect number of filter arguments")),
+        }
+    }
+
+    fn tag(name: &str) -> Result<Tag, Ack> {
+        Tag::parse(name).ok_or_else(|| Ack::arg(format!("Unknown filter type: {name}")))
+    }
+
+    pub fn matches(&self, track: &Track, uri: &str, ignore_case: bool) -> bool {
+        match self {
+            Filter::Tag {
+                tag,
+                operator,
+                value,
+            } => {
+                let fold = |s: &str| {
+                    if ignore_case {
+                        s.to_lowercase()
+                    } else {
+                        s.to_string()
+                    }
+                };
+                let value = fold(value);
+                let mut values = tag.values(track, uri).into_iter().map(|v| fold(&v));
+                match operator {
+                    Operator::Equals => values.any(|v| v == value),
+                    Operator::NotEquals => !values.any(|v| v == value),
+                    Operator::Contains => values.any(|v| v.contains(&value)),
+                    Operator::StartsWith => values.any(|v| v.starts_with(&value)),
+                }
+            }
+            Filter::Not(filter) => !filter.matches(track, uri, ignore_case),
+            Filter::And(filters) => filters.iter().all(|f| f.matches(track, uri, ignore_case)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn expression(&mut self) -> Result<Filter, Ack> {
+        self.skip_whitespace();
+        self.expect('(')?;
+        self.skip_whitespace();
+        let filter = match self.chars.peek() {
+            Some('!') => {
+                self.chars.next();
+                Filter::Not(Box::new(self.expression()?))
+            }
+            Some('(') => {
+                let mut filters = vec![self.expression()?];
+                loop {
+                    self.skip_whitespace();
+                    if self.chars.peek() == Some(&')') {
+                        break;
+                    }
+                    match self.word().as_str() {
+                        "AND" => filters.push(self.expression()?),
+                        other => return Err(Ack::arg(format!("Expected AND, got {other}"))),
+                    }
+                }
+                Filter::And(filters)
+            }
+            _ => {
+                let tag = Filter::tag(&self.word())?;
+                self.skip_whitespace();
+                let operator = match self.word().as_str() {
+                    "==" => Operator::Equals,
+                    "!=" => Operator::NotEquals,
+                    "contains" => Operator::Contains,
+                    "starts_with" => Operator::StartsWith,
+                    other => return Err(Ack::arg(format!("Unknown filter operator: {other}"))),
+                };
+                self.skip_whitespace();
+                Filter::Tag {
+                    tag,
+                    operator,
+                    value: self.quoted()?,
+                }
+            }
+        };
+        self.skip_whitespace();
+        self.expect(')')?;
+        Ok(filter)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Ack> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(Ack::arg(format!("'{expected}' expected"))),
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| !c.is_whitespace() && *c != '(' && *c != ')')
+        {
+            word.push(c);
+        }
+        word
+    }
+
+    /// A value in single or double quotes, with backslash escapes.
+    fn quoted(&mut self) -> Result<String, Ack> {
+        let quote = match self.chars.next() {
+            Some(quote @ ('\'' | '"')) => quote,
+            _ => return Err(Ack::arg("Quoted string expected")),
+        };
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') => value.extend(self.chars.next()),
+                Some(c) => value.push(c),
+                None => return Err(Ack::arg("Closing quote not found")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./filter.tests.rs"]
+mod tests;
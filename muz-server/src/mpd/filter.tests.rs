@@ -0,0 +1,84 @@
+use super::*;
+use muz_core::track::TrackMetadata;
+
+fn track() -> Track {
+    let mut track = Track::new("/music/Artist/Album/01 Song.flac");
+    track.metadata = Some(TrackMetadata {
+        title: Some("Song".to_string()),
+        album: Some("Album".to_string()),
+        artist: Some("Artist".to_string()),
+        album_artist: None,
+        track_number: Some(1),
+        disc_number: None,
+        genre: Some("Rock".to_string()),
+        year: Some("1999".to_string()),
+    });
+    track
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn test_legacy_pairs() {
+    let filter = Filter::parse(
+        &args(&["artist", "Artist", "ALBUM", "Album"]),
+        Operator::Equals,
+    )
+    .unwrap();
+    assert!(filter.matches(&track(), "Artist/Album/01 Song.flac", false));
+
+    let filter = Filter::parse(&args(&["any", "rock"]), Operator::Contains).unwrap();
+    assert!(filter.matches(&track(), "", true));
+    assert!(!filter.matches(&track(), "", false));
+
+    assert!(Filter::parse(&args(&["artist"]), Operator::Equals).is_err());
+    assert!(Filter::parse(&args(&["mood", "x"]), Operator::Equals).is_err());
+}
+
+#[test]
+fn test_expressions() {
+    let filter = Filter::parse(
+        &args(&["((artist == 'Artist') AND (!(title starts_with \"Intro\")))"]),
+        Operator::Equals,
+    )
+    .unwrap();
+    assert_eq!(
+        filter,
+        Filter::And(vec![
+            Filter::Tag {
+                tag: Tag::Artist,
+                operator: Operator::Equals,
+                value: "Artist".to_string(),
+            },
+            Filter::Not(Box::new(Filter::Tag {
+                tag: Tag::Title,
+                operator: Operator::StartsWith,
+                value: "Intro".to_string(),
+            })),
+        ])
+    );
+    assert!(filter.matches(&track(), "", false));
+
+    let filter = Filter::parse(&args(&["(file contains 'Album/')"]), Operator::Equals).unwrap();
+    assert!(filter.matches(&track(), "Artist/Album/01 Song.flac", false));
+    let filter = Filter::parse(&args(&["(AlbumArtist != 'Artist')"]), Operator::Equals).unwrap();
+    assert!(filter.matches(&track(), "", false));
+}
+
+#[test]
+fn test_malformed_expressions() {
+    for expression in [
+        "(artist == Artist)",
+        "(artist =~ 'Artist')",
+        "((artist == 'A') OR (album == 'B'))",
+        "(artist == 'A') trailing",
+        "(artist == 'A'",
+    ] {
+        assert!(
+            Filter::parse(&args(&[expression]), Operator::Equals).is_err(),
+            "{expression}"
+        );
+    }
+}
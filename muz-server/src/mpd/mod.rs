@@ -0,0 +1,68 @@
+use anyhow::Result;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::mpd::commands::Mpd;
+use crate::mpd::protocol::Subsystem;
+use crate::mpd::session::Session;
+use muz_core::bus::BusEvent;
+use muz_core::events::PlayerEvent;
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+
+pub mod commands;
+pub mod filter;
+pub mod protocol;
+mod session;
+
+/// Accepts MPD clients on `address`, returning the address actually bound.
+pub async fn serve(
+    address: &str,
+    playback: PlaybackService,
+    library: LibraryService,
+    mut events: broadcast::Receiver<BusEvent>,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(address).await?;
+    let local_address = listener.local_addr()?;
+    let mpd = Arc::new(Mpd::new(playback, library));
+
+    let watcher = mpd.clone();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(BusEvent { event, .. }) => match event {
+                    PlayerEvent::QueueChanged(_) => watcher.notify(Subsystem::Playlist),
+                    // Progress alone isn't a change clients are told about
+                    PlayerEvent::TrackChanged(_) | PlayerEvent::StateChanged(_) => {
+                        watcher.refresh()
+                    }
+                    _ => {}
+                },
+                // The state is read afresh, whatever was missed
+                Err(RecvError::Lagged(_)) => {
+                    watcher.notify(Subsystem::Playlist);
+                    watcher.refresh();
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    let session = Session::new(mpd.clone(), stream);
+                    tokio::spawn(async move {
+                        if let Err(e) = session.run().await {
+                            tracing::debug!("MPD client {peer} disconnected: {e}");
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept an MPD client: {e}"),
+            }
+        }
+    });
+    Ok(local_address)
+}
@@ -0,0 +1,235 @@
+use std::fmt::{self, Display, Write};
+use thiserror::Error;
+
+/// Sent to each client when it connects.
+pub const GREETING: &str = "OK MPD 0.23.5\n";
+
+/// Error codes from MPD's `ack.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckCode {
+    Arg = 2,
+    Unknown = 5,
+    NoExist = 50,
+    System = 52,
+}
+
+/// A failed command, sent as `ACK [code@index] {command} message`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("ACK [{}@{index}] {{{command}}} {message}", *code as u8)]
+pub struct Ack {
+    pub code: AckCode,
+    /// Position of the failed command in a command list
+    pub index: usize,
+    pub command: String,
+    pub message: String,
+}
+
+impl Ack {
+    pub fn new(code: AckCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            index: 0,
+            command: String::new(),
+            message: message.into(),
+        }
+    }
+
+    pub fn arg(message: impl Into<String>) -> Self {
+        Self::new(AckCode::Arg, message)
+    }
+
+    pub fn no_exist(message: impl Into<String>) -> Self {
+        Self::new(AckCode::NoExist, message)
+    }
+
+    /// Attributes the error to a command of the request.
+    pub fn at(mut self, index: usize, command: &str) -> Self {
+        self.index = index;
+        self.command = command.to_string();
+        self
+    }
+}
+
+impl From<anyhow::Error> for Ack {
+    fn from(error: anyhow::Error) -> Self {
+        Self::new(AckCode::System, format!("{error:#}"))
+    }
+}
+
+/// Parts of the player reported by `idle` when they change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    Database,
+    Playlist,
+    Player,
+    Mixer,
+    Options,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 5] = [
+        Subsystem::Database,
+        Subsystem::Playlist,
+        Subsystem::Player,
+        Subsystem::Mixer,
+        Subsystem::Options,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Subsystem::Database => "database",
+            Subsystem::Playlist => "playlist",
+            Subsystem::Player => "player",
+            Subsystem::Mixer => "mixer",
+            Subsystem::Options => "options",
+        }
+    }
+}
+
+/// One request line: a command name and its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl Request {
+    /// Splits a line on whitespace, arguments may be double quoted with `\"` and `\\`
+    /// escapes.
+    pub fn parse(line: &str) -> Result<Self, Ack> {
+        let mut words = Vec::new();
+        let mut chars = line.trim().chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+            let mut word = String::new();
+            if c == '"' {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => word.push(escaped),
+                            None => return Err(Ack::arg("Missing closing '\"'")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(Ack::arg("Missing closing '\"'")),
+                    }
+                }
+            } else {
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+            }
+            words.push(word);
+        }
+
+        let mut words = words.into_iter();
+        let command = words
+            .next()
+            .ok_or_else(|| Ack::new(AckCode::Unknown, "No command given"))?;
+        Ok(Self {
+            command,
+            args: words.collect(),
+        })
+    }
+
+    pub fn arg(&self, index: usize) -> Result<&str, Ack> {
+        self.args
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| Ack::arg("too few arguments"))
+    }
+
+    pub fn parsed_arg<T: std::str::FromStr>(&self, index: usize) -> Result<T, Ack> {
+        let arg = self.arg(index)?;
+        arg.parse()
+            .map_err(|_| Ack::arg(format!("Invalid argument: {arg}")))
+    }
+
+    pub fn optional_arg<T: std::str::FromStr>(&self, index: usize) -> Result<Option<T>, Ack> {
+        if index < self.args.len() {
+            self.parsed_arg(index).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// A boolean given as `0` or `1`.
+    pub fn flag(&self, index: usize) -> Result<bool, Ack> {
+        match self.arg(index)? {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            other => Err(Ack::arg(format!("Boolean (0/1) expected: {other}"))),
+        }
+    }
+
+    /// A position or a `start:end` range, open ended when `end` is missing.
+    pub fn range(&self, index: usize) -> Result<(usize, Option<usize>), Ack> {
+        let arg = self.arg(index)?;
+        let invalid = || Ack::arg(format!("Invalid range: {arg}"));
+        match arg.split_once(':') {
+            Some((start, "")) => Ok((start.parse().map_err(|_| invalid())?, None)),
+            Some((start, end)) => {
+                let start: usize = start.parse().map_err(|_| invalid())?;
+                let end: usize = end.parse().map_err(|_| invalid())?;
+                if end < start {
+                    return Err(invalid());
+                }
+                Ok((start, Some(end)))
+            }
+            None => {
+                let position: usize = arg.parse().map_err(|_| invalid())?;
+                Ok((position, Some(position + 1)))
+            }
+        }
+    }
+}
+
+/// The `key: value` lines answering a command, without the final `OK`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Response {
+    body: String,
+}
+
+impl Response {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(&mut self, key: &str, value: impl Display) -> &mut Self {
+        // Values can't span lines
+        let value = value.to_string().replace('\n', " ");
+        let _ = writeln!(self.body, "{key}: {value}");
+        self
+    }
+
+    pub fn append(&mut self, other: Response) {
+        self.body.push_str(&other.body);
+    }
+
+    /// A line that isn't a field, like `list_OK`.
+    pub fn append_line(&mut self, line: &str) {
+        let _ = writeln!(self.body, "{line}");
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.body)
+    }
+}
+
+#[cfg(test)]
+#[path = "./protocol.tests.rs"]
+mod tests;
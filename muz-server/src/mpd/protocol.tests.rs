@@ -0,0 +1,52 @@
+use super::*;
+
+#[test]
+fn test_parse_splits_words_and_quoted_arguments() {
+    let request = Request::parse("find  artist \"Sigur R\\\"os\" album \"a \\\\ b\"\n").unwrap();
+    assert_eq!(request.command, "find");
+    assert_eq!(
+        request.args,
+        vec!["artist", "Sigur R\"os", "album", "a \\ b"]
+    );
+}
+
+#[test]
+fn test_parse_rejects_unterminated_quotes_and_empty_lines() {
+    assert_eq!(Request::parse("add \"abc").unwrap_err().code, AckCode::Arg);
+    assert_eq!(Request::parse("   ").unwrap_err().code, AckCode::Unknown);
+}
+
+#[test]
+fn test_arguments() {
+    let request = Request::parse("cmd 3 1 x 2:5 7:").unwrap();
+    assert_eq!(request.parsed_arg::<u32>(0).unwrap(), 3);
+    assert!(request.flag(1).unwrap());
+    assert!(request.flag(2).is_err());
+    assert_eq!(request.range(0).unwrap(), (3, Some(4)));
+    assert_eq!(request.range(3).unwrap(), (2, Some(5)));
+    assert_eq!(request.range(4).unwrap(), (7, None));
+    assert_eq!(request.optional_arg::<u32>(9).unwrap(), None);
+    assert!(request.arg(9).is_err());
+}
+
+#[test]
+fn test_ack_format() {
+    let ack = Ack::no_exist("No such song").at(2, "playid");
+    assert_eq!(ack.to_string(), "ACK [50@2] {playid} No such song");
+}
+
+#[test]
+fn test_response_fields_stay_on_one_line() {
+    let mut response = Response::new();
+    response.field("Title", "two\nlines").field("Pos", 1);
+    response.append_line("list_OK");
+    assert_eq!(response.to_string(), "Title: two lines\nPos: 1\nlist_OK\n");
+}
+
+#[test]
+fn test_subsystem_names() {
+    for subsystem in Subsystem::ALL {
+        assert_eq!(Subsystem::parse(subsystem.name()), Some(subsystem));
+    }
+    assert_eq!(Subsystem::parse("sticker"), None);
+}
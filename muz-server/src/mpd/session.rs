@@ -0,0 +1,173 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::mpd::commands::Mpd;
+use crate::mpd::protocol::{Ack, Request, Response, Subsystem, GREETING};
+
+/// A client connection, from the greeting until `close` or the client disconnects.
+pub struct Session {
+    mpd: Arc<Mpd>,
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+    changes: broadcast::Receiver<Subsystem>,
+    /// Changes not reported by `idle` yet
+    pending: Vec<Subsystem>,
+}
+
+impl Session {
+    pub fn new(mpd: Arc<Mpd>, stream: TcpStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        Self {
+            changes: mpd.subscribe(),
+            mpd,
+            lines: BufReader::new(reader).lines(),
+            writer,
+            pending: Vec::new(),
+        }
+    }
+
+    pub async fn run(mut self) -> Result<()> {
+        self.writer.write_all(GREETING.as_bytes()).await?;
+        // Requests of an open command list, and whether each one is followed by `list_OK`
+        let mut list: Option<(Vec<Request>, bool)> = None;
+        while let Some(line) = self.lines.next_line().await? {
+            let request = match Request::parse(&line) {
+                Ok(request) => request,
+                Err(ack) => {
+                    self.reply(Err(ack)).await?;
+                    continue;
+                }
+            };
+            if let Some((requests, ok)) = &mut list {
+                if request.command == "command_list_end" {
+                    let reply = self.execute_list(requests, *ok).await;
+                    list = None;
+                    self.reply(reply).await?;
+                } else {
+                    requests.push(request);
+                }
+                continue;
+            }
+            match request.command.as_str() {
+                "command_list_begin" => list = Some((Vec::new(), false)),
+                "command_list_ok_begin" => list = Some((Vec::new(), true)),
+                "close" => break,
+                // Only meaningful while idle
+                "noidle" => {}
+                "idle" => {
+                    if !self.idle(&request).await? {
+                        break;
+                    }
+                }
+                command => {
+                    let reply = self.mpd.execute(&request).await;
+                    self.reply(reply.map_err(|ack| ack.at(0, command))).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute_list(&self, requests: &[Request], ok: bool) -> Result<Response, Ack> {
+        let mut response = Response::new();
+        for (index, request) in requests.iter().enumerate() {
+            let reply = self.mpd.execute(request).await;
+            response.append(reply.map_err(|ack| ack.at(index, &request.command))?);
+            if ok {
+                response.append_line("list_OK");
+            }
+        }
+        Ok(response)
+    }
+
+    async fn reply(&mut self, reply: Result<Response, Ack>) -> Result<()> {
+        let text = match reply {
+            Ok(response) => format!("{response}OK\n"),
+            Err(ack) => format!("{ack}\n"),
+        };
+        self.writer.write_all(text.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Waits for a change to one of the requested subsystems, or `noidle`. Returns false
+    /// when the client went away.
+    async fn idle(&mut self, request: &Request) -> Result<bool> {
+        let wanted = match request
+            .args
+            .iter()
+            .map(|name| {
+                Subsystem::parse(name)
+                    .ok_or_else(|| Ack::arg(format!("Unrecognized idle event: {name}")))
+            })
+            .collect::<Result<Vec<_>, Ack>>()
+        {
+            Ok(wanted) if wanted.is_empty() => Subsystem::ALL.to_vec(),
+            Ok(wanted) => wanted,
+            Err(ack) => {
+                self.reply(Err(ack.at(0, "idle"))).await?;
+                return Ok(true);
+            }
+        };
+
+        loop {
+            while let Some(change) = self.try_change() {
+                self.add_pending(change);
+            }
+            let ready: Vec<Subsystem> = Subsystem::ALL
+                .into_iter()
+                .filter(|s| wanted.contains(s) && self.pending.contains(s))
+                .collect();
+            if !ready.is_empty() {
+                self.pending.retain(|s| !ready.contains(s));
+                let mut response = Response::new();
+                for subsystem in ready {
+                    response.field("changed", subsystem.name());
+                }
+                self.reply(Ok(response)).await?;
+                return Ok(true);
+            }
+
+            tokio::select! {
+                change = self.changes.recv() => match change {
+                    Ok(change) => self.add_pending(change),
+                    Err(RecvError::Lagged(_)) => self.pending = Subsystem::ALL.to_vec(),
+                    Err(RecvError::Closed) => return Ok(false),
+                },
+                line = self.lines.next_line() => match line? {
+                    Some(line) if line.trim() == "noidle" => {
+                        self.reply(Ok(Response::new())).await?;
+                        return Ok(true);
+                    }
+                    // Anything else while idle ends the connection, like MPD does
+                    _ => return Ok(false),
+                },
+            }
+        }
+    }
+
+    fn try_change(&mut self) -> Option<Subsystem> {
+        loop {
+            match self.changes.try_recv() {
+                Ok(change) => return Some(change),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => {
+                    self.pending = Subsystem::ALL.to_vec();
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn add_pending(&mut self, change: Subsystem) {
+        if !self.pending.contains(&change) {
+            self.pending.push(change);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./session.tests.rs"]
+mod tests;
@@ -0,0 +1,302 @@
+use crate::mpd::serve;
+use muz_core::bus::EventBus;
+use muz_core::driver::{null::NullPlaybackDriver, render::Clock};
+use muz_core::library::Library;
+use muz_core::playback::Playback;
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+
+/// One second of silence tagged with an INFO list.
+fn write_wav(path: &Path, tags: &[(&[u8; 4], &str)]) {
+    let mut info = b"INFO".to_vec();
+    for (id, value) in tags {
+        info.extend_from_slice(*id);
+        info.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        info.extend_from_slice(value.as_bytes());
+        if value.len() % 2 == 1 {
+            info.push(0);
+        }
+    }
+    let data = vec![0u8; 44_100 * 4];
+
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend_from_slice(&((4 + 24 + 8 + info.len() + 8 + data.len()) as u32).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&44_100u32.to_le_bytes());
+    bytes.extend_from_slice(&(44_100u32 * 4).to_le_bytes());
+    bytes.extend_from_slice(&4u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"LIST");
+    bytes.extend_from_slice(&(info.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&info);
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&data);
+
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, bytes).unwrap();
+}
+
+/// A library of three songs by two artists, removed when dropped.
+struct TestLibrary {
+    root: PathBuf,
+}
+
+impl TestLibrary {
+    fn create() -> Self {
+        let root = std::env::temp_dir().join(format!("muz-mpd-{}", uuid::Uuid::new_v4()));
+        for (path, title, artist, album) in [
+            ("Band/First Album/01.wav", "Alpha", "Band", "First Album"),
+            ("Band/First Album/02.wav", "Beta", "Band", "First Album"),
+            ("Other/Gamma.wav", "Gamma", "Other", "Second"),
+        ] {
+            write_wav(
+                &root.join(path),
+                &[(b"INAM", title), (b"IART", artist), (b"IPRD", album)],
+            );
+        }
+        Self { root }
+    }
+}
+
+impl Drop for TestLibrary {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+async fn start(library: &TestLibrary) -> SocketAddr {
+    let events = EventBus::new();
+    let playback = Playback::create(
+        Box::new(NullPlaybackDriver::new(Clock::RealTime, 1.0)),
+        events.clone(),
+    );
+    let mut tracks = Library::new(library.root.clone(), "Test".to_string());
+    tracks.initialize().await;
+    let changes = events.subscribe().1;
+    let library = LibraryService::new(Arc::new(tokio::sync::Mutex::new(tracks)), events);
+    serve(
+        "127.0.0.1:0",
+        PlaybackService::new(playback),
+        library,
+        changes,
+    )
+    .await
+    .unwrap()
+}
+
+struct Client {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl Client {
+    async fn connect(address: SocketAddr) -> Self {
+        let (reader, writer) = TcpStream::connect(address).await.unwrap().into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let greeting = lines.next_line().await.unwrap().unwrap();
+        assert!(greeting.starts_with("OK MPD "), "{greeting}");
+        Self { lines, writer }
+    }
+
+    async fn send(&mut self, line: &str) {
+        self.writer
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            .unwrap();
+    }
+
+    /// Lines of the response, up to and including the final `OK` or `ACK`.
+    async fn response(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let line = self.lines.next_line().await.unwrap().unwrap();
+            let done = line == "OK" || line.starts_with("ACK ");
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+    }
+
+    async fn command(&mut self, line: &str) -> Vec<String> {
+        self.send(line).await;
+        self.response().await
+    }
+
+    async fn values(&mut self, line: &str, key: &str) -> Vec<String> {
+        let prefix = format!("{key}: ");
+        self.command(line)
+            .await
+            .iter()
+            .filter_map(|l| l.strip_prefix(&prefix).map(str::to_string))
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn test_playback_and_playlist_editing() {
+    let library = TestLibrary::create();
+    let mut client = Client::connect(start(&library).await).await;
+    assert_eq!(client.values("status", "state").await, vec!["stop"]);
+    assert_eq!(client.values("status", "playlistlength").await, vec!["0"]);
+
+    assert_eq!(client.command("add \"Band\"").await, vec!["OK"]);
+    assert_eq!(
+        client.values("playlistinfo", "file").await,
+        vec!["Band/First Album/01.wav", "Band/First Album/02.wav"]
+    );
+    let id = client.values("addid Other/Gamma.wav", "Id").await;
+    assert_eq!(client.values("playlistinfo 2", "Id").await, id);
+
+    assert_eq!(client.command("play").await, vec!["OK"]);
+    assert_eq!(client.values("status", "state").await, vec!["play"]);
+    assert_eq!(client.values("currentsong", "Title").await, vec!["Alpha"]);
+
+    assert_eq!(client.command("move 2 1").await, vec!["OK"]);
+    assert_eq!(
+        client.values("playlistinfo", "Title").await,
+        vec!["Alpha", "Gamma", "Beta"]
+    );
+    assert_eq!(
+        client.command(&format!("deleteid {}", id[0])).await,
+        vec!["OK"]
+    );
+    assert_eq!(client.command("next").await, vec!["OK"]);
+    assert_eq!(client.values("currentsong", "Title").await, vec!["Beta"]);
+
+    assert_eq!(client.command("seekcur 0.5").await, vec!["OK"]);
+    assert_eq!(client.command("pause 1").await, vec!["OK"]);
+    assert_eq!(client.values("status", "state").await, vec!["pause"]);
+    assert_eq!(client.command("setvol 40").await, vec!["OK"]);
+    assert_eq!(client.values("getvol", "volume").await, vec!["40"]);
+
+    assert_eq!(
+        client.command("playid 999").await,
+        vec!["ACK [50@0] {playid} No such song"]
+    );
+}
+
+#[tokio::test]
+async fn test_database_queries() {
+    let library = TestLibrary::create();
+    let mut client = Client::connect(start(&library).await).await;
+
+    assert_eq!(
+        client.values("list album", "Album").await,
+        vec!["First Album", "Second"]
+    );
+    assert_eq!(
+        client.command("list album group artist").await,
+        vec![
+            "Artist: Band",
+            "Album: First Album",
+            "Artist: Other",
+            "Album: Second",
+            "OK"
+        ]
+    );
+    assert_eq!(
+        client.values("list album Other", "Album").await,
+        vec!["Second"]
+    );
+    assert_eq!(
+        client.values("find artist Band", "Title").await,
+        vec!["Alpha", "Beta"]
+    );
+    assert_eq!(
+        client
+            .values(
+                "find \"((artist == 'Band') AND (title != 'Alpha'))\"",
+                "Title"
+            )
+            .await,
+        vec!["Beta"]
+    );
+    assert_eq!(
+        client.values("search title AMM", "Title").await,
+        vec!["Gamma"]
+    );
+    assert_eq!(client.values("count artist Band", "songs").await, vec!["2"]);
+
+    assert_eq!(
+        client.values("lsinfo", "directory").await,
+        vec!["Band", "Other"]
+    );
+    assert_eq!(
+        client.values("lsinfo Band", "directory").await,
+        vec!["Band/First Album"]
+    );
+    assert_eq!(
+        client.values("lsinfo Other", "file").await,
+        vec!["Other/Gamma.wav"]
+    );
+
+    assert_eq!(client.command("findadd album Second").await, vec!["OK"]);
+    assert_eq!(client.values("playlistinfo", "Title").await, vec!["Gamma"]);
+}
+
+#[tokio::test]
+async fn test_command_lists() {
+    let library = TestLibrary::create();
+    let mut client = Client::connect(start(&library).await).await;
+
+    for line in [
+        "command_list_ok_begin",
+        "ping",
+        "repeat 1",
+        "command_list_end",
+    ] {
+        client.send(line).await;
+    }
+    assert_eq!(client.response().await, vec!["list_OK", "list_OK", "OK"]);
+    assert_eq!(client.values("status", "repeat").await, vec!["1"]);
+
+    for line in [
+        "command_list_begin",
+        "random 1",
+        "bogus",
+        "ping",
+        "command_list_end",
+    ] {
+        client.send(line).await;
+    }
+    assert_eq!(
+        client.response().await,
+        vec!["ACK [5@1] {bogus} unknown command \"bogus\""]
+    );
+    // Commands before the failing one are applied
+    assert_eq!(client.values("status", "random").await, vec!["1"]);
+}
+
+#[tokio::test]
+async fn test_idle_reports_changes_from_other_clients() {
+    let library = TestLibrary::create();
+    let address = start(&library).await;
+    let mut idle = Client::connect(address).await;
+    let mut other = Client::connect(address).await;
+
+    idle.send("idle mixer playlist").await;
+    assert_eq!(other.command("repeat 1").await, vec!["OK"]);
+    assert_eq!(other.command("setvol 50").await, vec!["OK"]);
+    assert_eq!(idle.response().await, vec!["changed: mixer", "OK"]);
+
+    // Changes made while not idle are reported by the next idle
+    assert_eq!(other.command("add Other").await, vec!["OK"]);
+    assert_eq!(
+        idle.command("idle").await,
+        vec!["changed: playlist", "changed: options", "OK"]
+    );
+
+    idle.send("idle database").await;
+    assert_eq!(idle.command("noidle").await, vec!["OK"]);
+    assert_eq!(idle.command("ping").await, vec!["OK"]);
+}
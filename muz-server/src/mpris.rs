@@ -0,0 +1,645 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use zbus::blocking::connection;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{fdo, interface};
+
+use muz_core::artwork;
+use muz_core::bus::BusEvent;
+use muz_core::command::{PlaybackCommand, Speed, Volume};
+use muz_core::events::PlayerEvent;
+use muz_core::playback::{PlaybackSnapshot, PlaybackState, RepeatMode};
+use muz_core::services::playback_service::PlaybackService;
+use muz_core::time_stretch::{MAX_SPEED, MIN_SPEED};
+use muz_core::track::Track;
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.muz";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+/// Position jumps larger than this between two updates are reported as seeks
+const SEEK_TOLERANCE_MS: i64 = 1000;
+/// How often the state is checked for changes that arrive without an event
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The state last signalled and when it was read, shared so that seeks requested over
+/// D-Bus are not signalled again when their progress event comes in.
+type Signalled = Arc<Mutex<Option<(PlaybackSnapshot, Instant)>>>;
+
+enum MprisEvent {
+    TrackChanged,
+    QueueChanged,
+    Progress,
+}
+
+/// Serves the player on the session bus, or on the bus at `address`, until the event bus
+/// closes.
+pub fn serve(
+    service: PlaybackService,
+    events: broadcast::Receiver<BusEvent>,
+    address: Option<&str>,
+) -> Result<()> {
+    let signalled: Signalled = Arc::new(Mutex::new(
+        service.status().ok().map(|status| (status, Instant::now())),
+    ));
+    let builder = match address {
+        Some(address) => connection::Builder::address(address)?,
+        None => connection::Builder::session()?,
+    };
+    let connection = builder
+        .serve_at(OBJECT_PATH, Root)?
+        .serve_at(
+            OBJECT_PATH,
+            Player {
+                service: service.clone(),
+                signalled: signalled.clone(),
+            },
+        )?
+        .serve_at(
+            OBJECT_PATH,
+            TrackList {
+                service: service.clone(),
+            },
+        )?
+        .build()?;
+    // A second instance takes a unique name, as the spec allows
+    if connection.request_name(BUS_NAME).is_err() {
+        connection.request_name(format!("{BUS_NAME}.instance{}", std::process::id()))?;
+    }
+
+    let events = forward(events);
+    thread::spawn(move || {
+        let mut signals = Signals {
+            connection,
+            service,
+            signalled,
+        };
+        loop {
+            let event = match events.recv_timeout(POLL_INTERVAL) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if let Err(e) = signals.update(event) {
+                tracing::warn!("Failed to emit MPRIS signals: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Passes the events the signals depend on to a channel the MPRIS thread can wait on with a
+/// timeout.
+fn forward(mut events: broadcast::Receiver<BusEvent>) -> Receiver<MprisEvent> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || loop {
+        let event = match events.blocking_recv() {
+            Ok(BusEvent { event, .. }) => match event {
+                PlayerEvent::TrackChanged(_) => MprisEvent::TrackChanged,
+                PlayerEvent::QueueChanged(_) => MprisEvent::QueueChanged,
+                PlayerEvent::Progress(_) => MprisEvent::Progress,
+                _ => continue,
+            },
+            // Replacing the track list catches up on whatever was missed
+            Err(RecvError::Lagged(_)) => MprisEvent::QueueChanged,
+            Err(RecvError::Closed) => break,
+        };
+        if sender.send(event).is_err() {
+            break;
+        }
+    });
+    receiver
+}
+
+/// Emits the signals for what changed since the last update.
+struct Signals {
+    connection: zbus::blocking::Connection,
+    service: PlaybackService,
+    signalled: Signalled,
+}
+
+impl Signals {
+    fn update(&mut self, event: Option<MprisEvent>) -> Result<()> {
+        let (snapshot, last) = {
+            let mut signalled = self
+                .signalled
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock MPRIS state: {e}"))?;
+            let snapshot = self.service.status()?;
+            let last = signalled.replace((snapshot.clone(), Instant::now()));
+            (snapshot, last)
+        };
+        let server = self.connection.object_server();
+        let player = server.interface::<_, Player>(OBJECT_PATH)?;
+        let emitter = player.signal_emitter();
+        let player = player.get();
+
+        if let Some(MprisEvent::QueueChanged) = event {
+            let tracks = track_ids(&self.service.tracks()?);
+            let current = current_track_id(&snapshot);
+            zbus::block_on(TrackList::track_list_replaced(emitter, tracks, current))?;
+            zbus::block_on(player.can_go_next_changed(emitter))?;
+        }
+
+        let Some((last, last_read)) = last else {
+            return Ok(());
+        };
+        let elapsed = last_read.elapsed();
+        let track_changed =
+            last.track.as_ref().map(|t| &t.id) != snapshot.track.as_ref().map(|t| &t.id);
+        if track_changed {
+            zbus::block_on(player.metadata_changed(emitter))?;
+            zbus::block_on(player.can_go_next_changed(emitter))?;
+            zbus::block_on(player.can_go_previous_changed(emitter))?;
+        }
+        if last.state != snapshot.state {
+            zbus::block_on(player.playback_status_changed(emitter))?;
+        }
+        if (last.volume, last.muted) != (snapshot.volume, snapshot.muted) {
+            zbus::block_on(player.volume_changed(emitter))?;
+        }
+        if last.repeat != snapshot.repeat {
+            zbus::block_on(player.loop_status_changed(emitter))?;
+        }
+        if last.shuffle != snapshot.shuffle {
+            zbus::block_on(player.shuffle_changed(emitter))?;
+        }
+        if last.speed != snapshot.speed {
+            zbus::block_on(player.rate_changed(emitter))?;
+        }
+
+        // Position changes are only signalled when they don't follow from the elapsed time
+        if !track_changed && snapshot.state != PlaybackState::Stopped {
+            let expected = match last.state {
+                PlaybackState::Playing => {
+                    last.position_ms as i64
+                        + (elapsed.as_millis() as f64 * last.speed as f64) as i64
+                }
+                _ => last.position_ms as i64,
+            };
+            if (snapshot.position_ms as i64 - expected).abs() > SEEK_TOLERANCE_MS {
+                zbus::block_on(Player::seeked(emitter, micros(snapshot.position_ms)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn micros(ms: u64) -> i64 {
+    ms as i64 * 1000
+}
+
+fn failed(error: anyhow::Error) -> fdo::Error {
+    fdo::Error::Failed(format!("{error:#}"))
+}
+
+/// Object path identifying a track, from its id.
+fn track_id(track: &Track) -> OwnedObjectPath {
+    let path = format!("/org/muz/track/{}", track.id.replace('-', "_"));
+    ObjectPath::try_from(path)
+        .map(OwnedObjectPath::from)
+        .unwrap_or_else(|_| no_track())
+}
+
+fn track_ids(tracks: &[Track]) -> Vec<OwnedObjectPath> {
+    tracks.iter().map(track_id).collect()
+}
+
+fn current_track_id(snapshot: &PlaybackSnapshot) -> OwnedObjectPath {
+    snapshot.track.as_ref().map_or_else(no_track, track_id)
+}
+
+fn no_track() -> OwnedObjectPath {
+    OwnedObjectPath::from(ObjectPath::from_static_str_unchecked(NO_TRACK))
+}
+
+fn file_url(path: &Path) -> String {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut url = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    url
+}
+
+/// The `xesam`/`mpris` metadata map of a track.
+fn metadata(track: Option<&Track>) -> HashMap<String, OwnedValue> {
+    let mut values: Vec<(&str, Value<'static>)> = Vec::new();
+    match track {
+        None => values.push(("mpris:trackid", no_track().into())),
+        Some(track) => {
+            values.push(("mpris:trackid", track_id(track).into()));
+            values.push(("mpris:length", micros(track.duration_ms).into()));
+            values.push(("xesam:url", file_url(&track.path).into()));
+            if let Some(cover) = artwork::sidecar(track) {
+                values.push(("mpris:artUrl", file_url(&cover).into()));
+            }
+            let title = track
+                .metadata
+                .as_ref()
+                .and_then(|m| m.title.clone())
+                .unwrap_or_else(|| Track::default_title(&track.path));
+            values.push(("xesam:title", title.into()));
+            if let Some(m) = &track.metadata {
+                if let Some(album) = &m.album {
+                    values.push(("xesam:album", album.clone().into()));
+                }
+                if let Some(artist) = &m.artist {
+                    values.push(("xesam:artist", vec![artist.clone()].into()));
+                }
+                if let Some(album_artist) = &m.album_artist {
+                    values.push(("xesam:albumArtist", vec![album_artist.clone()].into()));
+                }
+                if let Some(genre) = &m.genre {
+                    values.push(("xesam:genre", vec![genre.clone()].into()));
+                }
+                if let Some(number) = m.track_number {
+                    values.push(("xesam:trackNumber", number.into()));
+                }
+                if let Some(number) = m.disc_number {
+                    values.push(("xesam:discNumber", number.into()));
+                }
+                if let Some(year) = &m.year {
+                    values.push(("xesam:contentCreated", year.clone().into()));
+                }
+            }
+        }
+    }
+    values
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value.try_into().ok()?)))
+        .collect()
+}
+
+/// `org.mpris.MediaPlayer2`, the application itself.
+struct Root;
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "Muz"
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> &str {
+        "muz"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// `org.mpris.MediaPlayer2.Player`, the transport controls.
+struct Player {
+    service: PlaybackService,
+    signalled: Signalled,
+}
+
+impl Player {
+    /// Seeks and marks the new position as signalled, returning it in microseconds.
+    fn seek_to(&self, command: PlaybackCommand) -> fdo::Result<i64> {
+        let mut signalled = self
+            .signalled
+            .lock()
+            .map_err(|e| fdo::Error::Failed(format!("Failed to lock MPRIS state: {e}")))?;
+        let snapshot = self.execute(command)?;
+        let position = micros(snapshot.position_ms);
+        *signalled = Some((snapshot, Instant::now()));
+        Ok(position)
+    }
+
+    fn execute(&self, command: PlaybackCommand) -> fdo::Result<PlaybackSnapshot> {
+        self.service.control_playback(command).map_err(failed)
+    }
+
+    fn status(&self) -> fdo::Result<PlaybackSnapshot> {
+        self.service.status().map_err(failed)
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) -> fdo::Result<()> {
+        self.execute(PlaybackCommand::Next).map(|_| ())
+    }
+
+    fn previous(&self) -> fdo::Result<()> {
+        self.execute(PlaybackCommand::Previous).map(|_| ())
+    }
+
+    fn pause(&self) -> fdo::Result<()> {
+        if self.status()?.state == PlaybackState::Playing {
+            self.execute(PlaybackCommand::Pause)?;
+        }
+        Ok(())
+    }
+
+    fn play_pause(&self) -> fdo::Result<()> {
+        self.execute(PlaybackCommand::TogglePlayPause).map(|_| ())
+    }
+
+    fn stop(&self) -> fdo::Result<()> {
+        self.execute(PlaybackCommand::Stop).map(|_| ())
+    }
+
+    fn play(&self) -> fdo::Result<()> {
+        if self.status()?.state != PlaybackState::Playing {
+            self.execute(PlaybackCommand::Play)?;
+        }
+        Ok(())
+    }
+
+    /// Moves by `offset` microseconds, skipping to the next track past the end.
+    async fn seek(
+        &self,
+        offset: i64,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let status = self.status()?;
+        let Some(track) = &status.track else {
+            return Ok(());
+        };
+        let target = status.position_ms as i64 + offset / 1000;
+        if target >= track.duration_ms as i64 && track.duration_ms > 0 {
+            return self.next();
+        }
+        let position = self.seek_to(PlaybackCommand::SeekRelative {
+            delta_ms: offset / 1000,
+        })?;
+        Self::seeked(&emitter, position).await?;
+        Ok(())
+    }
+
+    /// Ignored unless `track_id` is still the current track, as the spec requires.
+    async fn set_position(
+        &self,
+        track_id: ObjectPath<'_>,
+        position: i64,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let status = self.status()?;
+        let Some(track) = &status.track else {
+            return Ok(());
+        };
+        let position_ms = position / 1000;
+        if *self::track_id(track) != track_id
+            || position_ms < 0
+            || position_ms as u64 > track.duration_ms
+        {
+            return Ok(());
+        }
+        let position = self.seek_to(PlaybackCommand::Seek {
+            ms: position_ms as u64,
+        })?;
+        Self::seeked(&emitter, position).await?;
+        Ok(())
+    }
+
+    fn open_uri(&self, _uri: &str) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "Opening URIs is not supported".into(),
+        ))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> fdo::Result<&str> {
+        Ok(match self.status()?.state {
+            PlaybackState::Playing => "Playing",
+            PlaybackState::Paused => "Paused",
+            PlaybackState::Stopped => "Stopped",
+        })
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> fdo::Result<&str> {
+        Ok(match self.status()?.repeat {
+            RepeatMode::Off => "None",
+            RepeatMode::One => "Track",
+            RepeatMode::All => "Playlist",
+        })
+    }
+
+    #[zbus(property)]
+    fn set_loop_status(&self, loop_status: &str) -> fdo::Result<()> {
+        let mode = match loop_status {
+            "None" => RepeatMode::Off,
+            "Track" => RepeatMode::One,
+            "Playlist" => RepeatMode::All,
+            other => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "Unknown loop status {other}"
+                )))
+            }
+        };
+        self.execute(PlaybackCommand::SetRepeat { mode })
+            .map(|_| ())
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> fdo::Result<f64> {
+        Ok(self.status()?.speed as f64)
+    }
+
+    #[zbus(property)]
+    fn set_rate(&self, rate: f64) -> fdo::Result<()> {
+        // A rate of zero means pause, as the spec allows
+        if rate == 0.0 {
+            return self.pause();
+        }
+        let speed = Speed::try_from(rate as f32).map_err(fdo::Error::InvalidArgs)?;
+        self.execute(PlaybackCommand::SetSpeed { speed })
+            .map(|_| ())
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> fdo::Result<bool> {
+        Ok(self.status()?.shuffle)
+    }
+
+    #[zbus(property)]
+    fn set_shuffle(&self, shuffle: bool) -> fdo::Result<()> {
+        self.execute(PlaybackCommand::SetShuffle { shuffle })
+            .map(|_| ())
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> fdo::Result<HashMap<String, OwnedValue>> {
+        Ok(metadata(self.status()?.track.as_ref()))
+    }
+
+    /// Muted reads as zero
+    #[zbus(property)]
+    fn volume(&self) -> fdo::Result<f64> {
+        let status = self.status()?;
+        Ok(if status.muted {
+            0.0
+        } else {
+            status.volume as f64
+        })
+    }
+
+    #[zbus(property)]
+    fn set_volume(&self, volume: f64) -> fdo::Result<()> {
+        let volume =
+            Volume::try_from(volume.clamp(0.0, 1.0) as f32).map_err(fdo::Error::InvalidArgs)?;
+        self.execute(PlaybackCommand::SetVolume { volume })?;
+        self.execute(PlaybackCommand::SetMuted { muted: false })
+            .map(|_| ())
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> fdo::Result<i64> {
+        Ok(micros(self.status()?.position_ms))
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        MIN_SPEED as f64
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        MAX_SPEED as f64
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> fdo::Result<bool> {
+        let status = self.status()?;
+        Ok(status.repeat == RepeatMode::All || !self.service.queue().map_err(failed)?.is_empty())
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> fdo::Result<bool> {
+        Ok(self.status()?.track.is_some())
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// `org.mpris.MediaPlayer2.TrackList`: the current track followed by the queue.
+struct TrackList {
+    service: PlaybackService,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.TrackList")]
+impl TrackList {
+    fn get_tracks_metadata(
+        &self,
+        track_ids: Vec<OwnedObjectPath>,
+    ) -> fdo::Result<Vec<HashMap<String, OwnedValue>>> {
+        let tracks = self.service.tracks().map_err(failed)?;
+        Ok(track_ids
+            .iter()
+            .filter_map(|id| tracks.iter().find(|t| track_id(t) == *id))
+            .map(|track| metadata(Some(track)))
+            .collect())
+    }
+
+    fn add_track(
+        &self,
+        _uri: &str,
+        _after_track: ObjectPath<'_>,
+        _set_as_current: bool,
+    ) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "The track list is read-only".into(),
+        ))
+    }
+
+    fn remove_track(&self, _track_id: ObjectPath<'_>) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "The track list is read-only".into(),
+        ))
+    }
+
+    fn go_to(&self, track_id: ObjectPath<'_>) -> fdo::Result<()> {
+        let queue = self.service.queue().map_err(failed)?;
+        let Some(track) = queue.iter().find(|t| *self::track_id(t) == track_id) else {
+            return Ok(());
+        };
+        self.service
+            .select_from_queue(&track.id)
+            .map(|_| ())
+            .map_err(failed)
+    }
+
+    #[zbus(signal)]
+    async fn track_list_replaced(
+        emitter: &SignalEmitter<'_>,
+        tracks: Vec<OwnedObjectPath>,
+        current_track: OwnedObjectPath,
+    ) -> zbus::Result<()>;
+
+    #[zbus(property(emits_changed_signal = "invalidates"))]
+    fn tracks(&self) -> fdo::Result<Vec<OwnedObjectPath>> {
+        Ok(track_ids(&self.service.tracks().map_err(failed)?))
+    }
+
+    #[zbus(property)]
+    fn can_edit_tracks(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+#[path = "./mpris.tests.rs"]
+mod tests;
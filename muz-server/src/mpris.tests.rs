@@ -0,0 +1,233 @@
+use super::*;
+use muz_core::bus::EventBus;
+use muz_core::driver::{
+    null::NullPlaybackDriver,
+    render::{Clock, RenderOutput},
+    wav::WavOutput,
+};
+use muz_core::playback::Playback;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use zbus::blocking::{Connection, Proxy};
+
+const PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+/// A private session bus, stopped when dropped.
+struct TestBus {
+    daemon: Child,
+    address: String,
+}
+
+impl TestBus {
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.as_mut()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// Ten seconds of silence.
+fn silent_track(title: &str) -> Track {
+    let path = std::env::temp_dir().join(format!("muz-mpris-{}.wav", uuid::Uuid::new_v4()));
+    let mut output = WavOutput::new(&path);
+    output.write(&vec![0.0; 2 * 441_000], 44_100, 2).unwrap();
+    drop(output);
+    let mut track = Track::new(&path);
+    if let Some(metadata) = track.metadata.as_mut() {
+        metadata.title = Some(title.to_string());
+        metadata.artist = Some("Test Artist".to_string());
+    }
+    track
+}
+
+/// Serves a player with two queued tracks, returning a proxy to its `Player` interface.
+fn serve_player(bus: &TestBus) -> (Proxy<'static>, Vec<Track>) {
+    let events = EventBus::new();
+    let playback = Playback::create(
+        Box::new(NullPlaybackDriver::new(Clock::RealTime, 1.0)),
+        events.clone(),
+    );
+    let tracks = vec![silent_track("First"), silent_track("Second")];
+    playback.lock().unwrap().enqueue_multiple(tracks.clone());
+    serve(
+        PlaybackService::new(playback),
+        events.subscribe().1,
+        Some(&bus.address),
+    )
+    .unwrap();
+
+    let connection = zbus::blocking::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .build()
+        .unwrap();
+    (player_proxy(&connection), tracks)
+}
+
+/// Properties are read from the player rather than cached from change signals, which may
+/// still be on their way.
+fn player_proxy(connection: &Connection) -> Proxy<'static> {
+    zbus::blocking::proxy::Builder::new(connection)
+        .destination(BUS_NAME)
+        .unwrap()
+        .path(OBJECT_PATH)
+        .unwrap()
+        .interface(PLAYER)
+        .unwrap()
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_player_is_controlled_over_dbus() {
+    let Some(bus) = TestBus::start() else {
+        eprintln!("dbus-daemon is not available, skipping");
+        return;
+    };
+    let (player, tracks) = serve_player(&bus);
+    let status = || player.get_property::<String>("PlaybackStatus").unwrap();
+    assert_eq!(status(), "Stopped");
+
+    player.call_method("Play", &()).unwrap();
+    assert_eq!(status(), "Playing");
+    let metadata: HashMap<String, OwnedValue> = player.get_property("Metadata").unwrap();
+    assert_eq!(
+        String::try_from(metadata["xesam:title"].clone()).unwrap(),
+        "First"
+    );
+    assert_eq!(
+        Vec::<String>::try_from(metadata["xesam:artist"].clone()).unwrap(),
+        vec!["Test Artist".to_string()]
+    );
+    assert_eq!(
+        i64::try_from(metadata["mpris:length"].clone()).unwrap(),
+        10_000_000
+    );
+    assert_eq!(
+        OwnedObjectPath::try_from(metadata["mpris:trackid"].clone()).unwrap(),
+        track_id(&tracks[0])
+    );
+
+    player.call_method("Pause", &()).unwrap();
+    assert_eq!(status(), "Paused");
+
+    player.set_property("Volume", 0.25f64).unwrap();
+    assert_eq!(player.get_property::<f64>("Volume").unwrap(), 0.25);
+    player.set_property("LoopStatus", "Playlist").unwrap();
+    assert_eq!(
+        player.get_property::<String>("LoopStatus").unwrap(),
+        "Playlist"
+    );
+
+    player.call_method("Next", &()).unwrap();
+    let metadata: HashMap<String, OwnedValue> = player.get_property("Metadata").unwrap();
+    assert_eq!(
+        String::try_from(metadata["xesam:title"].clone()).unwrap(),
+        "Second"
+    );
+    for track in tracks {
+        let _ = std::fs::remove_file(track.path);
+    }
+}
+
+#[test]
+fn test_seeking_emits_seeked() {
+    let Some(bus) = TestBus::start() else {
+        eprintln!("dbus-daemon is not available, skipping");
+        return;
+    };
+    let (player, tracks) = serve_player(&bus);
+    let mut seeked = player.receive_signal("Seeked").unwrap();
+    player.call_method("Play", &()).unwrap();
+    player.call_method("Pause", &()).unwrap();
+
+    player
+        .call_method("SetPosition", &(track_id(&tracks[0]), 2_000_000i64))
+        .unwrap();
+    let position: i64 = seeked.next().unwrap().body().deserialize().unwrap();
+    assert_eq!(position, 2_000_000);
+
+    player.call_method("Seek", &(3_000_000i64)).unwrap();
+    let position: i64 = seeked.next().unwrap().body().deserialize().unwrap();
+    assert_eq!(position, 5_000_000);
+    assert_eq!(player.get_property::<i64>("Position").unwrap(), position);
+
+    // Positions for another track are ignored
+    player
+        .call_method("SetPosition", &(track_id(&tracks[1]), 1_000_000i64))
+        .unwrap();
+    assert_eq!(player.get_property::<i64>("Position").unwrap(), position);
+    for track in tracks {
+        let _ = std::fs::remove_file(track.path);
+    }
+}
+
+#[test]
+fn test_track_changes_are_signalled() {
+    let Some(bus) = TestBus::start() else {
+        eprintln!("dbus-daemon is not available, skipping");
+        return;
+    };
+    let (player, tracks) = serve_player(&bus);
+    let properties = zbus::blocking::fdo::PropertiesProxy::builder(player.connection())
+        .destination(BUS_NAME)
+        .unwrap()
+        .path(OBJECT_PATH)
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut changes = properties.receive_properties_changed().unwrap();
+    player.call_method("Play", &()).unwrap();
+
+    let mut changed = Vec::new();
+    while !(changed.contains(&"Metadata".to_string())
+        && changed.contains(&"PlaybackStatus".to_string()))
+    {
+        let signal = changes.next().unwrap();
+        let args = signal.args().unwrap();
+        changed.extend(args.changed_properties().keys().map(|k| k.to_string()));
+    }
+    let metadata: HashMap<String, OwnedValue> = player.get_property("Metadata").unwrap();
+    assert_eq!(
+        OwnedObjectPath::try_from(metadata["mpris:trackid"].clone()).unwrap(),
+        track_id(&tracks[0])
+    );
+    for track in tracks {
+        let _ = std::fs::remove_file(track.path);
+    }
+}
+
+#[test]
+fn test_metadata_without_a_track() {
+    let metadata = metadata(None);
+    assert_eq!(metadata.len(), 1);
+    assert_eq!(
+        OwnedObjectPath::try_from(metadata["mpris:trackid"].clone()).unwrap(),
+        no_track()
+    );
+}
+
+#[test]
+fn test_file_urls_are_percent_encoded() {
+    assert_eq!(
+        file_url(Path::new("/music/Björk/01 Army of Me.flac")),
+        "file:///music/Bj%C3%B6rk/01%20Army%20of%20Me.flac"
+    );
+}
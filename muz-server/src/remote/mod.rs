@@ -0,0 +1,84 @@
+use anyhow::{bail, Result};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+use crate::remote::routes::Api;
+use muz_core::bus::EventBus;
+use muz_core::config::RemoteSettings;
+use muz_core::events::{
+    PlayerEvent, ProgressEvent, QueueChangedEvent, SpectrumEvent, StateChangedEvent,
+    TrackChangedEvent,
+};
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+
+pub mod routes;
+mod websocket;
+
+/// The events the frontend receives, streamed as `{ "event": "track-changed", "payload": ... }`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", content = "payload", rename_all = "kebab-case")]
+pub enum RemoteEvent {
+    TrackChanged(Box<TrackChangedEvent>),
+    QueueChanged(QueueChangedEvent),
+    StateChanged(StateChangedEvent),
+    Progress(ProgressEvent),
+    Spectrum(SpectrumEvent),
+}
+
+impl RemoteEvent {
+    /// The WebSocket message for a bus event, if clients follow it.
+    pub fn from_event(event: &PlayerEvent) -> Option<Self> {
+        Some(match event {
+            PlayerEvent::TrackChanged(changed) => Self::TrackChanged(changed.clone()),
+            PlayerEvent::QueueChanged(changed) => Self::QueueChanged(changed.clone()),
+            PlayerEvent::StateChanged(state) => Self::StateChanged(state.clone()),
+            PlayerEvent::Progress(progress) => Self::Progress(progress.clone()),
+            PlayerEvent::Spectrum(spectrum) => Self::Spectrum(spectrum.clone()),
+            _ => return None,
+        })
+    }
+}
+
+/// Serves the API on the configured address, returning the address actually bound.
+pub async fn serve(
+    settings: &RemoteSettings,
+    playback: PlaybackService,
+    library: LibraryService,
+    events: EventBus,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(&settings.address).await?;
+    let local_address = listener.local_addr()?;
+    if settings.token.is_none() && !local_address.ip().is_loopback() {
+        bail!("A token is required to serve the remote API on {local_address}");
+    }
+    let api = Arc::new(Api::new(playback, library, events, settings.token.clone()));
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!("Failed to accept a remote API client: {e}");
+                    continue;
+                }
+            };
+            let api = api.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| api.clone().handle(request));
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .with_upgrades()
+                    .await
+                {
+                    tracing::debug!("Remote API client {peer} disconnected: {e}");
+                }
+            });
+        }
+    });
+    Ok(local_address)
+}
@@ -0,0 +1,417 @@
+use anyhow::anyhow;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::{Method, Request, Response, StatusCode, Uri};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::error::CommandError;
+use crate::remote::{websocket, RemoteEvent};
+use muz_core::artwork::Artwork;
+use muz_core::bus::EventBus;
+use muz_core::command::PlaybackCommand;
+use muz_core::events::{QueueChangedEvent, TrackChangedEvent};
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+use muz_core::track::Track;
+
+/// Request bodies are small JSON documents
+const MAX_BODY: usize = 1024 * 1024;
+
+type Body = Full<Bytes>;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TrackIds {
+    track_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Reorder {
+    old_index: usize,
+    new_index: usize,
+}
+
+/// Same as the `play_from_library` command: the whole album when it is given.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PlayFromLibrary {
+    track_id: String,
+    album: Option<String>,
+    artist: Option<String>,
+}
+
+/// A `CommandError` body with the HTTP status matching its code.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    error: CommandError,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            error: CommandError {
+                code,
+                message: message.into(),
+                details: None,
+            },
+        }
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "INVALID_REQUEST", message)
+    }
+
+    fn into_response(self) -> Response<Body> {
+        let mut response = match serde_json::to_vec(&self.error) {
+            Ok(body) => json_response(body),
+            Err(_) => Response::new(Body::default()),
+        };
+        *response.status_mut() = self.status;
+        response
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = CommandError::from(error);
+        let status = match error.code {
+            "TRACK_NOT_FOUND" | "ALBUM_NOT_FOUND" | "TRACK_NOT_IN_QUEUE" => StatusCode::NOT_FOUND,
+            "SPEED_OUT_OF_RANGE" | "PITCH_OUT_OF_RANGE" | "QUEUE_INDEX_OUT_OF_BOUNDS" => {
+                StatusCode::BAD_REQUEST
+            }
+            "NOTHING_TO_PLAY" | "NOT_PAUSED" | "QUEUE_UNAVAILABLE" | "ANALYSIS_ALREADY_RUNNING" => {
+                StatusCode::CONFLICT
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self { status, error }
+    }
+}
+
+/// The REST endpoints and the event WebSocket, backed by the same services as the
+/// desktop UI.
+pub struct Api {
+    playback: PlaybackService,
+    library: LibraryService,
+    events: EventBus,
+    token: Option<String>,
+}
+
+impl Api {
+    pub fn new(
+        playback: PlaybackService,
+        library: LibraryService,
+        events: EventBus,
+        token: Option<String>,
+    ) -> Self {
+        Self {
+            playback,
+            library,
+            events,
+            token,
+        }
+    }
+
+    pub async fn handle(
+        self: Arc<Self>,
+        request: Request<Incoming>,
+    ) -> Result<Response<Body>, Infallible> {
+        Ok(self
+            .route(request)
+            .await
+            .unwrap_or_else(ApiError::into_response))
+    }
+
+    async fn route(&self, request: Request<Incoming>) -> Result<Response<Body>, ApiError> {
+        if self.token.is_none() && !is_local(&request) {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "FORBIDDEN",
+                "Only local clients may use the API without a token",
+            ));
+        }
+        if !self.is_authorized(&request) {
+            return Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "A valid token is required",
+            ));
+        }
+        let segments: Vec<String> = request
+            .uri()
+            .path()
+            .trim_matches('/')
+            .split('/')
+            .map(|s| percent_decode(s, false))
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let method = request.method().clone();
+
+        match (method, segments.as_slice()) {
+            (Method::GET, ["api", "status"]) => json(&self.playback.status()?),
+            (Method::POST, ["api", "playback"]) => {
+                let command: PlaybackCommand = read_json(request).await?;
+                json(&self.playback.control_playback(command)?)
+            }
+
+            (Method::GET, ["api", "queue"]) => json(&self.playback.queue()?),
+            (Method::POST, ["api", "queue"]) => {
+                let TrackIds { track_ids } = read_json(request).await?;
+                self.playback
+                    .enqueue(self.library_tracks(&track_ids).await?)?;
+                json(&self.playback.queue()?)
+            }
+            (Method::PUT, ["api", "queue"]) => {
+                let TrackIds { track_ids } = read_json(request).await?;
+                self.playback
+                    .clear_queue_and_enqueue(self.library_tracks(&track_ids).await?)?;
+                json(&self.playback.queue()?)
+            }
+            (Method::DELETE, ["api", "queue"]) => {
+                self.playback.clear_queue()?;
+                json(&self.playback.queue()?)
+            }
+            (Method::POST, ["api", "queue", "reorder"]) => {
+                let Reorder {
+                    old_index,
+                    new_index,
+                } = read_json(request).await?;
+                self.playback.reorder_queue(old_index, new_index)?;
+                json(&self.playback.queue()?)
+            }
+            (Method::DELETE, ["api", "queue", track_id]) => {
+                self.playback.remove_from_queue(track_id)?;
+                json(&self.playback.queue()?)
+            }
+            (Method::POST, ["api", "queue", track_id, "play"]) => {
+                json(&self.playback.select_from_queue(track_id)?)
+            }
+
+            (Method::GET, ["api", "library", "artists"]) => {
+                json(&self.library.albums_by_artist().await?)
+            }
+            (Method::GET, ["api", "library", "tracks"]) => json(&self.library.tracks().await),
+            (Method::GET, ["api", "library", "search"]) => {
+                let query = query_param(request.uri(), "q")
+                    .ok_or_else(|| ApiError::invalid("The `q` parameter is required"))?;
+                json(&self.library.search(&query).await)
+            }
+            (Method::POST, ["api", "library", "play"]) => {
+                let play: PlayFromLibrary = read_json(request).await?;
+                let state = match (play.album, play.artist) {
+                    (Some(album), Some(artist)) => {
+                        let tracks = self.library.tracks_by_album(&album, &artist).await?;
+                        self.playback.play_album_tracks(tracks, &play.track_id)?
+                    }
+                    _ => {
+                        let track = self.library.track_by_id(&play.track_id).await?;
+                        self.playback.play_single_track(track)?
+                    }
+                };
+                json(&state)
+            }
+            (Method::GET, ["api", "tracks", track_id]) => {
+                json(&self.library.track_by_id(track_id).await?)
+            }
+            (Method::GET, ["api", "tracks", track_id, "artwork"]) => {
+                let track = self.library.track_by_id(track_id).await?;
+                let artwork = tokio::task::spawn_blocking(move || Artwork::load(&track))
+                    .await
+                    .map_err(|e| anyhow!("Failed to load the artwork: {e}"))?
+                    .ok_or_else(|| {
+                        ApiError::new(StatusCode::NOT_FOUND, "ARTWORK_NOT_FOUND", "No artwork")
+                    })?;
+                let mut response = Response::new(Body::from(artwork.data));
+                if let Ok(media_type) = HeaderValue::from_str(&artwork.media_type) {
+                    response
+                        .headers_mut()
+                        .insert(header::CONTENT_TYPE, media_type);
+                }
+                Ok(response)
+            }
+
+            (Method::GET, ["api", "events"]) => self.websocket(request),
+            _ => Err(ApiError::new(
+                StatusCode::NOT_FOUND,
+                "NOT_FOUND",
+                format!("No such endpoint: {}", request.uri().path()),
+            )),
+        }
+    }
+
+    /// Browsers can't set headers on WebSocket requests, so the token may also be given
+    /// in the query.
+    fn is_authorized(&self, request: &Request<Incoming>) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        let bearer = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        bearer == Some(token.as_str())
+            || query_param(request.uri(), "token").as_deref() == Some(token.as_str())
+    }
+
+    async fn library_tracks(&self, track_ids: &[String]) -> Result<Vec<Track>, ApiError> {
+        let mut tracks = Vec::with_capacity(track_ids.len());
+        for track_id in track_ids {
+            tracks.push(self.library.track_by_id(track_id).await?);
+        }
+        Ok(tracks)
+    }
+
+    /// Upgrades to a WebSocket that starts with the current track and queue.
+    fn websocket(&self, mut request: Request<Incoming>) -> Result<Response<Body>, ApiError> {
+        let key = request
+            .headers()
+            .get(header::SEC_WEBSOCKET_KEY)
+            .and_then(|key| key.to_str().ok())
+            .ok_or_else(|| ApiError::invalid("Expected a WebSocket upgrade"))?;
+        let accept = HeaderValue::from_str(&websocket::accept_key(key))
+            .map_err(|e| anyhow!("Invalid WebSocket key: {e}"))?;
+
+        // The snapshot is as of the first event streamed, nothing falls in between
+        let (snapshot, events) = self.events.subscribe();
+        let mut initial = vec![
+            RemoteEvent::TrackChanged(Box::new(TrackChangedEvent {
+                track: snapshot.track,
+            })),
+            RemoteEvent::QueueChanged(QueueChangedEvent {
+                queue: snapshot.queue,
+            }),
+        ];
+        initial.extend(snapshot.player.map(RemoteEvent::StateChanged));
+        let upgrade = hyper::upgrade::on(&mut request);
+        tokio::spawn(async move {
+            let result = match upgrade.await {
+                Ok(upgraded) => websocket::stream_events(upgraded, initial, events)
+                    .await
+                    .map_err(anyhow::Error::from),
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = result {
+                tracing::debug!("WebSocket client disconnected: {e}");
+            }
+        });
+
+        let mut response = Response::new(Body::default());
+        *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+        let headers = response.headers_mut();
+        headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+        headers.insert(header::SEC_WEBSOCKET_ACCEPT, accept);
+        Ok(response)
+    }
+}
+
+/// Without a token, any program on this machine may use the API but web pages the user
+/// has open may not: their requests carry the page's `Origin`, or a rebound `Host`.
+fn is_local(request: &Request<Incoming>) -> bool {
+    let value_of = |name| {
+        request
+            .headers()
+            .get(name)
+            .map(|value| value.to_str().unwrap_or_default())
+    };
+    let origin_is_local = value_of(header::ORIGIN).is_none_or(|origin| {
+        origin
+            .split_once("://")
+            .is_some_and(|(_, authority)| is_loopback_host(authority))
+    });
+    origin_is_local && value_of(header::HOST).is_some_and(is_loopback_host)
+}
+
+/// Whether `authority`, a host with an optional port, names this machine.
+fn is_loopback_host(authority: &str) -> bool {
+    let host = match authority.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    let host = host.to_ascii_lowercase();
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Only JSON bodies are taken, so that pages can't send commands as simple form posts.
+async fn read_json<T: DeserializeOwned>(request: Request<Incoming>) -> Result<T, ApiError> {
+    let is_json = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"));
+    if !is_json {
+        return Err(ApiError::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "UNSUPPORTED_MEDIA_TYPE",
+            "Request bodies must be application/json",
+        ));
+    }
+    let body = Limited::new(request.into_body(), MAX_BODY)
+        .collect()
+        .await
+        .map_err(|e| ApiError::invalid(format!("Failed to read the request body: {e}")))?
+        .to_bytes();
+    serde_json::from_slice(&body)
+        .map_err(|e| ApiError::invalid(format!("Invalid request body: {e}")))
+}
+
+fn json<T: Serialize>(value: &T) -> Result<Response<Body>, ApiError> {
+    let body = serde_json::to_vec(value).map_err(anyhow::Error::from)?;
+    Ok(json_response(body))
+}
+
+fn json_response(body: Vec<u8>) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn query_param(uri: &Uri, name: &str) -> Option<String> {
+    uri.query()?.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        (percent_decode(key, true) == name).then(|| percent_decode(value, true))
+    })
+}
+
+/// Decodes `%XX` escapes, and `+` as a space in query strings.
+pub(crate) fn percent_decode(value: &str, query: bool) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = match (byte, tail) {
+            (b'%', [high, low, ..]) => std::str::from_utf8(&[*high, *low])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(if query && byte == b'+' { b' ' } else { byte });
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+#[path = "./routes.tests.rs"]
+mod tests;
@@ -0,0 +1,351 @@
+use super::*;
+use crate::remote::serve;
+use crate::testing::{self, TestLibrary};
+use muz_core::config::RemoteSettings;
+use muz_core::driver::{null::NullPlaybackDriver, render::Clock};
+use muz_core::library::Library;
+use muz_core::playback::Playback;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+async fn start(library: &TestLibrary, token: Option<&str>) -> SocketAddr {
+    let events = EventBus::new();
+    let playback = Playback::create(
+        Box::new(NullPlaybackDriver::new(Clock::RealTime, 1.0)),
+        events.clone(),
+    );
+    let tracks = library.scan().await;
+    let library = LibraryService::new(Arc::new(tokio::sync::Mutex::new(tracks)), events.clone());
+    let settings = RemoteSettings {
+        enabled: true,
+        address: "127.0.0.1:0".to_string(),
+        token: token.map(str::to_string),
+    };
+    serve(&settings, PlaybackService::new(playback), library, events)
+        .await
+        .unwrap()
+}
+
+/// Sends one request with a JSON body, returning the status and the body.
+async fn request(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    body: Option<Value>,
+) -> (u16, Vec<u8>) {
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let headers = "Content-Type: application/json\r\n";
+    let (status, _, body) = testing::request(address, method, path, headers, &body).await;
+    (status, body)
+}
+
+async fn request_json(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    body: Option<Value>,
+) -> (u16, Value) {
+    let (status, body) = request(address, method, path, body).await;
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn titles(tracks: &Value) -> Vec<&str> {
+    tracks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["metadata"]["title"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_library_queue_and_playback() {
+    let library = TestLibrary::create();
+    let address = start(&library, None).await;
+
+    let (status, found) = request_json(address, "GET", "/api/library/search?q=alp", None).await;
+    assert_eq!(status, 200);
+    assert_eq!(titles(&found), vec!["Alpha.wav"]);
+    let alpha = found[0]["id"].as_str().unwrap().to_string();
+    let (_, tracks) = request_json(address, "GET", "/api/library/tracks", None).await;
+    let mut ids: Vec<String> = tracks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap().to_string())
+        .collect();
+    ids.sort_by_key(|id| *id != alpha);
+
+    let (status, queue) = request_json(
+        address,
+        "PUT",
+        "/api/queue",
+        Some(serde_json::json!({ "trackIds": ids })),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(titles(&queue), vec!["Alpha.wav", "Beta.wav"]);
+
+    let (status, snapshot) = request_json(
+        address,
+        "POST",
+        "/api/playback",
+        Some(serde_json::json!({ "type": "Play" })),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(snapshot["state"], "Playing");
+    assert_eq!(snapshot["track"]["id"], alpha.as_str());
+    let (_, queue) = request_json(address, "GET", "/api/queue", None).await;
+    assert_eq!(titles(&queue), vec!["Beta.wav"]);
+
+    let (status, queue) =
+        request_json(address, "DELETE", &format!("/api/queue/{}", ids[1]), None).await;
+    assert_eq!(status, 200);
+    assert_eq!(queue, serde_json::json!([]));
+
+    let (status, artwork) = request(
+        address,
+        "GET",
+        &format!("/api/tracks/{alpha}/artwork"),
+        None,
+    )
+    .await;
+    assert_eq!((status, artwork.as_slice()), (200, &b"jpeg"[..]));
+}
+
+#[tokio::test]
+async fn test_errors() {
+    let library = TestLibrary::create();
+    let address = start(&library, None).await;
+
+    let (status, error) = request_json(address, "GET", "/api/tracks/missing", None).await;
+    assert_eq!(
+        (status, error["code"].as_str()),
+        (404, Some("TRACK_NOT_FOUND"))
+    );
+    let (status, error) = request_json(
+        address,
+        "POST",
+        "/api/playback",
+        Some(serde_json::json!({ "type": "SetVolume", "volume": 2.0 })),
+    )
+    .await;
+    assert_eq!(
+        (status, error["code"].as_str()),
+        (400, Some("INVALID_REQUEST"))
+    );
+    let (status, error) = request_json(address, "GET", "/api/nothing", None).await;
+    assert_eq!((status, error["code"].as_str()), (404, Some("NOT_FOUND")));
+    // Nothing was queued yet
+    let (status, error) = request_json(
+        address,
+        "POST",
+        "/api/queue/reorder",
+        Some(serde_json::json!({ "oldIndex": 1, "newIndex": 0 })),
+    )
+    .await;
+    assert_eq!(
+        (status, error["code"].as_str()),
+        (409, Some("QUEUE_UNAVAILABLE"))
+    );
+}
+
+#[tokio::test]
+async fn test_token_is_required_when_set() {
+    let library = TestLibrary::create();
+    let address = start(&library, Some("secret")).await;
+
+    let (status, error) = request_json(address, "GET", "/api/status", None).await;
+    assert_eq!(
+        (status, error["code"].as_str()),
+        (401, Some("UNAUTHORIZED"))
+    );
+    let (status, _) = request(address, "GET", "/api/status?token=secret", None).await;
+    assert_eq!(status, 200);
+
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream
+        .write_all(
+            b"GET /api/status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+              Authorization: Bearer secret\r\n\r\n",
+        )
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+}
+
+#[tokio::test]
+async fn test_exposed_address_requires_a_token() {
+    let settings = RemoteSettings {
+        enabled: true,
+        address: "0.0.0.0:0".to_string(),
+        token: None,
+    };
+    let events = EventBus::new();
+    let library = LibraryService::new(
+        Arc::new(tokio::sync::Mutex::new(Library::new(
+            PathBuf::from("/nonexistent"),
+            "Test".to_string(),
+        ))),
+        events.clone(),
+    );
+    let playback = Playback::create(
+        Box::new(NullPlaybackDriver::new(Clock::RealTime, 1.0)),
+        events.clone(),
+    );
+    let result = serve(&settings, PlaybackService::new(playback), library, events).await;
+    assert!(result.is_err());
+}
+
+/// The status of a request sent raw, with headers of its own in place of `Host`.
+async fn raw_status(address: SocketAddr, method: &str, path: &str, headers: &str) -> u16 {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let request = format!(
+        "{method} {path} HTTP/1.1\r\nConnection: close\r\nContent-Length: 0\r\n{headers}\r\n"
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response[9..12].parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_pages_from_other_origins_are_refused() {
+    let library = TestLibrary::create();
+    let address = start(&library, None).await;
+
+    let evil = "Origin: https://evil.example\r\n";
+    let (status, _, body) = testing::request(address, "GET", "/api/status", evil, "").await;
+    let error: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!((status, error["code"].as_str()), (403, Some("FORBIDDEN")));
+    let (status, _, _) = testing::request(address, "GET", "/api/events", evil, "").await;
+    assert_eq!(status, 403);
+    let headers = "Origin: https://evil.example\r\nContent-Type: text/plain\r\n";
+    let command = r#"{"type":"Pause"}"#;
+    let (status, _, _) = testing::request(address, "POST", "/api/playback", headers, command).await;
+    assert_eq!(status, 403);
+
+    let local = "Origin: http://localhost:5173\r\n";
+    let (status, _, _) = testing::request(address, "GET", "/api/status", local, "").await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn test_rebound_host_names_are_refused() {
+    let library = TestLibrary::create();
+    let address = start(&library, None).await;
+
+    let rebound = "Host: rebound.example\r\n";
+    assert_eq!(
+        raw_status(address, "GET", "/api/library/tracks", rebound).await,
+        403
+    );
+    assert_eq!(
+        raw_status(address, "GET", "/api/library/tracks", "").await,
+        403
+    );
+    for host in ["localhost:4000", "127.0.0.1", "[::1]:4000"] {
+        let headers = format!("Host: {host}\r\n");
+        assert_eq!(
+            raw_status(address, "GET", "/api/status", &headers).await,
+            200
+        );
+    }
+
+    // A token makes up for both, for clients on other machines
+    let address = start(&library, Some("secret")).await;
+    let headers = "Host: muz.lan\r\nOrigin: https://remote.example\r\n\
+                   Authorization: Bearer secret\r\n";
+    assert_eq!(
+        raw_status(address, "GET", "/api/status", headers).await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn test_bodies_must_be_json() {
+    let library = TestLibrary::create();
+    let address = start(&library, None).await;
+
+    let command = r#"{"type":"Pause"}"#;
+    for headers in ["Content-Type: text/plain\r\n", ""] {
+        let (status, _, body) =
+            testing::request(address, "POST", "/api/playback", headers, command).await;
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            (status, error["code"].as_str()),
+            (415, Some("UNSUPPORTED_MEDIA_TYPE"))
+        );
+    }
+    let headers = "Content-Type: application/json; charset=utf-8\r\n";
+    let (status, _, _) = testing::request(address, "POST", "/api/playback", headers, command).await;
+    assert_eq!(status, 200);
+}
+
+async fn next_event(stream: &mut TcpStream) -> Value {
+    let (opcode, payload) = websocket::read_frame(stream).await.unwrap();
+    assert_eq!(opcode, websocket::OPCODE_TEXT);
+    serde_json::from_slice(&payload).unwrap()
+}
+
+#[tokio::test]
+async fn test_websocket_streams_events() {
+    let library = TestLibrary::create();
+    let address = start(&library, None).await;
+    let (_, tracks) = request_json(address, "GET", "/api/library/tracks", None).await;
+    let track_id = tracks[0]["id"].as_str().unwrap().to_string();
+
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream
+        .write_all(
+            b"GET /api/events HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+              Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+              Sec-WebSocket-Version: 13\r\n\r\n",
+        )
+        .await
+        .unwrap();
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        head.push(stream.read_u8().await.unwrap());
+    }
+    let head = String::from_utf8(head).unwrap().to_lowercase();
+    assert!(head.starts_with("http/1.1 101"), "{head}");
+    assert!(head.contains("sec-websocket-accept: s3pplmbitxaq9kygzzhzrbk+xoo="));
+
+    let event = next_event(&mut stream).await;
+    assert_eq!(event["event"], "track-changed");
+    assert_eq!(event["payload"]["track"], Value::Null);
+    assert_eq!(next_event(&mut stream).await["event"], "queue-changed");
+    let event = next_event(&mut stream).await;
+    assert_eq!(event["event"], "state-changed");
+    assert_eq!(event["payload"]["state"], "Stopped");
+
+    let (status, _) = request(
+        address,
+        "POST",
+        "/api/library/play",
+        Some(serde_json::json!({ "trackId": track_id })),
+    )
+    .await;
+    assert_eq!(status, 200);
+    let event = next_event(&mut stream).await;
+    assert_eq!(event["event"], "track-changed");
+    assert_eq!(event["payload"]["track"]["id"], track_id.as_str());
+}
+
+#[test]
+fn test_query_parameters_are_decoded() {
+    let uri: Uri = "/api/library/search?token=a%2Bb&q=sigur+r%C3%B3s"
+        .parse()
+        .unwrap();
+    assert_eq!(query_param(&uri, "q").as_deref(), Some("sigur rós"));
+    assert_eq!(query_param(&uri, "token").as_deref(), Some("a+b"));
+    assert_eq!(query_param(&uri, "missing"), None);
+    assert_eq!(percent_decode("a+b%2", false), "a+b%2");
+}
@@ -0,0 +1,191 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::remote::RemoteEvent;
+use muz_core::bus::BusEvent;
+
+/// Appended to the client key to build the `Sec-WebSocket-Accept` header (RFC 6455).
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Clients only send control frames, anything larger is refused
+const MAX_PAYLOAD: u64 = 64 * 1024;
+
+pub const OPCODE_TEXT: u8 = 0x1;
+pub const OPCODE_CLOSE: u8 = 0x8;
+pub const OPCODE_PING: u8 = 0x9;
+pub const OPCODE_PONG: u8 = 0xA;
+
+pub fn accept_key(key: &str) -> String {
+    STANDARD.encode(sha1(format!("{}{GUID}", key.trim()).as_bytes()))
+}
+
+/// A final, unmasked frame as sent by a server.
+pub fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Reads one frame, unmasking its payload. Fragmented messages aren't reassembled, each
+/// frame is returned on its own.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header).await?;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+    let len = match header[1] & 0x7F {
+        126 => reader.read_u16().await? as u64,
+        127 => reader.read_u64().await?,
+        len => len as u64,
+    };
+    if len > MAX_PAYLOAD {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Frame of {len} bytes is too large"),
+        ));
+    }
+    let mut mask = [0u8; 4];
+    if masked {
+        reader.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload).await?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+    Ok((opcode, payload))
+}
+
+/// Sends `initial` then every event as a text message until the client closes the
+/// connection, answering its pings meanwhile.
+pub async fn stream_events(
+    upgraded: Upgraded,
+    initial: Vec<RemoteEvent>,
+    mut events: broadcast::Receiver<BusEvent>,
+) -> io::Result<()> {
+    let (mut reader, mut writer) = tokio::io::split(TokioIo::new(upgraded));
+    // Frames are read on their own task, reads can't be cancelled halfway by `select!`
+    let (replies, mut pending_replies) = mpsc::channel::<(u8, Vec<u8>)>(8);
+    let reading = tokio::spawn(async move {
+        while let Ok((opcode, payload)) = read_frame(&mut reader).await {
+            let reply = match opcode {
+                OPCODE_PING => (OPCODE_PONG, payload),
+                OPCODE_CLOSE => (OPCODE_CLOSE, payload),
+                _ => continue,
+            };
+            if replies.send(reply).await.is_err() || opcode == OPCODE_CLOSE {
+                break;
+            }
+        }
+    });
+
+    let result = async {
+        for event in initial {
+            send_event(&mut writer, &event).await?;
+        }
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(BusEvent { event, .. }) => {
+                        if let Some(event) = RemoteEvent::from_event(&event) {
+                            send_event(&mut writer, &event).await?;
+                        }
+                    }
+                    // Progress and spectrum updates are superseded by the next ones anyway
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("WebSocket client skipped {skipped} events");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                reply = pending_replies.recv() => match reply {
+                    Some((opcode, payload)) => {
+                        writer.write_all(&encode_frame(opcode, &payload)).await?;
+                        if opcode == OPCODE_CLOSE {
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+            }
+        }
+        Ok(())
+    }
+    .await;
+    reading.abort();
+    result
+}
+
+async fn send_event<W: AsyncWrite + Unpin>(writer: &mut W, event: &RemoteEvent) -> io::Result<()> {
+    let json = serde_json::to_vec(event)?;
+    writer.write_all(&encode_frame(OPCODE_TEXT, &json)).await
+}
+
+/// SHA-1, only used for the handshake.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, state) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&state.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+#[path = "./websocket.tests.rs"]
+mod tests;
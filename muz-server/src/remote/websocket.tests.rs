@@ -0,0 +1,58 @@
+use super::*;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn test_sha1() {
+    assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(
+        hex(&sha1(b"abc")),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    // Two blocks once padded
+    assert_eq!(
+        hex(&sha1(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+}
+
+#[test]
+fn test_accept_key_from_rfc_6455() {
+    assert_eq!(
+        accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+}
+
+#[test]
+fn test_frame_lengths() {
+    assert_eq!(encode_frame(OPCODE_TEXT, b"hi"), vec![0x81, 2, b'h', b'i']);
+    let frame = encode_frame(OPCODE_TEXT, &[0; 300]);
+    assert_eq!(&frame[..4], &[0x81, 126, 1, 44]);
+    let frame = encode_frame(OPCODE_TEXT, &[0; 70_000]);
+    assert_eq!(&frame[..2], &[0x81, 127]);
+    assert_eq!(frame.len(), 10 + 70_000);
+}
+
+#[tokio::test]
+async fn test_read_masked_frame() {
+    let mask = [1, 2, 3, 4];
+    let mut frame = vec![0x80 | OPCODE_PING, 0x80 | 5];
+    frame.extend_from_slice(&mask);
+    frame.extend(b"hello".iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    let (opcode, payload) = read_frame(&mut frame.as_slice()).await.unwrap();
+    assert_eq!((opcode, payload.as_slice()), (OPCODE_PING, &b"hello"[..]));
+
+    let mut unmasked = encode_frame(OPCODE_CLOSE, &[]);
+    assert_eq!(
+        read_frame(&mut unmasked.as_slice()).await.unwrap(),
+        (OPCODE_CLOSE, vec![])
+    );
+    unmasked[1] = 127;
+    unmasked.extend_from_slice(&u64::MAX.to_be_bytes());
+    assert!(read_frame(&mut unmasked.as_slice()).await.is_err());
+}
@@ -0,0 +1,201 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::scrobble::{Listen, SubmitError};
+use muz_core::config::LastFmSettings;
+
+/// Error codes worth retrying: the service was down or busy, or the account or API key
+/// were refused, which isn't the listens' fault. The others are about the request.
+const TEMPORARY_ERRORS: [i64; 8] = [4, 8, 9, 10, 11, 16, 26, 29];
+/// Invalid session key, to be requested again
+const INVALID_SESSION: i64 = 9;
+
+type Params = Vec<(String, String)>;
+
+/// The Last.fm API, which takes signed form posts in a session.
+pub struct LastFm {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    secret: String,
+    /// Only used to request a session when none is configured
+    login: Option<(String, String)>,
+    session_key: Mutex<Option<String>>,
+}
+
+impl LastFm {
+    pub fn new(client: reqwest::Client, settings: &LastFmSettings) -> Result<Self> {
+        let (Some(api_key), Some(secret)) = (&settings.api_key, &settings.secret) else {
+            bail!("Last.fm needs an API key and secret");
+        };
+        let login = match (&settings.username, &settings.password) {
+            (Some(username), Some(password)) => Some((username.clone(), password.clone())),
+            _ => None,
+        };
+        if settings.session_key.is_none() && login.is_none() {
+            bail!("Last.fm needs a session key, or a username and password");
+        }
+        Ok(Self {
+            client,
+            url: settings.url.clone(),
+            api_key: api_key.clone(),
+            secret: secret.clone(),
+            login,
+            session_key: Mutex::new(settings.session_key.clone()),
+        })
+    }
+
+    pub async fn now_playing(&self, listen: &Listen) -> Result<(), SubmitError> {
+        let mut params = vec![
+            param("artist", &listen.artist),
+            param("track", &listen.title),
+            param("duration", listen.duration_ms / 1000),
+        ];
+        if let Some(album) = &listen.album {
+            params.push(param("album", album));
+        }
+        if let Some(number) = listen.track_number {
+            params.push(param("trackNumber", number));
+        }
+        self.call_in_session("track.updateNowPlaying", params)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn submit(&self, listens: &[Listen]) -> Result<(), SubmitError> {
+        let response = self
+            .call_in_session("track.scrobble", scrobbles(listens))
+            .await?;
+        let ignored = &response["scrobbles"]["@attr"]["ignored"];
+        if ignored
+            .as_u64()
+            .or(ignored.as_str().and_then(|n| n.parse().ok()))
+            > Some(0)
+        {
+            tracing::debug!("Last.fm ignored {ignored} of {} scrobbles", listens.len());
+        }
+        Ok(())
+    }
+
+    /// Calls `method` with the session key, requesting one first when needed.
+    async fn call_in_session(
+        &self,
+        method: &str,
+        mut params: Params,
+    ) -> Result<Value, SubmitError> {
+        let mut session_key = self.session_key.lock().await;
+        let key = match &*session_key {
+            Some(key) => key.clone(),
+            None => session_key.insert(self.request_session().await?).clone(),
+        };
+        params.push(param("sk", key));
+        let result = self.call(method, params).await;
+        if matches!(&result, Err(Error::Service(INVALID_SESSION, _))) && self.login.is_some() {
+            *session_key = None;
+        }
+        Ok(result?)
+    }
+
+    async fn request_session(&self) -> Result<String, SubmitError> {
+        let (username, password) = self
+            .login
+            .as_ref()
+            .ok_or_else(|| SubmitError::Failed("the Last.fm session key is invalid".to_string()))?;
+        let params = vec![param("username", username), param("password", password)];
+        let response = self.call("auth.getMobileSession", params).await?;
+        response["session"]["key"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| SubmitError::Failed("Last.fm sent no session key".to_string()))
+    }
+
+    async fn call(&self, method: &str, mut params: Params) -> Result<Value, Error> {
+        params.push(param("method", method));
+        params.push(param("api_key", &self.api_key));
+        params.push(param("api_sig", sign(&params, &self.secret)));
+        params.push(param("format", "json"));
+        let response = self.client.post(&self.url).form(&params).send().await?;
+        let status = response.status();
+        let body: Value = match response.json().await {
+            Ok(body) => body,
+            Err(_) => return Err(Error::Http(status.to_string())),
+        };
+        match body["error"].as_i64() {
+            Some(code) => {
+                let message = body["message"].as_str().unwrap_or_default().to_string();
+                Err(Error::Service(code, message))
+            }
+            None if status.is_success() => Ok(body),
+            None => Err(Error::Http(status.to_string())),
+        }
+    }
+}
+
+/// A failed call, before it is known whether it can be retried.
+#[derive(Debug)]
+enum Error {
+    Http(String),
+    Service(i64, String),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e.to_string())
+    }
+}
+
+impl From<Error> for SubmitError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Http(message) => Self::Failed(message),
+            Error::Service(code, message) if TEMPORARY_ERRORS.contains(&code) => {
+                Self::Failed(format!("error {code}: {message}"))
+            }
+            Error::Service(code, message) => Self::Rejected(format!("error {code}: {message}")),
+        }
+    }
+}
+
+fn param(name: &str, value: impl ToString) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+/// The indexed parameters of a batch of scrobbles.
+fn scrobbles(listens: &[Listen]) -> Params {
+    let mut params = Vec::new();
+    for (i, listen) in listens.iter().enumerate() {
+        params.push(param(&format!("artist[{i}]"), &listen.artist));
+        params.push(param(&format!("track[{i}]"), &listen.title));
+        params.push(param(&format!("timestamp[{i}]"), listen.listened_at));
+        params.push(param(&format!("duration[{i}]"), listen.duration_ms / 1000));
+        if let Some(album) = &listen.album {
+            params.push(param(&format!("album[{i}]"), album));
+        }
+        if let Some(number) = listen.track_number {
+            params.push(param(&format!("trackNumber[{i}]"), number));
+        }
+    }
+    params
+}
+
+/// The `api_sig` of a call: the MD5 of its parameters sorted by name and concatenated
+/// with their values, then the shared secret.
+pub fn sign(params: &[(String, String)], secret: &str) -> String {
+    let mut params: Vec<_> = params
+        .iter()
+        .filter(|(name, _)| name != "format" && name != "callback")
+        .collect();
+    params.sort();
+    let mut signed = String::new();
+    for (name, value) in params {
+        signed.push_str(name);
+        signed.push_str(value);
+    }
+    signed.push_str(secret);
+    format!("{:x}", md5::compute(signed))
+}
+
+#[cfg(test)]
+#[path = "./lastfm.tests.rs"]
+mod tests;
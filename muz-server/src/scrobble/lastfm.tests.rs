@@ -0,0 +1,43 @@
+use super::*;
+
+#[test]
+fn test_sign() {
+    let params = vec![
+        param("method", "track.scrobble"),
+        param("api_key", "key"),
+        param("artist[0]", "Air"),
+        param("format", "json"),
+    ];
+    let expected = md5::compute("api_keykeyartist[0]Airmethodtrack.scrobblesecret");
+    assert_eq!(sign(&params, "secret"), format!("{expected:x}"));
+}
+
+#[test]
+fn test_scrobbles() {
+    let listens = [
+        Listen {
+            artist: "Air".to_string(),
+            title: "Sexy Boy".to_string(),
+            album: Some("Moon Safari".to_string()),
+            track_number: Some(2),
+            duration_ms: 298_500,
+            listened_at: 1_700_000_000,
+        },
+        Listen {
+            artist: "Björk".to_string(),
+            title: "Crying".to_string(),
+            album: None,
+            track_number: None,
+            duration_ms: 290_000,
+            listened_at: 1_700_000_300,
+        },
+    ];
+    let params = scrobbles(&listens);
+    assert!(params.contains(&param("album[0]", "Moon Safari")));
+    assert!(params.contains(&param("trackNumber[0]", 2)));
+    assert!(params.contains(&param("duration[0]", 298)));
+    assert!(params.contains(&param("artist[1]", "Björk")));
+    assert!(params.contains(&param("timestamp[1]", 1_700_000_300)));
+    assert!(!params.iter().any(|(name, _)| name == "album[1]"));
+    assert_eq!(params.len(), 10);
+}
@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::scrobble::{Listen, SubmitError};
+use muz_core::config::ListenBrainzSettings;
+
+/// The ListenBrainz API, which takes listens as JSON with a user token.
+pub struct ListenBrainz {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl ListenBrainz {
+    pub fn new(client: reqwest::Client, settings: &ListenBrainzSettings) -> Result<Self> {
+        let token = settings
+            .token
+            .clone()
+            .context("ListenBrainz needs a user token")?;
+        Ok(Self {
+            client,
+            url: format!("{}/1/submit-listens", settings.url.trim_end_matches('/')),
+            token,
+        })
+    }
+
+    pub async fn now_playing(&self, listen: &Listen) -> Result<(), SubmitError> {
+        self.send("playing_now", vec![payload(listen, false)]).await
+    }
+
+    pub async fn submit(&self, listens: &[Listen]) -> Result<(), SubmitError> {
+        let listen_type = if listens.len() == 1 {
+            "single"
+        } else {
+            "import"
+        };
+        let payload = listens.iter().map(|listen| payload(listen, true)).collect();
+        self.send(listen_type, payload).await
+    }
+
+    async fn send(&self, listen_type: &str, payload: Vec<Value>) -> Result<(), SubmitError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .header("Authorization", format!("Token {}", self.token))
+            .json(&json!({ "listen_type": listen_type, "payload": payload }))
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body: Value = response.json().await.unwrap_or_default();
+        let message = match body["error"].as_str() {
+            Some(error) => format!("{status}: {error}"),
+            None => status.to_string(),
+        };
+        // Bad requests are about the listens, anything else may pass later
+        match status {
+            StatusCode::BAD_REQUEST => Err(SubmitError::Rejected(message)),
+            _ => Err(SubmitError::Failed(message)),
+        }
+    }
+}
+
+/// A listen as the API takes it, which refuses nulls.
+fn payload(listen: &Listen, listened: bool) -> Value {
+    let mut info = json!({
+        "duration_ms": listen.duration_ms,
+        "media_player": "muz",
+        "submission_client": "muz",
+        "submission_client_version": env!("CARGO_PKG_VERSION"),
+    });
+    if let Some(number) = listen.track_number {
+        info["tracknumber"] = json!(number);
+    }
+    let mut metadata = json!({
+        "artist_name": listen.artist,
+        "track_name": listen.title,
+        "additional_info": info,
+    });
+    if let Some(album) = &listen.album {
+        metadata["release_name"] = json!(album);
+    }
+    let mut payload = json!({ "track_metadata": metadata });
+    if listened {
+        payload["listened_at"] = json!(listen.listened_at);
+    }
+    payload
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::scrobble::lastfm::LastFm;
+use crate::scrobble::listenbrainz::ListenBrainz;
+use crate::scrobble::queue::{Queue, QUEUE_FILE};
+use crate::scrobble::scrobbler::{Scrobbler, RETRY_DELAY};
+use muz_core::bus::BusEvent;
+use muz_core::config::ScrobbleSettings;
+use muz_core::track::Track;
+
+mod lastfm;
+mod listenbrainz;
+mod queue;
+mod scrobbler;
+
+/// A track heard long enough to count, as the services take it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Listen {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub track_number: Option<i32>,
+    pub duration_ms: u64,
+    /// When the track started, in seconds since the epoch
+    pub listened_at: u64,
+}
+
+impl Listen {
+    /// `None` for tracks without the artist and title the services require.
+    pub fn new(track: &Track, listened_at: u64) -> Option<Self> {
+        let metadata = track.metadata.as_ref()?;
+        let artist = metadata.artist.clone().or(metadata.album_artist.clone())?;
+        Some(Self {
+            artist,
+            title: metadata.title.clone()?,
+            album: metadata.album.clone(),
+            track_number: metadata.track_number,
+            duration_ms: track.duration_ms,
+            listened_at,
+        })
+    }
+}
+
+/// Why a submission failed. Rejected listens are dropped, the others stay queued.
+#[derive(Debug, thiserror::Error)]
+pub enum SubmitError {
+    #[error("rejected: {0}")]
+    Rejected(String),
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl From<reqwest::Error> for SubmitError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Failed(e.to_string())
+    }
+}
+
+/// A service listens are submitted to.
+pub enum Service {
+    ListenBrainz(ListenBrainz),
+    LastFm(LastFm),
+}
+
+impl Service {
+    /// The key of its listens in the queue.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ListenBrainz(_) => "listenbrainz",
+            Self::LastFm(_) => "lastfm",
+        }
+    }
+
+    /// Listens submitted at once.
+    pub fn batch_size(&self) -> usize {
+        match self {
+            Self::ListenBrainz(_) => 100,
+            Self::LastFm(_) => 50,
+        }
+    }
+
+    pub async fn now_playing(&self, listen: &Listen) -> Result<(), SubmitError> {
+        match self {
+            Self::ListenBrainz(service) => service.now_playing(listen).await,
+            Self::LastFm(service) => service.now_playing(listen).await,
+        }
+    }
+
+    pub async fn submit(&self, listens: &[Listen]) -> Result<(), SubmitError> {
+        match self {
+            Self::ListenBrainz(service) => service.submit(listens).await,
+            Self::LastFm(service) => service.submit(listens).await,
+        }
+    }
+}
+
+/// Scrobbles what plays to the services enabled in `settings`. Listens not yet accepted
+/// are kept in `data_dir` and retried after restarts.
+pub async fn start(
+    settings: &ScrobbleSettings,
+    events: broadcast::Receiver<BusEvent>,
+    data_dir: Option<&Path>,
+) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("muz/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(30))
+        .build()?;
+    let mut services = Vec::new();
+    if settings.listenbrainz.enabled {
+        let service = ListenBrainz::new(client.clone(), &settings.listenbrainz)?;
+        services.push(Service::ListenBrainz(service));
+    }
+    if settings.lastfm.enabled {
+        services.push(Service::LastFm(LastFm::new(client, &settings.lastfm)?));
+    }
+    let queue = Queue::load(data_dir.map(|dir| dir.join(QUEUE_FILE))).await;
+    Scrobbler::new(services, queue, RETRY_DELAY).run(events);
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::json_file::JsonFile;
+use crate::scrobble::Listen;
+
+/// Listens not yet accepted, kept next to the config.
+pub const QUEUE_FILE: &str = "scrobbles.json";
+
+/// The listens waiting for each service, oldest first, written through on every change.
+/// Without a path they only last as long as the app.
+pub struct Queue {
+    pending: JsonFile<HashMap<String, Vec<Listen>>>,
+}
+
+impl Queue {
+    /// Reads the queue at `path`, starting empty when it is missing or unreadable.
+    pub async fn load(path: Option<PathBuf>) -> Self {
+        Self {
+            pending: JsonFile::load(path, "scrobble queue").await,
+        }
+    }
+
+    pub fn push(&self, service: &str, listen: Listen) -> Result<()> {
+        self.pending.update(|pending| {
+            pending.entry(service.to_string()).or_default().push(listen);
+        })
+    }
+
+    /// Up to `count` of the oldest listens for `service`.
+    pub fn peek(&self, service: &str, count: usize) -> Vec<Listen> {
+        self.pending
+            .get()
+            .get(service)
+            .map(|listens| listens.iter().take(count).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Removes the `count` oldest listens for `service`, once they were submitted.
+    pub fn remove(&self, service: &str, count: usize) -> Result<()> {
+        self.pending.update(|pending| {
+            if let Some(listens) = pending.get_mut(service) {
+                listens.drain(..count.min(listens.len()));
+                if listens.is_empty() {
+                    pending.remove(service);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "./queue.tests.rs"]
+mod tests;
@@ -0,0 +1,37 @@
+use super::*;
+
+fn listen(title: &str) -> Listen {
+    Listen {
+        artist: "Air".to_string(),
+        title: title.to_string(),
+        album: None,
+        track_number: None,
+        duration_ms: 300_000,
+        listened_at: 1_700_000_000,
+    }
+}
+
+#[tokio::test]
+async fn test_keeps_listens_across_restarts() {
+    let dir = std::env::temp_dir().join(format!("muz-scrobbles-{}", uuid::Uuid::new_v4()));
+    let path = dir.join(QUEUE_FILE);
+    let queue = Queue::load(Some(path.clone())).await;
+    for title in ["Sexy Boy", "Kelly Watch the Stars", "Talisman"] {
+        queue.push("lastfm", listen(title)).unwrap();
+    }
+    queue.push("listenbrainz", listen("Remember")).unwrap();
+    queue.remove("lastfm", 1).unwrap();
+
+    let queue = Queue::load(Some(path)).await;
+    assert_eq!(
+        queue.peek("lastfm", 5),
+        [listen("Kelly Watch the Stars"), listen("Talisman")]
+    );
+    assert_eq!(queue.peek("lastfm", 1), [listen("Kelly Watch the Stars")]);
+    assert_eq!(queue.peek("listenbrainz", 5), [listen("Remember")]);
+
+    queue.remove("listenbrainz", 5).unwrap();
+    assert!(queue.peek("listenbrainz", 5).is_empty());
+    assert!(queue.peek("librefm", 5).is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
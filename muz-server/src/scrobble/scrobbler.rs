@@ -0,0 +1,202 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::Notify;
+
+use crate::scrobble::queue::Queue;
+use crate::scrobble::{Listen, Service, SubmitError};
+use muz_core::bus::BusEvent;
+use muz_core::events::PlayerEvent;
+use muz_core::track::Track;
+
+/// Shorter tracks are never scrobbled
+const MIN_DURATION_MS: u64 = 30_000;
+/// Tracks are scrobbled once heard for half their length or this long, whichever is first
+const MAX_THRESHOLD_MS: u64 = 240_000;
+/// Bigger steps between progress events are seeks, not listening
+const MAX_STEP_MS: u64 = 5_000;
+/// Failed submissions are retried after this, doubling up to the maximum
+pub const RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// The track playing and how much of it was heard.
+struct Playing {
+    listen: Listen,
+    total_frames: u64,
+    position_ms: u64,
+    heard_ms: u64,
+    scrobbled: bool,
+}
+
+impl Playing {
+    fn new(track: &Track, now: u64) -> Option<Self> {
+        Some(Self {
+            listen: Listen::new(track, now)?,
+            total_frames: track.total_frames,
+            position_ms: 0,
+            heard_ms: 0,
+            scrobbled: false,
+        })
+    }
+
+    /// Counts the way to `frames_played` as heard unless it was a seek. True when that
+    /// makes the track cross the threshold.
+    fn advance(&mut self, frames_played: u64) -> bool {
+        let Some(position_ms) =
+            (frames_played * self.listen.duration_ms).checked_div(self.total_frames)
+        else {
+            return false;
+        };
+        let step = position_ms.saturating_sub(self.position_ms);
+        if position_ms > self.position_ms && step <= MAX_STEP_MS {
+            self.heard_ms += step;
+        }
+        self.position_ms = position_ms;
+        match threshold(self.listen.duration_ms) {
+            Some(threshold) if !self.scrobbled && self.heard_ms >= threshold => {
+                self.scrobbled = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// How long a track must be heard to be scrobbled, `None` when it is too short.
+fn threshold(duration_ms: u64) -> Option<u64> {
+    (duration_ms >= MIN_DURATION_MS).then(|| (duration_ms / 2).min(MAX_THRESHOLD_MS))
+}
+
+/// Follows playback on the bus and submits to every service, queueing listens until they
+/// are accepted.
+pub struct Scrobbler {
+    services: Vec<Service>,
+    queue: Queue,
+    queued: Notify,
+    retry_delay: Duration,
+}
+
+impl Scrobbler {
+    pub fn new(services: Vec<Service>, queue: Queue, retry_delay: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            services,
+            queue,
+            queued: Notify::new(),
+            retry_delay,
+        })
+    }
+
+    pub fn run(self: Arc<Self>, events: broadcast::Receiver<BusEvent>) {
+        tokio::spawn(self.clone().deliver());
+        tokio::spawn(self.follow(events));
+    }
+
+    async fn follow(self: Arc<Self>, mut events: broadcast::Receiver<BusEvent>) {
+        let mut playing = None;
+        loop {
+            let event = match events.recv().await {
+                Ok(BusEvent { event, .. }) => event,
+                // The skipped events may have changed the track, so whatever comes next
+                // can't be credited to this one
+                Err(RecvError::Lagged(_)) => {
+                    playing = None;
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            match event {
+                PlayerEvent::TrackChanged(changed) => {
+                    playing = changed.track.and_then(|track| Playing::new(&track, now()));
+                    if let Some(playing) = &playing {
+                        tokio::spawn(self.clone().now_playing(playing.listen.clone()));
+                    }
+                }
+                PlayerEvent::Progress(progress) => {
+                    if let Some(playing) = playing.as_mut() {
+                        if playing.advance(progress.frames_played) {
+                            self.enqueue(playing.listen.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn now_playing(self: Arc<Self>, listen: Listen) {
+        for service in &self.services {
+            if let Err(e) = service.now_playing(&listen).await {
+                tracing::debug!("Failed to send now playing to {}: {e}", service.name());
+            }
+        }
+    }
+
+    fn enqueue(&self, listen: Listen) {
+        for service in &self.services {
+            if let Err(e) = self.queue.push(service.name(), listen.clone()) {
+                tracing::warn!("{e:#}");
+            }
+        }
+        self.queued.notify_one();
+    }
+
+    /// Submits the queue whenever listens are added, backing off while it fails.
+    async fn deliver(self: Arc<Self>) {
+        let mut delay = self.retry_delay;
+        loop {
+            match self.flush().await {
+                Ok(()) => {
+                    delay = self.retry_delay;
+                    self.queued.notified().await;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to scrobble, retrying in {delay:?}: {e}");
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY.max(self.retry_delay));
+                }
+            }
+        }
+    }
+
+    /// Submits the listens queued for every service, returning the first failure. A
+    /// failing service doesn't hold back the others.
+    async fn flush(&self) -> Result<(), SubmitError> {
+        let mut result = Ok(());
+        for service in &self.services {
+            if let Err(e) = self.flush_service(service).await {
+                tracing::debug!("Failed to scrobble to {}: {e}", service.name());
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    async fn flush_service(&self, service: &Service) -> Result<(), SubmitError> {
+        loop {
+            let listens = self.queue.peek(service.name(), service.batch_size());
+            if listens.is_empty() {
+                return Ok(());
+            }
+            match service.submit(&listens).await {
+                Ok(()) => {}
+                Err(SubmitError::Rejected(e)) => {
+                    tracing::warn!("{} rejected {} listens: {e}", service.name(), listens.len());
+                }
+                Err(e) => return Err(e),
+            }
+            self.queue
+                .remove(service.name(), listens.len())
+                .map_err(|e| SubmitError::Failed(format!("{e:#}")))?;
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+#[path = "./scrobbler.tests.rs"]
+mod tests;
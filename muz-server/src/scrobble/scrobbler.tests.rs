@@ -0,0 +1,345 @@
+use super::*;
+use crate::remote::routes::percent_decode;
+use crate::scrobble::lastfm::{sign, LastFm};
+use crate::scrobble::listenbrainz::ListenBrainz;
+use crate::scrobble::queue::QUEUE_FILE;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use muz_core::bus::EventBus;
+use muz_core::config::{LastFmSettings, ListenBrainzSettings};
+use muz_core::events::{ProgressEvent, TrackChangedEvent};
+use muz_core::track::TrackMetadata;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::net::TcpListener;
+
+/// A local server standing in for the services, recording what it is sent.
+#[derive(Clone)]
+struct StandIn {
+    url: String,
+    requests: Arc<Mutex<Vec<Received>>>,
+    down: Arc<AtomicBool>,
+}
+
+#[derive(Clone, Debug)]
+struct Received {
+    authorization: Option<String>,
+    body: String,
+}
+
+impl StandIn {
+    /// Answers with `respond(body)`, or 503 while down.
+    async fn start(respond: fn(&str) -> String) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stand_in = Self {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            requests: Arc::default(),
+            down: Arc::default(),
+        };
+        let server = stand_in.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let server = server.clone();
+                let service = service_fn(move |request: Request<Incoming>| {
+                    let server = server.clone();
+                    async move {
+                        let authorization = request
+                            .headers()
+                            .get("authorization")
+                            .map(|value| value.to_str().unwrap().to_string());
+                        let body = request.into_body().collect().await.unwrap().to_bytes();
+                        let body = String::from_utf8(body.to_vec()).unwrap();
+                        let response = if server.down.load(Ordering::SeqCst) {
+                            Response::builder()
+                                .status(503)
+                                .body(Full::new(Bytes::new()))
+                        } else {
+                            Response::builder().body(Full::new(Bytes::from(respond(&body))))
+                        };
+                        server.requests.lock().unwrap().push(Received {
+                            authorization,
+                            body,
+                        });
+                        Ok::<_, Infallible>(response.unwrap())
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        stand_in
+    }
+
+    /// The first `count` requests, waiting for them a few seconds at most.
+    async fn wait_for(&self, count: usize) -> Vec<Received> {
+        for _ in 0..500 {
+            let requests = self.requests.lock().unwrap().clone();
+            if requests.len() >= count {
+                return requests[..count].to_vec();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("The stand-in got {:?}", self.requests.lock().unwrap());
+    }
+}
+
+fn listenbrainz(stand_in: &StandIn) -> Service {
+    let settings = ListenBrainzSettings {
+        enabled: true,
+        url: format!("{}/", stand_in.url),
+        token: Some("t0k3n".to_string()),
+    };
+    Service::ListenBrainz(ListenBrainz::new(reqwest::Client::new(), &settings).unwrap())
+}
+
+/// A minute long track with a frame a millisecond.
+fn track() -> Track {
+    let mut track = Track::new("/music/Air/Moon Safari/02.flac");
+    track.duration_ms = 60_000;
+    track.total_frames = 60_000;
+    track.metadata = Some(TrackMetadata {
+        title: Some("Sexy Boy".to_string()),
+        album: Some("Moon Safari".to_string()),
+        artist: Some("Air".to_string()),
+        album_artist: None,
+        track_number: Some(2),
+        disc_number: None,
+        genre: None,
+        year: None,
+    });
+    track
+}
+
+/// Plays the track up to `seconds` on the bus.
+fn play(events: &EventBus, seconds: u64) {
+    events.publish(PlayerEvent::TrackChanged(Box::new(TrackChangedEvent {
+        track: Some(track()),
+    })));
+    for second in 0..=seconds {
+        events.publish(PlayerEvent::Progress(ProgressEvent {
+            position: second as f64 / 60.0,
+            frames_played: second * 1000,
+            lyric_line: None,
+        }));
+    }
+}
+
+fn json(body: &str) -> Value {
+    serde_json::from_str(body).unwrap()
+}
+
+fn form(body: &str) -> HashMap<String, String> {
+    body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (percent_decode(name, true), percent_decode(value, true)))
+        .collect()
+}
+
+/// The saved queue once it is `expected`, waiting for it a few seconds at most.
+async fn saved(path: &Path, expected: fn(&Value) -> bool) -> Value {
+    for _ in 0..500 {
+        if let Some(queue) = std::fs::read_to_string(path)
+            .ok()
+            .map(|content| json(&content))
+        {
+            if expected(&queue) {
+                return queue;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("The queue is {:?}", std::fs::read_to_string(path));
+}
+
+#[test]
+fn test_threshold() {
+    assert_eq!(threshold(29_999), None);
+    assert_eq!(threshold(60_000), Some(30_000));
+    assert_eq!(threshold(600_000), Some(240_000));
+}
+
+#[test]
+fn test_counts_what_was_heard() {
+    let mut playing = Playing::new(&track(), 0).unwrap();
+    for second in 1..30 {
+        assert!(!playing.advance(second * 1000));
+    }
+    // Seeking ahead doesn't count
+    assert!(!playing.advance(50_000));
+    assert!(!playing.advance(49_000));
+    assert!(playing.advance(50_000));
+    assert!(!playing.advance(51_000));
+
+    let mut untitled = track();
+    untitled.metadata.as_mut().unwrap().title = None;
+    assert!(Playing::new(&untitled, 0).is_none());
+}
+
+#[tokio::test]
+async fn test_scrobbles_to_listenbrainz() {
+    let stand_in = StandIn::start(|_| r#"{"status":"ok"}"#.to_string()).await;
+    let events = EventBus::new();
+    let queue = Queue::load(None).await;
+    Scrobbler::new(vec![listenbrainz(&stand_in)], queue, RETRY_DELAY).run(events.subscribe().1);
+
+    play(&events, 29);
+    let requests = stand_in.wait_for(1).await;
+    assert_eq!(requests[0].authorization.as_deref(), Some("Token t0k3n"));
+    let now_playing = json(&requests[0].body);
+    assert_eq!(now_playing["listen_type"], "playing_now");
+    assert_eq!(
+        now_playing["payload"][0]["track_metadata"]["track_name"],
+        "Sexy Boy"
+    );
+    assert!(now_playing["payload"][0].get("listened_at").is_none());
+
+    events.publish(PlayerEvent::Progress(ProgressEvent {
+        position: 0.5,
+        frames_played: 30_000,
+        lyric_line: None,
+    }));
+    let requests = stand_in.wait_for(2).await;
+    let listen = json(&requests[1].body);
+    assert_eq!(listen["listen_type"], "single");
+    let payload = &listen["payload"][0];
+    assert!(payload["listened_at"].as_u64().unwrap() > 1_700_000_000);
+    assert_eq!(payload["track_metadata"]["artist_name"], "Air");
+    assert_eq!(payload["track_metadata"]["release_name"], "Moon Safari");
+    assert_eq!(
+        payload["track_metadata"]["additional_info"]["duration_ms"],
+        60_000
+    );
+    assert_eq!(
+        payload["track_metadata"]["additional_info"]["tracknumber"],
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_missed_track_changes_credit_no_track() {
+    let stand_in = StandIn::start(|_| r#"{"status":"ok"}"#.to_string()).await;
+    let events = EventBus::new();
+    let queue = Queue::load(None).await;
+    Scrobbler::new(vec![listenbrainz(&stand_in)], queue, RETRY_DELAY).run(events.subscribe().1);
+    play(&events, 20);
+    stand_in.wait_for(1).await;
+
+    // More events than the bus holds, the next track's change among the lost ones
+    let mut next = track();
+    next.metadata.as_mut().unwrap().title = Some("Kelly Watch the Stars".to_string());
+    events.publish(PlayerEvent::TrackChanged(Box::new(TrackChangedEvent {
+        track: Some(next.clone()),
+    })));
+    for step in 0..1100 {
+        events.publish(PlayerEvent::Progress(ProgressEvent {
+            position: step as f64 / 1200.0,
+            frames_played: step * 50,
+            lyric_line: None,
+        }));
+    }
+    next.metadata.as_mut().unwrap().title = Some("La femme d'argent".to_string());
+    events.publish(PlayerEvent::TrackChanged(Box::new(TrackChangedEvent {
+        track: Some(next),
+    })));
+
+    let requests = stand_in.wait_for(2).await;
+    let now_playing = json(&requests[1].body);
+    assert_eq!(now_playing["listen_type"], "playing_now");
+    assert_eq!(
+        now_playing["payload"][0]["track_metadata"]["track_name"],
+        "La femme d'argent"
+    );
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(stand_in.requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_queues_listens_until_accepted() {
+    let dir = std::env::temp_dir().join(format!("muz-scrobbles-{}", uuid::Uuid::new_v4()));
+    let path = dir.join(QUEUE_FILE);
+    let stand_in = StandIn::start(|_| "{}".to_string()).await;
+    stand_in.down.store(true, Ordering::SeqCst);
+    let events = EventBus::new();
+    let queue = Queue::load(Some(path.clone())).await;
+    let retry_delay = Duration::from_millis(20);
+    Scrobbler::new(vec![listenbrainz(&stand_in)], queue, retry_delay).run(events.subscribe().1);
+
+    play(&events, 40);
+    let queued = saved(&path, |queue| queue.get("listenbrainz").is_some()).await;
+    assert_eq!(queued["listenbrainz"][0]["title"], "Sexy Boy");
+    // Now playing, then the listen tried again and again
+    stand_in.wait_for(4).await;
+
+    // Another run picks up where this one left off
+    let restarted = StandIn::start(|_| "{}".to_string()).await;
+    let queue = Queue::load(Some(path.clone())).await;
+    Scrobbler::new(vec![listenbrainz(&restarted)], queue, retry_delay)
+        .run(EventBus::new().subscribe().1);
+    let requests = restarted.wait_for(1).await;
+    assert_eq!(json(&requests[0].body)["listen_type"], "single");
+    saved(&path, |queue| queue == &serde_json::json!({})).await;
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_scrobbles_to_lastfm() {
+    let stand_in = StandIn::start(|body| {
+        if body.contains("method=auth.getMobileSession") {
+            r#"{"session":{"name":"someone","key":"s3ss10n","subscriber":0}}"#.to_string()
+        } else {
+            r#"{"scrobbles":{"@attr":{"accepted":1,"ignored":0}}}"#.to_string()
+        }
+    })
+    .await;
+    let settings = LastFmSettings {
+        enabled: true,
+        url: stand_in.url.clone(),
+        api_key: Some("key".to_string()),
+        secret: Some("secret".to_string()),
+        session_key: None,
+        username: Some("someone".to_string()),
+        password: Some("hunter2".to_string()),
+    };
+    let service = Service::LastFm(LastFm::new(reqwest::Client::new(), &settings).unwrap());
+    let events = EventBus::new();
+    let queue = Queue::load(None).await;
+    Scrobbler::new(vec![service], queue, RETRY_DELAY).run(events.subscribe().1);
+
+    play(&events, 29);
+    let requests = stand_in.wait_for(2).await;
+    let login = form(&requests[0].body);
+    assert_eq!(login["method"], "auth.getMobileSession");
+    assert_eq!(login["password"], "hunter2");
+    let now_playing = form(&requests[1].body);
+    assert_eq!(now_playing["method"], "track.updateNowPlaying");
+    assert_eq!(now_playing["sk"], "s3ss10n");
+    assert_eq!(now_playing["duration"], "60");
+
+    events.publish(PlayerEvent::Progress(ProgressEvent {
+        position: 0.5,
+        frames_played: 30_000,
+        lyric_line: None,
+    }));
+    let requests = stand_in.wait_for(3).await;
+
+    let scrobble = form(&requests[2].body);
+    assert_eq!(scrobble["method"], "track.scrobble");
+    assert_eq!(scrobble["artist[0]"], "Air");
+    assert_eq!(scrobble["track[0]"], "Sexy Boy");
+    assert_eq!(scrobble["sk"], "s3ss10n");
+    assert_eq!(scrobble["format"], "json");
+    let signed: Vec<_> = scrobble
+        .iter()
+        .filter(|(name, _)| *name != "api_sig")
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    assert_eq!(scrobble["api_sig"], sign(&signed, "secret"));
+}
@@ -0,0 +1,251 @@
+//! Audio over HTTP for the media servers: files with range requests, and tracks decoded
+//! to WAV for clients that can't play the original or only want part of a file.
+
+use anyhow::{anyhow, Result};
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Frame};
+use hyper::header::{self, HeaderValue};
+use hyper::{Response, StatusCode};
+use std::convert::Infallible;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::mpsc;
+
+use muz_core::decoder::TrackDecoder;
+use muz_core::track::Track;
+
+const CHUNK_BYTES: usize = 64 * 1024;
+const WAV_HEADER_BYTES: u32 = 44;
+
+pub type Body = BoxBody<Bytes, Infallible>;
+
+pub fn full(bytes: impl Into<Bytes>) -> Body {
+    Full::new(bytes.into()).boxed()
+}
+
+/// A body written by another task, ending when the sender is dropped.
+struct ChannelBody {
+    receiver: mpsc::Receiver<Bytes>,
+}
+
+impl hyper::body::Body for ChannelBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        self.receiver
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|bytes| Ok(Frame::data(bytes))))
+    }
+}
+
+/// The part of a file a `Range` header asks for.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    Whole,
+    /// First and last byte, inclusive
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Reads a single `bytes=` range. Anything else, including several ranges, gets the
+/// whole file.
+pub fn parse_range(header: Option<&str>, len: u64) -> ByteRange {
+    let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Whole;
+    };
+    if spec.contains(',') {
+        return ByteRange::Whole;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Whole;
+    };
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return ByteRange::Whole,
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+            Err(_) => return ByteRange::Whole,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if end >= start => (start, end.min(len.saturating_sub(1))),
+            (Ok(start), Err(_)) if end.is_empty() => (start, len.saturating_sub(1)),
+            _ => return ByteRange::Whole,
+        },
+    };
+    if start >= len {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(start, end)
+    }
+}
+
+/// The media type of an audio file, from its extension.
+pub fn media_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("mp3" | "mp2" | "mp1") => "audio/mpeg",
+        Some("flac") => "audio/flac",
+        Some("ogg" | "oga" | "ogx" | "opus") => "audio/ogg",
+        Some("m4a" | "m4b" | "m4p" | "m4r" | "mp4" | "alac") => "audio/mp4",
+        Some("aac") => "audio/aac",
+        Some("wav" | "wave") => "audio/wav",
+        Some("aif" | "aifc" | "aiff") => "audio/aiff",
+        Some("weba" | "webm") => "audio/webm",
+        Some("mka" | "mkv") => "audio/x-matroska",
+        Some("caf") => "audio/x-caf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Serves the file, or the part of it `range` asks for.
+pub async fn serve_file(path: &Path, range: Option<&str>) -> Result<Response<Body>> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| anyhow!("Failed to open {}: {e}", path.display()))?;
+    let len = file.metadata().await?.len();
+
+    let mut response = Response::new(full(Bytes::new()));
+    let headers = response.headers_mut();
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(media_type(path)),
+    );
+    let (start, end) = match parse_range(range, len) {
+        ByteRange::Whole => (0, len.saturating_sub(1)),
+        ByteRange::Partial(start, end) => {
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            response.headers_mut().insert(
+                header::CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes {start}-{end}/{len}"))?,
+            );
+            (start, end)
+        }
+        ByteRange::Unsatisfiable => {
+            *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+            response.headers_mut().insert(
+                header::CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes */{len}"))?,
+            );
+            return Ok(response);
+        }
+    };
+    let length = if len == 0 { 0 } else { end - start + 1 };
+    response
+        .headers_mut()
+        .insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+
+    file.seek(std::io::SeekFrom::Start(start)).await?;
+    let (sender, receiver) = mpsc::channel(4);
+    let path = path.to_path_buf();
+    tokio::spawn(async move {
+        let mut file = file.take(length);
+        let mut buffer = vec![0; CHUNK_BYTES];
+        loop {
+            match file.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(read) => {
+                    if sender
+                        .send(Bytes::copy_from_slice(&buffer[..read]))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to read {path:?}: {e}");
+                    break;
+                }
+            }
+        }
+    });
+    *response.body_mut() = ChannelBody { receiver }.boxed();
+    Ok(response)
+}
+
+/// Decodes the track from `offset` into a 16-bit WAV stream. The header announces the
+/// expected length, the body ends wherever decoding does.
+pub async fn transcode_wav(track: Track, offset: Duration) -> Result<Response<Body>> {
+    let remaining_ms = track.duration_ms.saturating_sub(offset.as_millis() as u64);
+    let decoder = tokio::task::spawn_blocking(move || {
+        let mut decoder = TrackDecoder::open(&track)?;
+        if !offset.is_zero() {
+            decoder.seek(offset)?;
+        }
+        anyhow::Ok((decoder, track))
+    })
+    .await
+    .map_err(|e| anyhow!("Failed to open the decoder: {e}"))?;
+    let (mut decoder, track) = decoder?;
+
+    let sample_rate = decoder.sample_rate();
+    let channels = decoder.channels() as u16;
+    let frames = remaining_ms * sample_rate as u64 / 1000;
+    let data_bytes = (frames * channels as u64 * 2).min((u32::MAX - WAV_HEADER_BYTES) as u64);
+    let header = wav_header(sample_rate, channels, data_bytes as u32);
+
+    let (sender, receiver) = mpsc::channel(4);
+    tokio::task::spawn_blocking(move || {
+        if sender.blocking_send(Bytes::from(header)).is_err() {
+            return;
+        }
+        loop {
+            let samples = match decoder.next_block() {
+                Ok(Some(samples)) => samples,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!("Stopped transcoding {:?}: {e}", track.path);
+                    break;
+                }
+            };
+            let mut pcm = Vec::with_capacity(samples.len() * 2);
+            for sample in samples {
+                let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                pcm.extend_from_slice(&value.to_le_bytes());
+            }
+            if sender.blocking_send(Bytes::from(pcm)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut response = Response::new(ChannelBody { receiver }.boxed());
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("audio/wav"));
+    Ok(response)
+}
+
+fn wav_header(sample_rate: u32, channels: u16, data_bytes: u32) -> Vec<u8> {
+    let block_align = channels * 2;
+    let mut header = Vec::with_capacity(WAV_HEADER_BYTES as usize);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(WAV_HEADER_BYTES - 8 + data_bytes).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&channels.to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_bytes.to_le_bytes());
+    header
+}
+
+#[cfg(test)]
+#[path = "./streaming.tests.rs"]
+mod tests;
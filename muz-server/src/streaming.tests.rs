@@ -0,0 +1,38 @@
+use super::*;
+
+#[test]
+fn test_parse_range() {
+    let range = |header| parse_range(Some(header), 1000);
+    assert_eq!(range("bytes=0-499"), ByteRange::Partial(0, 499));
+    assert_eq!(range("bytes=500-"), ByteRange::Partial(500, 999));
+    assert_eq!(range("bytes=-100"), ByteRange::Partial(900, 999));
+    assert_eq!(range("bytes=900-5000"), ByteRange::Partial(900, 999));
+    assert_eq!(range("bytes=1000-"), ByteRange::Unsatisfiable);
+    assert_eq!(range("bytes=0-1,5-6"), ByteRange::Whole);
+    assert_eq!(range("bytes=5-1"), ByteRange::Whole);
+    assert_eq!(range("items=0-1"), ByteRange::Whole);
+    assert_eq!(parse_range(None, 1000), ByteRange::Whole);
+}
+
+#[test]
+fn test_wav_header() {
+    let header = wav_header(44_100, 2, 400);
+    assert_eq!(header.len(), WAV_HEADER_BYTES as usize);
+    assert_eq!(&header[..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(header[4..8].try_into().unwrap()), 436);
+    assert_eq!(
+        u32::from_le_bytes(header[28..32].try_into().unwrap()),
+        176_400
+    );
+    assert_eq!(u32::from_le_bytes(header[40..44].try_into().unwrap()), 400);
+}
+
+#[test]
+fn test_media_type() {
+    assert_eq!(media_type(Path::new("/music/a.FLAC")), "audio/flac");
+    assert_eq!(media_type(Path::new("/music/a.m4a")), "audio/mp4");
+    assert_eq!(
+        media_type(Path::new("/music/a")),
+        "application/octet-stream"
+    );
+}
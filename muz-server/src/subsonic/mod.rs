@@ -0,0 +1,61 @@
+use anyhow::{bail, Result};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+use crate::subsonic::routes::Api;
+use crate::subsonic::store::{Store, STORE_FILE};
+use muz_core::config::SubsonicSettings;
+use muz_core::services::library_service::LibraryService;
+
+mod response;
+pub mod routes;
+mod store;
+
+/// Serves the library to Subsonic clients on the configured address, returning the
+/// address actually bound. Playlists and stars are kept in `data_dir` when given.
+pub async fn serve(
+    settings: &SubsonicSettings,
+    library: LibraryService,
+    data_dir: Option<&Path>,
+) -> Result<SocketAddr> {
+    let Some(password) = settings.password.clone().filter(|p| !p.is_empty()) else {
+        bail!("A password is required to serve the Subsonic API");
+    };
+    let listener = TcpListener::bind(&settings.address).await?;
+    let local_address = listener.local_addr()?;
+    let store = Store::load(data_dir.map(|dir| dir.join(STORE_FILE))).await;
+    let api = Arc::new(Api::new(
+        library,
+        store,
+        settings.username.clone(),
+        password,
+    ));
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!("Failed to accept a Subsonic client: {e}");
+                    continue;
+                }
+            };
+            let api = api.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| api.clone().handle(request));
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    tracing::debug!("Subsonic client {peer} disconnected: {e}");
+                }
+            });
+        }
+    });
+    Ok(local_address)
+}
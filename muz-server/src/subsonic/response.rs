@@ -0,0 +1,209 @@
+use hyper::header::{self, HeaderValue};
+use hyper::Response;
+use serde_json::{json, Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::streaming::{full, Body};
+
+/// The Subsonic API version implemented, with the OpenSubsonic extensions flag set.
+pub const API_VERSION: &str = "1.16.1";
+const XML_NAMESPACE: &str = "http://subsonic.org/restapi";
+
+/// Clients ask for JSON with `f=json`, XML is the default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Xml,
+    Json,
+}
+
+impl Format {
+    pub fn from_param(value: Option<&str>) -> Self {
+        match value {
+            Some("json") => Format::Json,
+            _ => Format::Xml,
+        }
+    }
+}
+
+/// An error in the body of a successful HTTP response, as clients expect.
+#[derive(Debug)]
+pub struct SubsonicError {
+    pub code: u32,
+    pub message: String,
+}
+
+impl SubsonicError {
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn missing(name: &str) -> Self {
+        Self::new(10, format!("Required parameter is missing: {name}"))
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(40, "Wrong username or password")
+    }
+
+    pub fn not_found(what: &str) -> Self {
+        Self::new(70, format!("{what} not found"))
+    }
+}
+
+impl From<anyhow::Error> for SubsonicError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::new(0, format!("{error:#}"))
+    }
+}
+
+/// A `subsonic-response` with the given fields, e.g. `{ "album": { ... } }`. Fields
+/// that are null are left out.
+pub fn ok(format: Format, mut payload: Value) -> Response<Body> {
+    remove_nulls(&mut payload);
+    let fields = match payload {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    };
+    envelope(format, "ok", fields)
+}
+
+pub fn failed(format: Format, error: SubsonicError) -> Response<Body> {
+    let mut fields = Map::new();
+    fields.insert(
+        "error".to_string(),
+        json!({ "code": error.code, "message": error.message }),
+    );
+    envelope(format, "failed", fields)
+}
+
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            fields.retain(|_, value| !value.is_null());
+            fields.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+fn envelope(format: Format, status: &str, fields: Map<String, Value>) -> Response<Body> {
+    let mut response = Map::new();
+    response.insert("status".to_string(), json!(status));
+    response.insert("version".to_string(), json!(API_VERSION));
+    response.insert("type".to_string(), json!("muz"));
+    response.insert(
+        "serverVersion".to_string(),
+        json!(env!("CARGO_PKG_VERSION")),
+    );
+    response.insert("openSubsonic".to_string(), json!(true));
+    response.extend(fields);
+
+    let (body, media_type) = match format {
+        Format::Json => (
+            json!({ "subsonic-response": response }).to_string(),
+            "application/json",
+        ),
+        Format::Xml => {
+            let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+            response.insert("xmlns".to_string(), json!(XML_NAMESPACE));
+            write_element(&mut xml, "subsonic-response", &Value::Object(response));
+            (xml, "text/xml; charset=utf-8")
+        }
+    };
+    let mut response = Response::new(full(body));
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(media_type));
+    response
+}
+
+/// Writes the value the way Subsonic maps its XML to JSON: scalars are attributes,
+/// objects child elements and arrays repeated elements.
+fn write_element(xml: &mut String, name: &str, value: &Value) {
+    xml.push('<');
+    xml.push_str(name);
+    let Value::Object(fields) = value else {
+        xml.push('>');
+        xml.push_str(&escape(&scalar(value)));
+        xml.push_str(&format!("</{name}>"));
+        return;
+    };
+    for (key, value) in fields {
+        if !matches!(value, Value::Object(_) | Value::Array(_)) {
+            xml.push_str(&format!(" {key}=\"{}\"", escape(&scalar(value))));
+        }
+    }
+    let mut children = String::new();
+    for (key, value) in fields {
+        match value {
+            Value::Object(_) => write_element(&mut children, key, value),
+            Value::Array(items) => {
+                for item in items {
+                    write_element(&mut children, key, item);
+                }
+            }
+            _ => {}
+        }
+    }
+    if children.is_empty() {
+        xml.push_str("/>");
+    } else {
+        xml.push('>');
+        xml.push_str(&children);
+        xml.push_str(&format!("</{name}>"));
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// An ISO 8601 UTC date, as Subsonic gives `created`, `starred` and `played`.
+pub fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+    // Days to a civil date, after Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.000Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+#[path = "./response.tests.rs"]
+mod tests;
@@ -0,0 +1,33 @@
+use super::*;
+use std::time::Duration;
+
+#[test]
+fn test_xml_maps_scalars_to_attributes_and_arrays_to_elements() {
+    let mut xml = String::new();
+    let value = json!({
+        "id": "al-1",
+        "name": "Tom & Jerry's <Hits>",
+        "songCount": 2,
+        "song": [{ "id": "tr-1" }, { "id": "tr-2" }],
+    });
+    write_element(&mut xml, "album", &value);
+    assert_eq!(
+        xml,
+        "<album id=\"al-1\" name=\"Tom &amp; Jerry&apos;s &lt;Hits&gt;\" songCount=\"2\">\
+         <song id=\"tr-1\"/><song id=\"tr-2\"/></album>"
+    );
+}
+
+#[test]
+fn test_timestamp() {
+    assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+    assert_eq!(timestamp(leap_day), "2024-02-29T12:34:56.000Z");
+}
+
+#[test]
+fn test_nulls_are_left_out() {
+    let mut value = json!({ "song": [{ "id": "tr-1", "track": null }], "genre": null });
+    remove_nulls(&mut value);
+    assert_eq!(value, json!({ "song": [{ "id": "tr-1" }] }));
+}
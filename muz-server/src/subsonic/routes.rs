@@ -0,0 +1,752 @@
+use anyhow::anyhow;
+use http_body_util::{BodyExt, Limited};
+use hyper::body::Incoming;
+use hyper::header::{self, HeaderValue};
+use hyper::{Method, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::catalog::{song_id, Album, Artist, Catalog, Song};
+use crate::remote::routes::percent_decode;
+use crate::streaming::{self, full, Body};
+use crate::subsonic::response::{self, timestamp, Format, SubsonicError};
+use crate::subsonic::store::{self, Playlist, Store};
+use muz_core::artwork::Artwork;
+use muz_core::services::library_service::LibraryService;
+use muz_core::track::Track;
+
+/// Form bodies only carry parameters
+const MAX_BODY: usize = 1024 * 1024;
+const MAX_LIST_SIZE: usize = 500;
+
+/// The query and form parameters of a request. Some, like `id`, may repeat.
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    fn parse(&mut self, encoded: &str) {
+        self.0
+            .extend(encoded.split('&').filter(|p| !p.is_empty()).map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(key, true), percent_decode(value, true))
+            }));
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn all(&self, name: &str) -> Vec<String> {
+        self.0
+            .iter()
+            .filter(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    fn require(&self, name: &str) -> Result<&str, SubsonicError> {
+        self.get(name).ok_or_else(|| SubsonicError::missing(name))
+    }
+
+    fn parsed<T: FromStr>(&self, name: &str, default: T) -> Result<T, SubsonicError> {
+        match self.get(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| SubsonicError::new(0, format!("Invalid {name}: {value}"))),
+            None => Ok(default),
+        }
+    }
+}
+
+/// The Subsonic endpoints clients need to browse, search and play the library, with
+/// playlists, stars and play counts kept in a `Store`.
+pub struct Api {
+    library: LibraryService,
+    store: Store,
+    username: String,
+    password: String,
+}
+
+impl Api {
+    pub fn new(library: LibraryService, store: Store, username: String, password: String) -> Self {
+        Self {
+            library,
+            store,
+            username,
+            password,
+        }
+    }
+
+    pub async fn handle(
+        self: Arc<Self>,
+        request: Request<Incoming>,
+    ) -> Result<Response<Body>, Infallible> {
+        let Some(endpoint) = request.uri().path().strip_prefix("/rest/") else {
+            let mut response = Response::new(full("Not found"));
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            return Ok(response);
+        };
+        let endpoint = endpoint.trim_end_matches(".view").to_string();
+        let range = request
+            .headers()
+            .get(header::RANGE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let params = match read_params(request).await {
+            Ok(params) => params,
+            Err(e) => return Ok(response::failed(Format::Xml, e)),
+        };
+        let format = Format::from_param(params.get("f"));
+        let result = match self.authenticate(&params) {
+            Ok(()) => self.route(&endpoint, &params, range.as_deref()).await,
+            Err(e) => Err(e),
+        };
+        Ok(result.unwrap_or_else(|e| response::failed(format, e)))
+    }
+
+    /// Takes the password in the clear, hex encoded after `enc:`, or as the token
+    /// `md5(password + salt)`.
+    fn authenticate(&self, params: &Params) -> Result<(), SubsonicError> {
+        let username = params.require("u")?;
+        let valid = match (params.get("t"), params.get("p")) {
+            (Some(token), _) => {
+                let salt = params.require("s")?;
+                let expected = md5::compute(format!("{}{salt}", self.password));
+                token.eq_ignore_ascii_case(&format!("{expected:x}"))
+            }
+            (None, Some(password)) => decode_password(password) == self.password,
+            (None, None) => return Err(SubsonicError::missing("p")),
+        };
+        if username == self.username && valid {
+            Ok(())
+        } else {
+            Err(SubsonicError::unauthorized())
+        }
+    }
+
+    async fn route(
+        &self,
+        endpoint: &str,
+        params: &Params,
+        range: Option<&str>,
+    ) -> Result<Response<Body>, SubsonicError> {
+        let format = Format::from_param(params.get("f"));
+        let payload = match endpoint {
+            "ping" => json!({}),
+            "getLicense" => json!({ "license": { "valid": true } }),
+            "getOpenSubsonicExtensions" => json!({ "openSubsonicExtensions": [] }),
+            "getMusicFolders" => {
+                json!({ "musicFolders": { "musicFolder": [{ "id": 1, "name": "Library" }] } })
+            }
+
+            "getArtists" => json!({ "artists": self.listing().await?.artist_index() }),
+            "getIndexes" => {
+                let mut index = self.listing().await?.artist_index();
+                index["lastModified"] = json!(0);
+                json!({ "indexes": index })
+            }
+            "getArtist" => {
+                let listing = self.listing().await?;
+                let artist = listing
+                    .catalog
+                    .artist(params.require("id")?)
+                    .ok_or_else(|| SubsonicError::not_found("Artist"))?;
+                let mut value = listing.artist(artist);
+                value["album"] = artist.albums.iter().map(|a| listing.album(a)).collect();
+                json!({ "artist": value })
+            }
+            "getAlbum" => {
+                let listing = self.listing().await?;
+                let album = listing
+                    .catalog
+                    .album(params.require("id")?)
+                    .ok_or_else(|| SubsonicError::not_found("Album"))?;
+                let mut value = listing.album(album);
+                value["song"] = album.songs.iter().map(|s| listing.song(album, s)).collect();
+                json!({ "album": value })
+            }
+            "getSong" => {
+                let listing = self.listing().await?;
+                let (album, song) = listing
+                    .catalog
+                    .song(params.require("id")?)
+                    .ok_or_else(|| SubsonicError::not_found("Song"))?;
+                json!({ "song": listing.song(album, song) })
+            }
+            "getMusicDirectory" => {
+                let listing = self.listing().await?;
+                json!({ "directory": listing.directory(params.require("id")?)? })
+            }
+            "getAlbumList2" => {
+                let listing = self.listing().await?;
+                let albums = listing.album_list(params)?;
+                json!({ "albumList2": { "album": albums } })
+            }
+            "search3" => json!({ "searchResult3": self.search(params).await? }),
+            "getStarred2" => json!({ "starred2": self.listing().await?.starred() }),
+
+            "stream" | "download" => return self.stream(endpoint, params, range).await,
+            "getCoverArt" => return self.cover_art(params).await,
+
+            "scrobble" => {
+                let listing = self.listing().await?;
+                let ids = params.all("id");
+                if ids.is_empty() {
+                    return Err(SubsonicError::missing("id"));
+                }
+                let times = params.all("time");
+                for (i, id) in ids.iter().enumerate() {
+                    let (_, song) = listing
+                        .catalog
+                        .song(id)
+                        .ok_or_else(|| SubsonicError::not_found("Song"))?;
+                    if !params.parsed("submission", true)? {
+                        tracing::debug!("Subsonic client playing {:?}", song.track.path);
+                        continue;
+                    }
+                    let played_at = times
+                        .get(i)
+                        .and_then(|ms| ms.parse().ok())
+                        .map_or_else(SystemTime::now, |ms| {
+                            SystemTime::UNIX_EPOCH + Duration::from_millis(ms)
+                        });
+                    self.store.record_play(id, played_at)?;
+                }
+                json!({})
+            }
+            "star" | "unstar" => {
+                let ids: Vec<String> = ["id", "albumId", "artistId"]
+                    .into_iter()
+                    .flat_map(|name| params.all(name))
+                    .collect();
+                self.store.set_starred(&ids, endpoint == "star")?;
+                json!({})
+            }
+
+            "getPlaylists" => {
+                let listing = self.listing().await?;
+                let playlists: Vec<Value> = self
+                    .store
+                    .playlists()
+                    .iter()
+                    .map(|playlist| listing.playlist(playlist, &self.username).0)
+                    .collect();
+                json!({ "playlists": { "playlist": playlists } })
+            }
+            "getPlaylist" => {
+                let playlist = self
+                    .store
+                    .playlist(params.require("id")?)
+                    .ok_or_else(|| SubsonicError::not_found("Playlist"))?;
+                json!({ "playlist": self.playlist_with_entries(&playlist).await? })
+            }
+            "createPlaylist" => {
+                let song_ids = params.all("songId");
+                let playlist = match params.get("playlistId") {
+                    Some(id) => self
+                        .store
+                        .update_playlist(id, |playlist| {
+                            if let Some(name) = params.get("name") {
+                                playlist.name = name.to_string();
+                            }
+                            playlist.song_ids = song_ids;
+                        })?
+                        .ok_or_else(|| SubsonicError::not_found("Playlist"))?,
+                    None => {
+                        let name = params.require("name")?.to_string();
+                        self.store.create_playlist(name, song_ids)?
+                    }
+                };
+                json!({ "playlist": self.playlist_with_entries(&playlist).await? })
+            }
+            "updatePlaylist" => {
+                let mut removed: Vec<usize> = params
+                    .all("songIndexToRemove")
+                    .iter()
+                    .filter_map(|index| index.parse().ok())
+                    .collect();
+                removed.sort_unstable_by(|a, b| b.cmp(a));
+                removed.dedup();
+                self.store
+                    .update_playlist(params.require("playlistId")?, |playlist| {
+                        if let Some(name) = params.get("name") {
+                            playlist.name = name.to_string();
+                        }
+                        if let Some(comment) = params.get("comment") {
+                            playlist.comment = Some(comment.to_string());
+                        }
+                        for index in removed {
+                            if index < playlist.song_ids.len() {
+                                playlist.song_ids.remove(index);
+                            }
+                        }
+                        playlist.song_ids.extend(params.all("songIdToAdd"));
+                    })?
+                    .ok_or_else(|| SubsonicError::not_found("Playlist"))?;
+                json!({})
+            }
+            "deletePlaylist" => {
+                if !self.store.delete_playlist(params.require("id")?)? {
+                    return Err(SubsonicError::not_found("Playlist"));
+                }
+                json!({})
+            }
+
+            _ => {
+                return Err(SubsonicError::new(
+                    0,
+                    format!("Unsupported endpoint: {endpoint}"),
+                ))
+            }
+        };
+        Ok(response::ok(format, payload))
+    }
+
+    async fn listing(&self) -> Result<Listing<'_>, SubsonicError> {
+        Ok(Listing {
+            catalog: Catalog::new(self.library.albums_by_artist().await?),
+            root: PathBuf::from(self.library.library_path().await?),
+            store: &self.store,
+        })
+    }
+
+    async fn playlist_with_entries(&self, playlist: &Playlist) -> Result<Value, SubsonicError> {
+        let listing = self.listing().await?;
+        let (mut value, entries) = listing.playlist(playlist, &self.username);
+        value["entry"] = Value::Array(entries);
+        Ok(value)
+    }
+
+    /// Matches artists and albums by name, and songs the way the library search does.
+    /// An empty query, which clients send to sync the whole library, matches everything.
+    async fn search(&self, params: &Params) -> Result<Value, SubsonicError> {
+        let query = params.require("query")?.trim_matches('"');
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let matches = |fields: &[&str]| {
+            words.iter().all(|word| {
+                fields
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(word.as_str()))
+            })
+        };
+        let page = |kind: &str| -> Result<(usize, usize), SubsonicError> {
+            let count = params.parsed(&format!("{kind}Count"), 20)?;
+            Ok((params.parsed(&format!("{kind}Offset"), 0)?, count))
+        };
+
+        let listing = self.listing().await?;
+        let catalog = &listing.catalog;
+        let (offset, count) = page("artist")?;
+        let artists: Vec<Value> = catalog
+            .artists
+            .iter()
+            .filter(|artist| matches(&[&artist.name]))
+            .skip(offset)
+            .take(count.min(MAX_LIST_SIZE))
+            .map(|artist| listing.artist(artist))
+            .collect();
+        let (offset, count) = page("album")?;
+        let albums: Vec<Value> = catalog
+            .albums()
+            .filter(|album| matches(&[&album.name, &album.artist]))
+            .skip(offset)
+            .take(count.min(MAX_LIST_SIZE))
+            .map(|album| listing.album(album))
+            .collect();
+
+        let (offset, count) = page("song")?;
+        let songs_by_id: HashMap<&str, (&Album, &Song)> = catalog
+            .songs()
+            .map(|(album, song)| (song.id.as_str(), (album, song)))
+            .collect();
+        let songs: Vec<Value> = self
+            .library
+            .search(query)
+            .await
+            .iter()
+            .filter_map(|track| songs_by_id.get(song_id(track).as_str()).copied())
+            .skip(offset)
+            .take(count.min(MAX_LIST_SIZE))
+            .map(|(album, song)| listing.song(album, song))
+            .collect();
+        Ok(json!({ "artist": artists, "album": albums, "song": songs }))
+    }
+
+    /// Sends the original file, with range requests, unless the client asks for `wav`,
+    /// a time offset, or the song is part of a file. Those are decoded to WAV. Lossy
+    /// formats and `maxBitRate` can't be honoured without an encoder, so they get the
+    /// original too.
+    async fn stream(
+        &self,
+        endpoint: &str,
+        params: &Params,
+        range: Option<&str>,
+    ) -> Result<Response<Body>, SubsonicError> {
+        let listing = self.listing().await?;
+        let (_, song) = listing
+            .catalog
+            .song(params.require("id")?)
+            .ok_or_else(|| SubsonicError::not_found("Song"))?;
+        let offset = Duration::from_secs(params.parsed("timeOffset", 0)?);
+        let transcode = song.track.segment.is_some()
+            || (endpoint == "stream" && (params.get("format") == Some("wav") || !offset.is_zero()));
+        let response = if transcode {
+            streaming::transcode_wav(song.track.clone(), offset).await?
+        } else {
+            streaming::serve_file(&song.track.path, range).await?
+        };
+        Ok(response)
+    }
+
+    /// The picture of the artist's first album, the album or the song. `size` is ignored,
+    /// pictures are sent as they are.
+    async fn cover_art(&self, params: &Params) -> Result<Response<Body>, SubsonicError> {
+        let listing = self.listing().await?;
+        let track = listing
+            .catalog
+            .cover_track(params.require("id")?)
+            .cloned()
+            .ok_or_else(|| SubsonicError::not_found("Cover art"))?;
+        let artwork = tokio::task::spawn_blocking(move || Artwork::load(&track))
+            .await
+            .map_err(|e| anyhow!("Failed to load the artwork: {e}"))?
+            .ok_or_else(|| SubsonicError::not_found("Cover art"))?;
+        let mut response = Response::new(full(artwork.data));
+        if let Ok(media_type) = HeaderValue::from_str(&artwork.media_type) {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, media_type);
+        }
+        Ok(response)
+    }
+}
+
+/// The catalog as of one request, described the way clients expect.
+struct Listing<'a> {
+    catalog: Catalog,
+    root: PathBuf,
+    store: &'a Store,
+}
+
+impl Listing<'_> {
+    /// Artists by initial, for `getArtists` and `getIndexes`.
+    fn artist_index(&self) -> Value {
+        let mut index: Vec<(String, Vec<Value>)> = Vec::new();
+        for artist in &self.catalog.artists {
+            let initial = artist
+                .name
+                .chars()
+                .next()
+                .filter(|c| c.is_alphabetic())
+                .map_or_else(|| "#".to_string(), |c| c.to_uppercase().collect());
+            match index.iter_mut().find(|(name, _)| *name == initial) {
+                Some((_, artists)) => artists.push(self.artist(artist)),
+                None => index.push((initial, vec![self.artist(artist)])),
+            }
+        }
+        let index: Vec<Value> = index
+            .into_iter()
+            .map(|(name, artists)| json!({ "name": name, "artist": artists }))
+            .collect();
+        json!({ "ignoredArticles": "", "index": index })
+    }
+
+    fn artist(&self, artist: &Artist) -> Value {
+        json!({
+            "id": artist.id,
+            "name": artist.name,
+            "albumCount": artist.albums.len(),
+            "coverArt": artist.albums.first().map(|album| &album.id),
+            "starred": self.store.starred(&artist.id).map(timestamp),
+        })
+    }
+
+    fn album(&self, album: &Album) -> Value {
+        let plays = album
+            .songs
+            .iter()
+            .filter_map(|song| self.store.plays(&song.id))
+            .fold(store::Plays::default(), |total, plays| store::Plays {
+                count: total.count + plays.count,
+                last: total.last.max(plays.last),
+            });
+        json!({
+            "id": album.id,
+            "name": album.name,
+            "artist": album.artist,
+            "artistId": album.artist_id,
+            "coverArt": album.id,
+            "songCount": album.songs.len(),
+            "duration": album.duration_ms() / 1000,
+            "created": timestamp(created(album)),
+            "year": album
+                .tag(|t| t.metadata.as_ref()?.year.as_ref())
+                .map(String::as_str)
+                .and_then(year),
+            "genre": album.tag(|t| t.metadata.as_ref()?.genre.as_ref()),
+            "playCount": plays.count,
+            "played": (plays.count > 0).then(|| timestamp(store::time(plays.last))),
+            "starred": self.store.starred(&album.id).map(timestamp),
+        })
+    }
+
+    /// Songs that are part of a file are only streamed as WAV, and described as such.
+    fn song(&self, album: &Album, song: &Song) -> Value {
+        let track = &song.track;
+        let metadata = track.metadata.as_ref();
+        let title = metadata
+            .and_then(|m| m.title.clone())
+            .unwrap_or_else(|| Track::default_title(&track.path));
+        let (suffix, content_type, size) = if track.segment.is_some() {
+            ("wav".to_string(), "audio/wav", None)
+        } else {
+            let suffix = track.path.extension().and_then(|e| e.to_str());
+            (
+                suffix.unwrap_or_default().to_lowercase(),
+                streaming::media_type(&track.path),
+                std::fs::metadata(&track.path).ok().map(|m| m.len()),
+            )
+        };
+        let plays = self.store.plays(&song.id);
+        json!({
+            "id": song.id,
+            "parent": album.id,
+            "isDir": false,
+            "title": title,
+            "album": album.name,
+            "artist": metadata.and_then(|m| m.artist.as_ref()).unwrap_or(&album.artist),
+            "track": metadata.and_then(|m| m.track_number),
+            "discNumber": metadata.and_then(|m| m.disc_number),
+            "year": metadata.and_then(|m| m.year.as_deref()).and_then(year),
+            "genre": metadata.and_then(|m| m.genre.as_ref()),
+            "coverArt": album.id,
+            "size": size,
+            "contentType": content_type,
+            "suffix": suffix,
+            "duration": track.duration_ms / 1000,
+            "path": relative_path(&self.root, &track.path),
+            "albumId": album.id,
+            "artistId": album.artist_id,
+            "type": "music",
+            "isVideo": false,
+            "playCount": plays.map_or(0, |plays| plays.count),
+            "played": plays.map(|plays| timestamp(store::time(plays.last))),
+            "starred": self.store.starred(&song.id).map(timestamp),
+        })
+    }
+
+    fn starred(&self) -> Value {
+        let starred = |id: &str| self.store.starred(id).is_some();
+        let artists: Vec<Value> = self
+            .catalog
+            .artists
+            .iter()
+            .filter(|artist| starred(&artist.id))
+            .map(|artist| self.artist(artist))
+            .collect();
+        let albums: Vec<Value> = self
+            .catalog
+            .albums()
+            .filter(|album| starred(&album.id))
+            .map(|album| self.album(album))
+            .collect();
+        let songs: Vec<Value> = self
+            .catalog
+            .songs()
+            .filter(|(_, song)| starred(&song.id))
+            .map(|(album, song)| self.song(album, song))
+            .collect();
+        json!({ "artist": artists, "album": albums, "song": songs })
+    }
+
+    /// Folder browsing for older clients: artists hold albums, which hold songs.
+    fn directory(&self, id: &str) -> Result<Value, SubsonicError> {
+        if let Some(artist) = self.catalog.artist(id) {
+            let albums: Vec<Value> = artist
+                .albums
+                .iter()
+                .map(|album| {
+                    json!({
+                        "id": album.id,
+                        "parent": artist.id,
+                        "isDir": true,
+                        "title": album.name,
+                        "album": album.name,
+                        "artist": album.artist,
+                        "coverArt": album.id,
+                        "starred": self.store.starred(&album.id).map(timestamp),
+                    })
+                })
+                .collect();
+            return Ok(json!({ "id": artist.id, "name": artist.name, "child": albums }));
+        }
+        let album = self
+            .catalog
+            .album(id)
+            .ok_or_else(|| SubsonicError::not_found("Directory"))?;
+        let songs: Vec<Value> = album.songs.iter().map(|s| self.song(album, s)).collect();
+        Ok(json!({
+            "id": album.id,
+            "parent": album.artist_id,
+            "name": album.name,
+            "child": songs,
+        }))
+    }
+
+    fn album_list(&self, params: &Params) -> Result<Vec<Value>, SubsonicError> {
+        let kind = params.require("type")?;
+        let size = params.parsed("size", 10)?.min(MAX_LIST_SIZE);
+        let offset = params.parsed("offset", 0)?;
+        let mut albums: Vec<&Album> = self.catalog.albums().collect();
+        let plays = |album: &Album| {
+            let plays = album.songs.iter().filter_map(|s| self.store.plays(&s.id));
+            plays.fold((0, 0), |(count, last), p| {
+                (count + p.count, last.max(p.last))
+            })
+        };
+        match kind {
+            "alphabeticalByName" => albums.sort_by_key(|album| album.name.to_lowercase()),
+            "alphabeticalByArtist" => {}
+            "newest" => albums.sort_by_key(|album| std::cmp::Reverse(created(album))),
+            "random" => albums.sort_by_cached_key(|_| uuid::Uuid::new_v4()),
+            "frequent" => {
+                albums.retain(|album| plays(album).0 > 0);
+                albums.sort_by_key(|album| std::cmp::Reverse(plays(album).0));
+            }
+            "recent" => {
+                albums.retain(|album| plays(album).0 > 0);
+                albums.sort_by_key(|album| std::cmp::Reverse(plays(album).1));
+            }
+            "starred" => albums.retain(|album| self.store.starred(&album.id).is_some()),
+            "byYear" => {
+                let from: i32 = params.parsed("fromYear", 0)?;
+                let to: i32 = params.parsed("toYear", 9999)?;
+                let album_year = |album: &Album| {
+                    album
+                        .tag(|t| t.metadata.as_ref()?.year.as_ref())
+                        .map(String::as_str)
+                        .and_then(year)
+                };
+                albums.retain(|album| {
+                    album_year(album).is_some_and(|y| y >= from.min(to) && y <= from.max(to))
+                });
+                albums.sort_by_key(|album| album_year(album));
+                if from > to {
+                    albums.reverse();
+                }
+            }
+            "byGenre" => {
+                let genre = params.require("genre")?;
+                albums.retain(|album| {
+                    album
+                        .tag(|t| t.metadata.as_ref()?.genre.as_ref())
+                        .is_some_and(|g| g.eq_ignore_ascii_case(genre))
+                });
+            }
+            other => {
+                return Err(SubsonicError::new(
+                    0,
+                    format!("Unsupported list type: {other}"),
+                ))
+            }
+        }
+        Ok(albums
+            .into_iter()
+            .skip(offset)
+            .take(size)
+            .map(|album| self.album(album))
+            .collect())
+    }
+
+    /// The playlist without its entries, and the entries still in the library.
+    fn playlist(&self, playlist: &Playlist, owner: &str) -> (Value, Vec<Value>) {
+        let songs: Vec<(&Album, &Song)> = playlist
+            .song_ids
+            .iter()
+            .filter_map(|id| self.catalog.song(id))
+            .collect();
+        let duration_ms: u64 = songs.iter().map(|(_, s)| s.track.duration_ms).sum();
+        let value = json!({
+            "id": playlist.id,
+            "name": playlist.name,
+            "comment": playlist.comment,
+            "owner": owner,
+            "public": false,
+            "songCount": songs.len(),
+            "duration": duration_ms / 1000,
+            "created": timestamp(store::time(playlist.created)),
+            "changed": timestamp(store::time(playlist.changed)),
+            "coverArt": songs.first().map(|(album, _)| &album.id),
+        });
+        let entries = songs
+            .into_iter()
+            .map(|(album, song)| self.song(album, song))
+            .collect();
+        (value, entries)
+    }
+}
+
+async fn read_params(request: Request<Incoming>) -> Result<Params, SubsonicError> {
+    let mut params = Params(Vec::new());
+    params.parse(request.uri().query().unwrap_or_default());
+    let is_form = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    if request.method() == Method::POST && is_form {
+        let body = Limited::new(request.into_body(), MAX_BODY)
+            .collect()
+            .await
+            .map_err(|e| SubsonicError::new(0, format!("Failed to read the request: {e}")))?
+            .to_bytes();
+        params.parse(&String::from_utf8_lossy(&body));
+    }
+    Ok(params)
+}
+
+fn decode_password(password: &str) -> String {
+    let Some(hex) = password.strip_prefix("enc:") else {
+        return password.to_string();
+    };
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect();
+    bytes
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_default()
+}
+
+/// The leading year of a date tag like `1997` or `1997-05-21`.
+fn year(tag: &str) -> Option<i32> {
+    tag.get(..4)?.parse().ok()
+}
+
+/// When the album's first file was last modified, the closest to when it was added.
+fn created(album: &Album) -> SystemTime {
+    album
+        .songs
+        .first()
+        .and_then(|song| std::fs::metadata(&song.track.path).ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+#[path = "./routes.tests.rs"]
+mod tests;
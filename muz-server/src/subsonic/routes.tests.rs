@@ -0,0 +1,242 @@
+use super::*;
+use crate::subsonic::serve;
+use crate::testing::{request, TestLibrary};
+use muz_core::bus::EventBus;
+use muz_core::config::SubsonicSettings;
+use std::net::SocketAddr;
+
+async fn start(test_library: &TestLibrary) -> SocketAddr {
+    let tracks = test_library.scan_tagged().await;
+    let library = LibraryService::new(Arc::new(tokio::sync::Mutex::new(tracks)), EventBus::new());
+    let settings = SubsonicSettings {
+        enabled: true,
+        address: "127.0.0.1:0".to_string(),
+        username: "muz".to_string(),
+        password: Some("secret".to_string()),
+    };
+    serve(&settings, library, Some(&test_library.data_dir()))
+        .await
+        .unwrap()
+}
+
+/// Sends a GET request, returning the status, the head and the body.
+async fn get(address: SocketAddr, path: &str, headers: &str) -> (u16, String, Vec<u8>) {
+    request(address, "GET", path, headers, "").await
+}
+
+/// Calls an endpoint as the test user, returning the JSON `subsonic-response`.
+async fn call(address: SocketAddr, endpoint: &str, params: &str) -> Value {
+    let path = format!("/rest/{endpoint}.view?u=muz&p=secret&v=1.16.1&c=test&f=json{params}");
+    let (status, _, body) = get(address, &path, "").await;
+    assert_eq!(status, 200);
+    let mut response: Value = serde_json::from_slice(&body).unwrap();
+    response["subsonic-response"].take()
+}
+
+async fn album(address: SocketAddr) -> Value {
+    let artists = call(address, "getArtists", "").await;
+    let artist = &artists["artists"]["index"][0]["artist"][0];
+    assert_eq!(artists["artists"]["index"][0]["name"], "T");
+    assert_eq!(artist["name"], "The Rests");
+    let artist = call(
+        address,
+        "getArtist",
+        &format!("&id={}", artist["id"].as_str().unwrap()),
+    )
+    .await;
+    let album_id = artist["artist"]["album"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    call(address, "getAlbum", &format!("&id={album_id}")).await["album"].take()
+}
+
+#[tokio::test]
+async fn test_authentication() {
+    let library = TestLibrary::create();
+    let address = start(&library).await;
+
+    let token = format!("{:x}", md5::compute("secretsalt"));
+    for auth in [
+        "u=muz&p=secret".to_string(),
+        "u=muz&p=enc:736563726574".to_string(),
+        format!("u=muz&t={token}&s=salt"),
+    ] {
+        let (_, _, body) = get(address, &format!("/rest/ping?{auth}&f=json"), "").await;
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response["subsonic-response"]["status"], "ok", "{auth}");
+    }
+
+    let (status, _, body) = get(address, "/rest/ping?u=muz&p=wrong&f=json", "").await;
+    let response: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(status, 200);
+    assert_eq!(response["subsonic-response"]["status"], "failed");
+    assert_eq!(response["subsonic-response"]["error"]["code"], 40);
+
+    // XML unless JSON is asked for
+    let (_, head, body) = get(address, "/rest/ping.view?p=secret", "").await;
+    let body = String::from_utf8(body).unwrap();
+    assert!(head.contains("text/xml"));
+    assert!(body.contains("<subsonic-response "));
+    assert!(body.contains("<error code=\"10\""), "{body}");
+}
+
+#[tokio::test]
+async fn test_browses_artists_albums_and_songs() {
+    let library = TestLibrary::create();
+    let address = start(&library).await;
+
+    let album = album(address).await;
+    assert_eq!(album["name"], "Silence");
+    assert_eq!(album["songCount"], 2);
+    assert_eq!(album["year"], 1999);
+    assert_eq!(album["duration"], 2);
+    let titles: Vec<&str> = album["song"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|song| song["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, ["Alpha", "Beta"]);
+
+    let song_id = album["song"][1]["id"].as_str().unwrap();
+    let song = call(address, "getSong", &format!("&id={song_id}")).await;
+    assert_eq!(song["song"]["path"], "Silence/Beta.wav");
+    assert_eq!(song["song"]["contentType"], "audio/wav");
+    assert_eq!(song["song"]["track"], 2);
+
+    let missing = call(address, "getAlbum", "&id=al-missing").await;
+    assert_eq!(missing["error"]["code"], 70);
+}
+
+#[test]
+fn test_ids_survive_rescans() {
+    let path = "/music/Alpha.flac";
+    assert_ne!(Track::new(path).id, Track::new(path).id);
+    assert_eq!(song_id(&Track::new(path)), song_id(&Track::new(path)));
+}
+
+#[tokio::test]
+async fn test_search() {
+    let library = TestLibrary::create();
+    let address = start(&library).await;
+
+    let found = call(address, "search3", "&query=beta").await;
+    assert_eq!(found["searchResult3"]["song"][0]["title"], "Beta");
+    assert_eq!(found["searchResult3"]["album"], json!([]));
+
+    // Clients sync everything with an empty query
+    let all = call(address, "search3", "&query=%22%22&songCount=1&songOffset=1").await;
+    assert_eq!(all["searchResult3"]["artist"][0]["name"], "The Rests");
+    assert_eq!(all["searchResult3"]["album"][0]["name"], "Silence");
+    assert_eq!(all["searchResult3"]["song"].as_array().unwrap().len(), 1);
+    assert_eq!(all["searchResult3"]["song"][0]["title"], "Beta");
+}
+
+#[tokio::test]
+async fn test_streams_files_and_ranges_and_transcodes() {
+    let library = TestLibrary::create();
+    let address = start(&library).await;
+    let file = std::fs::read(library.song("Alpha.wav")).unwrap();
+    let album = album(address).await;
+    let song_id = album["song"][0]["id"].as_str().unwrap();
+    let stream = format!("/rest/stream?u=muz&p=secret&id={song_id}");
+
+    let (status, _, body) = get(address, &stream, "").await;
+    assert_eq!(status, 200);
+    assert_eq!(body, file);
+
+    let (status, head, body) = get(address, &stream, "Range: bytes=4-11\r\n").await;
+    assert_eq!(status, 206);
+    assert!(
+        head.contains(&format!("bytes 4-11/{}", file.len())),
+        "{head}"
+    );
+    assert_eq!(body, file[4..12]);
+
+    // 16-bit PCM instead of the 32-bit float original
+    let (status, head, body) = get(address, &format!("{stream}&format=wav"), "").await;
+    assert_eq!(status, 200);
+    assert!(head.contains("audio/wav"));
+    assert_eq!(&body[..4], b"RIFF");
+    assert_eq!(body.len(), 44 + 44_100 * 2 * 2);
+
+    let (_, head, body) = get(
+        address,
+        &format!(
+            "/rest/getCoverArt?u=muz&p=secret&id={}",
+            album["id"].as_str().unwrap()
+        ),
+        "",
+    )
+    .await;
+    assert!(head.contains("image/jpeg"), "{head}");
+    assert_eq!(body, b"jpeg");
+}
+
+#[tokio::test]
+async fn test_playlists_stars_and_plays_are_kept() {
+    let library = TestLibrary::create();
+    let address = start(&library).await;
+    let album = album(address).await;
+    let alpha = album["song"][0]["id"].as_str().unwrap();
+    let beta = album["song"][1]["id"].as_str().unwrap();
+
+    let created = call(
+        address,
+        "createPlaylist",
+        &format!("&name=Quiet&songId={alpha}&songId={beta}"),
+    )
+    .await;
+    let playlist_id = created["playlist"]["id"].as_str().unwrap();
+    assert_eq!(created["playlist"]["songCount"], 2);
+    call(
+        address,
+        "updatePlaylist",
+        &format!("&playlistId={playlist_id}&songIndexToRemove=0&songIdToAdd={beta}&name=Quieter"),
+    )
+    .await;
+
+    call(
+        address,
+        "star",
+        &format!("&albumId={}", album["id"].as_str().unwrap()),
+    )
+    .await;
+    call(
+        address,
+        "scrobble",
+        &format!("&id={alpha}&time=1700000000000"),
+    )
+    .await;
+    call(address, "scrobble", &format!("&id={beta}&submission=false")).await;
+
+    // A second server reads what the first saved
+    let address = start(&library).await;
+    let playlist = call(address, "getPlaylist", &format!("&id={playlist_id}")).await;
+    assert_eq!(playlist["playlist"]["name"], "Quieter");
+    let entries: Vec<&str> = playlist["playlist"]["entry"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|song| song["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(entries, ["Beta", "Beta"]);
+
+    let starred = call(address, "getStarred2", "").await;
+    assert_eq!(starred["starred2"]["album"][0]["name"], "Silence");
+    let song = call(address, "getSong", &format!("&id={alpha}")).await;
+    assert_eq!(song["song"]["playCount"], 1);
+    assert_eq!(song["song"]["played"], "2023-11-14T22:13:20.000Z");
+    let song = call(address, "getSong", &format!("&id={beta}")).await;
+    assert_eq!(song["song"]["playCount"], 0);
+
+    call(address, "deletePlaylist", &format!("&id={playlist_id}")).await;
+    let playlists = call(address, "getPlaylists", "").await;
+    assert!(playlists["playlists"]
+        .get("playlist")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .is_empty());
+}
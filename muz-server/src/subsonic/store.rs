@@ -0,0 +1,148 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::json_file::JsonFile;
+
+/// What clients save on the server, kept next to the config.
+pub const STORE_FILE: &str = "subsonic.json";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist {
+    pub id: String,
+    pub name: String,
+    pub comment: Option<String>,
+    pub song_ids: Vec<String>,
+    /// Seconds since the epoch, as are the other times here
+    pub created: u64,
+    pub changed: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Plays {
+    pub count: u64,
+    pub last: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Saved {
+    playlists: Vec<Playlist>,
+    next_playlist: u64,
+    /// When each starred artist, album or song was starred
+    starred: HashMap<String, u64>,
+    plays: HashMap<String, Plays>,
+}
+
+/// Playlists, stars and play counts by catalog id, written through on every change.
+/// Without a path they only last as long as the server.
+pub struct Store {
+    saved: JsonFile<Saved>,
+}
+
+impl Store {
+    /// Reads the store at `path`, starting empty when it is missing or unreadable.
+    pub async fn load(path: Option<PathBuf>) -> Self {
+        Self {
+            saved: JsonFile::load(path, "Subsonic store").await,
+        }
+    }
+
+    pub fn playlists(&self) -> Vec<Playlist> {
+        self.saved.get().playlists.clone()
+    }
+
+    pub fn playlist(&self, id: &str) -> Option<Playlist> {
+        self.saved
+            .get()
+            .playlists
+            .iter()
+            .find(|playlist| playlist.id == id)
+            .cloned()
+    }
+
+    pub fn create_playlist(&self, name: String, song_ids: Vec<String>) -> Result<Playlist> {
+        self.saved.update(|saved| {
+            saved.next_playlist += 1;
+            let now = now();
+            let playlist = Playlist {
+                id: format!("pl-{}", saved.next_playlist),
+                name,
+                comment: None,
+                song_ids,
+                created: now,
+                changed: now,
+            };
+            saved.playlists.push(playlist.clone());
+            playlist
+        })
+    }
+
+    /// Changes the playlist, `None` if there is no such playlist.
+    pub fn update_playlist(
+        &self,
+        id: &str,
+        update: impl FnOnce(&mut Playlist),
+    ) -> Result<Option<Playlist>> {
+        self.saved.update(|saved| {
+            let playlist = saved.playlists.iter_mut().find(|p| p.id == id)?;
+            update(playlist);
+            playlist.changed = now();
+            Some(playlist.clone())
+        })
+    }
+
+    /// Whether there was such a playlist.
+    pub fn delete_playlist(&self, id: &str) -> Result<bool> {
+        self.saved.update(|saved| {
+            let count = saved.playlists.len();
+            saved.playlists.retain(|playlist| playlist.id != id);
+            saved.playlists.len() < count
+        })
+    }
+
+    pub fn starred(&self, id: &str) -> Option<SystemTime> {
+        self.saved.get().starred.get(id).map(|&secs| time(secs))
+    }
+
+    pub fn set_starred(&self, ids: &[String], starred: bool) -> Result<()> {
+        let now = now();
+        self.saved.update(|saved| {
+            for id in ids {
+                if starred {
+                    saved.starred.entry(id.clone()).or_insert(now);
+                } else {
+                    saved.starred.remove(id);
+                }
+            }
+        })
+    }
+
+    pub fn plays(&self, id: &str) -> Option<Plays> {
+        self.saved.get().plays.get(id).copied()
+    }
+
+    pub fn record_play(&self, id: &str, at: SystemTime) -> Result<()> {
+        self.saved.update(|saved| {
+            let plays = saved.plays.entry(id.to_string()).or_default();
+            plays.count += 1;
+            plays.last = plays.last.max(secs(at));
+        })
+    }
+}
+
+pub fn time(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+fn now() -> u64 {
+    secs(SystemTime::now())
+}
@@ -0,0 +1,122 @@
+//! Fixtures shared by the tests of the servers.
+
+use muz_core::driver::{render::RenderOutput, wav::WavOutput};
+use muz_core::library::Library;
+use muz_core::track::TrackMetadata;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// An album of two silent songs with a cover under `music/`, and room for a data
+/// directory, removed when dropped.
+pub struct TestLibrary {
+    pub root: PathBuf,
+}
+
+impl TestLibrary {
+    pub fn create() -> Self {
+        let library = Self {
+            root: std::env::temp_dir().join(format!("muz-test-{}", uuid::Uuid::new_v4())),
+        };
+        std::fs::create_dir_all(library.song("")).unwrap();
+        for name in ["Alpha.wav", "Beta.wav"] {
+            let mut output = WavOutput::new(library.song(name));
+            output.write(&vec![0.0; 2 * 44_100], 44_100, 2).unwrap();
+        }
+        std::fs::write(library.song("cover.jpg"), b"jpeg").unwrap();
+        library
+    }
+
+    pub fn music_dir(&self) -> PathBuf {
+        self.root.join("music")
+    }
+
+    pub fn data_dir(&self) -> PathBuf {
+        self.root.join("data")
+    }
+
+    /// A file of the album.
+    pub fn song(&self, name: &str) -> PathBuf {
+        self.music_dir().join("Silence").join(name)
+    }
+
+    /// The songs as scanned, without tags, in file order.
+    pub async fn scan(&self) -> Library {
+        let mut library = Library::new(self.music_dir(), "Test".to_string());
+        library.initialize().await;
+        library.tracks.sort_by(|a, b| a.path.cmp(&b.path));
+        library
+    }
+
+    /// The songs tagged as tracks of "Silence" by "The Rests", titled after their files.
+    pub async fn scan_tagged(&self) -> Library {
+        let mut library = self.scan().await;
+        for (number, track) in library.tracks.iter_mut().enumerate() {
+            track.metadata = Some(TrackMetadata {
+                title: track
+                    .path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned()),
+                album: Some("Silence".to_string()),
+                artist: Some("The Rests".to_string()),
+                album_artist: None,
+                track_number: Some(number as i32 + 1),
+                disc_number: None,
+                genre: Some("Ambient".to_string()),
+                year: Some("1999-01-01".to_string()),
+            });
+        }
+        library
+    }
+}
+
+impl Drop for TestLibrary {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Sends one request over a new connection, returning the status, the head and the body,
+/// joined when it was chunked.
+pub async fn request(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    headers: &str,
+    body: &str,
+) -> (u16, String, Vec<u8>) {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let request = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\n{headers}\
+         Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+
+    let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+    let head = String::from_utf8_lossy(&response[..split]).to_string();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    let mut body = response[split + 4..].to_vec();
+    if head.to_lowercase().contains("transfer-encoding: chunked") {
+        body = dechunk(&body);
+    }
+    (status, head, body)
+}
+
+fn dechunk(mut chunked: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    while let Some(line_end) = chunked.windows(2).position(|w| w == b"\r\n") {
+        let size = std::str::from_utf8(&chunked[..line_end]).unwrap();
+        let size = usize::from_str_radix(size.trim(), 16).unwrap();
+        if size == 0 {
+            break;
+        }
+        let start = line_end + 2;
+        body.extend_from_slice(&chunked[start..start + size]);
+        chunked = &chunked[start + size + 2..];
+    }
+    body
+}
@@ -0,0 +1,278 @@
+use muz_core::command::{PlaybackCommand, Volume};
+use muz_core::events::PlayerEvent;
+use muz_core::playback::{PlaybackSnapshot, RepeatMode};
+use muz_core::track::Track;
+
+use crate::browser::{Browser, Selection};
+use crate::input::Key;
+
+const VOLUME_STEP: f32 = 0.05;
+const SEEK_STEP_MS: i64 = 10_000;
+/// Rows skipped by PageUp and PageDown
+const PAGE: isize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    Library,
+    Queue,
+}
+
+/// What a key asks of the engine, carried out by the main loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Control(PlaybackCommand),
+    Play(Selection),
+    Enqueue(Vec<Track>),
+    SelectFromQueue(String),
+    RemoveFromQueue(String),
+    ReorderQueue(usize, usize),
+    ClearQueue,
+    Search(String),
+    Rescan,
+    Quit,
+}
+
+#[derive(Debug, Default)]
+pub struct Search {
+    pub query: String,
+    pub results: Vec<Track>,
+    pub cursor: usize,
+}
+
+pub struct App {
+    pub browser: Browser,
+    pub queue: Vec<Track>,
+    pub queue_cursor: usize,
+    pub status: Option<PlaybackSnapshot>,
+    pub spectrum: Vec<f32>,
+    pub panel: Panel,
+    /// Open while typing a search, replacing the columns
+    pub search: Option<Search>,
+    pub show_help: bool,
+    /// The last error, shown until the next key
+    pub message: Option<String>,
+}
+
+impl App {
+    pub fn new(browser: Browser) -> Self {
+        Self {
+            browser,
+            queue: Vec::new(),
+            queue_cursor: 0,
+            status: None,
+            spectrum: Vec::new(),
+            panel: Panel::Library,
+            search: None,
+            show_help: false,
+            message: None,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: Key) -> Option<Action> {
+        self.message = None;
+        if self.show_help {
+            self.show_help = false;
+            return None;
+        }
+        if self.search.is_some() {
+            return self.search_key(key);
+        }
+        match key {
+            Key::Char('q') | Key::Ctrl('c') => Some(Action::Quit),
+            Key::Char('?') => {
+                self.show_help = true;
+                None
+            }
+            Key::Tab | Key::BackTab => {
+                self.panel = match self.panel {
+                    Panel::Library => Panel::Queue,
+                    Panel::Queue => Panel::Library,
+                };
+                None
+            }
+            Key::Char('/') => {
+                self.search = Some(Search::default());
+                None
+            }
+            Key::Char(' ') => Some(Action::Control(PlaybackCommand::TogglePlayPause)),
+            Key::Char('n') => Some(Action::Control(PlaybackCommand::Next)),
+            Key::Char('p') => Some(Action::Control(PlaybackCommand::Previous)),
+            Key::Char('x') => Some(Action::Control(PlaybackCommand::Stop)),
+            Key::Char('+') | Key::Char('=') => self.change_volume(VOLUME_STEP),
+            Key::Char('-') => self.change_volume(-VOLUME_STEP),
+            Key::Char('m') => Some(Action::Control(PlaybackCommand::SetMuted {
+                muted: !self.status.as_ref().is_some_and(|s| s.muted),
+            })),
+            Key::Char(',') => Some(Action::Control(PlaybackCommand::SeekRelative {
+                delta_ms: -SEEK_STEP_MS,
+            })),
+            Key::Char('.') => Some(Action::Control(PlaybackCommand::SeekRelative {
+                delta_ms: SEEK_STEP_MS,
+            })),
+            Key::Char('r') => {
+                let mode = match self.status.as_ref().map(|s| s.repeat) {
+                    Some(RepeatMode::Off) | None => RepeatMode::All,
+                    Some(RepeatMode::All) => RepeatMode::One,
+                    Some(RepeatMode::One) => RepeatMode::Off,
+                };
+                Some(Action::Control(PlaybackCommand::SetRepeat { mode }))
+            }
+            Key::Char('s') => Some(Action::Control(PlaybackCommand::SetShuffle {
+                shuffle: !self.status.as_ref().is_some_and(|s| s.shuffle),
+            })),
+            key => match self.panel {
+                Panel::Library => self.library_key(key),
+                Panel::Queue => self.queue_key(key),
+            },
+        }
+    }
+
+    fn library_key(&mut self, key: Key) -> Option<Action> {
+        match key {
+            Key::Up | Key::Char('k') => self.browser.move_cursor(-1),
+            Key::Down | Key::Char('j') => self.browser.move_cursor(1),
+            Key::PageUp => self.browser.move_cursor(-PAGE),
+            Key::PageDown => self.browser.move_cursor(PAGE),
+            Key::Home | Key::Char('g') => self.browser.move_cursor(isize::MIN),
+            Key::End | Key::Char('G') => self.browser.move_cursor(isize::MAX),
+            Key::Left | Key::Char('h') => self.browser.focus_left(),
+            Key::Right | Key::Char('l') => self.browser.focus_right(),
+            Key::Enter => return self.browser.selection().map(Action::Play),
+            Key::Char('R') => return Some(Action::Rescan),
+            Key::Char('a') => {
+                let tracks = match self.browser.selection()? {
+                    Selection::Tracks(tracks) => tracks,
+                    Selection::Track(track) => vec![*track],
+                    Selection::AlbumTrack { track_id, .. } => self
+                        .browser
+                        .tracks()
+                        .into_iter()
+                        .filter(|track| track.id == track_id)
+                        .cloned()
+                        .collect(),
+                };
+                return Some(Action::Enqueue(tracks));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn queue_key(&mut self, key: Key) -> Option<Action> {
+        let last = self.queue.len().saturating_sub(1);
+        let cursor = self.queue_cursor;
+        match key {
+            Key::Up | Key::Char('k') => self.move_queue_cursor(-1),
+            Key::Down | Key::Char('j') => self.move_queue_cursor(1),
+            Key::PageUp => self.move_queue_cursor(-PAGE),
+            Key::PageDown => self.move_queue_cursor(PAGE),
+            Key::Home | Key::Char('g') => self.queue_cursor = 0,
+            Key::End | Key::Char('G') => self.queue_cursor = last,
+            Key::Enter => {
+                let track = self.queue.get(cursor)?;
+                return Some(Action::SelectFromQueue(track.id.clone()));
+            }
+            Key::Char('d') | Key::Delete => {
+                let track = self.queue.get(cursor)?;
+                return Some(Action::RemoveFromQueue(track.id.clone()));
+            }
+            // The cursor moves along with the track
+            Key::Char('K') if cursor > 0 => {
+                self.queue_cursor -= 1;
+                return Some(Action::ReorderQueue(cursor, cursor - 1));
+            }
+            Key::Char('J') if cursor < last => {
+                self.queue_cursor += 1;
+                return Some(Action::ReorderQueue(cursor, cursor + 1));
+            }
+            Key::Char('c') => return Some(Action::ClearQueue),
+            _ => {}
+        }
+        None
+    }
+
+    fn search_key(&mut self, key: Key) -> Option<Action> {
+        let search = self.search.as_mut()?;
+        match key {
+            Key::Esc | Key::Ctrl('c') => self.search = None,
+            Key::Up => search.cursor = search.cursor.saturating_sub(1),
+            Key::Down => {
+                search.cursor = (search.cursor + 1).min(search.results.len().saturating_sub(1))
+            }
+            Key::Enter => {
+                let track = search.results.get(search.cursor)?.clone();
+                self.search = None;
+                return Some(Action::Play(Selection::Track(Box::new(track))));
+            }
+            Key::Tab => {
+                let track = search.results.get(search.cursor)?.clone();
+                return Some(Action::Enqueue(vec![track]));
+            }
+            Key::Backspace => {
+                search.query.pop();
+                return Some(Action::Search(search.query.clone()));
+            }
+            Key::Ctrl('u') => {
+                search.query.clear();
+                return Some(Action::Search(String::new()));
+            }
+            Key::Char(c) => {
+                search.query.push(c);
+                return Some(Action::Search(search.query.clone()));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Results of a search, dropped when the query moved on meanwhile.
+    pub fn set_search_results(&mut self, query: &str, results: Vec<Track>) {
+        if let Some(search) = self.search.as_mut().filter(|s| s.query == query) {
+            search.results = results;
+            search.cursor = 0;
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::QueueChanged(event) => {
+                self.queue = event.queue.clone();
+                self.queue_cursor = self.queue_cursor.min(self.queue.len().saturating_sub(1));
+            }
+            PlayerEvent::Spectrum(event) => self.spectrum = event.spectrum_data.clone(),
+            PlayerEvent::PlaybackError(error) => {
+                self.message = Some(format!(
+                    "Failed to play {:?}: {}",
+                    error.path, error.message
+                ));
+            }
+            PlayerEvent::OutputDeviceFallback(fallback) => {
+                self.message = Some(format!(
+                    "Output device {:?} is unavailable: {}",
+                    fallback.requested_device, fallback.reason
+                ));
+            }
+            PlayerEvent::OutputUnavailable(unavailable) => {
+                self.message = Some(format!("Paused, no audio output: {}", unavailable.reason));
+            }
+            _ => {}
+        }
+    }
+
+    fn move_queue_cursor(&mut self, delta: isize) {
+        self.queue_cursor = self
+            .queue_cursor
+            .saturating_add_signed(delta)
+            .min(self.queue.len().saturating_sub(1));
+    }
+
+    fn change_volume(&self, delta: f32) -> Option<Action> {
+        let volume = self.status.as_ref()?.volume;
+        let volume = Volume::try_from((volume + delta).clamp(0.0, 1.0)).ok()?;
+        Some(Action::Control(PlaybackCommand::SetVolume { volume }))
+    }
+}
+
+#[cfg(test)]
+#[path = "./app.tests.rs"]
+mod tests;
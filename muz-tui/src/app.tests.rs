@@ -0,0 +1,107 @@
+use super::*;
+use muz_core::events::QueueChangedEvent;
+use muz_core::playback::PlaybackState;
+
+fn queue(app: &mut App, titles: &[&str]) {
+    let queue = titles
+        .iter()
+        .map(|title| Track::new(format!("/music/{title}.flac")))
+        .collect();
+    app.handle_event(&PlayerEvent::QueueChanged(QueueChangedEvent { queue }));
+}
+
+fn status(volume: f32) -> PlaybackSnapshot {
+    PlaybackSnapshot {
+        state: PlaybackState::Playing,
+        track: None,
+        position_ms: 0,
+        position_frames: 0,
+        volume,
+        muted: false,
+        speed: 1.0,
+        pitch_semitones: 0.0,
+        repeat: RepeatMode::Off,
+        shuffle: false,
+    }
+}
+
+#[test]
+fn test_transport_keys() {
+    let mut app = App::new(Browser::new(Default::default()));
+    assert_eq!(
+        app.handle_key(Key::Char(' ')),
+        Some(Action::Control(PlaybackCommand::TogglePlayPause))
+    );
+    // Nothing to step from before the first status
+    assert_eq!(app.handle_key(Key::Char('+')), None);
+
+    app.status = Some(status(0.98));
+    let Some(Action::Control(PlaybackCommand::SetVolume { volume })) =
+        app.handle_key(Key::Char('+'))
+    else {
+        panic!("+ raises the volume");
+    };
+    assert_eq!(volume.get(), 1.0);
+    assert_eq!(
+        app.handle_key(Key::Char('r')),
+        Some(Action::Control(PlaybackCommand::SetRepeat {
+            mode: RepeatMode::All
+        }))
+    );
+    assert_eq!(app.handle_key(Key::Ctrl('c')), Some(Action::Quit));
+}
+
+#[test]
+fn test_queue_reorder_and_remove() {
+    let mut app = App::new(Browser::new(Default::default()));
+    queue(&mut app, &["one", "two", "three"]);
+    app.handle_key(Key::Tab);
+    assert_eq!(app.panel, Panel::Queue);
+
+    // Already at the top
+    assert_eq!(app.handle_key(Key::Char('K')), None);
+    assert_eq!(
+        app.handle_key(Key::Char('J')),
+        Some(Action::ReorderQueue(0, 1))
+    );
+    assert_eq!(app.queue_cursor, 1);
+
+    app.handle_key(Key::End);
+    let id = app.queue[2].id.clone();
+    assert_eq!(
+        app.handle_key(Key::Delete),
+        Some(Action::RemoveFromQueue(id))
+    );
+    queue(&mut app, &["one", "two"]);
+    assert_eq!(app.queue_cursor, 1);
+}
+
+#[test]
+fn test_search_takes_the_keys() {
+    let mut app = App::new(Browser::new(Default::default()));
+    app.handle_key(Key::Char('/'));
+    assert_eq!(
+        app.handle_key(Key::Char('q')),
+        Some(Action::Search("q".to_string()))
+    );
+    assert_eq!(
+        app.handle_key(Key::Char('u')),
+        Some(Action::Search("qu".to_string()))
+    );
+
+    // Results of an older query are dropped
+    let track = Track::new("/music/Quiet.flac");
+    app.set_search_results("q", vec![track.clone()]);
+    assert!(app.search.as_ref().unwrap().results.is_empty());
+    app.set_search_results("qu", vec![track.clone()]);
+
+    assert_eq!(
+        app.handle_key(Key::Tab),
+        Some(Action::Enqueue(vec![track.clone()]))
+    );
+    assert_eq!(
+        app.handle_key(Key::Enter),
+        Some(Action::Play(Selection::Track(Box::new(track))))
+    );
+    assert!(app.search.is_none());
+}
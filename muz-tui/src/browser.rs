@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use muz_core::track::Track;
+
+/// The library as `LibraryService::albums_by_artist` groups it.
+pub type AlbumsByArtist = HashMap<String, HashMap<String, Vec<Track>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Artists,
+    Albums,
+    Tracks,
+}
+
+/// What Enter plays or `a` queues.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    /// A track of the selected album, played along with the rest of it
+    AlbumTrack {
+        artist: String,
+        album: String,
+        track_id: String,
+    },
+    Track(Box<Track>),
+    /// Everything under the selected artist or album
+    Tracks(Vec<Track>),
+}
+
+/// Artist, album and track columns like the desktop `MillerColumnsView`. The first row of
+/// the artist and album columns stands for all of them, moving the cursor selects.
+pub struct Browser {
+    library: AlbumsByArtist,
+    artists: Vec<String>,
+    focus: Column,
+    /// Cursor of each column, in `Column` order
+    cursors: [usize; 3],
+}
+
+impl Browser {
+    pub fn new(library: AlbumsByArtist) -> Self {
+        let mut artists: Vec<String> = library.keys().cloned().collect();
+        artists.sort();
+        Self {
+            library,
+            artists,
+            focus: Column::Artists,
+            cursors: [0; 3],
+        }
+    }
+
+    /// Replaces the library after a rescan, keeping the selected artist and album when
+    /// they still exist.
+    pub fn set_library(&mut self, library: AlbumsByArtist) {
+        let (artist, album) = (self.selected_artist(), self.selected_album());
+        let focus = self.focus;
+        *self = Self::new(library);
+        self.focus = focus;
+        if let Some(index) = artist.and_then(|a| self.artists.iter().position(|b| *b == a)) {
+            self.cursors[0] = index + 1;
+        }
+        if let Some(index) = album.and_then(|a| self.albums().iter().position(|b| *b == a)) {
+            self.cursors[1] = index + 1;
+        }
+    }
+
+    pub fn focus(&self) -> Column {
+        self.focus
+    }
+
+    pub fn cursor(&self, column: Column) -> usize {
+        self.cursors[column as usize]
+    }
+
+    /// The rows of a column as displayed.
+    pub fn items(&self, column: Column) -> Vec<String> {
+        match column {
+            Column::Artists => std::iter::once("All artists".to_string())
+                .chain(self.artists.iter().cloned())
+                .collect(),
+            Column::Albums => std::iter::once("All albums".to_string())
+                .chain(self.albums())
+                .collect(),
+            Column::Tracks => self.tracks().into_iter().map(title).collect(),
+        }
+    }
+
+    pub fn selected_artist(&self) -> Option<String> {
+        self.cursors[0]
+            .checked_sub(1)
+            .and_then(|i| self.artists.get(i).cloned())
+    }
+
+    pub fn selected_album(&self) -> Option<String> {
+        self.cursors[1]
+            .checked_sub(1)
+            .and_then(|i| self.albums().get(i).cloned())
+    }
+
+    /// The albums of the selected artist, or of everyone.
+    pub fn albums(&self) -> Vec<String> {
+        let mut albums: Vec<String> = match self.selected_artist() {
+            Some(artist) => self.library[&artist].keys().cloned().collect(),
+            None => self
+                .library
+                .values()
+                .flat_map(|albums| albums.keys().cloned())
+                .collect(),
+        };
+        albums.sort();
+        albums.dedup();
+        albums
+    }
+
+    /// The tracks under the selected artist and album, album by album.
+    pub fn tracks(&self) -> Vec<&Track> {
+        let artist = self.selected_artist();
+        let album = self.selected_album();
+        let mut tracks = Vec::new();
+        for name in &self.artists {
+            if artist.as_ref().is_some_and(|a| a != name) {
+                continue;
+            }
+            let albums = &self.library[name];
+            let mut names: Vec<&String> = albums.keys().collect();
+            names.sort();
+            for name in names {
+                if album.as_ref().is_none_or(|a| a == name) {
+                    tracks.extend(&albums[name]);
+                }
+            }
+        }
+        tracks
+    }
+
+    pub fn move_cursor(&mut self, delta: isize) {
+        let len = match self.focus {
+            Column::Tracks => self.tracks().len(),
+            column => self.items(column).len(),
+        };
+        let column = self.focus as usize;
+        let cursor = self.cursors[column]
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+        if cursor != self.cursors[column] {
+            self.cursors[column] = cursor;
+            // Columns to the right follow the new selection
+            for next in &mut self.cursors[column + 1..] {
+                *next = 0;
+            }
+        }
+    }
+
+    pub fn focus_left(&mut self) {
+        self.focus = match self.focus {
+            Column::Tracks => Column::Albums,
+            _ => Column::Artists,
+        };
+    }
+
+    pub fn focus_right(&mut self) {
+        self.focus = match self.focus {
+            Column::Artists => Column::Albums,
+            _ => Column::Tracks,
+        };
+    }
+
+    pub fn selection(&self) -> Option<Selection> {
+        let tracks = self.tracks();
+        if self.focus != Column::Tracks {
+            let tracks: Vec<Track> = tracks.into_iter().cloned().collect();
+            return (!tracks.is_empty()).then_some(Selection::Tracks(tracks));
+        }
+        let track = (*tracks.get(self.cursors[2])?).clone();
+        match self.selected_album() {
+            Some(album) => {
+                // Like the desktop app, an album picked under all artists plays with the
+                // first artist having it
+                let artist = self.selected_artist().or_else(|| {
+                    self.artists
+                        .iter()
+                        .find(|artist| self.library[*artist].contains_key(&album))
+                        .cloned()
+                })?;
+                Some(Selection::AlbumTrack {
+                    artist,
+                    album,
+                    track_id: track.id,
+                })
+            }
+            None => Some(Selection::Track(Box::new(track))),
+        }
+    }
+}
+
+/// The title, or the file name of untagged tracks.
+pub fn title(track: &Track) -> String {
+    track
+        .metadata
+        .as_ref()
+        .and_then(|m| m.title.clone())
+        .unwrap_or_else(|| Track::default_title(&track.path))
+}
+
+#[cfg(test)]
+#[path = "./browser.tests.rs"]
+mod tests;
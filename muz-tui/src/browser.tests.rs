@@ -0,0 +1,146 @@
+use super::*;
+use muz_core::track::TrackMetadata;
+
+fn track(artist: &str, album: &str, title: &str) -> Track {
+    let mut track = Track::new(format!("/music/{artist}/{album}/{title}.flac"));
+    track.metadata = Some(TrackMetadata {
+        title: Some(title.to_string()),
+        album: Some(album.to_string()),
+        artist: Some(artist.to_string()),
+        album_artist: None,
+        track_number: None,
+        disc_number: None,
+        genre: None,
+        year: None,
+    });
+    track
+}
+
+fn library() -> AlbumsByArtist {
+    let mut library = AlbumsByArtist::new();
+    for (artist, album, title) in [
+        ("Boards of Canada", "Geogaddi", "Music Is Math"),
+        ("Boards of Canada", "Geogaddi", "Gyroscope"),
+        (
+            "Boards of Canada",
+            "Campfire Headphase",
+            "Chromakey Dreamcoat",
+        ),
+        ("Aphex Twin", "Drukqs", "Avril 14th"),
+        ("Various", "Geogaddi", "Cover"),
+    ] {
+        library
+            .entry(artist.to_string())
+            .or_default()
+            .entry(album.to_string())
+            .or_default()
+            .push(track(artist, album, title));
+    }
+    library
+}
+
+#[test]
+fn test_columns_follow_the_selection() {
+    let mut browser = Browser::new(library());
+    assert_eq!(
+        browser.items(Column::Artists),
+        vec!["All artists", "Aphex Twin", "Boards of Canada", "Various"]
+    );
+    assert_eq!(
+        browser.items(Column::Albums),
+        vec!["All albums", "Campfire Headphase", "Drukqs", "Geogaddi"]
+    );
+    assert_eq!(browser.tracks().len(), 5);
+
+    browser.move_cursor(2);
+    assert_eq!(
+        browser.selected_artist().as_deref(),
+        Some("Boards of Canada")
+    );
+    assert_eq!(
+        browser.items(Column::Albums),
+        vec!["All albums", "Campfire Headphase", "Geogaddi"]
+    );
+    assert_eq!(
+        browser.items(Column::Tracks),
+        vec!["Chromakey Dreamcoat", "Music Is Math", "Gyroscope"]
+    );
+
+    browser.focus_right();
+    browser.move_cursor(2);
+    assert_eq!(browser.selected_album().as_deref(), Some("Geogaddi"));
+    assert_eq!(
+        browser.items(Column::Tracks),
+        vec!["Music Is Math", "Gyroscope"]
+    );
+
+    // Another artist resets the album
+    browser.focus_left();
+    browser.move_cursor(-1);
+    assert_eq!(browser.selected_album(), None);
+    assert_eq!(browser.items(Column::Tracks), vec!["Avril 14th"]);
+}
+
+#[test]
+fn test_cursor_stays_in_the_column() {
+    let mut browser = Browser::new(library());
+    browser.move_cursor(-5);
+    assert_eq!(browser.cursor(Column::Artists), 0);
+    browser.move_cursor(isize::MAX);
+    assert_eq!(browser.cursor(Column::Artists), 3);
+    browser.focus_right();
+    browser.focus_right();
+    browser.focus_right();
+    assert_eq!(browser.focus(), Column::Tracks);
+    browser.move_cursor(isize::MAX);
+    assert_eq!(browser.cursor(Column::Tracks), 0);
+}
+
+#[test]
+fn test_selection() {
+    let mut browser = Browser::new(library());
+    browser.move_cursor(1);
+    let Some(Selection::Tracks(tracks)) = browser.selection() else {
+        panic!("the artist column selects all tracks");
+    };
+    assert_eq!(tracks.len(), 1);
+
+    // An album under all artists plays with the first artist having it
+    browser.move_cursor(-1);
+    browser.focus_right();
+    browser.move_cursor(3);
+    browser.focus_right();
+    browser.move_cursor(1);
+    let gyroscope = browser.tracks()[1].id.clone();
+    assert_eq!(
+        browser.selection(),
+        Some(Selection::AlbumTrack {
+            artist: "Boards of Canada".to_string(),
+            album: "Geogaddi".to_string(),
+            track_id: gyroscope,
+        })
+    );
+
+    browser.focus_left();
+    browser.move_cursor(-3);
+    browser.focus_right();
+    let first = browser.tracks()[0].clone();
+    assert_eq!(browser.selection(), Some(Selection::Track(Box::new(first))));
+}
+
+#[test]
+fn test_rescan_keeps_the_selection() {
+    let mut browser = Browser::new(library());
+    browser.move_cursor(2);
+    browser.focus_right();
+    browser.move_cursor(2);
+    let mut rescanned = library();
+    rescanned.insert("ABBA".to_string(), HashMap::new());
+    browser.set_library(rescanned);
+    assert_eq!(
+        browser.selected_artist().as_deref(),
+        Some("Boards of Canada")
+    );
+    assert_eq!(browser.selected_album().as_deref(), Some("Geogaddi"));
+    assert_eq!(browser.focus(), Column::Albums);
+}
@@ -0,0 +1,97 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    /// Ctrl with a letter, e.g. `Ctrl('c')`
+    Ctrl(char),
+    Enter,
+    Esc,
+    Backspace,
+    Delete,
+    Tab,
+    BackTab,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+/// Splits what the terminal sent into keys. A lone escape is the Esc key, unknown escape
+/// sequences are dropped.
+pub fn parse(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        i += 1;
+        let key = match byte {
+            0x1b if matches!(bytes.get(i), Some(b'[' | b'O')) => {
+                let (key, len) = parse_sequence(&bytes[i + 1..]);
+                i += 1 + len;
+                match key {
+                    Some(key) => key,
+                    None => continue,
+                }
+            }
+            0x1b => Key::Esc,
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x7f | 0x08 => Key::Backspace,
+            0x01..=0x1a => Key::Ctrl((b'a' + byte - 1) as char),
+            0x00..=0x1f => continue,
+            _ => {
+                let len = match byte {
+                    0xf0.. => 4,
+                    0xe0.. => 3,
+                    0xc0.. => 2,
+                    _ => 1,
+                };
+                let end = (i - 1 + len).min(bytes.len());
+                let decoded = std::str::from_utf8(&bytes[i - 1..end])
+                    .ok()
+                    .and_then(|s| s.chars().next());
+                i = end;
+                match decoded {
+                    Some(c) => Key::Char(c),
+                    None => continue,
+                }
+            }
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+/// Reads a CSI or SS3 sequence after its introducer, returning the key and the bytes used.
+fn parse_sequence(bytes: &[u8]) -> (Option<Key>, usize) {
+    let Some(end) = bytes.iter().position(|b| (0x40..=0x7e).contains(b)) else {
+        return (None, bytes.len());
+    };
+    let params = std::str::from_utf8(&bytes[..end]).unwrap_or_default();
+    let key = match bytes[end] {
+        b'A' => Some(Key::Up),
+        b'B' => Some(Key::Down),
+        b'C' => Some(Key::Right),
+        b'D' => Some(Key::Left),
+        b'H' => Some(Key::Home),
+        b'F' => Some(Key::End),
+        b'Z' => Some(Key::BackTab),
+        b'~' => match params.split(';').next() {
+            Some("1" | "7") => Some(Key::Home),
+            Some("4" | "8") => Some(Key::End),
+            Some("3") => Some(Key::Delete),
+            Some("5") => Some(Key::PageUp),
+            Some("6") => Some(Key::PageDown),
+            _ => None,
+        },
+        _ => None,
+    };
+    (key, end + 1)
+}
+
+#[cfg(test)]
+#[path = "./input.tests.rs"]
+mod tests;
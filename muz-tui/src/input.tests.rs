@@ -0,0 +1,44 @@
+use super::*;
+
+#[test]
+fn test_plain_and_control_keys() {
+    assert_eq!(
+        parse(b"a \r\t\x7f\x03"),
+        vec![
+            Key::Char('a'),
+            Key::Char(' '),
+            Key::Enter,
+            Key::Tab,
+            Key::Backspace,
+            Key::Ctrl('c'),
+        ]
+    );
+    assert_eq!(parse("é♪".as_bytes()), vec![Key::Char('é'), Key::Char('♪')]);
+}
+
+#[test]
+fn test_escape_sequences() {
+    assert_eq!(
+        parse(b"\x1b[A\x1b[B\x1bOC\x1b[D\x1b[5~\x1b[6~\x1b[3~\x1b[1~\x1b[F\x1b[Z"),
+        vec![
+            Key::Up,
+            Key::Down,
+            Key::Right,
+            Key::Left,
+            Key::PageUp,
+            Key::PageDown,
+            Key::Delete,
+            Key::Home,
+            Key::End,
+            Key::BackTab,
+        ]
+    );
+    // Modifiers are ignored, unknown sequences dropped
+    assert_eq!(parse(b"\x1b[1;5A\x1b[99~x"), vec![Key::Up, Key::Char('x')]);
+}
+
+#[test]
+fn test_lone_escape() {
+    assert_eq!(parse(b"\x1b"), vec![Key::Esc]);
+    assert_eq!(parse(b"\x1bq"), vec![Key::Esc, Key::Char('q')]);
+}
@@ -0,0 +1,59 @@
+//! Plays the library in a terminal, e.g. over SSH on a media server.
+
+mod app;
+mod browser;
+mod input;
+#[cfg(unix)]
+mod terminal;
+#[cfg(unix)]
+mod tui;
+mod view;
+mod visualizer;
+
+#[cfg(unix)]
+const USAGE: &str = "Usage: muz-tui [--data-dir DIR] [--ascii]";
+
+#[cfg(unix)]
+#[tokio::main]
+async fn main() {
+    let mut data_dir = None;
+    let mut ascii = !utf8_locale();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--data-dir" => data_dir = args.next().map(Into::into),
+            "--ascii" => ascii = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            other => {
+                eprintln!("Unknown argument: {other}\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Err(e) = tui::run(data_dir, ascii).await {
+        eprintln!("{e:#}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("muz-tui is only supported on Unix");
+    std::process::exit(1);
+}
+
+/// Whether the locale can show the block characters, assumed when it isn't set.
+#[cfg(unix)]
+fn utf8_locale() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        .is_none_or(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
@@ -0,0 +1,82 @@
+use std::io::{self, Write};
+
+/// The terminal in raw mode on the alternate screen, restored when dropped.
+pub struct Terminal {
+    original: libc::termios,
+    /// The lines on screen, to redraw only the ones that changed
+    drawn: Vec<String>,
+    size: (usize, usize),
+}
+
+impl Terminal {
+    pub fn enter() -> io::Result<Self> {
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut stdout = io::stdout();
+        stdout.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J")?;
+        stdout.flush()?;
+        Ok(Self {
+            original,
+            drawn: Vec::new(),
+            size: size(),
+        })
+    }
+
+    /// Columns and rows.
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    /// Picks up a new size, forcing the next frame to be drawn in full.
+    pub fn resize(&mut self) {
+        self.size = size();
+        self.drawn.clear();
+    }
+
+    /// Draws the frame, one string per row, rewriting only the rows that changed.
+    pub fn draw(&mut self, lines: &[String]) -> io::Result<()> {
+        let mut out = Vec::new();
+        if self.drawn.is_empty() {
+            out.extend_from_slice(b"\x1b[2J");
+        }
+        for (row, line) in lines.iter().enumerate() {
+            if self.drawn.get(row) != Some(line) {
+                out.extend_from_slice(
+                    format!("\x1b[{};1H\x1b[0m{line}\x1b[0m\x1b[K", row + 1).as_bytes(),
+                );
+            }
+        }
+        self.drawn = lines.to_vec();
+        let mut stdout = io::stdout();
+        stdout.write_all(&out)?;
+        stdout.flush()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(b"\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+/// Falls back to 80x24 when stdout isn't a terminal.
+fn size() -> (usize, usize) {
+    let mut size = unsafe { std::mem::zeroed::<libc::winsize>() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0
+        && size.ws_col > 0
+    {
+        (size.ws_col as usize, size.ws_row as usize)
+    } else {
+        (80, 24)
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{bail, Context, Result};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::app::{Action, App};
+use crate::browser::{Browser, Selection};
+use crate::terminal::Terminal;
+use crate::view;
+use muz_core::bus::{BusEvent, EventBus};
+use muz_core::config::AppConfig;
+use muz_core::events::{PlayerEvent, QueueChangedEvent};
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+use muz_core::{library::{Library, CACHE_FILE}, playback::Playback};
+
+/// How often the screen follows the playback position and the spectrum
+const FRAME: Duration = Duration::from_millis(50);
+
+/// Runs the player with the screen as its frontend until `q` is pressed.
+pub async fn run(data_dir: Option<PathBuf>, ascii: bool) -> Result<()> {
+    let data_dir = match data_dir {
+        Some(dir) => dir,
+        None => AppConfig::default_data_dir()?,
+    };
+    let config = AppConfig::load(&data_dir)
+        .await
+        .with_context(|| format!("Failed to load the config in {data_dir:?}"))?;
+
+    let mut library = Library::new(config.library_path.clone(), "Library".to_string());
+    library.use_cache(data_dir.join(CACHE_FILE));
+    library.initialize().await;
+    let tracks = library.tracks_cloned();
+    let bus = EventBus::new();
+    let (_, mut events) = bus.subscribe();
+    let library_service = LibraryService::new(Arc::new(Mutex::new(library)), bus.clone());
+
+    let playback = Playback::create(config.create_driver(1.0), bus.clone());
+    if let Ok(mut playback_guard) = playback.lock() {
+        playback_guard.enqueue_multiple(tracks);
+        config.apply_to(&mut playback_guard);
+    } else {
+        bail!("Failed to lock playback");
+    }
+    let playback_service = PlaybackService::new(playback);
+
+    let mut app = App::new(Browser::new(library_service.albums_by_artist().await?));
+    app.queue = playback_service.queue()?;
+    app.status = playback_service.status().ok();
+
+    let (key_tx, mut keys) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buffer = [0u8; 64];
+        while let Ok(read @ 1..) = stdin.read(&mut buffer) {
+            for key in crate::input::parse(&buffer[..read]) {
+                if key_tx.send(key).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let mut terminal = Terminal::enter().context("Failed to set up the terminal")?;
+    let mut resized = signal(SignalKind::window_change())?;
+    let mut frames = tokio::time::interval(FRAME);
+    loop {
+        tokio::select! {
+            Some(key) = keys.recv() => {
+                let Some(action) = app.handle_key(key) else {
+                    continue;
+                };
+                let result = match action {
+                    Action::Quit => break,
+                    Action::Control(command) => playback_service.control_playback(command).map(drop),
+                    Action::Play(selection) => {
+                        play(&playback_service, &library_service, selection).await
+                    }
+                    Action::Enqueue(tracks) => playback_service.enqueue(tracks),
+                    Action::SelectFromQueue(id) => playback_service.select_from_queue(&id).map(drop),
+                    Action::RemoveFromQueue(id) => playback_service.remove_from_queue(&id),
+                    Action::ReorderQueue(from, to) => playback_service.reorder_queue(from, to),
+                    Action::ClearQueue => playback_service.clear_queue(),
+                    Action::Search(query) => {
+                        let results = if query.trim().is_empty() {
+                            Vec::new()
+                        } else {
+                            library_service.search(&query).await
+                        };
+                        app.set_search_results(&query, results);
+                        Ok(())
+                    }
+                    Action::Rescan => rescan(&library_service, &mut app.browser).await,
+                };
+                if let Err(e) = result {
+                    app.message = Some(format!("{e:#}"));
+                }
+            }
+            event = events.recv() => match event {
+                Ok(BusEvent { event, .. }) => app.handle_event(&event),
+                // Only the queue isn't superseded by the next events or read every frame
+                Err(RecvError::Lagged(_)) => {
+                    let queue = bus.snapshot().queue;
+                    app.handle_event(&PlayerEvent::QueueChanged(QueueChangedEvent { queue }));
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = resized.recv() => terminal.resize(),
+            _ = frames.tick() => {
+                app.status = playback_service.status().ok();
+                let (width, height) = terminal.size();
+                terminal.draw(&view::render(&app, width, height, ascii))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn rescan(library_service: &LibraryService, browser: &mut Browser) -> Result<()> {
+    library_service.rescan_library().await?;
+    browser.set_library(library_service.albums_by_artist().await?);
+    Ok(())
+}
+
+async fn play(
+    playback_service: &PlaybackService,
+    library_service: &LibraryService,
+    selection: Selection,
+) -> Result<()> {
+    match selection {
+        Selection::Track(track) => playback_service.play_single_track(*track).map(drop),
+        Selection::AlbumTrack {
+            artist,
+            album,
+            track_id,
+        } => {
+            let tracks = library_service.tracks_by_album(&album, &artist).await?;
+            playback_service
+                .play_album_tracks(tracks, &track_id)
+                .map(drop)
+        }
+        Selection::Tracks(tracks) => {
+            let first = tracks.first().map(|track| track.id.clone());
+            playback_service.clear_queue_and_enqueue(tracks)?;
+            match first {
+                Some(id) => playback_service.select_from_queue(&id).map(drop),
+                None => Ok(()),
+            }
+        }
+    }
+}
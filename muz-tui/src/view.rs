@@ -0,0 +1,236 @@
+use muz_core::playback::{PlaybackSnapshot, PlaybackState, RepeatMode};
+use muz_core::track::Track;
+
+use crate::app::{App, Panel};
+use crate::browser::{title, Column};
+use crate::visualizer;
+
+const REVERSE: &str = "\x1b[7m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Below this width the queue takes the place of the columns instead of sitting next
+/// to them.
+const WIDE: usize = 100;
+const SPECTRUM_ROWS: usize = 4;
+
+const HELP: &[&str] = &[
+    "Arrows, hjkl   Move, switch columns",
+    "PgUp PgDn g G  Move by page, to the ends",
+    "Enter          Play",
+    "a              Add to the queue",
+    "R              Rescan the library",
+    "Tab            Library or queue",
+    "/              Search: Enter plays, Tab queues, Esc closes",
+    "Space          Play or pause",
+    "n p x          Next, previous, stop",
+    "+ - m          Volume, mute",
+    ", .            Seek 10s back or forward",
+    "r s            Repeat, shuffle",
+    "J K d c        Queue: move down or up, remove, clear",
+    "q              Quit",
+];
+
+/// The whole screen, one string per row.
+pub fn render(app: &App, width: usize, height: usize, ascii: bool) -> Vec<String> {
+    let spectrum_rows = if height >= 20 { SPECTRUM_ROWS } else { 0 };
+    // Title, now playing, progress and the hint line
+    let body_rows = height.saturating_sub(4 + spectrum_rows);
+
+    let mut lines = vec![format!("{REVERSE}{}", fit(" muz", width))];
+    lines.extend(if app.show_help {
+        help(width, body_rows)
+    } else if app.search.is_some() {
+        search(app, width, body_rows)
+    } else {
+        body(app, width, body_rows)
+    });
+    lines.extend(visualizer::render(
+        &app.spectrum,
+        width,
+        spectrum_rows,
+        ascii,
+    ));
+    lines.push(now_playing(app.status.as_ref(), width));
+    lines.push(progress(app.status.as_ref(), width, ascii));
+    lines.push(match &app.message {
+        Some(message) => format!("{BOLD}{}", fit(message, width)),
+        None => fit("? help  / search  Tab queue  q quit", width),
+    });
+    lines.truncate(height);
+    lines
+}
+
+fn body(app: &App, width: usize, rows: usize) -> Vec<String> {
+    let browser = &app.browser;
+    let library_focused = app.panel == Panel::Library;
+    // Name, rows, cursor and whether the keys go there
+    let mut columns: Vec<(String, Vec<String>, usize, bool)> = Vec::new();
+    if width >= WIDE || library_focused {
+        for (column, name) in [
+            (Column::Artists, "Artists"),
+            (Column::Albums, "Albums"),
+            (Column::Tracks, "Tracks"),
+        ] {
+            columns.push((
+                name.to_string(),
+                browser.items(column),
+                browser.cursor(column),
+                library_focused && browser.focus() == column,
+            ));
+        }
+    }
+    if width >= WIDE || !library_focused {
+        columns.push((
+            format!("Queue ({})", app.queue.len()),
+            app.queue.iter().map(describe).collect(),
+            app.queue_cursor,
+            !library_focused,
+        ));
+    }
+
+    let count = columns.len();
+    let column_width = width / count;
+    let rendered: Vec<Vec<String>> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (name, items, cursor, focused))| {
+            // The last column takes the remainder
+            let width = if i + 1 == count {
+                width - column_width * (count - 1)
+            } else {
+                column_width
+            };
+            list(name, items, *cursor, *focused, width, rows)
+        })
+        .collect();
+    (0..rows)
+        .map(|row| rendered.iter().map(|column| column[row].as_str()).collect())
+        .collect()
+}
+
+/// A titled column scrolled to show its cursor, `rows` high in total.
+fn list(
+    name: &str,
+    items: &[String],
+    cursor: usize,
+    focused: bool,
+    width: usize,
+    rows: usize,
+) -> Vec<String> {
+    let visible = rows.saturating_sub(1);
+    let offset = (cursor + 1).saturating_sub(visible);
+    let mut lines = vec![format!("{BOLD}{}{RESET}", fit(&format!(" {name}"), width))];
+    for row in 0..visible {
+        let index = offset + row;
+        let text = fit(
+            &format!(" {}", items.get(index).map(String::as_str).unwrap_or("")),
+            width,
+        );
+        // Unfocused columns keep their cursor in bold
+        lines.push(if index != cursor || index >= items.len() {
+            text
+        } else if focused {
+            format!("{REVERSE}{text}{RESET}")
+        } else {
+            format!("{BOLD}{text}{RESET}")
+        });
+    }
+    lines.truncate(rows);
+    lines
+}
+
+fn search(app: &App, width: usize, rows: usize) -> Vec<String> {
+    let Some(search) = &app.search else {
+        return Vec::new();
+    };
+    let items: Vec<String> = search.results.iter().map(describe).collect();
+    let name = format!("Search: {}_", search.query);
+    list(&name, &items, search.cursor, true, width, rows)
+}
+
+fn help(width: usize, rows: usize) -> Vec<String> {
+    (0..rows)
+        .map(|row| match row {
+            0 => format!("{BOLD}{}{RESET}", fit(" Keys, any key closes", width)),
+            row => fit(&format!("   {}", HELP.get(row - 1).unwrap_or(&"")), width),
+        })
+        .collect()
+}
+
+fn now_playing(status: Option<&PlaybackSnapshot>, width: usize) -> String {
+    let Some(status) = status else {
+        return fit("", width);
+    };
+    let state = match status.state {
+        PlaybackState::Playing => "Playing",
+        PlaybackState::Paused => "Paused",
+        PlaybackState::Stopped => "Stopped",
+    };
+    let track = match &status.track {
+        Some(track) => format!(
+            "{}  {} / {}",
+            describe(track),
+            format_ms(status.position_ms),
+            format_ms(track.duration_ms)
+        ),
+        None => "Nothing playing".to_string(),
+    };
+    let volume = if status.muted {
+        "muted".to_string()
+    } else {
+        format!("vol {:.0}%", status.volume * 100.0)
+    };
+    let repeat = match status.repeat {
+        RepeatMode::Off => "",
+        RepeatMode::One => "  repeat one",
+        RepeatMode::All => "  repeat all",
+    };
+    let shuffle = if status.shuffle { "  shuffle" } else { "" };
+    let right = format!("{volume}{repeat}{shuffle} ");
+    let left = fit(
+        &format!(" {state}  {track}"),
+        width.saturating_sub(right.chars().count()),
+    );
+    format!("{BOLD}{left}{RESET}{right}")
+}
+
+fn progress(status: Option<&PlaybackSnapshot>, width: usize, ascii: bool) -> String {
+    let fraction = status
+        .and_then(|status| {
+            let duration = status.track.as_ref()?.duration_ms;
+            (duration > 0).then(|| status.position_ms as f64 / duration as f64)
+        })
+        .unwrap_or(0.0)
+        .clamp(0.0, 1.0);
+    let (done, left) = if ascii { ('#', '-') } else { ('━', '─') };
+    let filled = (fraction * width as f64).round() as usize;
+    std::iter::repeat_n(done, filled)
+        .chain(std::iter::repeat_n(left, width - filled))
+        .collect()
+}
+
+/// `Artist - Title`, or just the title of tracks without an artist.
+fn describe(track: &Track) -> String {
+    match track.metadata.as_ref().and_then(|m| m.artist.as_deref()) {
+        Some(artist) => format!("{artist} - {}", title(track)),
+        None => title(track),
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    let seconds = ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Cuts or pads `text` to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.chars().take(width).collect();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat_n(' ', width - len));
+    fitted
+}
+
+#[cfg(test)]
+#[path = "./view.tests.rs"]
+mod tests;
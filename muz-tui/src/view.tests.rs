@@ -0,0 +1,46 @@
+use super::*;
+use crate::browser::Browser;
+
+/// The visible text, without the styles.
+fn strip(line: &str) -> String {
+    let mut text = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            text.push(c);
+        }
+    }
+    text
+}
+
+#[test]
+fn test_fills_the_screen() {
+    let app = App::new(Browser::new(Default::default()));
+    for (width, height) in [(120, 40), (60, 12), (10, 3)] {
+        let lines = render(&app, width, height, false);
+        assert_eq!(lines.len(), height);
+        for line in lines {
+            assert_eq!(strip(&line).chars().count(), width, "{line:?}");
+        }
+    }
+}
+
+#[test]
+fn test_narrow_screens_show_one_panel() {
+    let mut app = App::new(Browser::new(Default::default()));
+    let header = |app: &App, width| strip(&render(app, width, 24, true)[1]);
+    assert!(header(&app, 120).contains("Queue (0)"));
+    assert!(header(&app, 80).contains("Artists"));
+    assert!(!header(&app, 80).contains("Queue"));
+    app.panel = Panel::Queue;
+    assert!(header(&app, 80).starts_with(" Queue (0)"));
+}
+
+#[test]
+fn test_fit() {
+    assert_eq!(fit("Sigur Rós", 12), "Sigur Rós   ");
+    assert_eq!(fit("Sigur Rós", 7), "Sigur R");
+    assert_eq!(format_ms(61_999), "1:01");
+}
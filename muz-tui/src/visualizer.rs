@@ -0,0 +1,39 @@
+/// Partially filled cells, from empty to full
+const BLOCKS: &[char] = &[' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII: &[char] = &[' ', '.', ':', '|', '#'];
+
+/// Draws analyzer bins as `width` bars, `height` rows high, top row first. Each bar shows
+/// the loudest bin it covers.
+pub fn render(spectrum: &[f32], width: usize, height: usize, ascii: bool) -> Vec<String> {
+    let cells = if ascii { ASCII } else { BLOCKS };
+    let bars: Vec<f32> = (0..width)
+        .map(|column| {
+            if spectrum.is_empty() {
+                return 0.0;
+            }
+            let start = column * spectrum.len() / width;
+            let end = ((column + 1) * spectrum.len() / width).max(start + 1);
+            let peak = spectrum[start..end.min(spectrum.len())]
+                .iter()
+                .fold(0.0f32, |peak, &value| peak.max(value));
+            // Square root, the upper bins are quiet next to the bass
+            peak.clamp(0.0, 1.0).sqrt() * height as f32
+        })
+        .collect();
+
+    (0..height)
+        .map(|row| {
+            let floor = (height - 1 - row) as f32;
+            bars.iter()
+                .map(|bar| {
+                    let fill = (bar - floor).clamp(0.0, 1.0);
+                    cells[(fill * (cells.len() - 1) as f32).round() as usize]
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "./visualizer.tests.rs"]
+mod tests;
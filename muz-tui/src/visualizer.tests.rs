@@ -0,0 +1,26 @@
+use super::*;
+
+#[test]
+fn test_bars_fill_from_the_bottom() {
+    let rows = render(&[1.0, 0.0, 0.25, 0.0], 4, 2, false);
+    assert_eq!(rows, vec!["█   ".to_string(), "█ █ ".to_string()]);
+}
+
+#[test]
+fn test_partial_cells() {
+    // sqrt(0.0625) = 0.25 of two rows is half a cell
+    let rows = render(&[0.0625], 1, 2, false);
+    assert_eq!(rows, vec![" ".to_string(), "▄".to_string()]);
+    let rows = render(&[0.0625], 1, 2, true);
+    assert_eq!(rows, vec![" ".to_string(), ":".to_string()]);
+}
+
+#[test]
+fn test_bins_are_resampled_to_the_width() {
+    let spectrum: Vec<f32> = (0..64).map(|i| if i < 32 { 1.0 } else { 0.0 }).collect();
+    let rows = render(&spectrum, 8, 1, true);
+    assert_eq!(rows, vec!["####    ".to_string()]);
+    // Narrower spectra are stretched
+    assert_eq!(render(&[1.0, 0.0], 4, 1, true), vec!["##  ".to_string()]);
+    assert_eq!(render(&[], 3, 1, true), vec!["   ".to_string()]);
+}
@@ -1,34 +1,25 @@
 use std::collections::HashMap;
 
-use crate::player::{playback::PlaybackState, track::Track};
-use anyhow::Error;
-use serde::{Deserialize, Serialize};
-use tauri::{ipc::Channel, State};
+use muz_core::command::PlaybackCommand;
+use muz_core::driver::OutputDevice;
+use muz_core::dsp::DspSettings;
+use muz_core::equalizer::{EqualizerPreset, EqualizerSettings};
+use muz_core::error::LibraryError;
+use muz_core::loudness::AnalysisJob;
+use muz_core::normalization::NormalizationSettings;
+use muz_core::playback::{PlaybackSnapshot, PlaybackState};
+use muz_core::{lyrics::Lyrics, track::Track};
+use muz_server::error::CommandError;
+use serde::Deserialize;
+use tauri::{ipc::Channel, Manager, State};
 
-use crate::{AppState, ProgressEvent, SpectrumEvent};
-
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct ControlPlaybackPayload {
-    pub command: String,
-    pub volume: Option<f32>,
-    pub seek_position: Option<u64>, // in milliseconds
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct PlaybackError(String);
-
-impl PlaybackError {
-    fn from(err: Error) -> Self {
-        PlaybackError(err.to_string())
-    }
-}
+use crate::{app_data_dir, AppState, ProgressEvent, SpectrumEvent};
 
 #[tauri::command]
 pub async fn subscribe_to_progress(
     state: State<'_, AppState>,
     on_progress: Channel<ProgressEvent>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let mut channel_guard = state.progress_channel.lock().await;
     *channel_guard = Some(on_progress);
     Ok(())
@@ -38,21 +29,21 @@ pub async fn subscribe_to_progress(
 pub async fn subscribe_to_spectrum(
     state: State<'_, AppState>,
     on_spectrum: Channel<SpectrumEvent>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let mut channel_guard = state.spectrum_channel.lock().await;
     *channel_guard = Some(on_spectrum);
     Ok(())
 }
 
 #[tauri::command]
-pub async fn unsubscribe_from_progress(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn unsubscribe_from_progress(state: State<'_, AppState>) -> Result<(), CommandError> {
     let mut channel_guard = state.progress_channel.lock().await;
     *channel_guard = None;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn unsubscribe_from_spectrum(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn unsubscribe_from_spectrum(state: State<'_, AppState>) -> Result<(), CommandError> {
     let mut channel_guard = state.spectrum_channel.lock().await;
     *channel_guard = None;
     Ok(())
@@ -61,21 +52,27 @@ pub async fn unsubscribe_from_spectrum(state: State<'_, AppState>) -> Result<(),
 #[tauri::command]
 pub fn control_playback(
     state: State<'_, AppState>,
-    payload: ControlPlaybackPayload,
-) -> Result<PlaybackState, PlaybackError> {
+    command: PlaybackCommand,
+) -> Result<PlaybackSnapshot, CommandError> {
     state
         .playback_service
-        .control_playback(payload)
-        .map_err(PlaybackError::from)
+        .control_playback(command)
+        .map_err(CommandError::from)
 }
 
+/// The player state with the position as currently heard.
 #[tauri::command]
-pub async fn get_library_path(state: State<'_, AppState>) -> Result<String, String> {
+pub fn get_playback_status(state: State<'_, AppState>) -> Result<PlaybackSnapshot, CommandError> {
+    state.playback_service.status().map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn get_library_path(state: State<'_, AppState>) -> Result<String, CommandError> {
     state
         .library_service
         .library_path()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -83,61 +80,43 @@ pub async fn set_library_path(
     state: State<'_, AppState>,
     app_handle: tauri::AppHandle,
     path: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let new_path = std::path::PathBuf::from(&path);
 
     let mut config = state.config.lock().await;
-    config
-        .update_library_path(new_path.clone())
-        .map_err(|e| e.to_string())?;
-    config.save(&app_handle).await.map_err(|e| e.to_string())?;
+    config.update_library_path(new_path.clone())?;
+    config.save(&app_data_dir(&app_handle)?).await?;
     drop(config);
 
-    state
-        .library_service
-        .set_library_path(new_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    state.library_service.set_library_path(new_path).await?;
 
     let tracks = state
         .library_service
         .library_tracks()
-        .await
-        .map_err(|e| e.to_string())?
+        .await?
         .into_values()
         .flatten()
         .collect();
 
-    state
-        .playback_service
-        .clear_queue_and_enqueue(tracks)
-        .map_err(|e| e.to_string())?;
+    state.playback_service.clear_queue_and_enqueue(tracks)?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn rescan_library(state: State<'_, AppState>) -> Result<(), String> {
-    state
-        .library_service
-        .rescan_library()
-        .await
-        .map_err(|e| e.to_string())?;
+pub async fn rescan_library(state: State<'_, AppState>) -> Result<(), CommandError> {
+    state.library_service.rescan_library().await?;
 
     // Update playback queue with rescanned tracks
     let tracks = state
         .library_service
         .library_tracks()
-        .await
-        .map_err(|e| e.to_string())?
+        .await?
         .into_values()
         .flatten()
         .collect();
 
-    state
-        .playback_service
-        .clear_queue_and_enqueue(tracks)
-        .map_err(|e| e.to_string())?;
+    state.playback_service.clear_queue_and_enqueue(tracks)?;
 
     Ok(())
 }
@@ -145,23 +124,23 @@ pub async fn rescan_library(state: State<'_, AppState>) -> Result<(), String> {
 #[tauri::command]
 pub async fn get_albums_by_artist(
     state: State<'_, AppState>,
-) -> Result<HashMap<String, HashMap<String, Vec<Track>>>, String> {
+) -> Result<HashMap<String, HashMap<String, Vec<Track>>>, CommandError> {
     state
         .library_service
         .albums_by_artist()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
 pub fn select_track_from_queue(
     state: State<'_, AppState>,
     track_id: String,
-) -> Result<PlaybackState, PlaybackError> {
+) -> Result<PlaybackState, CommandError> {
     state
         .playback_service
         .select_from_queue(&track_id)
-        .map_err(PlaybackError::from)
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -170,28 +149,23 @@ pub async fn play_from_library(
     track_id: String,
     album: Option<String>,
     artist: Option<String>,
-) -> Result<PlaybackState, PlaybackError> {
+) -> Result<PlaybackState, CommandError> {
     if let (Some(album_name), Some(artist_name)) = (album, artist) {
         let album_tracks = state
             .library_service
             .tracks_by_album(&album_name, &artist_name)
-            .await
-            .map_err(PlaybackError::from)?;
+            .await?;
 
         state
             .playback_service
             .play_album_tracks(album_tracks, &track_id)
-            .map_err(PlaybackError::from)
+            .map_err(CommandError::from)
     } else {
-        let track = state
-            .library_service
-            .track_by_id(&track_id)
-            .await
-            .map_err(PlaybackError::from)?;
+        let track = state.library_service.track_by_id(&track_id).await?;
         state
             .playback_service
             .play_single_track(track)
-            .map_err(PlaybackError::from)
+            .map_err(CommandError::from)
     }
 }
 
@@ -206,9 +180,212 @@ pub struct ReorderQueuePayload {
 pub fn reorder_queue(
     state: State<'_, AppState>,
     payload: ReorderQueuePayload,
-) -> Result<(), PlaybackError> {
+) -> Result<(), CommandError> {
     state
         .playback_service
         .reorder_queue(payload.old_index, payload.new_index)
-        .map_err(PlaybackError::from)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn get_lyrics(
+    state: State<'_, AppState>,
+    track_id: String,
+) -> Result<Option<Lyrics>, CommandError> {
+    let track = state.library_service.track_by_id(&track_id).await?;
+    tauri::async_runtime::spawn_blocking(move || Lyrics::load(&track))
+        .await
+        .map_err(|e| CommandError::from(anyhow::Error::from(e)))
+}
+
+#[tauri::command]
+pub async fn get_normalization_settings(
+    state: State<'_, AppState>,
+) -> Result<NormalizationSettings, CommandError> {
+    Ok(state.config.lock().await.normalization.clone())
+}
+
+#[tauri::command]
+pub async fn set_normalization_settings(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    settings: NormalizationSettings,
+) -> Result<(), CommandError> {
+    state.playback_service.set_normalization(settings.clone())?;
+
+    let mut config = state.config.lock().await;
+    config.normalization = settings;
+    config
+        .save(&app_data_dir(&app_handle)?)
+        .await
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn get_equalizer_settings(
+    state: State<'_, AppState>,
+) -> Result<EqualizerSettings, CommandError> {
+    Ok(state.config.lock().await.equalizer.clone())
+}
+
+/// Applies the change to the playing track and persists it, returning the new settings.
+async fn update_equalizer(
+    state: &State<'_, AppState>,
+    app_handle: &tauri::AppHandle,
+    update: impl FnOnce(&mut EqualizerSettings) -> anyhow::Result<()>,
+) -> Result<EqualizerSettings, CommandError> {
+    let mut config = state.config.lock().await;
+    let mut settings = config.equalizer.clone();
+    update(&mut settings)?;
+    state.playback_service.set_equalizer(settings.clone())?;
+
+    config.equalizer = settings.clone();
+    config.save(&app_data_dir(app_handle)?).await?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_equalizer_settings(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    settings: EqualizerSettings,
+) -> Result<EqualizerSettings, CommandError> {
+    update_equalizer(&state, &app_handle, |current| {
+        *current = settings;
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_equalizer_presets(
+    state: State<'_, AppState>,
+) -> Result<Vec<EqualizerPreset>, CommandError> {
+    Ok(state.config.lock().await.equalizer.presets())
+}
+
+#[tauri::command]
+pub async fn apply_equalizer_preset(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<EqualizerSettings, CommandError> {
+    update_equalizer(&state, &app_handle, |settings| settings.apply_preset(&name)).await
+}
+
+#[tauri::command]
+pub async fn save_equalizer_preset(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<EqualizerSettings, CommandError> {
+    update_equalizer(&state, &app_handle, |settings| settings.save_preset(&name)).await
+}
+
+#[tauri::command]
+pub async fn delete_equalizer_preset(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<EqualizerSettings, CommandError> {
+    update_equalizer(&state, &app_handle, |settings| {
+        settings.delete_preset(&name);
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_dsp_settings(state: State<'_, AppState>) -> Result<DspSettings, CommandError> {
+    Ok(state.config.lock().await.dsp.clone())
+}
+
+#[tauri::command]
+pub async fn set_dsp_settings(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    settings: DspSettings,
+) -> Result<(), CommandError> {
+    state.playback_service.set_dsp(settings.clone())?;
+
+    let mut config = state.config.lock().await;
+    config.dsp = settings;
+    config
+        .save(&app_data_dir(&app_handle)?)
+        .await
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn get_output_devices(
+    state: State<'_, AppState>,
+) -> Result<Vec<OutputDevice>, CommandError> {
+    state
+        .playback_service
+        .output_devices()
+        .map_err(CommandError::from)
+}
+
+/// Switches the audio output, `None` selecting the system default. Playback continues on the
+/// new device.
+#[tauri::command]
+pub async fn set_output_device(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    device: Option<String>,
+) -> Result<(), CommandError> {
+    state.playback_service.set_output_device(device.clone())?;
+
+    let mut config = state.config.lock().await;
+    config.output_device = device;
+    config
+        .save(&app_data_dir(&app_handle)?)
+        .await
+        .map_err(CommandError::from)
+}
+
+/// Analyzes tracks without ReplayGain info in the background, optionally writing the tags
+/// back. The progress is reported on the event bus.
+#[tauri::command]
+pub async fn start_loudness_analysis(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    write_tags: bool,
+) -> Result<(), CommandError> {
+    let job = {
+        let mut current_job = state.loudness_job.lock().await;
+        if current_job.is_some() {
+            return Err(LibraryError::AnalysisRunning.into());
+        }
+        let job = AnalysisJob::default();
+        *current_job = Some(job.clone());
+        job
+    };
+
+    let library_service = state.library_service.clone();
+    let playback_service = state.playback_service.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = library_service
+            .analyze_loudness(&job, write_tags, |track| {
+                if let Some(replay_gain) = track.replay_gain {
+                    if let Err(e) = playback_service.update_replay_gain(&track.id, replay_gain) {
+                        tracing::warn!("Failed to update playback gain: {e}");
+                    }
+                }
+            })
+            .await;
+        if let Err(e) = result {
+            tracing::error!("Loudness analysis failed: {e}");
+        }
+        *app_handle.state::<AppState>().loudness_job.lock().await = None;
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_loudness_analysis(state: State<'_, AppState>) -> Result<(), CommandError> {
+    if let Some(job) = state.loudness_job.lock().await.as_ref() {
+        job.cancel();
+    }
+    Ok(())
 }
@@ -1,26 +1,20 @@
 use std::error::Error;
+use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::Mutex;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::player::{
-    driver::factory::{DefaultDriverFactory, PlaybackDriverFactory},
-    library::Library,
-    playback::Playback,
-    track::Track,
-};
-use crate::services::{library_service::LibraryService, playback_service::PlaybackService};
-use tauri::{ipc::Channel, Builder, Emitter, Manager};
+use muz_core::bus::{BusEvent, EventBus, StateSnapshot};
+use muz_core::services::{library_service::LibraryService, playback_service::PlaybackService};
+use muz_core::{library::{Library, CACHE_FILE}, loudness::AnalysisJob, playback::Playback};
+use muz_server::integrations;
+use tauri::{ipc::Channel, AppHandle, Builder, Emitter, Manager};
 
 mod commands;
-mod config;
-mod events;
-mod player;
-mod services;
 
 use commands::*;
-use config::AppConfig;
-use events::*;
+use muz_core::config::{AppConfig, ConfigError};
+use muz_core::events::*;
 
 pub struct AppState {
     pub playback_service: PlaybackService,
@@ -28,90 +22,158 @@ pub struct AppState {
     pub progress_channel: Arc<Mutex<Option<Channel<ProgressEvent>>>>,
     pub spectrum_channel: Arc<Mutex<Option<Channel<SpectrumEvent>>>>,
     pub config: Arc<Mutex<AppConfig>>,
+    pub loudness_job: Arc<Mutex<Option<AnalysisJob>>>,
 }
 
-fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn Error>> {
-    let config = tauri::async_runtime::block_on(AppConfig::load(app.handle())).unwrap_or_default();
-    let mut library = Library::new(config.library_path.clone(), "Library".to_string());
-    tauri::async_runtime::block_on(library.initialize());
-
-    let progress_channel: Arc<Mutex<Option<Channel<ProgressEvent>>>> = Arc::new(Mutex::new(None));
-    let spectrum_channel: Arc<Mutex<Option<Channel<SpectrumEvent>>>> = Arc::new(Mutex::new(None));
-    let progress_channel_clone = progress_channel.clone();
-    let spectrum_channel_clone = spectrum_channel.clone();
+/// Where the config is kept, the same directory the daemon finds with
+/// `AppConfig::default_data_dir`.
+fn app_data_dir(app_handle: &AppHandle) -> anyhow::Result<PathBuf> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| ConfigError::AppDataDir(e.to_string()))?;
+    Ok(data_dir)
+}
 
-    let on_progress = move |progress, frames_played| {
-        let event = ProgressEvent {
-            position: progress,
-            frames_played,
+/// Passes bus events on to the webview: progress and spectrum over the channels the
+/// frontend subscribed with, the rest as Tauri events.
+async fn forward_events(
+    bus: EventBus,
+    mut events: broadcast::Receiver<BusEvent>,
+    app_handle: AppHandle,
+    progress_channel: Arc<Mutex<Option<Channel<ProgressEvent>>>>,
+    spectrum_channel: Arc<Mutex<Option<Channel<SpectrumEvent>>>>,
+    library_service: LibraryService,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(BusEvent { event, .. }) => event,
+            // Whatever was skipped, the webview is sent the state it adds up to
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::debug!("The webview skipped {skipped} events");
+                resend_state(&app_handle, bus.snapshot());
+                continue;
+            }
+            Err(RecvError::Closed) => break,
         };
-        if let Ok(channel_guard) = progress_channel_clone.try_lock() {
-            if let Some(ref channel) = *channel_guard {
-                let _ = channel.send(event);
+        match event {
+            PlayerEvent::Progress(progress) => {
+                if let Some(channel) = &*progress_channel.lock().await {
+                    let _ = channel.send(progress);
+                }
             }
-        }
-    };
-
-    let on_spectrum = move |spectrum_data| {
-        let event = SpectrumEvent { spectrum_data };
-        if let Ok(channel_guard) = spectrum_channel_clone.try_lock() {
-            if let Some(ref channel) = *channel_guard {
-                let _ = channel.send(event);
+            PlayerEvent::Spectrum(spectrum) => {
+                if let Some(channel) = &*spectrum_channel.lock().await {
+                    let _ = channel.send(spectrum);
+                }
+            }
+            PlayerEvent::HistoryUpdate(history) => {
+                let _ = app_handle.emit("history-update", history);
+            }
+            PlayerEvent::TrackChanged(track) => {
+                let _ = app_handle.emit("track-changed", track);
+            }
+            PlayerEvent::QueueChanged(queue) => {
+                let _ = app_handle.emit("queue-changed", queue);
+            }
+            PlayerEvent::StateChanged(state) => {
+                let _ = app_handle.emit("state-changed", state);
+            }
+            PlayerEvent::OutputDeviceFallback(fallback) => {
+                let _ = app_handle.emit("output-device-fallback", fallback);
+            }
+            PlayerEvent::OutputUnavailable(unavailable) => {
+                let _ = app_handle.emit("output-unavailable", unavailable);
+            }
+            PlayerEvent::PlaybackError(error) => {
+                let library_service = library_service.clone();
+                let track_id = error.track_id.clone();
+                tauri::async_runtime::spawn(async move {
+                    library_service.mark_unplayable(&track_id).await;
+                });
+                let _ = app_handle.emit("playback-error", error);
+            }
+            PlayerEvent::LibraryChanged(changed) => {
+                let _ = app_handle.emit("library-changed", changed);
+            }
+            PlayerEvent::LoudnessAnalysisProgress(progress) => {
+                let _ = app_handle.emit("loudness-analysis-progress", progress);
+            }
+            PlayerEvent::LoudnessAnalysisFinished(finished) => {
+                let _ = app_handle.emit("loudness-analysis-finished", finished);
             }
         }
-    };
-
-    let app_handle = app.handle().clone();
-    let app_handle_track = app_handle.clone();
-    let app_handle_queue = app_handle.clone();
+    }
+}
 
-    let on_history_update = move |history: &Vec<Track>, current_track: Option<&Track>| {
-        let event = HistoryUpdateEvent {
-            has_history: history.len() > 1
-                || (history.len() == 1 && history.last() != current_track),
-        };
-        let _ = app_handle.emit("history-update", event);
+/// Sends the track, queue and player state the bus holds now, in place of the events
+/// that led to it.
+fn resend_state(app_handle: &AppHandle, snapshot: StateSnapshot) {
+    let track = TrackChangedEvent {
+        track: snapshot.track,
     };
-
-    let on_track_changed = move |track: Option<&Track>| {
-        let event = TrackChangedEvent {
-            track: track.cloned(),
-        };
-        let _ = app_handle_track.emit("track-changed", event);
+    let _ = app_handle.emit("track-changed", track);
+    let queue = QueueChangedEvent {
+        queue: snapshot.queue,
     };
-
-    let on_queue_changed = move |queue: &Vec<Track>| {
-        let event = QueueChangedEvent {
-            queue: queue.clone(),
-        };
-        let _ = app_handle_queue.emit("queue-changed", event);
+    let _ = app_handle.emit("queue-changed", queue);
+    let history = HistoryUpdateEvent {
+        has_history: snapshot.has_history,
     };
+    let _ = app_handle.emit("history-update", history);
+    if let Some(state) = snapshot.player {
+        let _ = app_handle.emit("state-changed", state);
+    }
+}
 
-    let volume = 1.0; // fetch from some settings
-    let playback_driver =
-        DefaultDriverFactory::create_driver(volume).expect("Failed to create playback driver");
-    let playback = Playback::create(
-        playback_driver,
-        on_progress,
-        on_spectrum,
-        on_history_update,
-        on_track_changed,
-        on_queue_changed,
-    );
+fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn Error>> {
+    let data_dir = app_data_dir(app.handle()).ok();
+    let config = data_dir
+        .as_ref()
+        .and_then(|data_dir| tauri::async_runtime::block_on(AppConfig::load(data_dir)).ok())
+        .unwrap_or_default();
+    let mut library = Library::new(config.library_path.clone(), "Library".to_string());
+    if let Some(data_dir) = &data_dir {
+        library.use_cache(data_dir.join(CACHE_FILE));
+    }
+    tauri::async_runtime::block_on(library.initialize());
 
+    let progress_channel: Arc<Mutex<Option<Channel<ProgressEvent>>>> = Arc::new(Mutex::new(None));
+    let spectrum_channel: Arc<Mutex<Option<Channel<SpectrumEvent>>>> = Arc::new(Mutex::new(None));
+    let events = EventBus::new();
     let library_arc = Arc::new(Mutex::new(library));
+    let library_service = LibraryService::new(library_arc.clone(), events.clone());
+    tauri::async_runtime::spawn(forward_events(
+        events.clone(),
+        events.subscribe().1,
+        app.handle().clone(),
+        progress_channel.clone(),
+        spectrum_channel.clone(),
+        library_service.clone(),
+    ));
+
+    let volume = 1.0; // fetch from some settings
+    let playback = Playback::create(config.create_driver(volume), events.clone());
+
     let tracks = tauri::async_runtime::block_on(async {
         let library = library_arc.lock().await;
         library.tracks_cloned()
     });
     if let Ok(mut playback_guard) = playback.lock() {
         playback_guard.enqueue_multiple(tracks.clone());
+        config.apply_to(&mut playback_guard);
     } else {
         tracing::error!("Failed to lock playback");
     }
 
     let playback_service: PlaybackService = PlaybackService::new(playback);
-    let library_service = LibraryService::new(library_arc);
+    tauri::async_runtime::block_on(integrations::start(
+        &events,
+        &config,
+        data_dir.as_deref(),
+        &playback_service,
+        &library_service,
+    ));
 
     let initial_track_event = TrackChangedEvent { track: None };
     let _ = app.emit("track-changed", initial_track_event);
@@ -125,19 +187,14 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn Error>> {
         progress_channel,
         spectrum_channel,
         config: Arc::new(Mutex::new(config)),
+        loudness_job: Arc::new(Mutex::new(None)),
     });
     Ok(())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "muz=debug,info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    muz_server::init_tracing();
 
     Builder::default()
         .setup(setup_app)
@@ -145,6 +202,7 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .invoke_handler(tauri::generate_handler![
             control_playback,
+            get_playback_status,
             subscribe_to_progress,
             subscribe_to_spectrum,
             unsubscribe_from_progress,
@@ -155,7 +213,22 @@ pub fn run() {
             get_albums_by_artist,
             select_track_from_queue,
             play_from_library,
-            reorder_queue
+            reorder_queue,
+            get_lyrics,
+            get_normalization_settings,
+            set_normalization_settings,
+            get_equalizer_settings,
+            set_equalizer_settings,
+            get_equalizer_presets,
+            apply_equalizer_preset,
+            save_equalizer_preset,
+            delete_equalizer_preset,
+            get_dsp_settings,
+            set_dsp_settings,
+            get_output_devices,
+            set_output_device,
+            start_loudness_analysis,
+            cancel_loudness_analysis
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
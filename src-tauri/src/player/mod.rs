@@ -1,6 +0,0 @@
-pub mod driver;
-pub mod library;
-pub mod playback;
-pub mod queue;
-pub mod spectrum;
-pub mod track;
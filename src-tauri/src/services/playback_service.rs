@@ -1,93 +0,0 @@
-use crate::commands::ControlPlaybackPayload;
-use crate::player::{playback::Playback, playback::PlaybackState, track::Track};
-use anyhow::Result;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-
-pub struct PlaybackService {
-    playback: Arc<Mutex<Playback>>,
-}
-
-impl PlaybackService {
-    pub fn new(playback: Arc<Mutex<Playback>>) -> Self {
-        Self { playback }
-    }
-
-    pub fn control_playback(&self, payload: ControlPlaybackPayload) -> Result<PlaybackState> {
-        let mut playback = self.playback.lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
-
-        match payload.command.as_str() {
-            "Play" => playback.play(),
-            "Pause" => playback.pause(),
-            "Next" => playback.next(),
-            "Previous" => playback.previous(),
-            "Seek" => {
-                tracing::debug!("Seek payload: {:?}", payload);
-                let position = payload.seek_position.unwrap_or(0);
-                tracing::debug!("Seek position received: {} ms", position);
-                playback.seek(Duration::from_millis(position))
-            }
-            "SetVolume" => {
-                if let Some(vol) = payload.volume {
-                    playback.set_volume(vol)
-                } else {
-                    Err(anyhow::anyhow!("Invalid volume payload"))
-                }
-            }
-            _ => Err(anyhow::anyhow!("Unknown playback command")),
-        }
-    }
-
-    pub fn play_single_track(&self, track: Track) -> Result<PlaybackState> {
-        let mut playback = self.playback.lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
-
-        playback.prepend(track);
-        if playback.state == PlaybackState::Playing || playback.state == PlaybackState::Paused {
-            playback.next()
-        } else {
-            playback.play()
-        }
-    }
-
-    pub fn play_album_tracks(
-        &self,
-        album_tracks: Vec<Track>,
-        track_id: &str,
-    ) -> Result<PlaybackState> {
-        let mut playback = self.playback.lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
-
-        let selected_track_id = album_tracks
-            .iter()
-            .find(|track| track.id == track_id)
-            .map(|track| track.id.clone())
-            .ok_or_else(|| anyhow::anyhow!("Track not found in album"))?;
-
-        playback.clear_queue();
-        playback.enqueue_multiple(album_tracks);
-
-        playback.select_track_from_queue(&selected_track_id)
-    }
-
-    pub fn select_from_queue(&self, track_id: &str) -> Result<PlaybackState> {
-        let mut playback = self.playback.lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
-        playback.select_track_from_queue(track_id)
-    }
-
-    pub fn clear_queue_and_enqueue(&self, tracks: Vec<Track>) -> Result<()> {
-        let mut playback = self.playback.lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
-        playback.clear_queue();
-        playback.enqueue_multiple(tracks);
-        Ok(())
-    }
-
-    pub fn reorder_queue(&self, old_index: usize, new_index: usize) -> Result<()> {
-        let mut playback = self.playback.lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock playback: {}", e))?;
-        playback.reorder_queue(old_index, new_index)
-    }
-}
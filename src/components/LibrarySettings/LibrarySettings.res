@@ -43,7 +43,7 @@ let make = () => {
       }
     } catch {
     | Exn.Error(e) => {
-        setError(_ => Some("Failed to set library path"))
+        setError(_ => Some(e->CommandError.message(~fallback="Failed to set library path")))
         Js.Console.error2("Error setting library path", e)
       }
     }
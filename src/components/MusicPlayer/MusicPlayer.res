@@ -38,11 +38,10 @@ let make = () => {
 
   let invokePlayerCommand = async command => {
     try {
-      let result = await PlaybackService.controlPlayback(command)
-      switch result {
-      | Playing => player.dispatch(SetState(State.Playing))
-      | Paused => player.dispatch(SetState(State.Paused))
-      | Stopped => player.dispatch(SetState(State.Stopped))
+      let snapshot = await PlaybackService.controlPlayback(command)
+      player.dispatch(SetState(State.fromString(snapshot.state)))
+      if snapshot.track->Nullable.isNullable {
+        player.dispatch(SetCurrentTrack(None))
       }
       Js.Console.log2("Player command invoked successfully", command)
     } catch {
@@ -55,6 +54,16 @@ let make = () => {
     None
   }, [player.volume])
 
+  // Hide playback errors after a while
+  React.useEffect(() => {
+    switch player.lastError {
+    | Some(_) =>
+      let timeout = Js.Global.setTimeout(() => player.dispatch(SetError(None)), 5000)
+      Some(() => Js.Global.clearTimeout(timeout))
+    | None => None
+    }
+  }, [player.lastError])
+
   let handlePlayPause = React.useCallback(() => {
     switch player.state {
     | State.Playing => invokePlayerCommand(Command.Pause)->ignore
@@ -107,6 +116,13 @@ let make = () => {
               <div className={MusicPlayerStyles.album}> {React.string("No album")} </div>
             </>
           }}
+          {switch player.lastError {
+          | Some(error) =>
+            <div className={MusicPlayerStyles.error} title={error.path}>
+              {React.string(error.message)}
+            </div>
+          | None => React.null
+          }}
         </div>
       </div>
       <div className={MusicPlayerStyles.controlsSection}>
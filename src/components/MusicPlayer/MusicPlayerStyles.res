@@ -121,6 +121,14 @@ let album = style([
   fontStyle(italic),
 ])
 
+let error = style([
+  fontSize(rem(0.75)),
+  color(Color.error),
+  whiteSpace(nowrap),
+  overflow(hidden),
+  textOverflow(ellipsis),
+])
+
 let controls = style([display(flexBox), alignItems(center), gap(Spacing.elementGap)])
 
 let trackSlider = style([
@@ -1,9 +1,16 @@
+type playbackError = {
+  trackId: string,
+  path: string,
+  message: string,
+}
+
 type playerAction =
   | SetCurrentTrack(option<Track.t>)
   | SetQueue(array<Track.t>)
   | SetHasHistory(bool)
   | SetVolume(float)
   | SetState(State.t)
+  | SetError(option<playbackError>)
 
 type playerState = {
   currentTrack: option<Track.t>,
@@ -11,6 +18,7 @@ type playerState = {
   hasHistory: bool,
   volume: float,
   state: State.t,
+  lastError: option<playbackError>,
   cleanupListeners: unit => unit,
   dispatch: playerAction => unit,
 }
@@ -21,6 +29,7 @@ let initialPlayerState: playerState = {
   hasHistory: false,
   volume: 0.5,
   state: State.Stopped,
+  lastError: None,
   cleanupListeners: () => (),
   dispatch: _ => (),
 }
@@ -72,10 +81,19 @@ module PlayerProvider = {
             dispatch(SetHasHistory(payload["hasHistory"]))
           })
 
+          // Listen for tracks that failed to play and were skipped
+          let unlistenPlaybackError = await Tauri.listenToEvent("playback-error", (
+            payload: playbackError,
+          ) => {
+            Js.Console.error2("Playback error", payload)
+            dispatch(SetError(Some(payload)))
+          })
+
           let cleanup = () => {
             unlistenTrackChanged()->ignore
             unlistenQueueChanged()->ignore
             unlistenHistoryChanged()->ignore
+            unlistenPlaybackError()->ignore
           }
 
           cleanupRef.current = cleanup
@@ -119,6 +137,7 @@ let usePlayerReducer = () => {
     | SetHasHistory(hasHistory) => {...state, hasHistory}
     | SetVolume(volume) => {...state, volume}
     | SetState(playerState) => {...state, state: playerState}
+    | SetError(lastError) => {...state, lastError}
     }
   }
 
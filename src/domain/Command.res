@@ -1,19 +1,50 @@
-type t = Play | Pause | Next | Previous | SetVolume(float) | Seek(int)
+type repeatMode = | @as("Off") Off | @as("One") One | @as("All") All
+
+type t =
+  | Play
+  | Pause
+  | TogglePlayPause
+  | Stop
+  | Next
+  | Previous
+  | Seek(int)
+  | SeekRelative(int)
+  | SetVolume(float)
+  | SetMuted(bool)
+  | SetSpeed(float)
+  | SetPitch(float)
+  | SetRepeat(repeatMode)
+  | SetShuffle(bool)
+
+let repeatModeToString = (mode: repeatMode) =>
+  switch mode {
+  | Off => "Off"
+  | One => "One"
+  | All => "All"
+  }
 
 let toJsonPayload = (command: t) => {
-  let (commandName, extraFields) = switch command {
+  let (commandType, extraFields) = switch command {
   | Play => ("Play", [])
   | Pause => ("Pause", [])
+  | TogglePlayPause => ("TogglePlayPause", [])
+  | Stop => ("Stop", [])
   | Next => ("Next", [])
   | Previous => ("Previous", [])
+  | Seek(ms) => ("Seek", [("ms", Js.Json.number(Float.fromInt(ms)))])
+  | SeekRelative(deltaMs) => ("SeekRelative", [("deltaMs", Js.Json.number(Float.fromInt(deltaMs)))])
   | SetVolume(vol) => ("SetVolume", [("volume", Js.Json.number(vol))])
-  | Seek(position) => ("Seek", [("seekPosition", Js.Json.number(Float.fromInt(position)))])
+  | SetMuted(muted) => ("SetMuted", [("muted", Js.Json.boolean(muted))])
+  | SetSpeed(speed) => ("SetSpeed", [("speed", Js.Json.number(speed))])
+  | SetPitch(semitones) => ("SetPitch", [("semitones", Js.Json.number(semitones))])
+  | SetRepeat(mode) => ("SetRepeat", [("mode", Js.Json.string(repeatModeToString(mode)))])
+  | SetShuffle(shuffle) => ("SetShuffle", [("shuffle", Js.Json.boolean(shuffle))])
   }
 
-  let baseFields = [("command", Js.Json.string(commandName))]
+  let baseFields = [("type", Js.Json.string(commandType))]
   let allFields = Array.concat(baseFields, extraFields)
-  let payload = Js.Json.object_(Js.Dict.fromArray(allFields))
-  let finalPayload = Js.Json.object_(Js.Dict.fromArray([("payload", payload)]))
+  let commandJson = Js.Json.object_(Js.Dict.fromArray(allFields))
+  let finalPayload = Js.Json.object_(Js.Dict.fromArray([("command", commandJson)]))
 
   finalPayload
 }
@@ -0,0 +1,27 @@
+// Rejection value of a failed Tauri command
+type t = {
+  code: string,
+  message: string,
+  details?: Js.Json.t,
+}
+
+let fromExn = (error: Exn.t): option<t> => {
+  let json: Js.Json.t = Obj.magic(error)
+  switch json->Js.Json.decodeObject {
+  | Some(dict) =>
+    switch (
+      dict->Js.Dict.get("code")->Option.flatMap(Js.Json.decodeString),
+      dict->Js.Dict.get("message")->Option.flatMap(Js.Json.decodeString),
+    ) {
+    | (Some(code), Some(message)) => Some({code, message, details: ?dict->Js.Dict.get("details")})
+    | _ => None
+    }
+  | None => None
+  }
+}
+
+let message = (error: Exn.t, ~fallback: string) =>
+  switch fromExn(error) {
+  | Some({message}) => message
+  | None => fallback
+  }
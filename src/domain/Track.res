@@ -15,6 +15,7 @@ type t = {
   totalFrames: int,
   durationMs: int,
   metadata: trackMetadata,
+  unplayable: bool,
 }
 
 let displayTitle = (track: t) => {
@@ -18,3 +18,18 @@ let rescanLibrary = (~onRescan: option<unit => unit>) => {
 let getAlbumsByArtist = (): Promise.t<Js.Dict.t<Js.Dict.t<array<Track.t>>>> => {
   Tauri.invoke("get_albums_by_artist", ())
 }
+
+type lyricLine = {timeMs: Nullable.t<int>, text: string}
+type lyrics = {synced: bool, lines: array<lyricLine>}
+
+let getLyrics = (trackId: string): Promise.t<Nullable.t<lyrics>> => {
+  Tauri.invoke("get_lyrics", {"trackId": trackId})
+}
+
+let startLoudnessAnalysis = (~writeTags: bool): Promise.t<unit> => {
+  Tauri.invoke("start_loudness_analysis", {"writeTags": writeTags})
+}
+
+let cancelLoudnessAnalysis = (): Promise.t<unit> => {
+  Tauri.invoke("cancel_loudness_analysis", ())
+}
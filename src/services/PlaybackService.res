@@ -2,12 +2,28 @@ let getLibraryTracks = (): Promise.t<Js.Dict.t<array<Track.t>>> => {
   Tauri.invoke("get_library_tracks", ())
 }
 
-let controlPlayback = async (command: Command.t): State.t => {
-  let result = await Tauri.invoke("control_playback", Command.toJsonPayload(command))
-  State.fromString(result)
+type playbackSnapshot = {
+  state: string,
+  track: Nullable.t<Track.t>,
+  positionMs: int,
+  positionFrames: int,
+  volume: float,
+  muted: bool,
+  speed: float,
+  pitchSemitones: float,
+  repeat: Command.repeatMode,
+  shuffle: bool,
+}
+
+let controlPlayback = (command: Command.t): Promise.t<playbackSnapshot> => {
+  Tauri.invoke("control_playback", Command.toJsonPayload(command))
+}
+
+let getPlaybackStatus = (): Promise.t<playbackSnapshot> => {
+  Tauri.invoke("get_playback_status", ())
 }
 
-type progressEvent = {position: float, framesPlayed: int}
+type progressEvent = {position: float, framesPlayed: int, lyricLine?: int}
 type spectrumEvent = {spectrumData: array<float>}
 
 let subscribeToProgress = (onProgress: progressEvent => unit): Promise.t<unit> => {
@@ -59,3 +75,105 @@ let playFromLibrary = async (
 let reorderQueue = (oldIndex: int, newIndex: int): Promise.t<unit> => {
   Tauri.invoke("reorder_queue", {"payload": {"oldIndex": oldIndex, "newIndex": newIndex}})
 }
+
+type normalizationMode = | @as("Off") Off | @as("Track") Track | @as("Album") Album | @as("Auto") Auto
+type normalizationSettings = {
+  mode: normalizationMode,
+  preampDb: float,
+  preventClipping: bool,
+}
+
+let getNormalizationSettings = (): Promise.t<normalizationSettings> => {
+  Tauri.invoke("get_normalization_settings", ())
+}
+
+let setNormalizationSettings = (settings: normalizationSettings): Promise.t<unit> => {
+  Tauri.invoke("set_normalization_settings", {"settings": settings})
+}
+
+type filterType =
+  | @as("Peaking") Peaking
+  | @as("LowShelf") LowShelf
+  | @as("HighShelf") HighShelf
+  | @as("LowPass") LowPass
+  | @as("HighPass") HighPass
+type equalizerBand = {
+  filterType: filterType,
+  frequencyHz: float,
+  gainDb: float,
+  q: float,
+}
+type equalizerPreset = {
+  name: string,
+  preampDb: float,
+  graphicGainsDb: array<float>,
+  parametricBands: array<equalizerBand>,
+}
+type equalizerSettings = {
+  enabled: bool,
+  preset: Nullable.t<string>,
+  preampDb: float,
+  graphicGainsDb: array<float>,
+  parametricBands: array<equalizerBand>,
+  customPresets: array<equalizerPreset>,
+}
+
+let getEqualizerSettings = (): Promise.t<equalizerSettings> => {
+  Tauri.invoke("get_equalizer_settings", ())
+}
+
+let setEqualizerSettings = (settings: equalizerSettings): Promise.t<equalizerSettings> => {
+  Tauri.invoke("set_equalizer_settings", {"settings": settings})
+}
+
+let getEqualizerPresets = (): Promise.t<array<equalizerPreset>> => {
+  Tauri.invoke("get_equalizer_presets", ())
+}
+
+let applyEqualizerPreset = (name: string): Promise.t<equalizerSettings> => {
+  Tauri.invoke("apply_equalizer_preset", {"name": name})
+}
+
+let saveEqualizerPreset = (name: string): Promise.t<equalizerSettings> => {
+  Tauri.invoke("save_equalizer_preset", {"name": name})
+}
+
+let deleteEqualizerPreset = (name: string): Promise.t<equalizerSettings> => {
+  Tauri.invoke("delete_equalizer_preset", {"name": name})
+}
+
+type dspStage =
+  | @as("Equalizer") Equalizer
+  | @as("Normalization") Normalization
+  | @as("Balance") Balance
+  | @as("Limiter") Limiter
+  | @as("Spectrum") Spectrum
+type dspSettings = {
+  stages: array<dspStage>,
+  balance: float,
+  limiterCeilingDb: float,
+}
+
+let getDspSettings = (): Promise.t<dspSettings> => {
+  Tauri.invoke("get_dsp_settings", ())
+}
+
+let setDspSettings = (settings: dspSettings): Promise.t<unit> => {
+  Tauri.invoke("set_dsp_settings", {"settings": settings})
+}
+
+type outputDevice = {
+  name: string,
+  isDefault: bool,
+  channels: array<int>,
+  sampleRates: array<int>,
+  sampleFormats: array<string>,
+}
+
+let getOutputDevices = (): Promise.t<array<outputDevice>> => {
+  Tauri.invoke("get_output_devices", ())
+}
+
+let setOutputDevice = (device: option<string>): Promise.t<unit> => {
+  Tauri.invoke("set_output_device", {"device": device})
+}